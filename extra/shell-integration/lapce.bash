@@ -0,0 +1,27 @@
+# Shell integration for the Lapce terminal. It marks the prompts and the
+# commands with OSC 133, and reports the working directory with OSC 7.
+
+# Lapce starts bash with --init-file, which replaces ~/.bashrc
+if [ -f ~/.bashrc ]; then
+    . ~/.bashrc
+fi
+
+if [ -n "$LAPCE_SHELL_INTEGRATION_LOADED" ]; then
+    return
+fi
+LAPCE_SHELL_INTEGRATION_LOADED=1
+
+__lapce_precmd() {
+    local status="$?"
+    printf '\e]133;D;%s\a' "$status"
+    printf '\e]7;file://%s%s\a' "$HOSTNAME" "$PWD"
+    # the prompt can be replaced by the user's prompt command
+    case "$PS1" in
+        *'133;A'*) ;;
+        *) PS1='\[\e]133;A\a\]'"$PS1"'\[\e]133;B\a\]' ;;
+    esac
+    return "$status"
+}
+
+PS0="$PS0"'\e]133;C\a'
+PROMPT_COMMAND="__lapce_precmd${PROMPT_COMMAND:+;$PROMPT_COMMAND}"
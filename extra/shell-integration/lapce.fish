@@ -0,0 +1,26 @@
+# Shell integration for the Lapce terminal. It marks the prompts and the
+# commands with OSC 133, and reports the working directory with OSC 7.
+
+if set -q LAPCE_SHELL_INTEGRATION_LOADED
+    exit
+end
+set -g LAPCE_SHELL_INTEGRATION_LOADED 1
+
+function __lapce_prompt_start --on-event fish_prompt
+    printf '\e]7;file://%s%s\a' $hostname $PWD
+    printf '\e]133;A\a'
+end
+
+function __lapce_preexec --on-event fish_preexec
+    printf '\e]133;C\a'
+end
+
+function __lapce_postexec --on-event fish_postexec
+    printf '\e]133;D;%s\a' $status
+end
+
+functions -c fish_prompt __lapce_fish_prompt
+function fish_prompt
+    __lapce_fish_prompt
+    printf '\e]133;B\a'
+end
@@ -41,8 +41,8 @@
     text::{Style as FontStyle, Weight},
     unit::PxPctAuto,
     views::{
-        Decorators, VirtualVector, clip, container, drag_resize_window_area,
-        drag_window_area, dyn_stack,
+        Container, Decorators, VirtualVector, clip, container,
+        drag_resize_window_area, drag_window_area, dyn_stack,
         editor::{core::register::Clipboard, text::SystemClipboard},
         empty, label, rich_text,
         scroll::{PropagatePointerWheel, VerticalScrollAsHorizontal, scroll},
@@ -79,6 +79,7 @@
     },
     db::LapceDb,
     debug::RunDebugMode,
+    disassembly::disassembly_view,
     editor::{
         diff::diff_show_more_section_view,
         location::{EditorLocation, EditorPosition},
@@ -94,11 +95,15 @@
         SplitContent, SplitData, SplitDirection, SplitMoveDirection, TabCloseKind,
     },
     markdown::MarkdownContent,
+    memory::memory_view,
     palette::{
         PaletteStatus,
         item::{PaletteItem, PaletteItemContent},
     },
-    panel::{position::PanelContainerPosition, view::panel_container_view},
+    panel::{
+        position::PanelContainerPosition, terminal_view::editor_terminal_view,
+        view::panel_container_view,
+    },
     plugin::{PluginData, plugin_info_view},
     settings::{settings_view, theme_color_settings_view},
     status::status,
@@ -107,7 +112,7 @@
     tracing::*,
     update::ReleaseInfo,
     window::{TabsInfo, WindowData, WindowInfo},
-    window_tab::{Focus, WindowTabData},
+    window_tab::{DragContent, Focus, WindowTabData},
     workspace::{LapceWorkspace, LapceWorkspaceType},
 };
 
@@ -689,10 +694,12 @@ fn editor_tab_header(
     let plugin = window_tab_data.plugin.clone();
     let editors = window_tab_data.main_split.editors;
     let diff_editors = window_tab_data.main_split.diff_editors;
+    let terminals = window_tab_data.main_split.terminals;
     let focus = window_tab_data.common.focus;
     let config = window_tab_data.common.config;
     let internal_command = window_tab_data.common.internal_command;
     let workbench_command = window_tab_data.common.workbench_command;
+    let common_dragging = window_tab_data.common.dragging;
     let editor_tab_id =
         editor_tab.with_untracked(|editor_tab| editor_tab.editor_tab_id);
 
@@ -728,10 +735,12 @@ fn editor_tab_header(
         let child_for_close = child.clone();
         let child_for_mouse_close = child.clone();
         let child_for_mouse_close_2 = child.clone();
+        let child_for_drag = child.clone();
         let main_split = main_split.clone();
         let plugin = plugin.clone();
         let child_view = {
-            let info = child.view_info(editors, diff_editors, plugin, config);
+            let info =
+                child.view_info(editors, diff_editors, plugin, terminals, config);
             let hovered = create_rw_signal(false);
 
             use crate::config::ui::TabCloseButton;
@@ -916,9 +925,12 @@ fn editor_tab_header(
                 })
                 .on_event_stop(EventListener::DragStart, move |_| {
                     dragging.set(Some((i, editor_tab_id)));
+                    common_dragging
+                        .set(Some(DragContent::EditorTab(child_for_drag.clone())));
                 })
                 .on_event_stop(EventListener::DragEnd, move |_| {
                     dragging.set(None);
+                    common_dragging.set(None);
                 })
                 .on_resize(move |rect| {
                     header_content_size.set(rect.size());
@@ -1406,6 +1418,22 @@ fn editor_tab_content(
             EditorTabChild::Volt(_, id) => {
                 plugin_info_view(plugin.clone(), id).into_any()
             }
+            EditorTabChild::Disassembly(_) => {
+                disassembly_view(window_tab_data.terminal.clone(), common).into_any()
+            }
+            EditorTabChild::Memory(_) => {
+                memory_view(window_tab_data.terminal.clone(), common).into_any()
+            }
+            EditorTabChild::Terminal(_, term_id) => {
+                if let Some(terminal) =
+                    window_tab_data.main_split.get_terminal(&term_id)
+                {
+                    editor_terminal_view(window_tab_data.clone(), terminal)
+                        .into_any()
+                } else {
+                    text("closed terminal").into_any()
+                }
+            }
         };
         child.style(|s| s.size_full())
     };
@@ -1442,6 +1470,12 @@ fn editor_tab(
     let internal_command = main_split.common.internal_command;
     let tab_size = create_rw_signal(Size::ZERO);
     let drag_over: RwSignal<Option<DragOverPosition>> = create_rw_signal(None);
+    // a tab of the terminal panel dragged here
+    let common_dragging = common.dragging;
+    let dragged_terminal_tab = move || match common_dragging.get_untracked() {
+        Some(DragContent::TerminalTab(terminal_tab_id)) => Some(terminal_tab_id),
+        _ => None,
+    };
     stack((
         editor_tab_header(
             window_tab_data.clone(),
@@ -1533,6 +1567,11 @@ fn editor_tab(
                                 drag_over.set(Some(new_drag_over));
                             }
                         }
+                    } else if dragged_terminal_tab().is_some()
+                        && drag_over.get_untracked()
+                            != Some(DragOverPosition::Middle)
+                    {
+                        drag_over.set(Some(DragOverPosition::Middle));
                     }
                 })
                 .on_event_stop(EventListener::DragLeave, move |_| {
@@ -1590,6 +1629,16 @@ fn editor_tab(
                         }
                         drag_over.set(None);
                         EventPropagation::Stop
+                    } else if let Some(terminal_tab_id) = dragged_terminal_tab() {
+                        drag_over.set(None);
+                        common_dragging.set(None);
+                        internal_command.send(
+                            InternalCommand::MoveTerminalTabToEditor {
+                                terminal_tab_id,
+                                editor_tab_id,
+                            },
+                        );
+                        EventPropagation::Stop
                     } else {
                         EventPropagation::Continue
                     }
@@ -1598,11 +1647,12 @@ fn editor_tab(
                     tab_size.set(rect.size());
                 })
                 .style(move |s| {
-                    s.absolute()
-                        .size_full()
-                        .apply_if(dragging.get().is_none(), |s| {
-                            s.pointer_events_none()
-                        })
+                    let is_dragging_terminal_tab = common_dragging
+                        .with(|d| matches!(d, Some(DragContent::TerminalTab(_))));
+                    s.absolute().size_full().apply_if(
+                        dragging.get().is_none() && !is_dragging_terminal_tab,
+                        |s| s.pointer_events_none(),
+                    )
                 }),
         ))
         .debug_name("Editor Content and Drag Over")
@@ -2443,70 +2493,27 @@ fn palette_item(
             mode,
             config: run_config,
         } => {
-            let mode = *mode;
             let text = format!("{mode} {}", run_config.name);
             let hint = format!(
                 "{} {}",
                 run_config.program,
                 run_config.args.clone().unwrap_or_default().join(" ")
             );
-            let text_indices: Vec<usize> = item
-                .indices
-                .iter()
-                .filter_map(|i| {
-                    let i = *i;
-                    if i < text.len() { Some(i) } else { None }
-                })
-                .collect();
-            let hint_indices: Vec<usize> = item
-                .indices
+            palette_run_debug_item(*mode, text, hint, item.indices, config)
+        }
+        PaletteItemContent::RunAndDebugCompound { launch } => {
+            let text = format!(
+                "{} {}",
+                launch.mode,
+                launch.compound.clone().unwrap_or_default()
+            );
+            let hint = launch
+                .configs
                 .iter()
-                .filter_map(|i| {
-                    let i = *i;
-                    if i >= text.len() {
-                        Some(i - text.len())
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-            container(
-                stack((
-                    svg(move || {
-                        let config = config.get();
-                        match mode {
-                            RunDebugMode::Run => config.ui_svg(LapceIcons::START),
-                            RunDebugMode::Debug => config.ui_svg(LapceIcons::DEBUG),
-                        }
-                    })
-                    .style(move |s| {
-                        let config = config.get();
-                        let size = config.ui.icon_size() as f32;
-                        s.min_width(size)
-                            .size(size, size)
-                            .margin_right(5.0)
-                            .color(config.color(LapceColor::LAPCE_ICON_ACTIVE))
-                    }),
-                    focus_text(
-                        move || text.clone(),
-                        move || text_indices.clone(),
-                        move || config.get().color(LapceColor::EDITOR_FOCUS),
-                    )
-                    .style(|s| s.margin_right(6.0).max_width_full()),
-                    focus_text(
-                        move || hint.clone(),
-                        move || hint_indices.clone(),
-                        move || config.get().color(LapceColor::EDITOR_FOCUS),
-                    )
-                    .style(move |s| {
-                        s.color(config.get().color(LapceColor::EDITOR_DIM))
-                            .min_width(0.0)
-                            .flex_grow(1.0)
-                            .flex_basis(0.0)
-                    }),
-                ))
-                .style(|s| s.align_items(Some(AlignItems::Center)).max_width_full()),
-            )
+                .map(|config| config.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            palette_run_debug_item(launch.mode, text, hint, item.indices, config)
         }
         PaletteItemContent::PaletteHelp { .. }
         | PaletteItemContent::Command { .. } => {
@@ -2563,6 +2570,8 @@ fn palette_item(
         | PaletteItemContent::ColorTheme { .. }
         | PaletteItemContent::SCMReference { .. }
         | PaletteItemContent::TerminalProfile { .. }
+        | PaletteItemContent::TerminalLayout { .. }
+        | PaletteItemContent::RunDebugInput { .. }
         | PaletteItemContent::IconTheme { .. } => {
             let text = item.filter_text;
             let indices = item.indices;
@@ -2601,6 +2610,70 @@ fn palette_item(
     })
 }
 
+fn palette_run_debug_item(
+    mode: RunDebugMode,
+    text: String,
+    hint: String,
+    indices: Vec<usize>,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> Container {
+    let text_indices: Vec<usize> = indices
+        .iter()
+        .filter_map(|i| {
+            let i = *i;
+            if i < text.len() { Some(i) } else { None }
+        })
+        .collect();
+    let hint_indices: Vec<usize> = indices
+        .iter()
+        .filter_map(|i| {
+            let i = *i;
+            if i >= text.len() {
+                Some(i - text.len())
+            } else {
+                None
+            }
+        })
+        .collect();
+    container(
+        stack((
+            svg(move || {
+                let config = config.get();
+                match mode {
+                    RunDebugMode::Run => config.ui_svg(LapceIcons::START),
+                    RunDebugMode::Debug => config.ui_svg(LapceIcons::DEBUG),
+                }
+            })
+            .style(move |s| {
+                let config = config.get();
+                let size = config.ui.icon_size() as f32;
+                s.min_width(size)
+                    .size(size, size)
+                    .margin_right(5.0)
+                    .color(config.color(LapceColor::LAPCE_ICON_ACTIVE))
+            }),
+            focus_text(
+                move || text.clone(),
+                move || text_indices.clone(),
+                move || config.get().color(LapceColor::EDITOR_FOCUS),
+            )
+            .style(|s| s.margin_right(6.0).max_width_full()),
+            focus_text(
+                move || hint.clone(),
+                move || hint_indices.clone(),
+                move || config.get().color(LapceColor::EDITOR_FOCUS),
+            )
+            .style(move |s| {
+                s.color(config.get().color(LapceColor::EDITOR_DIM))
+                    .min_width(0.0)
+                    .flex_grow(1.0)
+                    .flex_basis(0.0)
+            }),
+        ))
+        .style(|s| s.align_items(Some(AlignItems::Center)).max_width_full()),
+    )
+}
+
 fn palette_input(window_tab_data: Rc<WindowTabData>) -> impl View {
     let editor = window_tab_data.palette.input_editor.clone();
     let config = window_tab_data.common.config;
@@ -2610,7 +2683,7 @@ fn palette_input(window_tab_data: Rc<WindowTabData>) -> impl View {
     let input = TextInputBuilder::new()
         .is_focused(is_focused)
         .build_editor(editor)
-        .placeholder(move || window_tab_data.palette.placeholder_text().to_owned())
+        .placeholder(move || window_tab_data.palette.placeholder_text())
         .style(|s| s.width_full());
 
     container(container(input).style(move |s| {
@@ -4282,6 +4355,15 @@ fn tab_secondary_click(
     let child_other = child.clone();
     let child_right = child.clone();
     let child_left = child.clone();
+    if let EditorTabChild::Terminal(_, term_id) = &child {
+        let term_id = *term_id;
+        menu = menu
+            .entry(MenuItem::new("Move to Panel").action(move || {
+                internal_command
+                    .send(InternalCommand::MoveTerminalToPanel { term_id });
+            }))
+            .separator();
+    }
     menu = menu
         .entry(MenuItem::new("Close").action(move || {
             internal_command.send(InternalCommand::EditorTabChildClose {
@@ -87,6 +87,10 @@ fn get_rust_command_config(
                 cwd: None,
                 env: None,
                 prelaunch: None,
+                post_debug: None,
+                depends_on: None,
+                ready_pattern: None,
+                problem_matchers: None,
                 debug_command: None,
                 dap_id: Default::default(),
                 tracing_output: mode == RunDebugMode::Debug,
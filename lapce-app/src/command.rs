@@ -1,5 +1,6 @@
 use std::{path::PathBuf, rc::Rc};
 
+use alacritty_terminal::index::Direction;
 pub use floem::views::editor::command::CommandExecuted;
 use floem::{
     ViewId, keyboard::Modifiers, peniko::kurbo::Vec2,
@@ -23,12 +24,13 @@
 
 use crate::{
     alert::AlertButton,
-    debug::RunDebugMode,
+    debug::{RunDebugLaunch, RunDebugMode},
     doc::Doc,
     editor::location::EditorLocation,
     editor_tab::EditorTabChild,
-    id::EditorTabId,
+    id::{EditorTabId, TerminalTabId},
     main_split::{SplitDirection, SplitMoveDirection, TabCloseKind},
+    memory::MemoryTarget,
     workspace::LapceWorkspace,
 };
 
@@ -47,10 +49,21 @@ pub enum CommandKind {
     Focus(FocusCommand),
     MotionMode(MotionModeCommand),
     MultiSelection(MultiSelectionCommand),
+    Plugin(VoltCommand),
+}
+
+/// A command registered by a volt, which is run by the volt itself
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VoltCommand {
+    /// The plugin server that registered the command
+    pub plugin_id: PluginId,
+    pub volt: VoltID,
+    pub command: String,
+    pub title: String,
 }
 
 impl CommandKind {
-    pub fn desc(&self) -> Option<&'static str> {
+    pub fn desc(&self) -> Option<&str> {
         match &self {
             CommandKind::Workbench(cmd) => cmd.get_message(),
             CommandKind::Edit(cmd) => cmd.get_message(),
@@ -59,10 +72,11 @@ pub fn desc(&self) -> Option<&'static str> {
             CommandKind::Focus(cmd) => cmd.get_message(),
             CommandKind::MotionMode(cmd) => cmd.get_message(),
             CommandKind::MultiSelection(cmd) => cmd.get_message(),
+            CommandKind::Plugin(cmd) => Some(&cmd.title),
         }
     }
 
-    pub fn str(&self) -> &'static str {
+    pub fn str(&self) -> &str {
         match &self {
             CommandKind::Workbench(cmd) => cmd.into(),
             CommandKind::Edit(cmd) => cmd.into(),
@@ -71,6 +85,7 @@ pub fn str(&self) -> &'static str {
             CommandKind::Focus(cmd) => cmd.into(),
             CommandKind::MotionMode(cmd) => cmd.into(),
             CommandKind::MultiSelection(cmd) => cmd.into(),
+            CommandKind::Plugin(cmd) => &cmd.command,
         }
     }
 }
@@ -217,6 +232,22 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Run in Terminal")]
     RunInTerminal,
 
+    #[strum(serialize = "send_selection_to_terminal")]
+    #[strum(message = "Send Selection or Line to Terminal")]
+    SendSelectionToTerminal,
+
+    #[strum(serialize = "send_line_to_terminal")]
+    #[strum(message = "Send Line to Terminal")]
+    SendLineToTerminal,
+
+    #[strum(serialize = "send_block_to_terminal")]
+    #[strum(message = "Send Enclosing Block to Terminal")]
+    SendBlockToTerminal,
+
+    #[strum(serialize = "use_terminal_for_sent_code")]
+    #[strum(message = "Send Code from the Editor to the Active Terminal")]
+    UseTerminalForSentCode,
+
     #[strum(serialize = "reveal_active_file_in_file_explorer")]
     #[strum(message = "Reveal Active File in File Explorer")]
     RevealActiveFileInFileExplorer,
@@ -281,6 +312,22 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Open Plugins Directory")]
     OpenPluginsDirectory,
 
+    #[strum(serialize = "install_plugin_from_file")]
+    #[strum(message = "Install Plugin From File")]
+    InstallPluginFromFile,
+
+    #[strum(serialize = "install_plugin_from_folder")]
+    #[strum(message = "Install Plugin From Folder")]
+    InstallPluginFromFolder,
+
+    #[strum(serialize = "check_plugin_updates")]
+    #[strum(message = "Check For Plugin Updates")]
+    CheckPluginUpdates,
+
+    #[strum(serialize = "update_all_plugins")]
+    #[strum(message = "Update All Plugins")]
+    UpdateAllPlugins,
+
     #[strum(serialize = "open_grammars_directory")]
     #[strum(message = "Open Grammars Directory")]
     OpenGrammarsDirectory,
@@ -313,6 +360,18 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Create New Terminal Tab")]
     NewTerminalTab,
 
+    #[strum(serialize = "new_terminal_in_editor")]
+    #[strum(message = "Create New Terminal in Editor Area")]
+    NewTerminalInEditor,
+
+    #[strum(serialize = "move_terminal_to_editor")]
+    #[strum(message = "Move Terminal to Editor Area")]
+    MoveTerminalToEditor,
+
+    #[strum(serialize = "move_terminal_to_panel")]
+    #[strum(message = "Move Terminal to Panel")]
+    MoveTerminalToPanel,
+
     #[strum(serialize = "close_terminal_tab")]
     #[strum(message = "Close Terminal Tab")]
     CloseTerminalTab,
@@ -325,6 +384,18 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Previous Terminal Tab")]
     PreviousTerminalTab,
 
+    #[strum(serialize = "terminal_previous_prompt")]
+    #[strum(message = "Terminal: Go To Previous Prompt")]
+    TerminalPreviousPrompt,
+
+    #[strum(serialize = "terminal_next_prompt")]
+    #[strum(message = "Terminal: Go To Next Prompt")]
+    TerminalNextPrompt,
+
+    #[strum(serialize = "terminal_copy_last_command_output")]
+    #[strum(message = "Terminal: Copy Last Command Output")]
+    TerminalCopyLastCommandOutput,
+
     #[strum(serialize = "next_window_tab")]
     #[strum(message = "Go To Next Window Tab")]
     NextWindowTab,
@@ -410,6 +481,30 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "palette.run_and_debug_stop")]
     RunAndDebugStop,
 
+    #[strum(message = "Tasks: Run Default Build Task")]
+    #[strum(serialize = "run_default_build_task")]
+    RunDefaultBuildTask,
+
+    #[strum(message = "Tasks: Run Default Test Task")]
+    #[strum(serialize = "run_default_test_task")]
+    RunDefaultTestTask,
+
+    #[strum(message = "Tasks: Refresh Detected Tasks")]
+    #[strum(serialize = "refresh_tasks")]
+    RefreshTasks,
+
+    #[strum(message = "Testing: Discover Tests")]
+    #[strum(serialize = "testing_discover")]
+    TestingDiscover,
+
+    #[strum(message = "Testing: Run All Tests")]
+    #[strum(serialize = "testing_run_all")]
+    TestingRunAll,
+
+    #[strum(message = "Testing: Run Failed Tests")]
+    #[strum(serialize = "testing_run_failed")]
+    TestingRunFailed,
+
     #[strum(serialize = "source_control.checkout_reference")]
     CheckoutReference,
 
@@ -466,6 +561,18 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "toggle_search_focus")]
     ToggleSearchFocus,
 
+    #[strum(message = "Toggle Task Focus")]
+    #[strum(serialize = "toggle_task_focus")]
+    ToggleTaskFocus,
+
+    #[strum(message = "Toggle Testing Focus")]
+    #[strum(serialize = "toggle_testing_focus")]
+    ToggleTestingFocus,
+
+    #[strum(message = "Toggle Output Focus")]
+    #[strum(serialize = "toggle_output_focus")]
+    ToggleOutputFocus,
+
     // Visual toggle commands
     #[strum(serialize = "toggle_terminal_visual")]
     ToggleTerminalVisual,
@@ -488,6 +595,9 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "toggle_search_visual")]
     ToggleSearchVisual,
 
+    #[strum(serialize = "toggle_output_visual")]
+    ToggleOutputVisual,
+
     #[strum(serialize = "focus_editor")]
     FocusEditor,
 
@@ -663,6 +773,9 @@ pub enum InternalCommand {
     NewTerminal {
         profile: Option<TerminalProfile>,
     },
+    OpenTerminalLayout {
+        name: String,
+    },
     SplitTerminal {
         term_id: TermId,
     },
@@ -675,6 +788,20 @@ pub enum InternalCommand {
     SplitTerminalExchange {
         term_id: TermId,
     },
+    MoveTerminalToEditor {
+        term_id: TermId,
+    },
+    MoveTerminalTabToEditor {
+        terminal_tab_id: TerminalTabId,
+        editor_tab_id: EditorTabId,
+    },
+    MoveTerminalToPanel {
+        term_id: TermId,
+    },
+    ShowTerminalFind,
+    TerminalFindNext {
+        direction: Direction,
+    },
     EditorTabClose {
         editor_tab_id: EditorTabId,
     },
@@ -690,8 +817,7 @@ pub enum InternalCommand {
     ShowCodeActions {
         offset: usize,
         mouse_click: bool,
-        plugin_id: PluginId,
-        code_actions: im::Vector<CodeActionOrCommand>,
+        code_actions: im::Vector<(PluginId, CodeActionOrCommand)>,
     },
     RunCodeAction {
         plugin_id: PluginId,
@@ -704,6 +830,17 @@ pub enum InternalCommand {
         mode: RunDebugMode,
         config: RunDebugConfig,
     },
+    /// Start configs together with the ones they depend on, asking for the
+    /// inputs they use that haven't been entered yet
+    RunAndDebugLaunch {
+        launch: RunDebugLaunch,
+    },
+    /// Start a config whose dependencies are ready
+    StartRunDebug {
+        mode: RunDebugMode,
+        config: RunDebugConfig,
+        compound: Option<String>,
+    },
     StartRename {
         path: PathBuf,
         placeholder: String,
@@ -774,6 +911,10 @@ pub enum InternalCommand {
     OpenVoltView {
         volt_id: VoltID,
     },
+    OpenDisassembly,
+    OpenMemory {
+        target: MemoryTarget,
+    },
     ResetBlinkCursor,
     OpenDiffFiles {
         left_path: PathBuf,
@@ -797,6 +938,13 @@ pub enum InternalCommand {
     RestartTerminal {
         term_id: TermId,
     },
+    /// The process of a detected task has exited, or its terminal was closed
+    TaskExited {
+        name: String,
+        exit_code: Option<i32>,
+    },
+    /// Volts added or changed languages, which open documents may be of
+    VoltLanguagesChanged,
 }
 
 #[derive(Clone)]
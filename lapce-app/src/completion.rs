@@ -6,9 +6,13 @@
     views::editor::{id::EditorId, text::Document},
 };
 use lapce_core::{
-    buffer::rope_text::RopeText, movement::Movement, rope_text_pos::RopeTextPosition,
+    buffer::rope_text::RopeText, language::LapceLanguage, movement::Movement,
+    rope_text_pos::RopeTextPosition,
+};
+use lapce_rpc::{
+    plugin::{PluginId, VoltID},
+    proxy::ProxyRpcHandler,
 };
-use lapce_rpc::{plugin::PluginId, proxy::ProxyRpcHandler};
 use lsp_types::{
     CompletionItem, CompletionResponse, CompletionTextEdit, InsertTextFormat,
     Position,
@@ -17,6 +21,10 @@
 
 use crate::{config::LapceConfig, editor::EditorData, snippet::Snippet};
 
+/// The snippets of volts aren't from a plugin, so their items have an id that
+/// no plugin gets
+const SNIPPETS_PLUGIN_ID: PluginId = PluginId(0);
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum CompletionStatus {
     Inactive,
@@ -61,6 +69,8 @@ pub struct CompletionData {
     pub layout_rect: Rect,
     /// The editor id that was most recently used to trigger a completion.
     pub latest_editor_id: Option<EditorId>,
+    /// The items of the snippets of each volt, with the language they're for
+    snippets: im::HashMap<VoltID, Arc<Vec<(LapceLanguage, CompletionItem)>>>,
     /// Matcher for filtering the completion items
     matcher: RwSignal<nucleo::Matcher>,
     config: ReadSignal<Arc<LapceConfig>>,
@@ -83,6 +93,7 @@ pub fn new(cx: Scope, config: ReadSignal<Arc<LapceConfig>>) -> Self {
             matcher: cx
                 .create_rw_signal(nucleo::Matcher::new(nucleo::Config::DEFAULT)),
             latest_editor_id: None,
+            snippets: im::HashMap::new(),
             config,
         }
     }
@@ -106,17 +117,18 @@ pub fn receive(
             // TODO: Possibly handle the 'is_incomplete' field on List.
             CompletionResponse::List(list) => &list.items,
         };
-        let items: im::Vector<ScoredCompletionItem> = items
-            .iter()
-            .map(|i| ScoredCompletionItem {
-                item: i.to_owned(),
-                plugin_id,
-                score: 0,
-                label_score: 0,
-                indices: Vec::new(),
-            })
-            .collect();
-        self.input_items.insert(input.to_string(), items);
+        let items = items.iter().map(|i| ScoredCompletionItem {
+            item: i.to_owned(),
+            plugin_id,
+            score: 0,
+            label_score: 0,
+            indices: Vec::new(),
+        });
+        // Each language server of the document responds on its own, so its
+        // items are merged with the ones of the others for the same input
+        let input_items = self.input_items.entry(input.to_string()).or_default();
+        input_items.retain(|i| i.plugin_id != plugin_id);
+        input_items.extend(items);
         self.filter_items();
     }
 
@@ -126,14 +138,53 @@ pub fn request(
         editor_id: EditorId,
         proxy_rpc: &ProxyRpcHandler,
         path: PathBuf,
+        language: LapceLanguage,
         input: String,
         position: Position,
     ) {
         self.latest_editor_id = Some(editor_id);
-        self.input_items.insert(input.clone(), im::Vector::new());
+        // The snippets of the language are there from the start, and the items
+        // of the language servers are added as they respond
+        let snippets = self.language_snippets(language);
+        let has_snippets = !snippets.is_empty();
+        self.input_items.insert(input.clone(), snippets);
+        if has_snippets {
+            self.filter_items();
+        }
         proxy_rpc.completion(self.request_id, path, input, position);
     }
 
+    fn language_snippets(
+        &self,
+        language: LapceLanguage,
+    ) -> im::Vector<ScoredCompletionItem> {
+        self.snippets
+            .values()
+            .flat_map(|snippets| snippets.iter())
+            .filter(|(l, _)| *l == language)
+            .map(|(_, item)| ScoredCompletionItem {
+                item: item.clone(),
+                plugin_id: SNIPPETS_PLUGIN_ID,
+                score: 0,
+                label_score: 0,
+                indices: Vec::new(),
+            })
+            .collect()
+    }
+
+    /// Set the items of the snippets of a volt
+    pub fn set_volt_snippets(
+        &mut self,
+        volt: VoltID,
+        snippets: Vec<(LapceLanguage, CompletionItem)>,
+    ) {
+        if snippets.is_empty() {
+            self.snippets.remove(&volt);
+        } else {
+            self.snippets.insert(volt, Arc::new(snippets));
+        }
+    }
+
     /// Close the completion, clearing all the data.
     pub fn cancel(&mut self) {
         if self.status == CompletionStatus::Inactive {
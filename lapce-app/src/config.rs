@@ -9,7 +9,7 @@
 use itertools::Itertools;
 use lapce_core::directory::Directory;
 use lapce_proxy::plugin::wasi::find_all_volts;
-use lapce_rpc::plugin::VoltID;
+use lapce_rpc::plugin::{LanguageServerConfig, LanguageServerPolicy, VoltID};
 use lsp_types::{CompletionItemKind, SymbolKind};
 use once_cell::sync::Lazy;
 use parking_lot::RwLock;
@@ -104,6 +104,13 @@ pub struct LapceConfig {
     pub color_theme: ColorThemeConfig,
     #[serde(default)]
     pub icon_theme: IconThemeConfig,
+    /// Language servers declared in `[lsp.<name>]` sections
+    #[serde(default)]
+    pub lsp: HashMap<String, LanguageServerConfig>,
+    /// Which language server does what for a language, from the
+    /// `[lsp-policy.<language>]` sections
+    #[serde(default)]
+    pub lsp_policy: HashMap<String, LanguageServerPolicy>,
     #[serde(flatten)]
     pub plugins: HashMap<String, HashMap<String, serde_json::Value>>,
     #[serde(skip)]
@@ -213,13 +220,17 @@ fn merge_config(
 
         match workspace.kind {
             LapceWorkspaceType::Local => {
-                if let Some(path) = workspace.path.as_ref() {
-                    let path = path.join("./.lapce/settings.toml");
+                if let Some(settings) = workspace
+                    .path
+                    .as_ref()
+                    .and_then(|path| Self::workspace_settings(path))
+                {
                     config = config::Config::builder()
                         .add_source(config.clone())
-                        .add_source(
-                            config::File::from(path.as_path()).required(false),
-                        )
+                        .add_source(config::File::from_str(
+                            &settings,
+                            config::FileFormat::Toml,
+                        ))
                         .build()
                         .unwrap_or_else(|_| config.clone());
                 }
@@ -232,6 +243,17 @@ fn merge_config(
         config
     }
 
+    /// The settings file of the workspace, without its `[lsp]` sections.
+    /// Language servers are commands that get run, so they're only taken from
+    /// the user's settings, not from whatever folder is opened.
+    fn workspace_settings(workspace: &Path) -> Option<String> {
+        let path = workspace.join("./.lapce/settings.toml");
+        let content = std::fs::read_to_string(path).ok()?;
+        let mut document: toml_edit::Document = content.parse().ok()?;
+        document.as_table_mut().remove("lsp");
+        Some(document.to_string())
+    }
+
     fn update_id(&mut self) {
         self.id = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -1018,3 +1040,36 @@ pub fn update_file(
         Some(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::LapceConfig;
+    use crate::workspace::{LapceWorkspace, LapceWorkspaceType};
+
+    #[test]
+    fn test_workspace_lsp_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join(".lapce")).unwrap();
+        std::fs::write(
+            dir.path().join(".lapce").join("settings.toml"),
+            "[editor]\nfont-size = 23\n\n\
+             [lsp.lapce-test-workspace-server]\ncommand = \"touch\"\n",
+        )
+        .unwrap();
+        let workspace = LapceWorkspace {
+            kind: LapceWorkspaceType::Local,
+            path: Some(dir.path().to_path_buf()),
+            last_open: 0,
+        };
+
+        let config = LapceConfig::merge_config(&workspace, None, None);
+        assert_eq!(config.get_int("editor.font-size").unwrap(), 23);
+        assert!(
+            config
+                .get_table("lsp")
+                .unwrap_or_default()
+                .get("lapce-test-workspace-server")
+                .is_none()
+        );
+    }
+}
@@ -22,4 +22,21 @@ pub struct CoreConfig {
         desc = "Enable auto-reload for the plugin when its configuration changes."
     )]
     pub auto_reload_plugin: bool,
+    /// The registries that plugins are searched for and installed from, in
+    /// order. Either the url of a server with the same api as the default
+    /// registry, or the path of a directory with a volt in each folder.
+    #[field_names(skip)]
+    pub plugin_registries: Vec<String>,
+    #[field_names(
+        desc = "How often to check for plugin updates, in minutes. Set it to 0 to disable the checks."
+    )]
+    pub plugin_update_check_interval: u64,
+    #[field_names(
+        desc = "Install plugin updates as soon as they're found, instead of only telling you about them."
+    )]
+    pub auto_update_plugins: bool,
+    /// Plugins that are kept at a version rather than updated to the latest
+    /// one, as `<author>.<name>@<version>`
+    #[field_names(skip)]
+    pub pinned_plugins: Vec<String>,
 }
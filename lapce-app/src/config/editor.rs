@@ -182,6 +182,10 @@ pub struct EditorConfig {
         desc = "Set the error lens font size. If 0 it uses the inlay hint font size."
     )]
     pub error_lens_font_size: usize,
+    #[field_names(
+        desc = "If the values of local variables should be displayed inline while debugging"
+    )]
+    pub enable_inline_debug_values: bool,
     #[field_names(
         desc = "If the editor should display the completion item as phantom text"
     )]
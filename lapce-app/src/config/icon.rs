@@ -29,6 +29,11 @@ impl LapceIcons {
     pub const DEBUG_DISCONNECT: &'static str = "debug_disconnect";
     pub const START: &'static str = "start";
     pub const RUN_ERRORS: &'static str = "run_errors";
+    pub const TEST_STATUS: &'static str = "test_status";
+    pub const OUTPUT: &'static str = "output";
+    pub const OUTPUT_TRACE: &'static str = "output.trace";
+    pub const OUTPUT_CLEAR: &'static str = "output.clear";
+    pub const OUTPUT_EXPORT: &'static str = "output.export";
     pub const UNSAVED: &'static str = "unsaved";
     pub const WARNING: &'static str = "warning";
     pub const TERMINAL: &'static str = "terminal";
@@ -1,9 +1,19 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use floem::peniko::Color;
+use lapce_core::language::LapceLanguage;
+use lapce_rpc::dap_types::ProblemMatcher;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use structdesc::FieldNames;
 
+/// The pattern of the urls that can be clicked in the terminal
+pub const URL_PATTERN: &str = r#"(?:https?|file|ftp)://[^\s<>"'`]+"#;
+
 #[derive(FieldNames, Debug, Clone, Deserialize, Serialize, Default)]
 #[serde(rename_all = "kebab-case")]
 pub struct TerminalConfig {
@@ -19,11 +29,30 @@ pub struct TerminalConfig {
         desc = "Set the terminal line height, If 0, it uses editor line height"
     )]
     pub line_height: f64,
+    #[field_names(
+        desc = "Load the shell integration into bash, zsh and fish, to mark the prompts and the status of the commands"
+    )]
+    pub shell_integration: bool,
+    #[field_names(
+        desc = "Save the terminals with the workspace, and start them again in the same directories with their last output when it's reopened"
+    )]
+    pub restore_sessions: bool,
 
     #[field_names(skip)]
     pub profiles: HashMap<String, TerminalProfile>,
     #[field_names(skip)]
     pub default_profile: HashMap<String, String>,
+    /// The terminal profile of the REPL of each language, by language name
+    #[field_names(skip)]
+    pub repl_profiles: HashMap<String, String>,
+    /// Named arrangements of terminals, each a list of tabs
+    #[field_names(skip)]
+    pub layouts: HashMap<String, Vec<TerminalLayoutPane>>,
+
+    /// Regexes for the file locations that can be clicked in the terminal,
+    /// with the named groups `path`, `line` and `column`.
+    #[field_names(skip)]
+    pub link_matchers: Vec<String>,
 
     #[serde(skip)]
     #[field_names(skip)]
@@ -41,6 +70,47 @@ pub struct TerminalProfile {
     pub workdir: Option<std::path::PathBuf>,
     #[field_names(desc = "Arguments passed to command")]
     pub environment: Option<HashMap<String, String>>,
+    #[field_names(desc = "Turn the output into diagnostics in the Problems panel")]
+    pub problem_matchers: Option<Vec<ProblemMatcher>>,
+}
+
+/// A pane of a terminal layout, either a split of more panes or a terminal
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum TerminalLayoutPane {
+    Split(TerminalLayoutSplit),
+    Terminal(TerminalLayoutTerminal),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TerminalSplitDirection {
+    /// The panes side by side
+    #[default]
+    Horizontal,
+    /// The panes above one another
+    Vertical,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TerminalLayoutSplit {
+    #[serde(default)]
+    pub direction: TerminalSplitDirection,
+    pub panes: Vec<TerminalLayoutPane>,
+}
+
+/// A terminal of a layout, starting from a profile or the default one, with
+/// what it sets taking precedence.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TerminalLayoutTerminal {
+    pub profile: Option<String>,
+    pub command: Option<String>,
+    pub arguments: Option<Vec<String>>,
+    /// Relative to the workspace, unless it's absolute
+    pub workdir: Option<PathBuf>,
+    pub environment: Option<HashMap<String, String>>,
 }
 
 impl TerminalConfig {
@@ -74,6 +144,89 @@ pub fn get_indexed_colors(&mut self) {
         self.indexed_colors = Arc::new(indexed_colors);
     }
 
+    pub fn link_matchers(&self) -> Vec<Regex> {
+        self.link_matchers
+            .iter()
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(regex) => Some(regex),
+                Err(err) => {
+                    tracing::error!("invalid terminal link matcher: {err}");
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// The profile of the given name to start a terminal with
+    pub fn profile(
+        &self,
+        name: &str,
+    ) -> Option<lapce_rpc::terminal::TerminalProfile> {
+        let Some(profile) = self.profiles.get(name) else {
+            tracing::error!("unknown terminal profile {name}");
+            return None;
+        };
+        Some(lapce_rpc::terminal::TerminalProfile {
+            name: name.to_string(),
+            command: profile.command.clone(),
+            arguments: profile.arguments.clone(),
+            workdir: profile
+                .workdir
+                .as_ref()
+                .and_then(|workdir| url::Url::from_file_path(workdir).ok()),
+            environment: profile.environment.clone(),
+            problem_matchers: profile.problem_matchers.clone(),
+            shell_integration: false,
+        })
+    }
+
+    /// The profile of the REPL that code of the language is sent to
+    pub fn repl_profile(
+        &self,
+        language: LapceLanguage,
+    ) -> Option<lapce_rpc::terminal::TerminalProfile> {
+        let (_, name) = self
+            .repl_profiles
+            .iter()
+            .find(|(lang, _)| LapceLanguage::from_name(lang) == Some(language))?;
+        self.profile(name)
+    }
+
+    /// The profile to start a terminal of a layout with
+    pub fn layout_profile(
+        &self,
+        terminal: &TerminalLayoutTerminal,
+        workspace: Option<&Path>,
+    ) -> lapce_rpc::terminal::TerminalProfile {
+        let mut profile = terminal
+            .profile
+            .as_ref()
+            .and_then(|name| self.profile(name))
+            .or_else(|| self.get_default_profile())
+            .unwrap_or_default();
+
+        if let Some(command) = &terminal.command {
+            profile.command = Some(command.clone());
+            profile.arguments = terminal.arguments.clone();
+        } else if let Some(arguments) = &terminal.arguments {
+            profile.arguments = Some(arguments.clone());
+        }
+        if let Some(workdir) = &terminal.workdir {
+            let workdir = match workspace {
+                Some(workspace) if workdir.is_relative() => workspace.join(workdir),
+                _ => workdir.clone(),
+            };
+            profile.workdir = url::Url::from_file_path(workdir).ok();
+        }
+        if let Some(environment) = &terminal.environment {
+            profile
+                .environment
+                .get_or_insert_with(HashMap::new)
+                .extend(environment.clone());
+        }
+        profile
+    }
+
     pub fn get_default_profile(
         &self,
     ) -> Option<lapce_rpc::terminal::TerminalProfile> {
@@ -96,6 +249,79 @@ pub fn get_default_profile(
             arguments: profile.arguments,
             workdir,
             environment: profile.environment,
+            problem_matchers: profile.problem_matchers,
+            shell_integration: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+
+    use super::URL_PATTERN;
+    use crate::{config::LapceConfig, workspace::LapceWorkspace};
+
+    fn default_link_matchers() -> Vec<Regex> {
+        let workspace = LapceWorkspace::default();
+        let config = LapceConfig::merge_config(&workspace, None, None);
+        let config: LapceConfig = config.try_deserialize().unwrap();
+        config.terminal.link_matchers()
+    }
+
+    /// The path, line and column of the first link matcher that matches
+    fn find_link(text: &str) -> Option<(String, String, Option<String>)> {
+        default_link_matchers().iter().find_map(|reg| {
+            let captures = reg.captures(text)?;
+            Some((
+                captures.name("path")?.as_str().to_string(),
+                captures.name("line")?.as_str().to_string(),
+                captures.name("column").map(|c| c.as_str().to_string()),
+            ))
         })
     }
+
+    #[test]
+    fn test_default_link_matchers() {
+        assert_eq!(
+            find_link(r#"  File "/home/user/app.py", line 12, in <module>"#),
+            Some(("/home/user/app.py".to_string(), "12".to_string(), None))
+        );
+        assert_eq!(
+            find_link("src/app.ts(10,5): error TS2322"),
+            Some((
+                "src/app.ts".to_string(),
+                "10".to_string(),
+                Some("5".to_string())
+            ))
+        );
+        assert_eq!(
+            find_link("  --> src/main.rs:10:5"),
+            Some((
+                "src/main.rs".to_string(),
+                "10".to_string(),
+                Some("5".to_string())
+            ))
+        );
+        assert_eq!(
+            find_link(r"C:\work\main.go:7 undefined"),
+            Some((r"C:\work\main.go".to_string(), "7".to_string(), None))
+        );
+        assert_eq!(find_link("no location here: 10:5"), None);
+    }
+
+    #[test]
+    fn test_url_pattern() {
+        let reg = Regex::new(URL_PATTERN).unwrap();
+        let url = |text: &str| reg.find(text).map(|m| m.as_str().to_string());
+        assert_eq!(
+            url("see https://lapce.dev/docs?a=1 for more"),
+            Some("https://lapce.dev/docs?a=1".to_string())
+        );
+        assert_eq!(
+            url(r#"href="file:///tmp/report.html">"#),
+            Some("file:///tmp/report.html".to_string())
+        );
+        assert_eq!(url("src/main.rs:10:5"), None);
+    }
 }
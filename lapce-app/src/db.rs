@@ -8,13 +8,14 @@
 use crossbeam_channel::{Sender, unbounded};
 use floem::{peniko::kurbo::Vec2, reactive::SignalGet};
 use lapce_core::directory::Directory;
-use lapce_rpc::plugin::VoltID;
+use lapce_rpc::plugin::{VoltID, VoltPermissions};
 use sha2::{Digest, Sha256};
 
 use crate::{
     app::{AppData, AppInfo},
     doc::DocInfo,
     panel::{data::PanelOrder, kind::PanelKind},
+    terminal::panel::TerminalPanelInfo,
     window::{WindowData, WindowInfo},
     window_tab::WindowTabData,
     workspace::{LapceWorkspace, WorkspaceInfo},
@@ -26,7 +27,10 @@
 const WORKSPACE_FILES: &str = "workspace_files";
 const PANEL_ORDERS: &str = "panel_orders";
 const DISABLED_VOLTS: &str = "disabled_volts";
+const VOLT_PERMISSIONS: &str = "volt_permissions";
+const DENIED_VOLT_PERMISSIONS: &str = "denied_volt_permissions";
 const RECENT_WORKSPACES: &str = "recent_workspaces";
+const WORKSPACE_TERMINALS: &str = "workspace_terminals";
 
 pub enum SaveEvent {
     App(AppInfo),
@@ -35,7 +39,10 @@ pub enum SaveEvent {
     Doc(DocInfo),
     DisabledVolts(Vec<VoltID>),
     WorkspaceDisabledVolts(Arc<LapceWorkspace>, Vec<VoltID>),
+    VoltPermissions(Vec<(VoltID, VoltPermissions)>),
+    DeniedVoltPermissions(Vec<(VoltID, VoltPermissions)>),
     PanelOrder(PanelOrder),
+    Terminals(LapceWorkspace, TerminalPanelInfo),
 }
 
 #[derive(Clone)]
@@ -105,11 +112,32 @@ pub fn new() -> Result<Self> {
                                 tracing::error!("{:?}", err);
                             }
                         }
+                        SaveEvent::VoltPermissions(permissions) => {
+                            if let Err(err) =
+                                local_db.insert_volt_permissions(permissions)
+                            {
+                                tracing::error!("{:?}", err);
+                            }
+                        }
+                        SaveEvent::DeniedVoltPermissions(permissions) => {
+                            if let Err(err) =
+                                local_db.insert_denied_volt_permissions(permissions)
+                            {
+                                tracing::error!("{:?}", err);
+                            }
+                        }
                         SaveEvent::PanelOrder(order) => {
                             if let Err(err) = local_db.insert_panel_orders(&order) {
                                 tracing::error!("{:?}", err);
                             }
                         }
+                        SaveEvent::Terminals(workspace, info) => {
+                            if let Err(err) =
+                                local_db.insert_terminals(&workspace, &info)
+                            {
+                                tracing::error!("{:?}", err);
+                            }
+                        }
                     }
                 }
             })
@@ -175,6 +203,67 @@ pub fn get_workspace_disabled_volts(
         Ok(volts)
     }
 
+    /// The permissions the user has granted to each volt
+    pub fn get_volt_permissions(&self) -> Result<Vec<(VoltID, VoltPermissions)>> {
+        let permissions =
+            std::fs::read_to_string(self.folder.join(VOLT_PERMISSIONS))?;
+        let permissions: Vec<(VoltID, VoltPermissions)> =
+            serde_json::from_str(&permissions)?;
+        Ok(permissions)
+    }
+
+    pub fn save_volt_permissions(
+        &self,
+        permissions: Vec<(VoltID, VoltPermissions)>,
+    ) {
+        if let Err(err) = self.save_tx.send(SaveEvent::VoltPermissions(permissions))
+        {
+            tracing::error!("{:?}", err);
+        }
+    }
+
+    pub fn insert_volt_permissions(
+        &self,
+        permissions: Vec<(VoltID, VoltPermissions)>,
+    ) -> Result<()> {
+        let permissions = serde_json::to_string_pretty(&permissions)?;
+        std::fs::write(self.folder.join(VOLT_PERMISSIONS), permissions)?;
+        Ok(())
+    }
+
+    /// The permissions the user refused each volt, which they aren't asked
+    /// about again
+    pub fn get_denied_volt_permissions(
+        &self,
+    ) -> Result<Vec<(VoltID, VoltPermissions)>> {
+        let permissions =
+            std::fs::read_to_string(self.folder.join(DENIED_VOLT_PERMISSIONS))?;
+        let permissions: Vec<(VoltID, VoltPermissions)> =
+            serde_json::from_str(&permissions)?;
+        Ok(permissions)
+    }
+
+    pub fn save_denied_volt_permissions(
+        &self,
+        permissions: Vec<(VoltID, VoltPermissions)>,
+    ) {
+        if let Err(err) = self
+            .save_tx
+            .send(SaveEvent::DeniedVoltPermissions(permissions))
+        {
+            tracing::error!("{:?}", err);
+        }
+    }
+
+    pub fn insert_denied_volt_permissions(
+        &self,
+        permissions: Vec<(VoltID, VoltPermissions)>,
+    ) -> Result<()> {
+        let permissions = serde_json::to_string_pretty(&permissions)?;
+        std::fs::write(self.folder.join(DENIED_VOLT_PERMISSIONS), permissions)?;
+        Ok(())
+    }
+
     pub fn recent_workspaces(&self) -> Result<Vec<LapceWorkspace>> {
         let workspaces =
             std::fs::read_to_string(self.folder.join(RECENT_WORKSPACES))?;
@@ -224,6 +313,10 @@ pub fn save_window_tab(&self, data: Rc<WindowTabData>) -> Result<()> {
         let workspace = (*data.workspace).clone();
         let workspace_info = data.workspace_info();
 
+        if let Some(terminals) = data.terminal_info() {
+            self.save_tx
+                .send(SaveEvent::Terminals(workspace.clone(), terminals))?;
+        }
         self.save_tx
             .send(SaveEvent::Workspace(workspace, workspace_info))?;
         // self.insert_unsaved_buffer(main_split)?;
@@ -360,11 +453,41 @@ pub fn insert_window_tab(&self, data: Rc<WindowTabData>) -> Result<()> {
         let workspace_info = data.workspace_info();
 
         self.insert_workspace(&workspace, &workspace_info)?;
+        if let Some(terminals) = data.terminal_info() {
+            self.insert_terminals(&workspace, &terminals)?;
+        }
         // self.insert_unsaved_buffer(main_split)?;
 
         Ok(())
     }
 
+    pub fn get_terminals(
+        &self,
+        workspace: &LapceWorkspace,
+    ) -> Result<TerminalPanelInfo> {
+        let info = std::fs::read_to_string(
+            self.workspace_folder
+                .join(workspace_folder_name(workspace))
+                .join(WORKSPACE_TERMINALS),
+        )?;
+        let info: TerminalPanelInfo = serde_json::from_str(&info)?;
+        Ok(info)
+    }
+
+    fn insert_terminals(
+        &self,
+        workspace: &LapceWorkspace,
+        info: &TerminalPanelInfo,
+    ) -> Result<()> {
+        let folder = self.workspace_folder.join(workspace_folder_name(workspace));
+        if let Err(err) = std::fs::create_dir_all(&folder) {
+            tracing::error!("{:?}", err);
+        }
+        let info = serde_json::to_string(info)?;
+        std::fs::write(folder.join(WORKSPACE_TERMINALS), info)?;
+        Ok(())
+    }
+
     pub fn get_panel_orders(&self) -> Result<PanelOrder> {
         let panel_orders = std::fs::read_to_string(self.folder.join(PANEL_ORDERS))?;
         let mut panel_orders: PanelOrder = serde_json::from_str(&panel_orders)?;
@@ -1,5 +1,5 @@
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     fmt::Display,
     path::PathBuf,
     rc::Rc,
@@ -8,22 +8,36 @@
 
 use floem::{
     ext_event::create_ext_action,
+    keyboard::Modifiers,
     reactive::{Memo, RwSignal, Scope, SignalGet, SignalUpdate, SignalWith},
     views::VirtualVector,
 };
+use lapce_core::{
+    command::{EditCommand, FocusCommand},
+    mode::Mode,
+    selection::Selection,
+};
 use lapce_rpc::{
     dap_types::{
-        self, DapId, RunDebugConfig, SourceBreakpoint, StackFrame, Stopped,
-        ThreadId, Variable,
+        self, DapId, DebuggerCapabilities, RunDebugConfig, SourceBreakpoint,
+        StackFrame, Stopped, Thread, ThreadId, Variable,
     },
     proxy::ProxyResponse,
     terminal::TermId,
 };
+use lapce_xi_rope::Rope;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    command::InternalCommand,
-    editor::location::{EditorLocation, EditorPosition},
+    command::{CommandExecuted, CommandKind, InternalCommand, LapceCommand},
+    disassembly::DisassemblyData,
+    editor::{
+        EditorData,
+        location::{EditorLocation, EditorPosition},
+    },
+    keypress::{KeyPressFocus, condition::Condition},
+    main_split::Editors,
+    memory::MemoryData,
     window_tab::CommonData,
 };
 
@@ -50,11 +64,193 @@ pub struct RunDebugProcess {
     pub stopped: bool,
     pub created: Instant,
     pub is_prelaunch: bool,
+    /// The compound the process was started as part of
+    pub compound: Option<String>,
+    /// Whether the configs that depend on this one can start
+    pub ready: bool,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Default, Deserialize, Serialize)]
 pub struct RunDebugConfigs {
     pub configs: Vec<RunDebugConfig>,
+    #[serde(default)]
+    pub compounds: Vec<RunDebugCompound>,
+    #[serde(default)]
+    pub inputs: Vec<RunDebugInput>,
+}
+
+impl RunDebugConfigs {
+    pub fn config(&self, name: &str) -> Option<&RunDebugConfig> {
+        self.configs.iter().find(|config| config.name == name)
+    }
+
+    pub fn input(&self, id: &str) -> Option<&RunDebugInput> {
+        self.inputs.iter().find(|input| input.id == id)
+    }
+
+    /// The configs the given ones depend on, directly or through other
+    /// configs, with every config coming after the ones it depends on.
+    pub fn dependencies(&self, configs: &[RunDebugConfig]) -> Vec<RunDebugConfig> {
+        fn visit(
+            configs: &RunDebugConfigs,
+            config: &RunDebugConfig,
+            visited: &mut HashSet<String>,
+            dependencies: &mut Vec<RunDebugConfig>,
+        ) {
+            for name in config.depends_on.iter().flatten() {
+                if !visited.insert(name.clone()) {
+                    continue;
+                }
+                if let Some(dependency) = configs.config(name) {
+                    visit(configs, dependency, visited, dependencies);
+                    dependencies.push(dependency.clone());
+                } else {
+                    tracing::error!(
+                        "{} depends on {name}, which isn't in run.toml",
+                        config.name
+                    );
+                }
+            }
+        }
+
+        let mut visited: HashSet<String> =
+            configs.iter().map(|config| config.name.clone()).collect();
+        let mut dependencies = Vec::new();
+        for config in configs {
+            visit(self, config, &mut visited, &mut dependencies);
+        }
+        dependencies
+    }
+}
+
+/// Several configs that are started and stopped together.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct RunDebugCompound {
+    pub name: String,
+    pub configs: Vec<String>,
+}
+
+/// A value that is asked for when a config using `${input:<id>}` starts.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct RunDebugInput {
+    pub id: String,
+    pub description: Option<String>,
+    pub default: Option<String>,
+    /// The values to pick from, any value can be entered if not set
+    pub options: Option<Vec<String>>,
+}
+
+/// Configs that are started together, e.g. the ones of a compound.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunDebugLaunch {
+    pub mode: RunDebugMode,
+    pub configs: Vec<RunDebugConfig>,
+    pub compound: Option<String>,
+    /// The values of the inputs that have been entered so far
+    pub inputs: HashMap<String, String>,
+}
+
+/// A config waiting for the configs it depends on to be ready.
+#[derive(Clone)]
+pub struct PendingRunDebug {
+    pub mode: RunDebugMode,
+    pub config: RunDebugConfig,
+    pub compound: Option<String>,
+    pub waiting_on: HashSet<String>,
+}
+
+/// The values of the variables that can be used in the program, arguments,
+/// working directory and environment of a run config.
+///
+/// `${workspace}` and `${lapce}` are replaced when the process is created.
+pub struct RunDebugVariables {
+    /// The file open in the active editor
+    pub file: Option<PathBuf>,
+    pub inputs: HashMap<String, String>,
+}
+
+impl RunDebugVariables {
+    /// Replace `${file}`, `${env:<name>}` and `${input:<id>}` in `value`.
+    pub fn substitute(&self, value: &str) -> String {
+        let mut result = String::with_capacity(value.len());
+        let mut rest = value;
+        while let Some(start) = rest.find("${") {
+            let Some(len) = rest[start..].find('}') else {
+                break;
+            };
+            let variable = &rest[start + 2..start + len];
+            result.push_str(&rest[..start]);
+            if variable == "file" {
+                if let Some(file) = self.file.as_ref() {
+                    result.push_str(&file.to_string_lossy());
+                }
+            } else if let Some(name) = variable.strip_prefix("env:") {
+                result.push_str(&std::env::var(name).unwrap_or_default());
+            } else if let Some(value) = variable
+                .strip_prefix("input:")
+                .and_then(|id| self.inputs.get(id))
+            {
+                result.push_str(value);
+            } else {
+                result.push_str(&rest[start..start + len + 1]);
+            }
+            rest = &rest[start + len + 1..];
+        }
+        result.push_str(rest);
+        result
+    }
+
+    pub fn substitute_config(&self, config: &mut RunDebugConfig) {
+        config_strings_mut(config, |value| *value = self.substitute(value));
+    }
+}
+
+/// The ids of the inputs used by the config.
+pub fn config_input_ids(config: &RunDebugConfig) -> Vec<String> {
+    let mut ids = Vec::new();
+    let mut config = config.clone();
+    config_strings_mut(&mut config, |value| {
+        let mut rest = value.as_str();
+        while let Some(start) = rest.find("${input:") {
+            let Some(len) = rest[start..].find('}') else {
+                break;
+            };
+            let id = rest[start + "${input:".len()..start + len].to_string();
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+            rest = &rest[start + len + 1..];
+        }
+    });
+    ids
+}
+
+fn config_strings_mut(config: &mut RunDebugConfig, mut f: impl FnMut(&mut String)) {
+    f(&mut config.program);
+    config.args.iter_mut().flatten().for_each(&mut f);
+    config.cwd.iter_mut().for_each(&mut f);
+    config
+        .env
+        .iter_mut()
+        .flat_map(|env| env.values_mut())
+        .for_each(&mut f);
+    for program in config
+        .prelaunch
+        .iter_mut()
+        .chain(config.post_debug.iter_mut())
+    {
+        f(&mut program.program);
+        program.args.iter_mut().flatten().for_each(&mut f);
+    }
+}
+
+/// The variable whose value is being edited inline in the variables view.
+#[derive(Clone, PartialEq, Eq)]
+pub struct DapVariableEditing {
+    pub dap_id: DapId,
+    pub parent: Vec<usize>,
+    pub name: String,
+    pub evaluate_name: Option<String>,
 }
 
 #[derive(Clone)]
@@ -62,12 +258,72 @@ pub struct RunDebugData {
     pub active_term: RwSignal<Option<TermId>>,
     pub daps: RwSignal<im::HashMap<DapId, DapData>>,
     pub breakpoints: RwSignal<BTreeMap<PathBuf, BTreeMap<usize, LapceBreakpoint>>>,
+    pub watches: RwSignal<im::Vector<String>>,
+    pub variable_editing: RwSignal<Option<DapVariableEditing>>,
+    pub variable_editor: EditorData,
+    pub disassembly: DisassemblyData,
+    pub memory: MemoryData,
+    /// The configs waiting for the ones they depend on
+    pub pending: RwSignal<Vec<PendingRunDebug>>,
+    /// The compound each debug session was started as part of
+    pub dap_compounds: RwSignal<HashMap<DapId, String>>,
+    /// The last value entered for each input, used when restarting
+    pub inputs: RwSignal<HashMap<String, String>>,
+    pub capabilities: DapCapabilities,
+}
+
+impl KeyPressFocus for RunDebugData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        self.variable_editing
+            .with_untracked(|editing| editing.is_some())
+            && condition == Condition::ModalFocus
+    }
+
+    fn run_command(
+        &self,
+        command: &LapceCommand,
+        count: Option<usize>,
+        mods: Modifiers,
+    ) -> CommandExecuted {
+        if self
+            .variable_editing
+            .with_untracked(|editing| editing.is_none())
+        {
+            return CommandExecuted::No;
+        }
+        match command.kind {
+            CommandKind::Focus(FocusCommand::ModalClose) => {
+                self.cancel_variable_edit();
+                CommandExecuted::Yes
+            }
+            CommandKind::Edit(EditCommand::InsertNewLine) => {
+                self.finish_variable_edit();
+                CommandExecuted::Yes
+            }
+            _ => self.variable_editor.run_command(command, count, mods),
+        }
+    }
+
+    fn receive_char(&self, c: &str) {
+        if self
+            .variable_editing
+            .with_untracked(|editing| editing.is_some())
+        {
+            self.variable_editor.receive_char(c);
+        }
+    }
 }
 
 impl RunDebugData {
     pub fn new(
         cx: Scope,
         breakpoints: RwSignal<BTreeMap<PathBuf, BTreeMap<usize, LapceBreakpoint>>>,
+        editors: Editors,
+        common: Rc<CommonData>,
     ) -> Self {
         let active_term: RwSignal<Option<TermId>> = cx.create_rw_signal(None);
         let daps: RwSignal<im::HashMap<DapId, DapData>> =
@@ -77,9 +333,76 @@ pub fn new(
             active_term,
             daps,
             breakpoints,
+            watches: cx.create_rw_signal(im::Vector::new()),
+            variable_editing: cx.create_rw_signal(None),
+            variable_editor: editors.make_local(cx, common.clone()),
+            disassembly: DisassemblyData::new(cx, common.clone()),
+            memory: MemoryData::new(cx, common),
+            pending: cx.create_rw_signal(Vec::new()),
+            dap_compounds: cx.create_rw_signal(HashMap::new()),
+            inputs: cx.create_rw_signal(HashMap::new()),
+            capabilities: DapCapabilities::new(cx),
         }
     }
 
+    /// Start editing the value of a variable inline, with the current value
+    /// selected.
+    pub fn start_variable_edit(
+        &self,
+        dap_id: DapId,
+        parent: Vec<usize>,
+        var: &Variable,
+    ) {
+        let doc = self.variable_editor.doc();
+        doc.reload(Rope::from(var.value.as_str()), true);
+        self.variable_editor.cursor().update(|cursor| {
+            cursor.set_insert(Selection::region(0, var.value.len()))
+        });
+        self.variable_editing.set(Some(DapVariableEditing {
+            dap_id,
+            parent,
+            name: var.name.clone(),
+            evaluate_name: var.evaluate_name.clone(),
+        }));
+    }
+
+    pub fn cancel_variable_edit(&self) {
+        self.variable_editing.set(None);
+    }
+
+    pub fn finish_variable_edit(&self) {
+        let Some(editing) = self.variable_editing.get_untracked() else {
+            return;
+        };
+        self.variable_editing.set(None);
+
+        let value = self.variable_editor.text().to_string();
+        let dap = self
+            .daps
+            .with_untracked(|daps| daps.get(&editing.dap_id).cloned());
+        if let Some(dap) = dap {
+            dap.set_variable(editing, value);
+        }
+    }
+
+    pub fn add_watch(&self, expression: String) {
+        if self
+            .watches
+            .with_untracked(|watches| watches.contains(&expression))
+        {
+            return;
+        }
+        self.watches.update(|watches| watches.push_back(expression));
+        for dap in self.daps.get_untracked().values() {
+            dap.evaluate_watches();
+        }
+    }
+
+    pub fn remove_watch(&self, expression: &str) {
+        self.watches
+            .update(|watches| watches.retain(|w| w != expression));
+    }
+
     pub fn source_breakpoints(&self) -> HashMap<PathBuf, Vec<SourceBreakpoint>> {
         self.breakpoints
             .get_untracked()
@@ -116,6 +439,23 @@ pub struct StackTraceData {
     pub frames_shown: usize,
 }
 
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct DapThread {
+    pub name: String,
+    pub stopped: bool,
+}
+
+/// The values of the locals of the frame the debuggee is stopped at, shown at
+/// the end of the lines that use them.
+#[derive(Clone, PartialEq)]
+pub struct DebugInlineValues {
+    pub dap_id: DapId,
+    pub path: PathBuf,
+    /// The line the frame is stopped at, zero based
+    pub line: usize,
+    pub values: HashMap<String, String>,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct LapceBreakpoint {
     pub id: Option<usize>,
@@ -180,15 +520,60 @@ pub struct DapVariable {
     pub children_expanded_count: usize,
 }
 
+/// The capabilities of each debug adapter. They're reported when the adapter is
+/// initialized, before the `DapData` of its session exists, and are kept when
+/// the session is started again.
+#[derive(Clone, Copy)]
+pub struct DapCapabilities {
+    cx: Scope,
+    capabilities: RwSignal<HashMap<DapId, RwSignal<Option<DebuggerCapabilities>>>>,
+}
+
+impl DapCapabilities {
+    pub fn new(cx: Scope) -> Self {
+        Self {
+            cx,
+            capabilities: cx.create_rw_signal(HashMap::new()),
+        }
+    }
+
+    /// The capabilities of the adapter, which are `None` until it reports them
+    pub fn get(&self, dap_id: DapId) -> RwSignal<Option<DebuggerCapabilities>> {
+        if let Some(capabilities) = self
+            .capabilities
+            .with_untracked(|capabilities| capabilities.get(&dap_id).copied())
+        {
+            return capabilities;
+        }
+        let capabilities = self.cx.create_rw_signal(None);
+        self.capabilities.update(|all| {
+            all.insert(dap_id, capabilities);
+        });
+        capabilities
+    }
+
+    pub fn set(&self, dap_id: DapId, capabilities: DebuggerCapabilities) {
+        self.get(dap_id).set(Some(capabilities));
+    }
+}
+
 #[derive(Clone)]
 pub struct DapData {
     pub term_id: TermId,
     pub dap_id: DapId,
+    /// Whether the selected thread is stopped
     pub stopped: RwSignal<bool>,
+    /// The selected thread, which the stepping commands act on
     pub thread_id: RwSignal<Option<ThreadId>>,
+    pub threads: RwSignal<BTreeMap<ThreadId, DapThread>>,
+    /// The frame the variables view and watches are evaluated in
+    pub frame_id: RwSignal<Option<usize>>,
     pub stack_traces: RwSignal<BTreeMap<ThreadId, StackTraceData>>,
     pub variables_id: RwSignal<usize>,
     pub variables: RwSignal<DapVariable>,
+    pub capabilities: RwSignal<Option<DebuggerCapabilities>>,
+    pub watches: RwSignal<im::Vector<String>>,
+    pub watch_values: RwSignal<im::HashMap<String, Result<String, String>>>,
     pub breakline: Memo<Option<(usize, PathBuf)>>,
     pub common: Rc<CommonData>,
 }
@@ -198,6 +583,8 @@ pub fn new(
         cx: Scope,
         dap_id: DapId,
         term_id: TermId,
+        watches: RwSignal<im::Vector<String>>,
+        capabilities: RwSignal<Option<DebuggerCapabilities>>,
         common: Rc<CommonData>,
     ) -> Self {
         let stopped = cx.create_rw_signal(false);
@@ -232,6 +619,8 @@ pub fn new(
             dap_id,
             stopped,
             thread_id,
+            threads: cx.create_rw_signal(BTreeMap::new()),
+            frame_id: cx.create_rw_signal(None),
             stack_traces,
             variables_id: cx.create_rw_signal(0),
             variables: cx.create_rw_signal(DapVariable {
@@ -242,6 +631,9 @@ pub fn new(
                 children: Vec::new(),
                 children_expanded_count: 0,
             }),
+            capabilities,
+            watches,
+            watch_values: cx.create_rw_signal(im::HashMap::new()),
             breakline,
             common,
         }
@@ -251,20 +643,48 @@ pub fn stopped(
         &self,
         cx: Scope,
         stopped: &Stopped,
+        threads: &[Thread],
         stack_traces: &HashMap<ThreadId, Vec<StackFrame>>,
         variables: &[(dap_types::Scope, Vec<Variable>)],
     ) {
+        let all_threads_stopped = stopped.all_threads_stopped.unwrap_or(false);
+        let stopped_thread_id = stopped.thread_id.unwrap_or_default();
+        if !threads.is_empty() {
+            self.set_threads(threads);
+        }
+        self.threads.update(|threads| {
+            for (thread_id, thread) in threads.iter_mut() {
+                if all_threads_stopped || stack_traces.contains_key(thread_id) {
+                    thread.stopped = true;
+                }
+            }
+            threads
+                .entry(stopped_thread_id)
+                .or_insert_with(|| DapThread {
+                    name: String::new(),
+                    stopped: true,
+                })
+                .stopped = true;
+        });
+
         self.stopped.set(true);
         self.thread_id.update(|thread_id| {
-            *thread_id = Some(stopped.thread_id.unwrap_or_default());
+            *thread_id = Some(stopped_thread_id);
         });
 
         let main_thread_id = self.thread_id.get_untracked();
         let mut current_stack_traces = self.stack_traces.get_untracked();
-        current_stack_traces.retain(|t, _| stack_traces.contains_key(t));
+        // keep the stack traces of the threads that stopped earlier and
+        // haven't continued since
+        let still_stopped = self.threads.get_untracked();
+        current_stack_traces.retain(|t, _| {
+            stack_traces.contains_key(t)
+                || still_stopped.get(t).is_some_and(|thread| thread.stopped)
+        });
         for (thread_id, frames) in stack_traces {
             let is_main_thread = main_thread_id.as_ref() == Some(thread_id);
             if is_main_thread {
+                self.frame_id.set(frames.first().map(|frame| frame.id));
                 if let Some(frame) = frames.first() {
                     if let Some(path) =
                         frame.source.as_ref().and_then(|source| source.path.clone())
@@ -331,6 +751,132 @@ pub fn stopped(
                 .map(|v| v.children_expanded_count + 1)
                 .sum::<usize>();
         });
+        self.evaluate_watches();
+        self.update_inline_values();
+    }
+
+    /// Update the list of threads, keeping the state of the known ones.
+    pub fn set_threads(&self, threads: &[Thread]) {
+        self.threads.update(|current| {
+            let old = std::mem::take(current);
+            *current = threads
+                .iter()
+                .map(|thread| {
+                    (
+                        thread.id,
+                        DapThread {
+                            name: thread.name.clone(),
+                            stopped: old
+                                .get(&thread.id)
+                                .is_some_and(|thread| thread.stopped),
+                        },
+                    )
+                })
+                .collect();
+        });
+    }
+
+    pub fn continued(
+        &self,
+        thread_id: Option<ThreadId>,
+        all_threads_continued: bool,
+    ) {
+        let all = all_threads_continued || thread_id.is_none();
+        let is_continued = move |id: &ThreadId| all || Some(*id) == thread_id;
+        self.threads.update(|threads| {
+            for (id, thread) in threads.iter_mut() {
+                if is_continued(id) {
+                    thread.stopped = false;
+                }
+            }
+        });
+        self.stack_traces.update(|stack_traces| {
+            stack_traces.retain(|id, _| !is_continued(id));
+        });
+        self.update_stopped();
+        if !self.stopped.get_untracked() {
+            self.clear_inline_values();
+        }
+    }
+
+    /// The thread the stepping commands should act on.
+    pub fn selected_thread(&self) -> ThreadId {
+        self.thread_id
+            .get_untracked()
+            .or_else(|| {
+                self.threads
+                    .with_untracked(|threads| threads.keys().next().copied())
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn select_thread(&self, thread_id: ThreadId) {
+        let frame_id = self.stack_traces.with_untracked(|stack_traces| {
+            stack_traces.get(&thread_id).and_then(|stack_trace| {
+                stack_trace
+                    .frames
+                    .with_untracked(|frames| frames.front().map(|f| f.id))
+            })
+        });
+        self.thread_id.set(Some(thread_id));
+        self.update_stopped();
+        if let Some(frame_id) = frame_id {
+            self.select_frame(thread_id, frame_id);
+        }
+    }
+
+    pub fn select_frame(&self, thread_id: ThreadId, frame_id: usize) {
+        if self.thread_id.get_untracked() != Some(thread_id) {
+            self.thread_id.set(Some(thread_id));
+            self.update_stopped();
+        }
+        self.common
+            .internal_command
+            .send(InternalCommand::DapFrameScopes {
+                dap_id: self.dap_id,
+                frame_id,
+            });
+    }
+
+    /// The frame the variables view is showing, which is the top frame of
+    /// the selected thread unless another one was picked.
+    pub fn selected_frame(&self, tracked: bool) -> Option<StackFrame> {
+        let (thread_id, frame_id) = if tracked {
+            (self.thread_id.get()?, self.frame_id.get())
+        } else {
+            (
+                self.thread_id.get_untracked()?,
+                self.frame_id.get_untracked(),
+            )
+        };
+        let frames = if tracked {
+            self.stack_traces
+                .with(|stack_traces| stack_traces.get(&thread_id).cloned())?
+                .frames
+                .get()
+        } else {
+            self.stack_traces
+                .with_untracked(|stack_traces| {
+                    stack_traces.get(&thread_id).cloned()
+                })?
+                .frames
+                .get_untracked()
+        };
+        frame_id
+            .and_then(|frame_id| frames.iter().find(|frame| frame.id == frame_id))
+            .or_else(|| frames.front())
+            .cloned()
+    }
+
+    fn update_stopped(&self) {
+        let stopped = self.thread_id.get_untracked().is_some_and(|thread_id| {
+            self.threads.with_untracked(|threads| {
+                threads.get(&thread_id).is_some_and(|thread| thread.stopped)
+            })
+        });
+        if self.stopped.get_untracked() != stopped {
+            self.stopped.set(stopped);
+        }
     }
 
     pub fn toggle_expand(&self, parent: Vec<usize>, reference: usize) {
@@ -370,17 +916,7 @@ fn read_var_children(&self, parent: &[usize], reference: usize) {
                         let mut new_parent = parent.clone();
                         new_parent.push(reference);
                         var.read = true;
-                        var.children = varialbes
-                            .into_iter()
-                            .map(|v| DapVariable {
-                                item: ScopeOrVar::Var(v),
-                                parent: new_parent.clone(),
-                                expanded: false,
-                                read: false,
-                                children: Vec::new(),
-                                children_expanded_count: 0,
-                            })
-                            .collect();
+                        var.merge_children(varialbes, new_parent);
                         root.update_count_recursive(&parent, reference);
                     }
                 });
@@ -392,6 +928,164 @@ fn read_var_children(&self, parent: &[usize], reference: usize) {
                 send(result);
             });
     }
+
+    /// Show the values of the selected frame's first scope, which is the
+    /// locals for most debug adapters, in the editor.
+    pub fn update_inline_values(&self) {
+        let inline_values = self
+            .selected_frame(false)
+            .filter(|_| self.stopped.get_untracked())
+            .and_then(|frame| {
+                let path = frame.source?.path?;
+                let values = self.variables.with_untracked(|root| {
+                    root.children
+                        .first()
+                        .map(|scope| {
+                            scope
+                                .children
+                                .iter()
+                                .filter_map(|var| match &var.item {
+                                    ScopeOrVar::Var(var) => {
+                                        Some((var.name.clone(), var.value.clone()))
+                                    }
+                                    ScopeOrVar::Scope(_) => None,
+                                })
+                                .collect::<HashMap<_, _>>()
+                        })
+                        .unwrap_or_default()
+                });
+                Some(DebugInlineValues {
+                    dap_id: self.dap_id,
+                    path,
+                    line: frame.line.saturating_sub(1),
+                    values,
+                })
+            });
+        match inline_values {
+            Some(inline_values) => {
+                self.common.debug_inline_values.set(Some(inline_values));
+            }
+            None => self.clear_inline_values(),
+        }
+    }
+
+    pub fn clear_inline_values(&self) {
+        let dap_id = self.dap_id;
+        let is_own = self.common.debug_inline_values.with_untracked(|values| {
+            values
+                .as_ref()
+                .is_some_and(|values| values.dap_id == dap_id)
+        });
+        if is_own {
+            self.common.debug_inline_values.set(None);
+        }
+    }
+
+    /// Re-read the children of every expanded scope and variable, keeping the
+    /// expanded state of the ones that are still there.
+    pub fn refresh_variables(&self) {
+        let mut expanded = Vec::new();
+        self.variables.with_untracked(|root| {
+            root.collect_expanded(&mut expanded);
+        });
+        for (parent, reference) in expanded {
+            self.read_var_children(&parent, reference);
+        }
+    }
+
+    /// Set a new value for a variable, with `setVariable` if the adapter
+    /// supports it, otherwise with `setExpression` on its `evaluateName`.
+    pub fn set_variable(&self, editing: DapVariableEditing, value: String) {
+        let capabilities = self.capabilities.get_untracked();
+        let supports_set_variable = capabilities
+            .as_ref()
+            .and_then(|c| c.supports_set_variable)
+            .unwrap_or(false);
+        let supports_set_expression = capabilities
+            .as_ref()
+            .and_then(|c| c.supports_set_expression)
+            .unwrap_or(false);
+
+        let dap = self.clone();
+        let send =
+            create_ext_action(self.common.scope, move |result| match result {
+                Ok(
+                    ProxyResponse::DapSetVariableResponse { .. }
+                    | ProxyResponse::DapSetExpressionResponse { .. },
+                ) => {
+                    dap.refresh_variables();
+                    dap.evaluate_watches();
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    tracing::error!("failed to set variable: {:?}", err);
+                }
+            });
+
+        match (editing.parent.last(), editing.evaluate_name) {
+            (Some(reference), _) if supports_set_variable => {
+                self.common.proxy.dap_set_variable(
+                    self.dap_id,
+                    *reference,
+                    editing.name,
+                    value,
+                    move |result| {
+                        send(result);
+                    },
+                );
+            }
+            (_, Some(expression)) if supports_set_expression => {
+                self.common.proxy.dap_set_expression(
+                    self.dap_id,
+                    self.frame_id.get_untracked(),
+                    expression,
+                    value,
+                    move |result| {
+                        send(result);
+                    },
+                );
+            }
+            _ => {
+                tracing::error!(
+                    "debug adapter doesn't support setting the value of {}",
+                    editing.name
+                );
+            }
+        }
+    }
+
+    /// Evaluate all the watch expressions in the current frame.
+    pub fn evaluate_watches(&self) {
+        if !self.stopped.get_untracked() {
+            return;
+        }
+        let frame_id = self.frame_id.get_untracked();
+        for expression in self.watches.get_untracked() {
+            let watch_values = self.watch_values;
+            let key = expression.clone();
+            let send = create_ext_action(self.common.scope, move |result| {
+                let value = match result {
+                    Ok(ProxyResponse::DapEvaluateResponse { resp }) => {
+                        Ok(resp.result)
+                    }
+                    Ok(_) => return,
+                    Err(err) => Err(err.message),
+                };
+                watch_values.update(|values| {
+                    values.insert(key, value);
+                });
+            });
+            self.common.proxy.dap_evaluate(
+                self.dap_id,
+                frame_id,
+                expression,
+                Some("watch".to_string()),
+                move |result| {
+                    send(result);
+                },
+            );
+        }
+    }
 }
 
 pub struct DapVariableViewdata {
@@ -482,6 +1176,51 @@ pub fn get_var_mut(
             .find(|c| c.item.reference() == reference)
     }
 
+    /// Replace the children with `vars`, keeping the expanded state and the
+    /// already read children of the ones that didn't change.
+    pub fn merge_children(&mut self, vars: Vec<Variable>, parent: Vec<usize>) {
+        let mut old_children = std::mem::take(&mut self.children);
+        self.children = vars
+            .into_iter()
+            .map(|v| {
+                let old = old_children
+                    .iter()
+                    .position(|c| {
+                        c.item.name() == v.name
+                            && c.item.reference() == v.variables_reference
+                    })
+                    .map(|i| old_children.remove(i));
+                match old {
+                    Some(old) => DapVariable {
+                        item: ScopeOrVar::Var(v),
+                        parent: parent.clone(),
+                        ..old
+                    },
+                    None => DapVariable {
+                        item: ScopeOrVar::Var(v),
+                        parent: parent.clone(),
+                        expanded: false,
+                        read: false,
+                        children: Vec::new(),
+                        children_expanded_count: 0,
+                    },
+                }
+            })
+            .collect();
+    }
+
+    /// Collect the `(parent, reference)` of the expanded nodes that have been
+    /// read, parents before their children.
+    fn collect_expanded(&self, expanded: &mut Vec<(Vec<usize>, usize)>) {
+        for child in self.children.iter() {
+            let reference = child.item.reference();
+            if child.expanded && child.read && reference > 0 {
+                expanded.push((child.parent.clone(), reference));
+                child.collect_expanded(expanded);
+            }
+        }
+    }
+
     pub fn update_count_recursive(&mut self, parent: &[usize], reference: usize) {
         let mut parent = parent.to_vec();
         self.update_count(&parent, reference);
@@ -515,9 +1254,18 @@ pub fn update_count(
 
 #[cfg(test)]
 mod tests {
-    use lapce_rpc::dap_types::{Scope, Variable};
+    use std::{collections::HashMap, path::PathBuf};
+
+    use floem::reactive::{SignalGet, SignalWith};
+    use lapce_rpc::dap_types::{
+        DapId, DebuggerCapabilities, RunDebugConfig, Scope, Variable,
+    };
+    use serde_json::json;
 
-    use super::{DapVariable, ScopeOrVar};
+    use super::{
+        DapCapabilities, DapVariable, RunDebugConfigs, RunDebugVariables,
+        ScopeOrVar, config_input_ids,
+    };
 
     #[test]
     fn test_update_count() {
@@ -637,4 +1385,143 @@ fn test_update_count() {
         assert_eq!(var.children_expanded_count, 4);
         assert_eq!(root.children_expanded_count, 11);
     }
+
+    #[test]
+    fn test_merge_children() {
+        let mut var = DapVariable {
+            item: ScopeOrVar::Scope(Scope {
+                variables_reference: 1,
+                ..Default::default()
+            }),
+            parent: Vec::new(),
+            expanded: true,
+            read: true,
+            children: vec![DapVariable {
+                item: ScopeOrVar::Var(Variable {
+                    name: "a".to_string(),
+                    value: "1".to_string(),
+                    variables_reference: 2,
+                    ..Default::default()
+                }),
+                parent: vec![1],
+                expanded: true,
+                read: true,
+                children: vec![DapVariable::default()],
+                children_expanded_count: 1,
+            }],
+            children_expanded_count: 2,
+        };
+
+        var.merge_children(
+            vec![
+                Variable {
+                    name: "a".to_string(),
+                    value: "2".to_string(),
+                    variables_reference: 2,
+                    ..Default::default()
+                },
+                Variable {
+                    name: "b".to_string(),
+                    value: "3".to_string(),
+                    variables_reference: 0,
+                    ..Default::default()
+                },
+            ],
+            vec![1],
+        );
+
+        assert_eq!(var.children.len(), 2);
+        let a = &var.children[0];
+        assert_eq!(a.item.value(), Some("2"));
+        assert!(a.expanded);
+        assert_eq!(a.children.len(), 1);
+        let b = &var.children[1];
+        assert_eq!(b.item.value(), Some("3"));
+        assert!(!b.expanded);
+        assert_eq!(b.parent, vec![1]);
+
+        let mut expanded = Vec::new();
+        DapVariable {
+            children: vec![var],
+            ..Default::default()
+        }
+        .collect_expanded(&mut expanded);
+        assert_eq!(expanded, vec![(Vec::new(), 1), (vec![1], 2)]);
+    }
+
+    #[test]
+    fn test_substitute_variables() {
+        let variables = RunDebugVariables {
+            file: Some(PathBuf::from("/src/main.rs")),
+            inputs: HashMap::from([("port".to_string(), "8080".to_string())]),
+        };
+        assert_eq!(
+            variables.substitute("${file} --port=${input:port} ${workspace}"),
+            "/src/main.rs --port=8080 ${workspace}"
+        );
+        assert_eq!(
+            variables.substitute("${env:LAPCE_TEST_UNSET_VARIABLE}-${input:host"),
+            "-${input:host"
+        );
+    }
+
+    #[test]
+    fn test_dependencies() {
+        let config = |name: &str, depends_on: &[&str]| RunDebugConfig {
+            ty: None,
+            name: name.to_string(),
+            program: format!("${{input:{name}}}"),
+            args: None,
+            cwd: None,
+            env: None,
+            prelaunch: None,
+            post_debug: None,
+            depends_on: Some(depends_on.iter().map(|s| s.to_string()).collect()),
+            ready_pattern: None,
+            problem_matchers: None,
+            debug_command: None,
+            dap_id: Default::default(),
+            tracing_output: false,
+            config_source: Default::default(),
+        };
+        let configs = RunDebugConfigs {
+            configs: vec![
+                config("db", &[]),
+                config("server", &["db"]),
+                config("client", &["server", "db"]),
+            ],
+            compounds: Vec::new(),
+            inputs: Vec::new(),
+        };
+
+        let dependencies =
+            configs.dependencies(&[configs.config("client").unwrap().clone()]);
+        let names: Vec<&str> =
+            dependencies.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["db", "server"]);
+        assert_eq!(config_input_ids(&dependencies[1]), vec!["server"]);
+    }
+
+    #[test]
+    fn test_capabilities_before_session() {
+        let capabilities = DapCapabilities::new(floem::reactive::Scope::new());
+        let dap_id = DapId::next();
+        let reported: DebuggerCapabilities =
+            serde_json::from_value(json!({ "supportsSetVariable": true })).unwrap();
+
+        // the adapter reports them when it's initialized, before the
+        // session's `DapData` is created with them
+        capabilities.set(dap_id, reported.clone());
+        let session = capabilities.get(dap_id);
+        assert_eq!(session.get_untracked(), Some(reported));
+
+        // the session is started again and keeps them
+        let restarted = capabilities.get(dap_id);
+        assert!(restarted.with_untracked(|c| {
+            c.as_ref()
+                .and_then(|c| c.supports_set_variable)
+                .unwrap_or(false)
+        }));
+        assert_eq!(capabilities.get(DapId::next()).get_untracked(), None);
+    }
 }
@@ -0,0 +1,438 @@
+use std::{rc::Rc, sync::Arc};
+
+use floem::{
+    View,
+    ext_event::create_ext_action,
+    peniko::kurbo::{Point, Rect, Size},
+    reactive::{
+        ReadSignal, RwSignal, Scope, SignalGet, SignalUpdate, SignalWith,
+        create_effect, create_memo,
+    },
+    style::CursorStyle,
+    views::{Decorators, container, label, scroll, stack, text, virtual_stack},
+};
+use lapce_rpc::{
+    dap_types::{
+        DapId, DebuggerCapabilities, DisassembledInstruction, SteppingGranularity,
+    },
+    proxy::ProxyResponse,
+};
+
+use crate::{
+    app::clickable_icon,
+    command::InternalCommand,
+    config::{LapceConfig, color::LapceColor, icon::LapceIcons},
+    editor::location::{EditorLocation, EditorPosition},
+    terminal::panel::TerminalPanelData,
+    window_tab::CommonData,
+};
+
+/// How many instructions are fetched before the instruction pointer.
+const INSTRUCTIONS_BEFORE: usize = 50;
+/// How many instructions are fetched from the instruction pointer onwards.
+const INSTRUCTIONS_AFTER: usize = 150;
+
+/// Parse an address as reported by the debug adapter, which is usually
+/// hexadecimal with a `0x` prefix.
+pub fn parse_address(address: &str) -> Option<u64> {
+    let address = address.trim();
+    if let Some(hex) = address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"))
+    {
+        u64::from_str_radix(hex, 16).ok()
+    } else {
+        address.parse().ok()
+    }
+}
+
+fn same_address(a: &str, b: &str) -> bool {
+    match (parse_address(a), parse_address(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[derive(Clone)]
+pub struct DisassemblyData {
+    /// The debug session the instructions were fetched from
+    pub dap_id: RwSignal<Option<DapId>>,
+    pub instructions: RwSignal<im::Vector<DisassembledInstruction>>,
+    /// The address the selected frame is paused at
+    pub instruction_pointer: RwSignal<Option<String>>,
+    pub error: RwSignal<Option<String>>,
+    pub common: Rc<CommonData>,
+}
+
+impl DisassemblyData {
+    pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
+        Self {
+            dap_id: cx.create_rw_signal(None),
+            instructions: cx.create_rw_signal(im::Vector::new()),
+            instruction_pointer: cx.create_rw_signal(None),
+            error: cx.create_rw_signal(None),
+            common,
+        }
+    }
+
+    fn contains(&self, address: &str) -> bool {
+        self.instructions.with_untracked(|instructions| {
+            instructions
+                .iter()
+                .any(|instruction| same_address(&instruction.address, address))
+        })
+    }
+
+    /// Move to the given instruction pointer, fetching the instructions around
+    /// it if they aren't loaded yet.
+    pub fn follow(&self, dap_id: DapId, instruction_pointer: Option<String>) {
+        self.instruction_pointer.set(instruction_pointer.clone());
+        let Some(instruction_pointer) = instruction_pointer else {
+            return;
+        };
+        if self.dap_id.get_untracked() == Some(dap_id)
+            && self.contains(&instruction_pointer)
+        {
+            return;
+        }
+
+        let dap_id_signal = self.dap_id;
+        let instructions = self.instructions;
+        let error = self.error;
+        let send =
+            create_ext_action(self.common.scope, move |result| match result {
+                Ok(ProxyResponse::DapDisassembleResponse { resp }) => {
+                    dap_id_signal.set(Some(dap_id));
+                    instructions.set(resp.instructions.into());
+                    error.set(None);
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    dap_id_signal.set(Some(dap_id));
+                    instructions.set(im::Vector::new());
+                    error.set(Some(err.message));
+                }
+            });
+        self.common.proxy.dap_disassemble(
+            dap_id,
+            instruction_pointer,
+            -(INSTRUCTIONS_BEFORE as i64),
+            INSTRUCTIONS_BEFORE + INSTRUCTIONS_AFTER,
+            move |result| {
+                send(result);
+            },
+        );
+    }
+
+    /// The index of the instruction the selected frame is paused at
+    pub fn instruction_pointer_index(&self) -> Option<usize> {
+        let instruction_pointer = self.instruction_pointer.get()?;
+        self.instructions.with(|instructions| {
+            instructions.iter().position(|instruction| {
+                same_address(&instruction.address, &instruction_pointer)
+            })
+        })
+    }
+}
+
+/// Whether the adapter can step by instruction rather than by line
+fn can_step_instructions(
+    capabilities: RwSignal<Option<DebuggerCapabilities>>,
+) -> bool {
+    capabilities.with(|c| {
+        c.as_ref()
+            .and_then(|c| c.supports_stepping_granularity)
+            .unwrap_or(false)
+    })
+}
+
+fn instruction_step_icons(
+    terminal: TerminalPanelData,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let disabled = {
+        let terminal = terminal.clone();
+        create_memo(move |_| {
+            terminal.get_active_dap(true).is_none_or(|dap| {
+                !dap.stopped.get() || !can_step_instructions(dap.capabilities)
+            })
+        })
+    };
+    let disabled = move || disabled.get();
+    stack((
+        {
+            let terminal = terminal.clone();
+            clickable_icon(
+                || LapceIcons::DEBUG_STEP_OVER,
+                move || {
+                    if let Some(dap) = terminal.get_active_dap(false) {
+                        terminal.dap_step_over(
+                            dap.term_id,
+                            Some(SteppingGranularity::Instruction),
+                        );
+                    }
+                },
+                || false,
+                disabled,
+                || "Step Over Instruction",
+                config,
+            )
+            .style(|s| s.margin_right(4.0))
+        },
+        {
+            let terminal = terminal.clone();
+            clickable_icon(
+                || LapceIcons::DEBUG_STEP_INTO,
+                move || {
+                    if let Some(dap) = terminal.get_active_dap(false) {
+                        terminal.dap_step_into(
+                            dap.term_id,
+                            Some(SteppingGranularity::Instruction),
+                        );
+                    }
+                },
+                || false,
+                disabled,
+                || "Step Into Instruction",
+                config,
+            )
+            .style(|s| s.margin_right(4.0))
+        },
+        clickable_icon(
+            || LapceIcons::DEBUG_STEP_OUT,
+            move || {
+                if let Some(dap) = terminal.get_active_dap(false) {
+                    terminal.dap_step_out(
+                        dap.term_id,
+                        Some(SteppingGranularity::Instruction),
+                    );
+                }
+            },
+            || false,
+            disabled,
+            || "Step Out",
+            config,
+        ),
+    ))
+}
+
+pub fn disassembly_view(
+    terminal: TerminalPanelData,
+    common: Rc<CommonData>,
+) -> impl View {
+    let config = common.config;
+    let ui_line_height = common.ui_line_height;
+    let internal_command = common.internal_command;
+    let disassembly = terminal.debug.disassembly.clone();
+
+    {
+        let terminal = terminal.clone();
+        let disassembly = disassembly.clone();
+        create_effect(move |_| {
+            let Some(dap) = terminal.get_active_dap(true) else {
+                return;
+            };
+            let instruction_pointer = if dap.stopped.get() {
+                dap.selected_frame(true)
+                    .and_then(|frame| frame.instruction_pointer_reference)
+            } else {
+                None
+            };
+            disassembly.follow(dap.dap_id, instruction_pointer);
+        });
+    }
+
+    let instructions = disassembly.instructions;
+    let error = disassembly.error;
+    let instruction_pointer_index =
+        create_memo(move |_| disassembly.instruction_pointer_index());
+
+    stack((
+        stack((
+            label(move || {
+                error
+                    .get()
+                    .map(|err| format!("Disassembly unavailable: {err}"))
+                    .unwrap_or_else(|| "Disassembly".to_string())
+            })
+            .style(|s| s.flex_grow(1.0).min_width(0.0).text_ellipsis()),
+            instruction_step_icons(terminal, config),
+        ))
+        .style(move |s| {
+            s.items_center()
+                .width_pct(100.0)
+                .padding_horiz(10.0)
+                .height(ui_line_height.get() as f32 * 1.5)
+                .border_bottom(1.0)
+                .border_color(config.get().color(LapceColor::LAPCE_BORDER))
+        }),
+        container(
+            scroll(
+                virtual_stack(
+                    move || instructions.get().into_iter().enumerate(),
+                    |(i, instruction)| (*i, instruction.address.clone()),
+                    move |(i, instruction)| {
+                        let is_current =
+                            move || instruction_pointer_index.get() == Some(i);
+                        let location = instruction
+                            .location
+                            .as_ref()
+                            .and_then(|source| source.path.clone())
+                            .zip(instruction.line);
+                        let has_location = location.is_some();
+                        let source = location
+                            .as_ref()
+                            .map(|(path, line)| {
+                                format!(
+                                    "{}:{line}",
+                                    path.file_name()
+                                        .and_then(|s| s.to_str())
+                                        .unwrap_or("")
+                                )
+                            })
+                            .unwrap_or_default();
+                        let symbol = instruction.symbol.clone().unwrap_or_default();
+                        stack((
+                            label(move || {
+                                if is_current() { "\u{25B6}" } else { "" }
+                                    .to_string()
+                            })
+                            .style(move |s| {
+                                s.width(20.0).color(
+                                    config.get().color(LapceColor::DEBUG_BREAKPOINT),
+                                )
+                            }),
+                            text(instruction.address.clone())
+                                .style(|s| s.width(180.0).text_ellipsis()),
+                            text(
+                                instruction
+                                    .instruction_bytes
+                                    .clone()
+                                    .unwrap_or_default(),
+                            )
+                            .style(move |s| {
+                                s.width(200.0).text_ellipsis().color(
+                                    config.get().color(LapceColor::EDITOR_DIM),
+                                )
+                            }),
+                            text(instruction.instruction.clone()).style(|s| {
+                                s.flex_grow(1.0)
+                                    .flex_basis(0.0)
+                                    .min_width(0.0)
+                                    .text_ellipsis()
+                            }),
+                            text(symbol).style(move |s| {
+                                s.width(200.0)
+                                    .text_ellipsis()
+                                    .margin_left(10.0)
+                                    .color(
+                                        config.get().color(LapceColor::EDITOR_DIM),
+                                    )
+                            }),
+                            text(source).style(move |s| {
+                                s.width(150.0)
+                                    .text_ellipsis()
+                                    .margin_left(10.0)
+                                    .color(
+                                        config.get().color(LapceColor::EDITOR_DIM),
+                                    )
+                            }),
+                        ))
+                        .on_click_stop(move |_| {
+                            if let Some((path, line)) = location.clone() {
+                                internal_command.send(
+                                    InternalCommand::JumpToLocation {
+                                        location: EditorLocation {
+                                            path,
+                                            position: Some(
+                                                EditorPosition::Position(
+                                                    lsp_types::Position {
+                                                        line: line.saturating_sub(1)
+                                                            as u32,
+                                                        character: 0,
+                                                    },
+                                                ),
+                                            ),
+                                            scroll_offset: None,
+                                            ignore_unconfirmed: false,
+                                            same_editor_tab: false,
+                                        },
+                                    },
+                                );
+                            }
+                        })
+                        .style(move |s| {
+                            let config = config.get();
+                            s.items_center()
+                                .padding_horiz(10.0)
+                                .height(ui_line_height.get() as f32)
+                                .width_pct(100.0)
+                                .font_family(config.editor.font_family.clone())
+                                .apply_if(is_current(), |s| {
+                                    s.background(
+                                        config.color(
+                                            LapceColor::EDITOR_DEBUG_BREAK_LINE,
+                                        ),
+                                    )
+                                })
+                                .hover(|s| {
+                                    s.background(
+                                        config.color(
+                                            LapceColor::PANEL_HOVERED_BACKGROUND,
+                                        ),
+                                    )
+                                    .apply_if(has_location, |s| {
+                                        s.cursor(CursorStyle::Pointer)
+                                    })
+                                })
+                        })
+                    },
+                )
+                .item_size_fixed(move || ui_line_height.get())
+                .style(|s| s.flex_col().width_pct(100.0)),
+            )
+            .ensure_visible(move || {
+                let line_height = ui_line_height.get();
+                instruction_pointer_index
+                    .get()
+                    .map(|i| {
+                        Rect::from_origin_size(
+                            Point::new(0.0, i as f64 * line_height),
+                            Size::new(1.0, line_height),
+                        )
+                    })
+                    .unwrap_or(Rect::ZERO)
+            })
+            .style(|s| s.absolute().size_pct(100.0, 100.0)),
+        )
+        .style(|s| s.width_pct(100.0).flex_basis(0.0).flex_grow(1.0)),
+    ))
+    .style(|s| s.absolute().size_pct(100.0, 100.0).flex_col())
+}
+
+#[cfg(test)]
+mod tests {
+    use floem::reactive::Scope;
+    use lapce_rpc::dap_types::DapId;
+    use serde_json::json;
+
+    use super::can_step_instructions;
+    use crate::debug::DapCapabilities;
+
+    #[test]
+    fn test_can_step_instructions() {
+        let capabilities = DapCapabilities::new(Scope::new());
+        let dap_id = DapId::next();
+        assert!(!can_step_instructions(capabilities.get(dap_id)));
+
+        capabilities.set(
+            dap_id,
+            serde_json::from_value(json!({ "supportsSteppingGranularity": true }))
+                .unwrap(),
+        );
+        assert!(can_step_instructions(capabilities.get(dap_id)));
+
+        capabilities.set(dap_id, serde_json::from_value(json!({})).unwrap());
+        assert!(!can_step_instructions(capabilities.get(dap_id)));
+    }
+}
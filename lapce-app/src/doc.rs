@@ -88,11 +88,45 @@
     workspace::LapceWorkspace,
 };
 
+/// Values longer than this are cut off when shown inline while debugging.
+const DEBUG_INLINE_VALUE_MAX_LEN: usize = 50;
+
 #[derive(Clone, Debug)]
 pub struct DiagnosticData {
     pub expanded: RwSignal<bool>,
+    /// The diagnostics of the language servers and of the tasks together
     pub diagnostics: RwSignal<im::Vector<Diagnostic>>,
     pub diagnostics_span: RwSignal<Spans<Diagnostic>>,
+    pub lsp_diagnostics: RwSignal<im::Vector<Diagnostic>>,
+    /// The diagnostics found by the problem matchers, by their source
+    pub task_diagnostics: RwSignal<im::HashMap<String, im::Vector<Diagnostic>>>,
+}
+
+impl DiagnosticData {
+    pub fn new(cx: Scope) -> Self {
+        Self {
+            expanded: cx.create_rw_signal(true),
+            diagnostics: cx.create_rw_signal(im::Vector::new()),
+            diagnostics_span: cx.create_rw_signal(SpansBuilder::new(0).build()),
+            lsp_diagnostics: cx.create_rw_signal(im::Vector::new()),
+            task_diagnostics: cx.create_rw_signal(im::HashMap::new()),
+        }
+    }
+
+    /// Combine the diagnostics of the language servers and the tasks.
+    pub fn merge(&self) {
+        let mut diagnostics = self.lsp_diagnostics.get_untracked();
+        self.task_diagnostics.with_untracked(|tasks| {
+            for task in tasks.values() {
+                diagnostics.append(task.clone());
+            }
+        });
+        let diagnostics = diagnostics
+            .into_iter()
+            .sorted_by_key(|d| d.range.start)
+            .collect();
+        self.diagnostics.set(diagnostics);
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -155,9 +189,9 @@ pub struct DocInfo {
     pub cursor_offset: usize,
 }
 
-/// (Offset -> (Plugin the code actions are from, Code Actions))
+/// (Offset -> Code Actions of all the plugins, each with the plugin it's from)
 pub type CodeActions =
-    im::HashMap<usize, (PluginId, im::Vector<CodeActionOrCommand>)>;
+    im::HashMap<usize, im::Vector<(PluginId, CodeActionOrCommand)>>;
 
 pub type AllCodeLens = im::HashMap<usize, (PluginId, usize, im::Vector<CodeLens>)>;
 
@@ -288,11 +322,7 @@ pub fn new_content(
             ))),
             semantic_styles: cx.create_rw_signal(None),
             inlay_hints: cx.create_rw_signal(None),
-            diagnostics: DiagnosticData {
-                expanded: cx.create_rw_signal(true),
-                diagnostics: cx.create_rw_signal(im::Vector::new()),
-                diagnostics_span: cx.create_rw_signal(SpansBuilder::new(0).build()),
-            },
+            diagnostics: DiagnosticData::new(cx),
             completion_lens: cx.create_rw_signal(None),
             completion_pos: cx.create_rw_signal((0, 0)),
             inline_completion: cx.create_rw_signal(None),
@@ -339,11 +369,7 @@ pub fn new_history(
             ))),
             semantic_styles: cx.create_rw_signal(None),
             inlay_hints: cx.create_rw_signal(None),
-            diagnostics: DiagnosticData {
-                expanded: cx.create_rw_signal(true),
-                diagnostics: cx.create_rw_signal(im::Vector::new()),
-                diagnostics_span: cx.create_rw_signal(SpansBuilder::new(0).build()),
-            },
+            diagnostics: DiagnosticData::new(cx),
             completion_lens: cx.create_rw_signal(None),
             completion_pos: cx.create_rw_signal((0, 0)),
             inline_completion: cx.create_rw_signal(None),
@@ -1314,6 +1340,69 @@ pub fn sticky_headers(&self, line: usize) -> Option<Vec<usize>> {
         lines
     }
 
+    /// The values of the locals used on the given line, if the debuggee is
+    /// stopped in this file and the line is in the function it's stopped in.
+    fn debug_inline_values(&self, line: usize) -> Option<String> {
+        let path = self.content.with_untracked(|c| c.path().cloned())?;
+        let stopped_line =
+            self.common.debug_inline_values.with_untracked(|values| {
+                values
+                    .as_ref()
+                    .filter(|values| values.path == path)
+                    .map(|values| values.line)
+            })?;
+        let in_function = self.buffer.with_untracked(|buffer| {
+            let offset = buffer.first_non_blank_character_on_line(stopped_line);
+            self.syntax
+                .with_untracked(|syntax| syntax.sticky_header_range(offset))
+                .is_none_or(|(start, end)| {
+                    buffer.line_of_offset(start) <= line
+                        && line <= buffer.line_of_offset(end)
+                })
+        });
+        if !in_function {
+            return None;
+        }
+
+        let (start_offset, end_offset) = self.buffer.with_untracked(|buffer| {
+            (buffer.offset_of_line(line), buffer.offset_of_line(line + 1))
+        });
+        let identifiers = self
+            .syntax
+            .with_untracked(|syntax| syntax.identifiers(start_offset, end_offset));
+        if identifiers.is_empty() {
+            return None;
+        }
+        let names = self.buffer.with_untracked(|buffer| {
+            identifiers
+                .into_iter()
+                .map(|(start, end)| buffer.slice_to_cow(start..end).to_string())
+                .unique()
+                .collect::<Vec<_>>()
+        });
+
+        let text = self.common.debug_inline_values.with_untracked(|values| {
+            let values = &values.as_ref()?.values;
+            let text = names
+                .iter()
+                .filter_map(|name| {
+                    let value = values.get(name)?.lines().join(" ");
+                    Some(if value.chars().count() > DEBUG_INLINE_VALUE_MAX_LEN {
+                        let value = value
+                            .chars()
+                            .take(DEBUG_INLINE_VALUE_MAX_LEN)
+                            .collect::<String>();
+                        format!("{name} = {value}\u{2026}")
+                    } else {
+                        format!("{name} = {value}")
+                    })
+                })
+                .join(", ");
+            (!text.is_empty()).then_some(text)
+        })?;
+        Some(format!("    {text}"))
+    }
+
     pub fn head_changes(&self) -> RwSignal<im::Vector<DiffLines>> {
         self.head_changes
     }
@@ -1506,6 +1595,17 @@ pub fn find_enclosing_brackets(&self, offset: usize) -> Option<(usize, usize)> {
                 })
             })
     }
+
+    /// Returns the offsets of the top level block around the given offset, if
+    /// there's syntax support for the language.
+    pub fn find_top_level_block(&self, offset: usize) -> Option<(usize, usize)> {
+        let rev = self.rev();
+        self.syntax.with_untracked(|syntax| {
+            (!syntax.text.is_empty() && syntax.rev == rev)
+                .then(|| syntax.top_level_range(offset))
+                .flatten()
+        })
+    }
 }
 impl Document for Doc {
     fn text(&self) -> Rope {
@@ -1784,6 +1884,24 @@ fn phantom_text(
 
         text.append(&mut diag_text);
 
+        if let Some(values) = config
+            .editor
+            .enable_inline_debug_values
+            .then(|| self.debug_inline_values(line))
+            .flatten()
+        {
+            text.push(PhantomText {
+                kind: PhantomTextKind::Diagnostic,
+                col: end_offset - start_offset,
+                affinity: Some(CursorAffinity::Backward),
+                text: values,
+                fg: Some(config.color(LapceColor::INLAY_HINT_FOREGROUND)),
+                font_size: Some(config.editor.inlay_hint_font_size()),
+                bg: None,
+                under_line: None,
+            });
+        }
+
         let (completion_line, completion_col) = self.completion_pos.get_untracked();
         let completion_text = config
             .editor
@@ -51,7 +51,7 @@
 use lapce_rpc::{buffer::BufferId, plugin::PluginId, proxy::ProxyResponse};
 use lapce_xi_rope::{Rope, RopeDelta, Transformer};
 use lsp_types::{
-    CodeActionResponse, CompletionItem, CompletionTextEdit, GotoDefinitionResponse,
+    CodeActionOrCommand, CompletionItem, CompletionTextEdit, GotoDefinitionResponse,
     HoverContents, InlayHint, InlayHintLabel, InlineCompletionTriggerKind, Location,
     MarkedString, MarkupKind, Range, TextEdit,
 };
@@ -84,6 +84,7 @@
         kind::PanelKind,
     },
     snippet::Snippet,
+    terminal::repl::ReplUnit,
     tracing::*,
     window_tab::{CommonData, Focus, WindowTabData},
 };
@@ -1809,6 +1810,7 @@ fn update_completion(&self, display_if_empty_input: bool) {
             };
             (start_offset, input, char)
         });
+        let language = doc.syntax().with_untracked(|s| s.language);
         if !display_if_empty_input && input.is_empty() && char != "." && char != ":"
         {
             self.cancel_completion();
@@ -1831,6 +1833,7 @@ fn update_completion(&self, display_if_empty_input: bool) {
                         self.id(),
                         &self.common.proxy,
                         path.clone(),
+                        language,
                         "".to_string(),
                         start_pos,
                     );
@@ -1844,6 +1847,7 @@ fn update_completion(&self, display_if_empty_input: bool) {
                         self.id(),
                         &self.common.proxy,
                         path,
+                        language,
                         input,
                         position,
                     );
@@ -1873,6 +1877,7 @@ fn update_completion(&self, display_if_empty_input: bool) {
                 self.id(),
                 &self.common.proxy,
                 path.clone(),
+                language,
                 "".to_string(),
                 start_pos,
             );
@@ -1885,6 +1890,7 @@ fn update_completion(&self, display_if_empty_input: bool) {
                     self.id(),
                     &self.common.proxy,
                     path,
+                    language,
                     input,
                     position,
                 );
@@ -1968,6 +1974,18 @@ fn apply_completion_item(&self, item: &CompletionItem) -> anyhow::Result<()> {
         let end_offset = buffer.next_code_boundary(offset);
         let selection = Selection::region(start_offset, end_offset);
 
+        if let (lsp_types::InsertTextFormat::SNIPPET, Some(insert_text)) =
+            (text_format, &item.insert_text)
+        {
+            self.completion_apply_snippet(
+                insert_text,
+                &selection,
+                additional_edit,
+                start_offset,
+            )?;
+            return Ok(());
+        }
+
         self.do_edit(
             &selection,
             &[
@@ -2240,7 +2258,7 @@ pub fn get_code_actions(&self) {
 
         // insert some empty data, so that we won't make the request again
         doc.code_actions().update(|c| {
-            c.insert(offset, (PluginId(0), im::Vector::new()));
+            c.insert(offset, im::Vector::new());
         });
 
         let (position, rev, diagnostics) = doc.buffer.with_untracked(|buffer| {
@@ -2264,10 +2282,10 @@ pub fn get_code_actions(&self) {
 
         let send = create_ext_action(
             self.scope,
-            move |resp: (PluginId, CodeActionResponse)| {
+            move |actions: Vec<(PluginId, CodeActionOrCommand)>| {
                 if doc.rev() == rev {
                     doc.code_actions().update(|c| {
-                        c.insert(offset, (resp.0, resp.1.into()));
+                        c.insert(offset, actions.into());
                     });
                 }
             },
@@ -2278,12 +2296,9 @@ pub fn get_code_actions(&self) {
             position,
             diagnostics,
             move |result| {
-                if let Ok(ProxyResponse::GetCodeActionsResponse {
-                    plugin_id,
-                    resp,
-                }) = result
+                if let Ok(ProxyResponse::GetCodeActionsResponse { actions }) = result
                 {
-                    send((plugin_id, resp))
+                    send(actions)
                 }
             },
         );
@@ -2295,13 +2310,12 @@ pub fn show_code_actions(&self, mouse_click: bool) {
         let code_actions = doc
             .code_actions()
             .with_untracked(|c| c.get(&offset).cloned());
-        if let Some((plugin_id, code_actions)) = code_actions {
+        if let Some(code_actions) = code_actions {
             if !code_actions.is_empty() {
                 self.common.internal_command.send(
                     InternalCommand::ShowCodeActions {
                         offset,
                         mouse_click,
-                        plugin_id,
                         code_actions,
                     },
                 );
@@ -2597,6 +2611,38 @@ fn rename(&self) {
             });
     }
 
+    /// The code around the cursor to send to a terminal
+    pub fn repl_code(&self, unit: ReplUnit) -> String {
+        let doc = self.doc();
+        let (offset, selection) = self.cursor().with_untracked(|c| {
+            let selection = match &c.mode {
+                lapce_core::cursor::CursorMode::Normal(_) => None,
+                lapce_core::cursor::CursorMode::Visual { start, end, .. } => Some((
+                    *start.min(end),
+                    doc.buffer.with_untracked(|buffer| {
+                        buffer.next_grapheme_offset(*start.max(end), 1, buffer.len())
+                    }),
+                )),
+                lapce_core::cursor::CursorMode::Insert(selection) => selection
+                    .last_inserted()
+                    .filter(|region| !region.is_caret())
+                    .map(|region| (region.min(), region.max())),
+            };
+            (c.offset(), selection)
+        });
+        let range = match unit {
+            ReplUnit::Selection => selection,
+            ReplUnit::Line => None,
+            ReplUnit::Block => doc.find_top_level_block(offset),
+        };
+        doc.buffer.with_untracked(|buffer| match range {
+            Some((start, end)) => buffer.slice_to_cow(start..end).to_string(),
+            None => buffer
+                .line_content(buffer.line_of_offset(offset))
+                .to_string(),
+        })
+    }
+
     #[instrument]
     pub fn word_at_cursor(&self) -> String {
         let doc = self.doc();
@@ -3322,7 +3368,8 @@ fn run_command(
         }
 
         match &command.kind {
-            crate::command::CommandKind::Workbench(_) => CommandExecuted::No,
+            crate::command::CommandKind::Workbench(_)
+            | crate::command::CommandKind::Plugin(_) => CommandExecuted::No,
             crate::command::CommandKind::Edit(cmd) => self.run_edit_command(cmd),
             crate::command::CommandKind::Move(cmd) => {
                 let movement = cmd.to_movement(count);
@@ -1808,7 +1808,7 @@ fn editor_gutter_code_actions(
             cursor.with(|cursor| (cursor.offset(), cursor.affinity));
         let has_code_actions = doc
             .code_actions()
-            .with(|c| c.get(&offset).map(|c| !c.1.is_empty()).unwrap_or(false));
+            .with(|c| c.get(&offset).map(|c| !c.is_empty()).unwrap_or(false));
         if has_code_actions {
             let vline = ed.vline_of_offset(offset, affinity);
             Some(vline)
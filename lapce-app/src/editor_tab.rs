@@ -15,7 +15,7 @@
     },
     views::editor::id::EditorId,
 };
-use lapce_rpc::plugin::VoltID;
+use lapce_rpc::{plugin::VoltID, terminal::TermId};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -27,11 +27,12 @@
         location::EditorLocation,
     },
     id::{
-        DiffEditorId, EditorTabId, KeymapId, SettingsId, SplitId,
-        ThemeColorSettingsId, VoltViewId,
+        DiffEditorId, DisassemblyId, EditorTabId, KeymapId, MemoryViewId,
+        SettingsId, SplitId, TerminalViewId, ThemeColorSettingsId, VoltViewId,
     },
     main_split::{Editors, MainSplitData},
     plugin::PluginData,
+    terminal::data::{TerminalData, TerminalInfo},
     window_tab::WindowTabData,
 };
 
@@ -43,6 +44,11 @@ pub enum EditorTabChildInfo {
     ThemeColorSettings,
     Keymap,
     Volt(VoltID),
+    Disassembly,
+    Memory,
+    /// A terminal, with what it had if the terminals are restored, otherwise
+    /// a new one is started in its place.
+    Terminal(Option<TerminalInfo>),
 }
 
 impl EditorTabChildInfo {
@@ -67,9 +73,41 @@ pub fn to_data(
                 EditorTabChild::ThemeColorSettings(ThemeColorSettingsId::next())
             }
             EditorTabChildInfo::Keymap => EditorTabChild::Keymap(KeymapId::next()),
+            EditorTabChildInfo::Disassembly => {
+                EditorTabChild::Disassembly(DisassemblyId::next())
+            }
+            EditorTabChildInfo::Memory => {
+                EditorTabChild::Memory(MemoryViewId::next())
+            }
             EditorTabChildInfo::Volt(id) => {
                 EditorTabChild::Volt(VoltViewId::next(), id.to_owned())
             }
+            EditorTabChildInfo::Terminal(info) => {
+                let workspace = data.common.workspace.clone();
+                let terminal = match info {
+                    Some(info) => TerminalData::restore(
+                        data.scope,
+                        workspace,
+                        info.clone(),
+                        data.common.clone(),
+                    ),
+                    None => TerminalData::new(
+                        data.scope,
+                        workspace,
+                        data.common
+                            .config
+                            .get_untracked()
+                            .terminal
+                            .get_default_profile(),
+                        data.common.clone(),
+                    ),
+                };
+                let term_id = terminal.term_id;
+                data.terminals.update(|terminals| {
+                    terminals.insert(term_id, terminal);
+                });
+                EditorTabChild::Terminal(TerminalViewId::next(), term_id)
+            }
         }
     }
 }
@@ -131,6 +169,9 @@ pub enum EditorTabChildSource {
     ThemeColorSettings,
     Keymap,
     Volt(VoltID),
+    Disassembly,
+    Memory,
+    Terminal(TermId),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -141,6 +182,9 @@ pub enum EditorTabChild {
     ThemeColorSettings(ThemeColorSettingsId),
     Keymap(KeymapId),
     Volt(VoltViewId, VoltID),
+    Disassembly(DisassemblyId),
+    Memory(MemoryViewId),
+    Terminal(TerminalViewId, TermId),
 }
 
 #[derive(PartialEq)]
@@ -162,6 +206,9 @@ pub fn id(&self) -> u64 {
             EditorTabChild::ThemeColorSettings(id) => id.to_raw(),
             EditorTabChild::Keymap(id) => id.to_raw(),
             EditorTabChild::Volt(id, _) => id.to_raw(),
+            EditorTabChild::Disassembly(id) => id.to_raw(),
+            EditorTabChild::Memory(id) => id.to_raw(),
+            EditorTabChild::Terminal(id, _) => id.to_raw(),
         }
     }
 
@@ -195,6 +242,22 @@ pub fn child_info(&self, data: &WindowTabData) -> EditorTabChildInfo {
             }
             EditorTabChild::Keymap(_) => EditorTabChildInfo::Keymap,
             EditorTabChild::Volt(_, id) => EditorTabChildInfo::Volt(id.to_owned()),
+            EditorTabChild::Disassembly(_) => EditorTabChildInfo::Disassembly,
+            EditorTabChild::Memory(_) => EditorTabChildInfo::Memory,
+            EditorTabChild::Terminal(_, term_id) => {
+                let restore = data
+                    .common
+                    .config
+                    .with_untracked(|config| config.terminal.restore_sessions);
+                let info = data
+                    .main_split
+                    .terminals
+                    .with_untracked(|terminals| {
+                        terminals.get(term_id).and_then(|t| t.info())
+                    })
+                    .filter(|_| restore);
+                EditorTabChildInfo::Terminal(info)
+            }
         }
     }
 
@@ -203,6 +266,7 @@ pub fn view_info(
         editors: Editors,
         diff_editors: RwSignal<im::HashMap<DiffEditorId, DiffEditorData>>,
         plugin: PluginData,
+        terminals: RwSignal<im::HashMap<TermId, TerminalData>>,
         config: ReadSignal<Arc<LapceConfig>>,
     ) -> Memo<EditorTabChildViewInfo> {
         match self.clone() {
@@ -363,6 +427,28 @@ pub fn view_info(
                     is_pristine: true,
                 }
             }),
+            EditorTabChild::Disassembly(_) => create_memo(move |_| {
+                let config = config.get();
+                EditorTabChildViewInfo {
+                    icon: config.ui_svg(LapceIcons::DEBUG),
+                    color: Some(config.color(LapceColor::LAPCE_ICON_ACTIVE)),
+                    name: "Disassembly".to_string(),
+                    path: None,
+                    confirmed: None,
+                    is_pristine: true,
+                }
+            }),
+            EditorTabChild::Memory(_) => create_memo(move |_| {
+                let config = config.get();
+                EditorTabChildViewInfo {
+                    icon: config.ui_svg(LapceIcons::FILE_TYPE_BINARY),
+                    color: Some(config.color(LapceColor::LAPCE_ICON_ACTIVE)),
+                    name: "Memory".to_string(),
+                    path: None,
+                    confirmed: None,
+                    is_pristine: true,
+                }
+            }),
             EditorTabChild::Keymap(_) => create_memo(move |_| {
                 let config = config.get();
                 EditorTabChildViewInfo {
@@ -398,6 +484,21 @@ pub fn view_info(
                     is_pristine: true,
                 }
             }),
+            EditorTabChild::Terminal(_, term_id) => create_memo(move |_| {
+                let config = config.get();
+                let title = terminals
+                    .with(|terminals| terminals.get(&term_id).map(|t| t.title))
+                    .map(|title| title.get())
+                    .unwrap_or_default();
+                EditorTabChildViewInfo {
+                    icon: config.ui_svg(LapceIcons::TERMINAL),
+                    color: Some(config.color(LapceColor::LAPCE_ICON_ACTIVE)),
+                    name: title,
+                    path: None,
+                    confirmed: None,
+                    is_pristine: true,
+                }
+            }),
         }
     }
 }
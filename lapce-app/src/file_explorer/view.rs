@@ -496,6 +496,7 @@ fn file_explorer_view(
 fn open_editors_view(window_tab_data: Rc<WindowTabData>) -> impl View {
     let diff_editors = window_tab_data.main_split.diff_editors;
     let editors = window_tab_data.main_split.editors;
+    let terminals = window_tab_data.main_split.terminals;
     let editor_tabs = window_tab_data.main_split.editor_tabs;
     let config = window_tab_data.common.config;
     let internal_command = window_tab_data.common.internal_command;
@@ -509,7 +510,7 @@ fn open_editors_view(window_tab_data: Rc<WindowTabData>) -> impl View {
         let editor_tab_id =
             editor_tab.with_untracked(|editor_tab| editor_tab.editor_tab_id);
         let child_for_close = child.clone();
-        let info = child.view_info(editors, diff_editors, plugin, config);
+        let info = child.view_info(editors, diff_editors, plugin, terminals, config);
         let hovered = create_rw_signal(false);
 
         stack((
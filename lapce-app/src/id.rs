@@ -7,5 +7,8 @@
 pub type KeymapId = Id;
 pub type ThemeColorSettingsId = Id;
 pub type VoltViewId = Id;
+pub type DisassemblyId = Id;
+pub type MemoryViewId = Id;
 pub type DiffEditorId = Id;
 pub type TerminalTabId = Id;
+pub type TerminalViewId = Id;
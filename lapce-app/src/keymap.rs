@@ -350,7 +350,9 @@ pub fn keymap_view(editors: Editors, common: Rc<CommonData>) -> impl View {
                     |(i, (cmd, keymap)): &(
                         usize,
                         (LapceCommand, Option<KeyMap>),
-                    )| { (*i, cmd.kind.str(), keymap.clone()) },
+                    )| {
+                        (*i, cmd.kind.str().to_string(), keymap.clone())
+                    },
                     view_fn,
                 )
                 .item_size_fixed(ui_line_height)
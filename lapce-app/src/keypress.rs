@@ -6,7 +6,7 @@
 
 use std::{path::PathBuf, rc::Rc, str::FromStr, time::SystemTime};
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use floem::{
     keyboard::{Key, KeyEvent, KeyEventExtModifierSupplement, Modifiers, NamedKey},
     pointer::{MouseButton, PointerButton, PointerInputEvent},
@@ -23,7 +23,10 @@
     loader::KeyMapLoader,
 };
 use crate::{
-    command::{CommandExecuted, CommandKind, LapceCommand, lapce_internal_commands},
+    command::{
+        CommandExecuted, CommandKind, LapceCommand, VoltCommand,
+        lapce_internal_commands,
+    },
     config::LapceConfig,
     keypress::{
         condition::{CheckCondition, Condition},
@@ -153,12 +156,14 @@ pub struct KeyPressData {
     pub command_keymaps: Rc<IndexMap<String, Vec<KeyMap>>>,
     pub commands_with_keymap: Rc<Vec<KeyMap>>,
     pub commands_without_keymap: Rc<Vec<LapceCommand>>,
+    /// Default keybindings of the commands registered by volts
+    plugin_keymaps: Rc<Vec<KeyMap>>,
 }
 
 impl KeyPressData {
     pub fn new(cx: Scope, config: &LapceConfig) -> Self {
-        let (keymaps, command_keymaps) =
-            Self::get_keymaps(config).unwrap_or((IndexMap::new(), IndexMap::new()));
+        let (keymaps, command_keymaps) = Self::get_keymaps(config, &[])
+            .unwrap_or((IndexMap::new(), IndexMap::new()));
         let mut keypress = Self {
             count: cx.create_rw_signal(None),
             pending_keypress: cx.create_rw_signal((Vec::new(), None)),
@@ -167,19 +172,81 @@ pub fn new(cx: Scope, config: &LapceConfig) -> Self {
             commands: Rc::new(lapce_internal_commands()),
             commands_with_keymap: Rc::new(Vec::new()),
             commands_without_keymap: Rc::new(Vec::new()),
+            plugin_keymaps: Rc::new(Vec::new()),
         };
         keypress.load_commands();
         keypress
     }
 
     pub fn update_keymaps(&mut self, config: &LapceConfig) {
-        if let Ok((new_keymaps, new_command_keymaps)) = Self::get_keymaps(config) {
+        if let Ok((new_keymaps, new_command_keymaps)) =
+            Self::get_keymaps(config, &self.plugin_keymaps)
+        {
             self.keymaps = Rc::new(new_keymaps);
             self.command_keymaps = Rc::new(new_command_keymaps);
             self.load_commands();
         }
     }
 
+    /// Add a command registered by a volt, along with its default keybinding,
+    /// replacing the command with the same id the volt registered before
+    pub fn add_plugin_command(
+        &mut self,
+        command: VoltCommand,
+        keymap: Option<KeyMap>,
+        config: &LapceConfig,
+    ) {
+        if let Err(err) = check_plugin_command(&self.commands, &command) {
+            trace!(
+                TraceLevel::ERROR,
+                "{} can't register command {}: {err}", command.volt, command.command
+            );
+            return;
+        }
+
+        let id = command.command.clone();
+        Rc::make_mut(&mut self.plugin_keymaps).retain(|k| k.command != id);
+        if let Some(keymap) = keymap {
+            Rc::make_mut(&mut self.plugin_keymaps).push(keymap);
+        }
+        Rc::make_mut(&mut self.commands).insert(
+            id,
+            LapceCommand {
+                kind: CommandKind::Plugin(command),
+                data: None,
+            },
+        );
+        self.update_keymaps(config);
+    }
+
+    /// Remove the commands registered by volts that match `f`, along with their
+    /// default keybindings
+    pub fn remove_plugin_commands(
+        &mut self,
+        f: impl Fn(&VoltCommand) -> bool,
+        config: &LapceConfig,
+    ) {
+        let removed: Vec<String> = self
+            .commands
+            .iter()
+            .filter_map(|(id, cmd)| match &cmd.kind {
+                CommandKind::Plugin(command) if f(command) => Some(id.clone()),
+                _ => None,
+            })
+            .collect();
+        if removed.is_empty() {
+            return;
+        }
+
+        let commands = Rc::make_mut(&mut self.commands);
+        for id in removed.iter() {
+            commands.shift_remove(id);
+        }
+        Rc::make_mut(&mut self.plugin_keymaps)
+            .retain(|k| !removed.contains(&k.command));
+        self.update_keymaps(config);
+    }
+
     fn load_commands(&mut self) {
         let mut commands_with_keymap = Vec::new();
         let mut commands_without_keymap = Vec::new();
@@ -565,6 +632,7 @@ fn check_one_condition<T: KeyPressFocus + ?Sized>(
     #[allow(clippy::type_complexity)]
     fn get_keymaps(
         config: &LapceConfig,
+        plugin_keymaps: &[KeyMap],
     ) -> Result<(
         IndexMap<Vec<KeyMapPress>, Vec<KeyMap>>,
         IndexMap<String, Vec<KeyMap>>,
@@ -587,6 +655,12 @@ fn get_keymaps(
             trace!(TraceLevel::ERROR, "Failed to load OS defaults: {err}");
         }
 
+        // The defaults of volts go after the ones of Lapce, so that they can be
+        // overridden or unbound in the keymaps file like any other
+        for keymap in plugin_keymaps {
+            loader.add_keymap(keymap.clone());
+        }
+
         if let Some(path) = Self::file() {
             if let Ok(content) = std::fs::read_to_string(&path) {
                 if let Err(err) = loader.load_from_str(&content, is_modal) {
@@ -699,3 +773,72 @@ fn get_modes(toml_keymap: &toml_edit::Table) -> Modes {
         .map(Modes::parse)
         .unwrap_or_else(Modes::empty)
 }
+
+/// Check that the id of a command registered by a volt is in the namespace of
+/// the volt, `<volt name>.<command>`, and isn't a command of Lapce or of
+/// another volt
+fn check_plugin_command(
+    commands: &IndexMap<String, LapceCommand>,
+    command: &VoltCommand,
+) -> Result<()> {
+    let id = &command.command;
+    let namespace = format!("{}.", command.volt.name);
+    // A `-` prefix unbinds the keys of the command in the keymaps
+    if id.starts_with('-') || !id.starts_with(&namespace) || id == &namespace {
+        return Err(anyhow!("the id doesn't start with {namespace}"));
+    }
+    match commands.get(id).map(|existing| &existing.kind) {
+        Some(CommandKind::Plugin(existing)) if existing.volt != command.volt => {
+            Err(anyhow!("it's a command of {}", existing.volt))
+        }
+        Some(CommandKind::Plugin(_)) | None => Ok(()),
+        Some(_) => Err(anyhow!("it's a command of Lapce")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lapce_rpc::plugin::{PluginId, VoltID};
+
+    use super::check_plugin_command;
+    use crate::command::{
+        CommandKind, LapceCommand, VoltCommand, lapce_internal_commands,
+    };
+
+    fn volt_command(author: &str, command: &str) -> VoltCommand {
+        VoltCommand {
+            plugin_id: PluginId(0),
+            volt: VoltID {
+                author: author.to_string(),
+                name: "mydsl".to_string(),
+            },
+            command: command.to_string(),
+            title: command.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_check_plugin_command() {
+        let mut commands = lapce_internal_commands();
+        let registered = volt_command("lapce", "mydsl.restartServer");
+        commands.insert(
+            registered.command.clone(),
+            LapceCommand {
+                kind: CommandKind::Plugin(registered),
+                data: None,
+            },
+        );
+
+        let check = |author: &str, command: &str| {
+            check_plugin_command(&commands, &volt_command(author, command))
+        };
+        assert!(check("lapce", "mydsl.format").is_ok());
+        assert!(check("lapce", "mydsl.restartServer").is_ok());
+        assert!(check("other", "mydsl.restartServer").is_err());
+        assert!(check("lapce", "-mydsl.format").is_err());
+        assert!(check("lapce", "-save").is_err());
+        assert!(check("lapce", "save").is_err());
+        assert!(check("lapce", "mydsl.").is_err());
+        assert!(check("lapce", "other.format").is_err());
+    }
+}
@@ -42,38 +42,43 @@ pub fn load_from_str<'a>(
                 }
             };
 
-            let (command, bind) = match keymap.command.strip_prefix('-') {
-                Some(cmd) => (cmd.to_string(), false),
-                None => (keymap.command.clone(), true),
-            };
+            self.add_keymap(keymap);
+        }
 
-            let current_keymaps = self.command_keymaps.entry(command).or_default();
-            if bind {
-                current_keymaps.push(keymap.clone());
-                for i in 1..keymap.key.len() + 1 {
-                    let key = keymap.key[..i].to_vec();
-                    self.keymaps.entry(key).or_default().push(keymap.clone());
-                }
-            } else {
-                let is_keymap = |k: &KeyMap| -> bool {
-                    k.when == keymap.when
-                        && k.modes == keymap.modes
-                        && k.key == keymap.key
-                };
-                if let Some(index) = current_keymaps.iter().position(is_keymap) {
-                    current_keymaps.remove(index);
-                }
-                for i in 1..keymap.key.len() + 1 {
-                    if let Some(keymaps) = self.keymaps.get_mut(&keymap.key[..i]) {
-                        if let Some(index) = keymaps.iter().position(is_keymap) {
-                            keymaps.remove(index);
-                        }
+        Ok(self)
+    }
+
+    /// Bind the keymap, or unbind it if its command starts with `-`
+    pub fn add_keymap(&mut self, keymap: KeyMap) {
+        let (command, bind) = match keymap.command.strip_prefix('-') {
+            Some(cmd) => (cmd.to_string(), false),
+            None => (keymap.command.clone(), true),
+        };
+
+        let current_keymaps = self.command_keymaps.entry(command).or_default();
+        if bind {
+            current_keymaps.push(keymap.clone());
+            for i in 1..keymap.key.len() + 1 {
+                let key = keymap.key[..i].to_vec();
+                self.keymaps.entry(key).or_default().push(keymap.clone());
+            }
+        } else {
+            let is_keymap = |k: &KeyMap| -> bool {
+                k.when == keymap.when
+                    && k.modes == keymap.modes
+                    && k.key == keymap.key
+            };
+            if let Some(index) = current_keymaps.iter().position(is_keymap) {
+                current_keymaps.remove(index);
+            }
+            for i in 1..keymap.key.len() + 1 {
+                if let Some(keymaps) = self.keymaps.get_mut(&keymap.key[..i]) {
+                    if let Some(index) = keymaps.iter().position(is_keymap) {
+                        keymaps.remove(index);
                     }
                 }
             }
         }
-
-        Ok(self)
     }
 
     #[allow(clippy::type_complexity)]
@@ -250,4 +255,35 @@ fn test_keymap() {
             KeyMapKey::Logical(Key::Character("+".into()))
         );
     }
+
+    #[test]
+    fn test_unbind_added_keymap() {
+        let mut loader = KeyMapLoader::new();
+        loader.add_keymap(KeyMap {
+            key: KeyMapPress::parse("ctrl+k r"),
+            modes: Modes::empty(),
+            when: None,
+            command: "mydsl.restartServer".to_string(),
+        });
+        loader
+            .load_from_str(
+                r#"
+[[keymaps]]
+key = "ctrl+k r"
+command = "-mydsl.restartServer"
+        "#,
+                true,
+            )
+            .unwrap();
+
+        let (keymaps, command_keymaps) = loader.finalize();
+        let keypress = KeyMapPress::parse("ctrl+k r");
+        assert!(keymaps.get(&keypress).unwrap().is_empty());
+        assert!(
+            command_keymaps
+                .get("mydsl.restartServer")
+                .unwrap()
+                .is_empty()
+        );
+    }
 }
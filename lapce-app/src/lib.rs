@@ -8,6 +8,7 @@
 pub mod config;
 pub mod db;
 pub mod debug;
+pub mod disassembly;
 pub mod doc;
 pub mod editor;
 pub mod editor_tab;
@@ -25,6 +26,8 @@
 pub mod lsp;
 pub mod main_split;
 pub mod markdown;
+pub mod memory;
+pub mod output;
 pub mod palette;
 pub mod panel;
 pub mod plugin;
@@ -34,7 +37,9 @@
 pub mod snippet;
 pub mod source_control;
 pub mod status;
+pub mod task;
 pub mod terminal;
+pub mod testing;
 pub mod text_area;
 pub mod text_input;
 pub mod title;
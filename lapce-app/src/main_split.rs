@@ -23,11 +23,13 @@
     core::FileChanged,
     plugin::{PluginId, VoltID},
     proxy::ProxyResponse,
+    terminal::{TermId, TerminalProfile},
 };
-use lapce_xi_rope::{Rope, spans::SpansBuilder};
+use lapce_xi_rope::Rope;
 use lsp_types::{
-    CodeAction, CodeActionOrCommand, DiagnosticSeverity, DocumentChangeOperation,
-    DocumentChanges, OneOf, Position, TextEdit, Url, WorkspaceEdit,
+    CodeAction, CodeActionOrCommand, Diagnostic, DiagnosticSeverity,
+    DocumentChangeOperation, DocumentChanges, OneOf, Position, TextEdit, Url,
+    WorkspaceEdit,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -47,11 +49,12 @@
         EditorTabChild, EditorTabChildSource, EditorTabData, EditorTabInfo,
     },
     id::{
-        DiffEditorId, EditorTabId, KeymapId, SettingsId, SplitId,
-        ThemeColorSettingsId, VoltViewId,
+        DiffEditorId, DisassemblyId, EditorTabId, KeymapId, MemoryViewId,
+        SettingsId, SplitId, TerminalViewId, ThemeColorSettingsId, VoltViewId,
     },
     keypress::{EventRef, KeyPressData, KeyPressHandle},
     panel::implementation_view::ReferencesRoot,
+    terminal::data::TerminalData,
     window_tab::{CommonData, Focus, WindowTabData},
 };
 
@@ -396,6 +399,8 @@ pub struct MainSplitData {
     pub editor_tabs: RwSignal<im::HashMap<EditorTabId, RwSignal<EditorTabData>>>,
     pub editors: Editors,
     pub diff_editors: RwSignal<im::HashMap<DiffEditorId, DiffEditorData>>,
+    /// The terminals opened in the editor tabs
+    pub terminals: RwSignal<im::HashMap<TermId, TerminalData>>,
     pub docs: RwSignal<im::HashMap<PathBuf, Rc<Doc>>>,
     pub scratch_docs: RwSignal<im::HashMap<String, Rc<Doc>>>,
     pub diagnostics: RwSignal<im::HashMap<PathBuf, DiagnosticData>>,
@@ -485,6 +490,7 @@ pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
             editor_tabs,
             editors,
             diff_editors,
+            terminals: cx.create_rw_signal(im::HashMap::new()),
             docs,
             scratch_docs,
             active_editor,
@@ -503,7 +509,7 @@ pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
 
     pub fn key_down<'a>(
         &self,
-        event: impl Into<EventRef<'a>>,
+        event: impl Into<EventRef<'a>> + Copy,
         keypress: &KeyPressData,
     ) -> Option<KeyPressHandle> {
         let active_editor_tab = self.active_editor_tab.get_untracked()?;
@@ -538,6 +544,12 @@ pub fn key_down<'a>(
             EditorTabChild::ThemeColorSettings(_) => None,
             EditorTabChild::Keymap(_) => None,
             EditorTabChild::Volt(_, _) => None,
+            EditorTabChild::Disassembly(_) => None,
+            EditorTabChild::Memory(_) => None,
+            EditorTabChild::Terminal(_, term_id) => {
+                let terminal = self.get_terminal(&term_id)?;
+                Some(terminal.key_down(event, keypress))
+            }
         }
     }
 
@@ -871,6 +883,13 @@ fn get_editor_tab_child(
                         EditorTabChild::ThemeColorSettings(_) => true,
                         EditorTabChild::Keymap(_) => true,
                         EditorTabChild::Volt(_, _) => true,
+                        EditorTabChild::Disassembly(_) => true,
+                        EditorTabChild::Memory(_) => true,
+                        // a running terminal is never replaced
+                        EditorTabChild::Terminal(_, term_id) => matches!(
+                            &source,
+                            EditorTabChildSource::Terminal(id) if id == term_id
+                        ),
                     };
 
                     if can_be_selected {
@@ -1010,6 +1029,50 @@ fn get_editor_tab_child(
                         })
                     }
                 }
+                EditorTabChildSource::Disassembly => {
+                    if let Some(index) =
+                        active_editor_tab.with_untracked(|editor_tab| {
+                            editor_tab.children.iter().position(|(_, _, child)| {
+                                matches!(child, EditorTabChild::Disassembly(_))
+                            })
+                        })
+                    {
+                        Some(index)
+                    } else if ignore_unconfirmed {
+                        None
+                    } else {
+                        active_editor_tab.with_untracked(|editor_tab| {
+                            editor_tab
+                                .get_unconfirmed_editor_tab_child(
+                                    editors,
+                                    &diff_editors,
+                                )
+                                .map(|(i, _)| i)
+                        })
+                    }
+                }
+                EditorTabChildSource::Memory => {
+                    if let Some(index) =
+                        active_editor_tab.with_untracked(|editor_tab| {
+                            editor_tab.children.iter().position(|(_, _, child)| {
+                                matches!(child, EditorTabChild::Memory(_))
+                            })
+                        })
+                    {
+                        Some(index)
+                    } else if ignore_unconfirmed {
+                        None
+                    } else {
+                        active_editor_tab.with_untracked(|editor_tab| {
+                            editor_tab
+                                .get_unconfirmed_editor_tab_child(
+                                    editors,
+                                    &diff_editors,
+                                )
+                                .map(|(i, _)| i)
+                        })
+                    }
+                }
                 EditorTabChildSource::Volt(id) => {
                     if let Some(index) =
                         active_editor_tab.with_untracked(|editor_tab| {
@@ -1036,6 +1099,15 @@ fn get_editor_tab_child(
                         })
                     }
                 }
+                EditorTabChildSource::Terminal(term_id) => active_editor_tab
+                    .with_untracked(|editor_tab| {
+                        editor_tab.children.iter().position(|(_, _, child)| {
+                            matches!(
+                                child,
+                                EditorTabChild::Terminal(_, id) if id == term_id
+                            )
+                        })
+                    }),
             }
         };
 
@@ -1090,9 +1162,18 @@ fn get_editor_tab_child(
                 EditorTabChildSource::Keymap => {
                     EditorTabChild::Keymap(KeymapId::next())
                 }
+                EditorTabChildSource::Disassembly => {
+                    EditorTabChild::Disassembly(DisassemblyId::next())
+                }
+                EditorTabChildSource::Memory => {
+                    EditorTabChild::Memory(MemoryViewId::next())
+                }
                 EditorTabChildSource::Volt(id) => {
                     EditorTabChild::Volt(VoltViewId::next(), id.to_owned())
                 }
+                EditorTabChildSource::Terminal(term_id) => {
+                    EditorTabChild::Terminal(TerminalViewId::next(), *term_id)
+                }
                 EditorTabChildSource::DiffEditor { left, right } => {
                     let diff_editor_id = DiffEditorId::next();
                     let diff_editor = DiffEditorData::new(
@@ -1129,6 +1210,9 @@ fn get_editor_tab_child(
                         EditorTabChild::ThemeColorSettings(_) => {}
                         EditorTabChild::Keymap(_) => {}
                         EditorTabChild::Volt(_, _) => {}
+                        EditorTabChild::Disassembly(_) => {}
+                        EditorTabChild::Memory(_) => {}
+                        EditorTabChild::Terminal(_, _) => {}
                     }
                     (editor_tab_id, current_child.clone())
                 });
@@ -1169,6 +1253,10 @@ fn get_editor_tab_child(
                 (EditorTabChild::Settings(_), EditorTabChildSource::Settings) => {
                     true
                 }
+                (
+                    EditorTabChild::Terminal(_, term_id),
+                    EditorTabChildSource::Terminal(id),
+                ) => term_id == id,
                 _ => false,
             };
             if is_same {
@@ -1195,6 +1283,11 @@ fn get_editor_tab_child(
                 EditorTabChild::ThemeColorSettings(_) => {}
                 EditorTabChild::Keymap(_) => {}
                 EditorTabChild::Volt(_, _) => {}
+                EditorTabChild::Disassembly(_) => {}
+                EditorTabChild::Memory(_) => {}
+                EditorTabChild::Terminal(_, term_id) => {
+                    self.remove_terminal(term_id);
+                }
             }
 
             // Now loading the new child
@@ -1258,6 +1351,18 @@ fn get_editor_tab_child(
                                 .position(|(_, _, child)| {
                                     matches!(child, EditorTabChild::Keymap(_))
                                 }),
+                            EditorTabChildSource::Disassembly => editor_tab
+                                .children
+                                .iter()
+                                .position(|(_, _, child)| {
+                                    matches!(child, EditorTabChild::Disassembly(_))
+                                }),
+                            EditorTabChildSource::Memory => editor_tab
+                                .children
+                                .iter()
+                                .position(|(_, _, child)| {
+                                    matches!(child, EditorTabChild::Memory(_))
+                                }),
                             EditorTabChildSource::Volt(id) => editor_tab
                                 .children
                                 .iter()
@@ -1270,6 +1375,16 @@ fn get_editor_tab_child(
                                         false
                                     }
                                 }),
+                            EditorTabChildSource::Terminal(term_id) => editor_tab
+                                .children
+                                .iter()
+                                .position(|(_, _, child)| {
+                                    matches!(
+                                        child,
+                                        EditorTabChild::Terminal(_, id)
+                                            if id == term_id
+                                    )
+                                }),
                             EditorTabChildSource::NewFileEditor => None,
                         })
                     {
@@ -1571,9 +1686,21 @@ fn split_editor_tab(
                 EditorTabChild::ThemeColorSettings(ThemeColorSettingsId::next())
             }
             EditorTabChild::Keymap(_) => EditorTabChild::Keymap(KeymapId::next()),
+            EditorTabChild::Disassembly(_) => {
+                EditorTabChild::Disassembly(DisassemblyId::next())
+            }
+            EditorTabChild::Memory(_) => {
+                EditorTabChild::Memory(MemoryViewId::next())
+            }
             EditorTabChild::Volt(_, id) => {
                 EditorTabChild::Volt(VoltViewId::next(), id.to_owned())
             }
+            // a new shell is started with the same profile
+            EditorTabChild::Terminal(_, term_id) => {
+                let profile = self.get_terminal(term_id)?.profile;
+                let terminal = self.new_terminal_data(profile);
+                EditorTabChild::Terminal(TerminalViewId::next(), terminal.term_id)
+            }
         };
 
         let editor_tab = {
@@ -1916,6 +2043,9 @@ fn editor_tab_child_close_warning(
             EditorTabChild::ThemeColorSettings(_) => None,
             EditorTabChild::Keymap(_) => None,
             EditorTabChild::Volt(_, _) => None,
+            EditorTabChild::Disassembly(_) => None,
+            EditorTabChild::Memory(_) => None,
+            EditorTabChild::Terminal(_, _) => None,
         }
     }
 
@@ -2154,6 +2284,11 @@ pub fn editor_tab_child_close(
             EditorTabChild::ThemeColorSettings(_) => {}
             EditorTabChild::Keymap(_) => {}
             EditorTabChild::Volt(_, _) => {}
+            EditorTabChild::Disassembly(_) => {}
+            EditorTabChild::Memory(_) => {}
+            EditorTabChild::Terminal(_, term_id) => {
+                self.remove_terminal(&term_id);
+            }
         }
 
         if editor_tab_children_len == 0 {
@@ -2341,13 +2476,7 @@ pub fn get_diagnostic_data(&self, path: &Path) -> DiagnosticData {
         if let Some(d) = self.diagnostics.with_untracked(|d| d.get(path).cloned()) {
             d
         } else {
-            let diagnostic_data = DiagnosticData {
-                expanded: self.scope.create_rw_signal(true),
-                diagnostics: self.scope.create_rw_signal(im::Vector::new()),
-                diagnostics_span: self
-                    .scope
-                    .create_rw_signal(SpansBuilder::new(0).build()),
-            };
+            let diagnostic_data = DiagnosticData::new(self.scope);
             self.diagnostics.update(|d| {
                 d.insert(path.to_path_buf(), diagnostic_data.clone());
             });
@@ -2355,6 +2484,52 @@ pub fn get_diagnostic_data(&self, path: &Path) -> DiagnosticData {
         }
     }
 
+    /// Add the problems found in the output of a task to the diagnostics.
+    pub fn add_task_diagnostics(
+        &self,
+        source: &str,
+        problems: &[(PathBuf, Diagnostic)],
+    ) {
+        let problems = problems
+            .iter()
+            .into_group_map_by(|(path, _)| path)
+            .into_iter();
+        for (path, problems) in problems {
+            let diagnostic_data = self.get_diagnostic_data(path);
+            diagnostic_data.task_diagnostics.update(|tasks| {
+                tasks.entry(source.to_string()).or_default().extend(
+                    problems
+                        .into_iter()
+                        .map(|(_, diagnostic)| diagnostic.clone()),
+                );
+            });
+            diagnostic_data.merge();
+            self.init_diagnostics(path);
+        }
+    }
+
+    /// Remove the diagnostics of a task, before it runs again.
+    pub fn clear_task_diagnostics(&self, source: &str) {
+        let diagnostics = self.diagnostics.get_untracked();
+        for (path, diagnostic_data) in diagnostics.iter() {
+            let removed = diagnostic_data
+                .task_diagnostics
+                .try_update(|tasks| tasks.remove(source))
+                .flatten();
+            if removed.is_some() {
+                diagnostic_data.merge();
+                self.init_diagnostics(path);
+            }
+        }
+    }
+
+    /// Update the diagnostics of the open document of the path.
+    pub fn init_diagnostics(&self, path: &Path) {
+        if let Some(doc) = self.docs.with_untracked(|docs| docs.get(path).cloned()) {
+            doc.init_diagnostics();
+        }
+    }
+
     pub fn open_file_changed(&self, path: &Path, content: &FileChanged) {
         tracing::debug!("open_file_changed {:?}", path);
         match content {
@@ -2413,6 +2588,120 @@ pub fn open_volt_view(&self, id: VoltID) {
         self.get_editor_tab_child(EditorTabChildSource::Volt(id), false, false);
     }
 
+    /// Start a terminal in the active editor tab
+    pub fn new_terminal(&self, profile: Option<TerminalProfile>) {
+        let terminal = self.new_terminal_data(profile);
+        self.get_editor_tab_child(
+            EditorTabChildSource::Terminal(terminal.term_id),
+            false,
+            false,
+        );
+    }
+
+    fn new_terminal_data(&self, profile: Option<TerminalProfile>) -> TerminalData {
+        let terminal = TerminalData::new(
+            self.scope,
+            self.common.workspace.clone(),
+            profile,
+            self.common.clone(),
+        );
+        self.terminals.update(|terminals| {
+            terminals.insert(terminal.term_id, terminal.clone());
+        });
+        terminal
+    }
+
+    /// Show a terminal from the panel in the active editor tab, where it
+    /// keeps running.
+    pub fn open_terminal(&self, terminal: &TerminalData) {
+        let terminal = terminal.moved_to(self.scope);
+        let term_id = terminal.term_id;
+        self.terminals.update(|terminals| {
+            terminals.insert(term_id, terminal);
+        });
+        self.get_editor_tab_child(
+            EditorTabChildSource::Terminal(term_id),
+            false,
+            false,
+        );
+    }
+
+    /// Take the terminal out of the editor tabs without stopping it, with its
+    /// signals in the scope, to be shown elsewhere.
+    pub fn take_terminal(&self, term_id: TermId, cx: Scope) -> Option<TerminalData> {
+        let terminal = self
+            .terminals
+            .try_update(|terminals| terminals.remove(&term_id))
+            .flatten()?;
+        let moved = terminal.moved_to(cx);
+        self.close_terminal(term_id);
+        terminal.scope.dispose();
+        Some(moved)
+    }
+
+    pub fn get_terminal(&self, term_id: &TermId) -> Option<TerminalData> {
+        self.terminals
+            .with_untracked(|terminals| terminals.get(term_id).cloned())
+    }
+
+    /// The terminal of the active editor tab, if it's showing one
+    pub fn active_terminal(&self, tracked: bool) -> Option<TermId> {
+        let child = |editor_tab: &EditorTabData| {
+            editor_tab
+                .children
+                .get(editor_tab.active)
+                .map(|(_, _, child)| child.clone())
+        };
+        let child = if tracked {
+            let editor_tab_id = self.active_editor_tab.get()?;
+            self.editor_tabs
+                .with(|editor_tabs| editor_tabs.get(&editor_tab_id).copied())?
+                .with(child)
+        } else {
+            let editor_tab_id = self.active_editor_tab.get_untracked()?;
+            self.editor_tabs
+                .with_untracked(|editor_tabs| {
+                    editor_tabs.get(&editor_tab_id).copied()
+                })?
+                .with_untracked(child)
+        };
+        match child? {
+            EditorTabChild::Terminal(_, term_id) => Some(term_id),
+            _ => None,
+        }
+    }
+
+    /// Close the editor tab of the terminal, e.g. once its shell exited
+    pub fn close_terminal(&self, term_id: TermId) {
+        let found = self.editor_tabs.with_untracked(|editor_tabs| {
+            editor_tabs.iter().find_map(|(editor_tab_id, editor_tab)| {
+                editor_tab.with_untracked(|editor_tab| {
+                    editor_tab.children.iter().find_map(
+                        |(_, _, child)| match child {
+                            EditorTabChild::Terminal(_, id) if *id == term_id => {
+                                Some((*editor_tab_id, child.clone()))
+                            }
+                            _ => None,
+                        },
+                    )
+                })
+            })
+        });
+        if let Some((editor_tab_id, child)) = found {
+            self.editor_tab_child_close(editor_tab_id, child, true);
+        }
+    }
+
+    fn remove_terminal(&self, term_id: &TermId) {
+        let terminal = self
+            .terminals
+            .try_update(|terminals| terminals.remove(term_id))
+            .flatten();
+        if let Some(terminal) = terminal {
+            terminal.stop();
+        }
+    }
+
     pub fn open_settings(&self) {
         self.get_editor_tab_child(EditorTabChildSource::Settings, false, false);
     }
@@ -2429,6 +2718,14 @@ pub fn open_keymap(&self) {
         self.get_editor_tab_child(EditorTabChildSource::Keymap, false, false);
     }
 
+    pub fn open_disassembly(&self) {
+        self.get_editor_tab_child(EditorTabChildSource::Disassembly, false, false);
+    }
+
+    pub fn open_memory(&self) {
+        self.get_editor_tab_child(EditorTabChildSource::Memory, false, false);
+    }
+
     pub fn new_file(&self) -> EditorTabChild {
         self.get_editor_tab_child(EditorTabChildSource::NewFileEditor, false, false)
     }
@@ -2747,6 +3044,9 @@ fn editor_tab_child_set_parent(
             EditorTabChild::ThemeColorSettings(_) => {}
             EditorTabChild::Keymap(_) => {}
             EditorTabChild::Volt(_, _) => {}
+            EditorTabChild::Disassembly(_) => {}
+            EditorTabChild::Memory(_) => {}
+            EditorTabChild::Terminal(_, _) => {}
         }
         Some(())
     }
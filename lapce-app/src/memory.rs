@@ -0,0 +1,311 @@
+use std::{rc::Rc, sync::Arc};
+
+use base64::{Engine as _, engine::general_purpose};
+use floem::{
+    View,
+    ext_event::create_ext_action,
+    reactive::{
+        ReadSignal, RwSignal, Scope, SignalGet, SignalUpdate, SignalWith,
+        create_effect,
+    },
+    views::{Decorators, container, label, scroll, stack, text, virtual_stack},
+};
+use lapce_rpc::{
+    dap_types::{DapId, ReadMemoryResponse},
+    proxy::ProxyResponse,
+};
+
+use crate::{
+    app::clickable_icon,
+    config::{LapceConfig, color::LapceColor, icon::LapceIcons},
+    disassembly::parse_address,
+    terminal::panel::TerminalPanelData,
+    window_tab::CommonData,
+};
+
+/// How many bytes are shown on one row of the inspector.
+const BYTES_PER_ROW: usize = 16;
+/// How many bytes are read at a time.
+const PAGE_SIZE: usize = 1024;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryTarget {
+    pub dap_id: DapId,
+    /// The name of the variable the memory was opened from
+    pub name: String,
+    pub memory_reference: String,
+}
+
+#[derive(Clone)]
+pub struct MemoryData {
+    pub target: RwSignal<Option<MemoryTarget>>,
+    /// The offset from the memory reference the page starts at
+    pub offset: RwSignal<i64>,
+    /// The address of the first byte of the page
+    pub address: RwSignal<Option<u64>>,
+    /// The bytes of the page, `None` for bytes that couldn't be read
+    pub bytes: RwSignal<im::Vector<Option<u8>>>,
+    pub error: RwSignal<Option<String>>,
+    pub common: Rc<CommonData>,
+}
+
+impl MemoryData {
+    pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
+        Self {
+            target: cx.create_rw_signal(None),
+            offset: cx.create_rw_signal(0),
+            address: cx.create_rw_signal(None),
+            bytes: cx.create_rw_signal(im::Vector::new()),
+            error: cx.create_rw_signal(None),
+            common,
+        }
+    }
+
+    /// Inspect the memory behind the given reference, starting at its first
+    /// byte.
+    pub fn open(&self, target: MemoryTarget) {
+        self.offset.set(0);
+        self.target.set(Some(target));
+        self.read();
+    }
+
+    pub fn previous_page(&self) {
+        self.offset.update(|offset| *offset -= PAGE_SIZE as i64);
+        self.read();
+    }
+
+    pub fn next_page(&self) {
+        self.offset.update(|offset| *offset += PAGE_SIZE as i64);
+        self.read();
+    }
+
+    pub fn read(&self) {
+        let Some(target) = self.target.get_untracked() else {
+            return;
+        };
+        let address = self.address;
+        let bytes = self.bytes;
+        let error = self.error;
+        let send =
+            create_ext_action(self.common.scope, move |result| match result {
+                Ok(ProxyResponse::DapReadMemoryResponse { resp }) => {
+                    address.set(parse_address(&resp.address));
+                    bytes.set(decode_memory(&resp));
+                    error.set(None);
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    bytes.set(im::Vector::new());
+                    error.set(Some(err.message));
+                }
+            });
+        self.common.proxy.dap_read_memory(
+            target.dap_id,
+            target.memory_reference,
+            self.offset.get_untracked(),
+            PAGE_SIZE,
+            move |result| {
+                send(result);
+            },
+        );
+    }
+}
+
+fn decode_memory(resp: &ReadMemoryResponse) -> im::Vector<Option<u8>> {
+    let mut bytes: im::Vector<Option<u8>> = resp
+        .data
+        .as_ref()
+        .and_then(|data| general_purpose::STANDARD.decode(data).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .map(Some)
+        .collect();
+    bytes.extend(std::iter::repeat_n(
+        None,
+        resp.unreadable_bytes.unwrap_or(0),
+    ));
+    bytes
+}
+
+/// Format one row of bytes as hex and as printable ascii, with `??` and `.`
+/// for the bytes that couldn't be read.
+fn format_row(bytes: &[Option<u8>]) -> (String, String) {
+    let hex = bytes
+        .iter()
+        .map(|byte| match byte {
+            Some(byte) => format!("{byte:02x}"),
+            None => "??".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let ascii = bytes
+        .iter()
+        .map(|byte| match byte {
+            Some(byte) if byte.is_ascii_graphic() || *byte == b' ' => *byte as char,
+            _ => '.',
+        })
+        .collect();
+    (hex, ascii)
+}
+
+fn memory_header(
+    memory: MemoryData,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let target = memory.target;
+    let error = memory.error;
+    let previous = memory.clone();
+    stack((
+        label(move || {
+            let title = target
+                .with(|target| {
+                    target.as_ref().map(|target| {
+                        format!("{} ({})", target.name, target.memory_reference)
+                    })
+                })
+                .unwrap_or_else(|| "No memory reference selected".to_string());
+            match error.get() {
+                Some(err) => format!("{title}: {err}"),
+                None => title,
+            }
+        })
+        .style(|s| s.flex_grow(1.0).min_width(0.0).text_ellipsis()),
+        clickable_icon(
+            || LapceIcons::TAB_PREVIOUS,
+            move || previous.previous_page(),
+            || false,
+            move || target.with(|target| target.is_none()),
+            || "Previous Page",
+            config,
+        )
+        .style(|s| s.margin_right(4.0)),
+        clickable_icon(
+            || LapceIcons::TAB_NEXT,
+            move || memory.next_page(),
+            || false,
+            move || target.with(|target| target.is_none()),
+            || "Next Page",
+            config,
+        ),
+    ))
+}
+
+pub fn memory_view(
+    terminal: TerminalPanelData,
+    common: Rc<CommonData>,
+) -> impl View {
+    let config = common.config;
+    let ui_line_height = common.ui_line_height;
+    let memory = terminal.debug.memory.clone();
+
+    {
+        // read the memory again whenever the debuggee stops, as it could
+        // have been changed by the program
+        let memory = memory.clone();
+        let daps = terminal.debug.daps;
+        create_effect(move |was_stopped: Option<bool>| {
+            let dap_id = memory
+                .target
+                .with(|target| target.as_ref().map(|t| t.dap_id));
+            let stopped = dap_id
+                .and_then(|dap_id| {
+                    daps.with(|daps| daps.get(&dap_id).map(|dap| dap.stopped.get()))
+                })
+                .unwrap_or(false);
+            if stopped && was_stopped == Some(false) {
+                memory.read();
+            }
+            stopped
+        });
+    }
+
+    let address = memory.address;
+    let bytes = memory.bytes;
+
+    stack((
+        memory_header(memory, config).style(move |s| {
+            s.items_center()
+                .width_pct(100.0)
+                .padding_horiz(10.0)
+                .height(ui_line_height.get() as f32 * 1.5)
+                .border_bottom(1.0)
+                .border_color(config.get().color(LapceColor::LAPCE_BORDER))
+        }),
+        container(
+            scroll(
+                virtual_stack(
+                    move || {
+                        let bytes = bytes.get();
+                        let rows = bytes.len().div_ceil(BYTES_PER_ROW);
+                        (0..rows)
+                            .map(|row| {
+                                let start = row * BYTES_PER_ROW;
+                                (
+                                    row,
+                                    bytes
+                                        .iter()
+                                        .skip(start)
+                                        .take(BYTES_PER_ROW)
+                                        .copied()
+                                        .collect::<Vec<_>>(),
+                                )
+                            })
+                            .collect::<im::Vector<_>>()
+                    },
+                    |(row, bytes)| (*row, bytes.clone()),
+                    move |(row, row_bytes)| {
+                        let (hex, ascii) = format_row(&row_bytes);
+                        stack((
+                            label(move || {
+                                address
+                                    .get()
+                                    .map(|address| {
+                                        format!(
+                                            "{:016x}",
+                                            address.wrapping_add(
+                                                (row * BYTES_PER_ROW) as u64
+                                            )
+                                        )
+                                    })
+                                    .unwrap_or_else(|| {
+                                        format!("+{:x}", row * BYTES_PER_ROW)
+                                    })
+                            })
+                            .style(move |s| {
+                                s.width(180.0).color(
+                                    config.get().color(LapceColor::EDITOR_DIM),
+                                )
+                            }),
+                            text(hex).style(|s| s.width(480.0)),
+                            text(ascii),
+                        ))
+                        .style(move |s| {
+                            let config = config.get();
+                            s.items_center()
+                                .padding_horiz(10.0)
+                                .height(ui_line_height.get() as f32)
+                                .font_family(config.editor.font_family.clone())
+                        })
+                    },
+                )
+                .item_size_fixed(move || ui_line_height.get())
+                .style(|s| s.flex_col().min_width_pct(100.0)),
+            )
+            .style(|s| s.absolute().size_pct(100.0, 100.0)),
+        )
+        .style(|s| s.width_pct(100.0).flex_basis(0.0).flex_grow(1.0)),
+    ))
+    .style(|s| s.absolute().size_pct(100.0, 100.0).flex_col())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_row;
+
+    #[test]
+    fn test_format_row() {
+        let (hex, ascii) = format_row(&[Some(b'L'), Some(0), Some(0xff), None]);
+        assert_eq!(hex, "4c 00 ff ??");
+        assert_eq!(ascii, "L...");
+    }
+}
@@ -0,0 +1,259 @@
+use std::rc::Rc;
+
+use floem::{
+    action::save_as,
+    file::{FileDialogOptions, FileInfo},
+    keyboard::Modifiers,
+    reactive::{RwSignal, Scope, SignalGet, SignalUpdate, SignalWith},
+};
+use indexmap::IndexMap;
+use lapce_core::{command::EditCommand, mode::Mode};
+use lapce_rpc::plugin::{PluginOutputChannel, PluginOutputKind, PluginOutputLine};
+use lsp_types::MessageType;
+
+use crate::{
+    command::{CommandExecuted, CommandKind},
+    editor::EditorData,
+    keypress::{KeyPressFocus, condition::Condition},
+    main_split::Editors,
+    window_tab::CommonData,
+};
+
+/// The lines kept for each channel, the oldest ones are dropped after that
+const MAX_LINES: usize = 10_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutputLine {
+    pub id: u64,
+    pub time: String,
+    pub kind: PluginOutputKind,
+    pub text: String,
+    /// How long the request took in milliseconds, for the trace of a response
+    pub duration: Option<u64>,
+}
+
+impl OutputLine {
+    pub fn prefix(&self) -> &'static str {
+        match self.kind {
+            PluginOutputKind::Stderr => "[stderr]",
+            PluginOutputKind::Log(MessageType::ERROR) => "[error]",
+            PluginOutputKind::Log(MessageType::WARNING) => "[warn]",
+            PluginOutputKind::Log(MessageType::INFO) => "[info]",
+            PluginOutputKind::Log(_) => "[log]",
+            PluginOutputKind::Sent => "→",
+            PluginOutputKind::Received => "←",
+        }
+    }
+}
+
+impl std::fmt::Display for OutputLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.time, self.prefix(), self.text)?;
+        if let Some(duration) = self.duration {
+            write!(f, " ({duration} ms)")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct OutputChannelData {
+    pub lines: RwSignal<im::Vector<OutputLine>>,
+    /// Whether the JSON-RPC messages of the channel are traced
+    pub tracing: RwSignal<bool>,
+}
+
+#[derive(Clone)]
+pub struct OutputData {
+    pub channels: RwSignal<IndexMap<PluginOutputChannel, OutputChannelData>>,
+    pub active: RwSignal<Option<PluginOutputChannel>>,
+    pub filter_editor: EditorData,
+    next_id: RwSignal<u64>,
+    pub common: Rc<CommonData>,
+}
+
+impl KeyPressFocus for OutputData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        matches!(condition, Condition::PanelFocus)
+    }
+
+    fn run_command(
+        &self,
+        command: &crate::command::LapceCommand,
+        count: Option<usize>,
+        mods: Modifiers,
+    ) -> CommandExecuted {
+        match &command.kind {
+            CommandKind::Edit(EditCommand::InsertNewLine) => CommandExecuted::Yes,
+            CommandKind::Edit(_)
+            | CommandKind::Move(_)
+            | CommandKind::MultiSelection(_) => {
+                self.filter_editor.run_command(command, count, mods)
+            }
+            _ => CommandExecuted::No,
+        }
+    }
+
+    fn receive_char(&self, c: &str) {
+        self.filter_editor.receive_char(c);
+    }
+}
+
+impl OutputData {
+    pub fn new(cx: Scope, editors: Editors, common: Rc<CommonData>) -> Self {
+        Self {
+            channels: cx.create_rw_signal(IndexMap::new()),
+            active: cx.create_rw_signal(None),
+            filter_editor: editors.make_local(cx, common.clone()),
+            next_id: cx.create_rw_signal(0),
+            common,
+        }
+    }
+
+    pub fn add_line(&self, channel: PluginOutputChannel, line: PluginOutputLine) {
+        let data = self.channels.with_untracked(|c| c.get(&channel).cloned());
+        let data = match data {
+            Some(data) => data,
+            None => {
+                let data = OutputChannelData {
+                    lines: self.common.scope.create_rw_signal(im::Vector::new()),
+                    tracing: self.common.scope.create_rw_signal(false),
+                };
+                self.channels.update(|channels| {
+                    channels.insert(channel.clone(), data.clone());
+                });
+                if self.active.with_untracked(|a| a.is_none()) {
+                    self.active.set(Some(channel));
+                }
+                data
+            }
+        };
+
+        let time = chrono::Local::now().format("%H:%M:%S%.3f").to_string();
+        let mut next_id = self.next_id.get_untracked();
+        data.lines.update(|lines| {
+            // Each line of the text is its own line in the panel, the first
+            // one has the duration
+            let mut duration = line.duration;
+            for text in line.text.lines() {
+                lines.push_back(OutputLine {
+                    id: next_id,
+                    time: time.clone(),
+                    kind: line.kind.clone(),
+                    text: text.to_string(),
+                    duration: duration.take(),
+                });
+                next_id += 1;
+            }
+            while lines.len() > MAX_LINES {
+                lines.pop_front();
+            }
+        });
+        self.next_id.set(next_id);
+    }
+
+    pub fn active_channel(&self) -> Option<OutputChannelData> {
+        let active = self.active.get()?;
+        self.channels
+            .with(|channels| channels.get(&active).cloned())
+    }
+
+    /// The lines of the active channel that match the filter
+    pub fn filtered_lines(&self) -> im::Vector<OutputLine> {
+        let Some(channel) = self.active_channel() else {
+            return im::Vector::new();
+        };
+        let filter = self
+            .filter_editor
+            .doc_signal()
+            .get()
+            .buffer
+            .with(|buffer| buffer.to_string().to_lowercase());
+        channel.lines.with(|lines| {
+            if filter.is_empty() {
+                return lines.clone();
+            }
+            lines
+                .iter()
+                .filter(|line| line.text.to_lowercase().contains(&filter))
+                .cloned()
+                .collect()
+        })
+    }
+
+    pub fn toggle_trace(&self) {
+        let Some(channel) = self.active.get_untracked() else {
+            return;
+        };
+        let Some(data) = self.channels.with_untracked(|c| c.get(&channel).cloned())
+        else {
+            return;
+        };
+        let enabled = !data.tracing.get_untracked();
+        data.tracing.set(enabled);
+        self.common.proxy.set_plugin_trace(channel, enabled);
+    }
+
+    pub fn clear(&self) {
+        if let Some(channel) = self.active_channel() {
+            channel.lines.update(|lines| lines.clear());
+        }
+    }
+
+    /// Save the lines of the active channel that match the filter to a file
+    pub fn export(&self) {
+        let lines = self.filtered_lines();
+        let title = match self.active.get_untracked() {
+            Some(channel) => format!("Export {} Output", channel.name),
+            None => return,
+        };
+        save_as(
+            FileDialogOptions::new().title(&title),
+            move |file: Option<FileInfo>| {
+                let Some(path) = file.and_then(|mut file| file.path.pop()) else {
+                    return;
+                };
+                let mut text = String::new();
+                for line in lines.iter() {
+                    text.push_str(&line.to_string());
+                    text.push('\n');
+                }
+                if let Err(err) = std::fs::write(&path, text) {
+                    tracing::error!("{:?}", err);
+                }
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lapce_rpc::plugin::PluginOutputKind;
+    use lsp_types::MessageType;
+
+    use super::OutputLine;
+
+    #[test]
+    fn test_output_line_display() {
+        let mut line = OutputLine {
+            id: 0,
+            time: "12:00:00.000".to_string(),
+            kind: PluginOutputKind::Received,
+            text: "response textDocument/hover (2) null".to_string(),
+            duration: Some(15),
+        };
+        assert_eq!(
+            line.to_string(),
+            "12:00:00.000 ← response textDocument/hover (2) null (15 ms)"
+        );
+
+        line.kind = PluginOutputKind::Log(MessageType::WARNING);
+        line.text = "no Cargo.toml found".to_string();
+        line.duration = None;
+        assert_eq!(line.to_string(), "12:00:00.000 [warn] no Cargo.toml found");
+    }
+}
@@ -27,7 +27,7 @@
     line_ending::LineEnding, mode::Mode, movement::Movement, selection::Selection,
     syntax::Syntax,
 };
-use lapce_rpc::proxy::ProxyResponse;
+use lapce_rpc::{dap_types::RunDebugConfig, proxy::ProxyResponse};
 use lapce_xi_rope::Rope;
 use lsp_types::{DocumentSymbol, DocumentSymbolResponse};
 use nucleo::Utf32Str;
@@ -43,7 +43,7 @@
         CommandExecuted, CommandKind, InternalCommand, LapceCommand, WindowCommand,
     },
     db::LapceDb,
-    debug::{RunDebugConfigs, RunDebugMode},
+    debug::{RunDebugConfigs, RunDebugInput, RunDebugLaunch, RunDebugMode},
     editor::{
         EditorData,
         location::{EditorLocation, EditorPosition},
@@ -52,6 +52,7 @@
     lsp::path_from_url,
     main_split::MainSplitData,
     source_control::SourceControlData,
+    task::TaskData,
     window_tab::{CommonData, Focus},
     workspace::{LapceWorkspace, LapceWorkspaceType, SshHost},
 };
@@ -105,8 +106,11 @@ pub struct PaletteData {
     pub main_split: MainSplitData,
     pub references: RwSignal<Vec<EditorLocation>>,
     pub source_control: SourceControlData,
+    pub tasks: TaskData,
     pub common: Rc<CommonData>,
     left_diff_path: RwSignal<Option<PathBuf>>,
+    /// The launch waiting for the value of the input being asked for
+    run_debug_input: RwSignal<Option<(RunDebugLaunch, RunDebugInput)>>,
 }
 
 impl std::fmt::Debug for PaletteData {
@@ -122,6 +126,7 @@ pub fn new(
         main_split: MainSplitData,
         keypress: ReadSignal<KeyPressData>,
         source_control: SourceControlData,
+        tasks: TaskData,
         common: Rc<CommonData>,
     ) -> Self {
         let status = cx.create_rw_signal(PaletteStatus::Inactive);
@@ -214,6 +219,7 @@ pub fn new(
 
         let clicked_index = cx.create_rw_signal(Option::<usize>::None);
         let left_diff_path = cx.create_rw_signal(None);
+        let run_debug_input = cx.create_rw_signal(None);
 
         let palette = Self {
             run_id_counter,
@@ -236,8 +242,10 @@ pub fn new(
             executed_run_configs: Rc::new(RefCell::new(HashMap::new())),
             references,
             source_control,
+            tasks,
             common,
             left_diff_path,
+            run_debug_input,
         };
 
         {
@@ -295,10 +303,15 @@ pub fn new(
                         .unwrap();
                     if let Some(new_kind) = new_kind {
                         palette.run_inner(new_kind);
-                    } else if input
-                        .with_untracked(|i| i.kind == PaletteKind::WorkspaceSymbol)
-                    {
-                        palette.run_inner(PaletteKind::WorkspaceSymbol);
+                    } else if let Some(kind) = input.with_untracked(|i| {
+                        matches!(
+                            i.kind,
+                            PaletteKind::WorkspaceSymbol
+                                | PaletteKind::RunDebugInput
+                        )
+                        .then_some(i.kind)
+                    }) {
+                        palette.run_inner(kind);
                     }
                 }
                 Some(new_input)
@@ -341,20 +354,39 @@ pub fn run(&self, kind: PaletteKind) {
             .update(|cursor| cursor.set_insert(Selection::caret(symbol.len())));
     }
 
+    /// Ask for the value of an input used by the configs of the launch,
+    /// launching them once it's entered.
+    pub fn run_debug_input(&self, launch: RunDebugLaunch, input: RunDebugInput) {
+        self.run_debug_input.set(Some((launch, input)));
+        self.run(PaletteKind::RunDebugInput);
+    }
+
     /// Get the placeholder text to use in the palette input field.
-    pub fn placeholder_text(&self) -> &'static str {
+    pub fn placeholder_text(&self) -> String {
         match self.kind.get() {
             PaletteKind::SshHost => {
                 "Type [user@]host or select a previously connected workspace below"
+                    .to_string()
             }
             PaletteKind::DiffFiles => {
                 if self.left_diff_path.with(Option::is_some) {
-                    "Select right file"
+                    "Select right file".to_string()
                 } else {
-                    "Seleft left file"
+                    "Seleft left file".to_string()
                 }
             }
-            _ => "",
+            PaletteKind::RunDebugInput => self
+                .run_debug_input
+                .with(|input| {
+                    input.as_ref().map(|(_, input)| {
+                        input
+                            .description
+                            .clone()
+                            .unwrap_or_else(|| format!("Enter {}", input.id))
+                    })
+                })
+                .unwrap_or_default(),
+            _ => String::new(),
         }
     }
 
@@ -400,6 +432,9 @@ fn run_inner(&self, kind: PaletteKind) {
             PaletteKind::RunAndDebug => {
                 self.get_run_configs();
             }
+            PaletteKind::RunDebugInput => {
+                self.get_run_debug_input_values();
+            }
             PaletteKind::ColorTheme => {
                 self.get_color_themes();
             }
@@ -881,7 +916,8 @@ fn get_wsl_hosts(&self) {
 
     fn set_run_configs(&self, content: String) {
         let configs: Option<RunDebugConfigs> = toml::from_str(&content).ok();
-        if configs.is_none() {
+        let tasks = self.tasks.tasks.get_untracked();
+        if configs.is_none() && tasks.is_empty() {
             if let Some(path) = self.workspace.path.as_ref() {
                 let path = path.join(".lapce").join("run.toml");
                 self.common
@@ -935,11 +971,92 @@ fn set_run_configs(&self, content: String) {
             }
         }
 
+        if let Some(configs) = configs.as_ref() {
+            for compound in &configs.compounds {
+                let compound_configs: Vec<RunDebugConfig> = compound
+                    .configs
+                    .iter()
+                    .filter_map(|name| configs.config(name).cloned())
+                    .collect();
+                let can_debug =
+                    compound_configs.iter().any(|config| config.ty.is_some());
+                for mode in [RunDebugMode::Run, RunDebugMode::Debug] {
+                    if mode == RunDebugMode::Debug && !can_debug {
+                        continue;
+                    }
+                    items.push((
+                        executed_run_configs.get(&(mode, compound.name.clone())),
+                        PaletteItem {
+                            content: PaletteItemContent::RunAndDebugCompound {
+                                launch: RunDebugLaunch {
+                                    mode,
+                                    configs: compound_configs.clone(),
+                                    compound: Some(compound.name.clone()),
+                                    inputs: HashMap::new(),
+                                },
+                            },
+                            filter_text: format!(
+                                "{mode} {} {}",
+                                compound.name,
+                                compound.configs.join(" ")
+                            ),
+                            score: 0,
+                            indices: vec![],
+                        },
+                    ));
+                }
+            }
+        }
+
+        for task in tasks.iter() {
+            let config = TaskData::config(task);
+            items.push((
+                executed_run_configs.get(&(RunDebugMode::Run, config.name.clone())),
+                PaletteItem {
+                    filter_text: format!("Run Task {}", config.name),
+                    content: PaletteItemContent::RunAndDebug {
+                        mode: RunDebugMode::Run,
+                        config,
+                    },
+                    score: 0,
+                    indices: vec![],
+                },
+            ));
+        }
+
         items.sort_by_key(|(executed, _item)| std::cmp::Reverse(executed.copied()));
         self.items
             .set(items.into_iter().map(|(_, item)| item).collect());
     }
 
+    /// The values to pick from for the input being asked for, or what has
+    /// been typed so far if any value can be entered.
+    fn get_run_debug_input_values(&self) {
+        let Some((_, input)) = self.run_debug_input.get_untracked() else {
+            return;
+        };
+        let values = if let Some(options) = input.options {
+            options
+        } else {
+            let typed = self.input.with_untracked(|input| input.input.clone());
+            if !typed.is_empty() {
+                vec![typed]
+            } else {
+                input.default.into_iter().collect()
+            }
+        };
+        let items = values
+            .into_iter()
+            .map(|value| PaletteItem {
+                filter_text: value.clone(),
+                content: PaletteItemContent::RunDebugInput { value },
+                score: 0,
+                indices: Vec::new(),
+            })
+            .collect();
+        self.items.set(items);
+    }
+
     fn get_run_configs(&self) {
         if let Some(workspace) = self.common.workspace.path.as_deref() {
             let run_toml = workspace.join(".lapce").join("run.toml");
@@ -1075,7 +1192,8 @@ fn get_scm_references(&self) {
     }
 
     fn get_terminal_profiles(&self) {
-        let profiles = self.common.config.get().terminal.profiles.clone();
+        let config = self.common.config.get();
+        let profiles = config.terminal.profiles.clone();
         let mut items: im::Vector<PaletteItem> = im::Vector::new();
 
         for (name, profile) in profiles.into_iter() {
@@ -1099,6 +1217,8 @@ fn get_terminal_profiles(&self) {
                         arguments: profile.arguments,
                         workdir: uri,
                         environment: profile.environment,
+                        problem_matchers: profile.problem_matchers,
+                        shell_integration: false,
                     },
                 },
                 filter_text: name.to_owned(),
@@ -1107,6 +1227,15 @@ fn get_terminal_profiles(&self) {
             });
         }
 
+        for name in config.terminal.layouts.keys().sorted() {
+            items.push_back(PaletteItem {
+                content: PaletteItemContent::TerminalLayout { name: name.clone() },
+                filter_text: format!("Layout: {name}"),
+                score: 0,
+                indices: Vec::new(),
+            });
+        }
+
         self.items.set(items);
     }
 
@@ -1269,6 +1398,23 @@ fn select(&self) {
                         },
                     );
                 }
+                PaletteItemContent::RunAndDebugCompound { launch } => {
+                    self.common.internal_command.send(
+                        InternalCommand::RunAndDebugLaunch {
+                            launch: launch.clone(),
+                        },
+                    );
+                }
+                PaletteItemContent::RunDebugInput { value } => {
+                    if let Some((mut launch, input)) =
+                        self.run_debug_input.try_update(Option::take).flatten()
+                    {
+                        launch.inputs.insert(input.id, value.clone());
+                        self.common
+                            .internal_command
+                            .send(InternalCommand::RunAndDebugLaunch { launch });
+                    }
+                }
                 PaletteItemContent::ColorTheme { name } => self
                     .common
                     .internal_command
@@ -1329,6 +1475,11 @@ fn select(&self) {
                     .send(InternalCommand::NewTerminal {
                         profile: Some(profile.to_owned()),
                     }),
+                PaletteItemContent::TerminalLayout { name } => {
+                    self.common.internal_command.send(
+                        InternalCommand::OpenTerminalLayout { name: name.clone() },
+                    )
+                }
             }
         } else if self.kind.get_untracked() == PaletteKind::SshHost {
             let input = self.input.with_untracked(|input| input.input.clone());
@@ -1389,6 +1540,8 @@ fn preview(&self) {
                 PaletteItemContent::Command { .. } => {}
                 PaletteItemContent::Workspace { .. } => {}
                 PaletteItemContent::RunAndDebug { .. } => {}
+                PaletteItemContent::RunAndDebugCompound { .. } => {}
+                PaletteItemContent::RunDebugInput { .. } => {}
                 PaletteItemContent::SshHost { .. } => {}
                 #[cfg(windows)]
                 PaletteItemContent::WslHost { .. } => {}
@@ -1461,6 +1614,7 @@ fn preview(&self) {
                     }),
                 PaletteItemContent::SCMReference { .. } => {}
                 PaletteItemContent::TerminalProfile { .. } => {}
+                PaletteItemContent::TerminalLayout { .. } => {}
             }
         }
     }
@@ -1690,6 +1844,7 @@ fn run_command(
                 self.input_editor.run_command(command, count, mods);
             }
             CommandKind::MotionMode(_) => {}
+            CommandKind::Plugin(_) => {}
         }
         CommandExecuted::Yes
     }
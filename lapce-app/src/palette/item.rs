@@ -6,7 +6,7 @@
 
 use crate::{
     command::{LapceCommand, LapceWorkbenchCommand},
-    debug::RunDebugMode,
+    debug::{RunDebugLaunch, RunDebugMode},
     editor::location::EditorLocation,
     workspace::{LapceWorkspace, SshHost},
 };
@@ -65,6 +65,12 @@ pub enum PaletteItemContent {
         mode: RunDebugMode,
         config: RunDebugConfig,
     },
+    RunAndDebugCompound {
+        launch: RunDebugLaunch,
+    },
+    RunDebugInput {
+        value: String,
+    },
     ColorTheme {
         name: String,
     },
@@ -84,4 +90,7 @@ pub enum PaletteItemContent {
         name: String,
         profile: lapce_rpc::terminal::TerminalProfile,
     },
+    TerminalLayout {
+        name: String,
+    },
 }
@@ -16,6 +16,7 @@ pub enum PaletteKind {
     #[cfg(windows)]
     WslHost,
     RunAndDebug,
+    RunDebugInput,
     ColorTheme,
     IconTheme,
     Language,
@@ -42,6 +43,7 @@ pub fn symbol(&self) -> &'static str {
             | PaletteKind::Reference
             | PaletteKind::SshHost
             | PaletteKind::RunAndDebug
+            | PaletteKind::RunDebugInput
             | PaletteKind::ColorTheme
             | PaletteKind::IconTheme
             | PaletteKind::Language
@@ -92,6 +94,7 @@ pub fn command(self) -> Option<LapceWorkbenchCommand> {
             PaletteKind::RunAndDebug => {
                 Some(LapceWorkbenchCommand::PaletteRunAndDebug)
             }
+            PaletteKind::RunDebugInput => None, // InternalCommand::RunAndDebugLaunch
             PaletteKind::ColorTheme => Some(LapceWorkbenchCommand::ChangeColorTheme),
             PaletteKind::IconTheme => Some(LapceWorkbenchCommand::ChangeIconTheme),
             PaletteKind::Language => Some(LapceWorkbenchCommand::ChangeFileLanguage),
@@ -125,6 +128,7 @@ pub fn get_input<'a>(&self, input: &'a str) -> &'a str {
             | PaletteKind::Reference
             | PaletteKind::SshHost
             | PaletteKind::RunAndDebug
+            | PaletteKind::RunDebugInput
             | PaletteKind::ColorTheme
             | PaletteKind::IconTheme
             | PaletteKind::Language
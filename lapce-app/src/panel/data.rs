@@ -29,6 +29,8 @@ pub fn default_panel_order() -> PanelOrder {
             PanelKind::Plugin,
             PanelKind::SourceControl,
             PanelKind::Debug,
+            PanelKind::Task,
+            PanelKind::Testing,
         ],
     );
     order.insert(
@@ -39,7 +41,8 @@ pub fn default_panel_order() -> PanelOrder {
             PanelKind::Problem,
             PanelKind::CallHierarchy,
             PanelKind::References,
-            PanelKind::Implementation
+            PanelKind::Implementation,
+            PanelKind::Output,
         ],
     );
     order.insert(
@@ -61,8 +64,11 @@ pub enum PanelSection {
     Available,
     Process,
     Variable,
+    Watch,
     StackFrame,
     Breakpoint,
+    Task,
+    Tests,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
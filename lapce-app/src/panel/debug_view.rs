@@ -1,35 +1,46 @@
 use std::{rc::Rc, sync::Arc};
 
 use floem::{
-    View,
+    IntoView, View,
+    action::show_context_menu,
     event::EventListener,
+    menu::{Menu, MenuItem},
     peniko::Color,
+    prelude::SignalTrack,
     reactive::{
         ReadSignal, RwSignal, SignalGet, SignalUpdate, SignalWith, create_rw_signal,
     },
     style::CursorStyle,
     text::Style as FontStyle,
     views::{
-        Decorators, container, dyn_stack, label, scroll, stack, svg, text,
+        Decorators, container, dyn_container, dyn_stack,
+        editor::text::SystemClipboard, label, scroll, stack, svg, text,
         virtual_stack,
     },
 };
+use lapce_core::register::Clipboard;
 use lapce_rpc::{
-    dap_types::{DapId, ThreadId},
+    dap_types::{DapId, DebuggerCapabilities, StackFrame, ThreadId, Variable},
     terminal::TermId,
 };
 
-use super::{data::PanelSection, position::PanelPosition, view::PanelBuilder};
+use super::{
+    data::PanelSection, kind::PanelKind, position::PanelPosition, view::PanelBuilder,
+};
 use crate::{
     app::clickable_icon,
     command::InternalCommand,
     config::{LapceConfig, color::LapceColor, icon::LapceIcons},
-    debug::{DapVariable, RunDebugMode, StackTraceData},
+    debug::{
+        DapData, DapThread, DapVariable, RunDebugData, RunDebugMode, ScopeOrVar,
+    },
     editor::location::{EditorLocation, EditorPosition},
     listener::Listener,
+    memory::MemoryTarget,
     settings::checkbox,
     terminal::panel::TerminalPanelData,
-    window_tab::WindowTabData,
+    text_input::TextInputBuilder,
+    window_tab::{Focus, WindowTabData},
 };
 
 pub fn debug_panel(
@@ -52,6 +63,12 @@ pub fn debug_panel(
             variables_view(window_tab_data.clone()),
             window_tab_data.panel.section_open(PanelSection::Variable),
         )
+        .add_height(
+            "Watch",
+            100.0,
+            watch_view(window_tab_data.clone()),
+            window_tab_data.panel.section_open(PanelSection::Watch),
+        )
         .add(
             "Stack Frames",
             debug_stack_traces(terminal.clone(), internal_command, config),
@@ -161,7 +178,7 @@ fn debug_process_icons(
                 clickable_icon(
                     || LapceIcons::DEBUG_STEP_OVER,
                     move || {
-                        terminal.dap_step_over(term_id);
+                        terminal.dap_step_over(term_id, None);
                     },
                     || false,
                     move || !paused() || stopped,
@@ -175,7 +192,7 @@ fn debug_process_icons(
                 clickable_icon(
                     || LapceIcons::DEBUG_STEP_INTO,
                     move || {
-                        terminal.dap_step_into(term_id);
+                        terminal.dap_step_into(term_id, None);
                     },
                     || false,
                     move || !paused() || stopped,
@@ -189,7 +206,7 @@ fn debug_process_icons(
                 clickable_icon(
                     || LapceIcons::DEBUG_STEP_OUT,
                     move || {
-                        terminal.dap_step_out(term_id);
+                        terminal.dap_step_out(term_id, None);
                     },
                     || false,
                     move || !paused() || stopped,
@@ -335,6 +352,8 @@ fn variables_view(window_tab_data: Rc<WindowTabData>) -> impl View {
     let local_terminal = window_tab_data.terminal.clone();
     let ui_line_height = window_tab_data.common.ui_line_height;
     let config = window_tab_data.common.config;
+    let focus = window_tab_data.common.focus;
+    let internal_command = window_tab_data.common.internal_command;
     container(
         scroll(
             virtual_stack(
@@ -368,11 +387,51 @@ fn variables_view(window_tab_data: Rc<WindowTabData>) -> impl View {
                 },
                 move |node| {
                     let local_terminal = local_terminal.clone();
+                    let run_debug = local_terminal.debug.clone();
                     let level = node.level;
                     let reference = node.item.reference();
                     let name = node.item.name();
                     let ty = node.item.ty();
                     let type_exists = ty.map(|ty| !ty.is_empty()).unwrap_or(false);
+                    let var = match &node.item {
+                        ScopeOrVar::Var(var) => Some(var.clone()),
+                        ScopeOrVar::Scope(_) => None,
+                    };
+                    let is_editing = {
+                        let parent = node.parent.clone();
+                        let name = name.to_string();
+                        let variable_editing = run_debug.variable_editing;
+                        move || {
+                            variable_editing.with(|editing| {
+                                editing.as_ref().is_some_and(|editing| {
+                                    editing.parent == parent && editing.name == name
+                                })
+                            })
+                        }
+                    };
+                    let value = node.item.value().unwrap_or("").to_string();
+                    let value_view = {
+                        let run_debug = run_debug.clone();
+                        dyn_container(is_editing, move |is_editing| {
+                            if is_editing {
+                                variable_value_input(
+                                    run_debug.clone(),
+                                    focus,
+                                    config,
+                                )
+                                .into_any()
+                            } else {
+                                text(format!(" = {value}")).into_any()
+                            }
+                        })
+                        .style(move |s| s.apply_if(reference > 0, |s| s.hide()))
+                    };
+                    let secondary_click_terminal = local_terminal.clone();
+                    let secondary_click_var = var.clone();
+                    let secondary_click_parent = node.parent.clone();
+                    let double_click_terminal = local_terminal.clone();
+                    let double_click_var = var;
+                    let double_click_parent = node.parent.clone();
                     stack((
                         svg(move || {
                             let config = config.get();
@@ -403,8 +462,7 @@ fn variables_view(window_tab_data: Rc<WindowTabData>) -> impl View {
                                     s.hide()
                                 })
                         }),
-                        text(format!(" = {}", node.item.value().unwrap_or("")))
-                            .style(move |s| s.apply_if(reference > 0, |s| s.hide())),
+                        value_view,
                     ))
                     .on_click_stop(move |_| {
                         if reference > 0 {
@@ -426,6 +484,43 @@ fn variables_view(window_tab_data: Rc<WindowTabData>) -> impl View {
                             }
                         }
                     })
+                    .on_double_click_stop(move |_| {
+                        if reference > 0 {
+                            return;
+                        }
+                        let Some(var) = double_click_var.as_ref() else {
+                            return;
+                        };
+                        if let Some(dap) =
+                            double_click_terminal.get_active_dap(false)
+                        {
+                            if can_set_variable(&dap.capabilities, var) {
+                                double_click_terminal.debug.start_variable_edit(
+                                    dap.dap_id,
+                                    double_click_parent.clone(),
+                                    var,
+                                );
+                            }
+                        }
+                    })
+                    .on_secondary_click_stop(move |_| {
+                        let Some(var) = secondary_click_var.as_ref() else {
+                            return;
+                        };
+                        if let Some(dap) =
+                            secondary_click_terminal.get_active_dap(false)
+                        {
+                            variable_secondary_click(
+                                secondary_click_terminal.debug.clone(),
+                                dap.dap_id,
+                                secondary_click_parent.clone(),
+                                var.clone(),
+                                can_set_variable(&dap.capabilities, var),
+                                can_read_memory(&dap.capabilities, var),
+                                internal_command,
+                            );
+                        }
+                    })
                     .style(move |s| {
                         s.items_center()
                             .padding_right(10.0)
@@ -451,40 +546,335 @@ fn variables_view(window_tab_data: Rc<WindowTabData>) -> impl View {
     .style(|s| s.width_full().line_height(1.6).flex_grow(1.0).flex_basis(0))
 }
 
-fn debug_stack_frames(
+fn can_set_variable(
+    capabilities: &RwSignal<Option<DebuggerCapabilities>>,
+    var: &Variable,
+) -> bool {
+    capabilities.with_untracked(|capabilities| {
+        capabilities.as_ref().is_some_and(|c| {
+            c.supports_set_variable.unwrap_or(false)
+                || (c.supports_set_expression.unwrap_or(false)
+                    && var.evaluate_name.is_some())
+        })
+    })
+}
+
+/// Input used for editing the value of a variable
+fn variable_value_input(
+    run_debug: RunDebugData,
+    focus: RwSignal<Focus>,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let is_focused = move || {
+        focus.with_untracked(|focus| focus == &Focus::Panel(PanelKind::Debug))
+    };
+    let cancel = run_debug.clone();
+    let text_input_view = TextInputBuilder::new()
+        .is_focused(is_focused)
+        .key_focus(run_debug.clone())
+        .build_editor(run_debug.variable_editor.clone())
+        .on_event_stop(EventListener::FocusLost, move |_| {
+            cancel.cancel_variable_edit();
+        })
+        .style(move |s| {
+            s.min_width(100.0)
+                .padding(0.0)
+                .margin(0.0)
+                .margin_left(6.0)
+                .border_radius(6.0)
+                .border(1.0)
+                .border_color(config.get().color(LapceColor::LAPCE_BORDER))
+        });
+    text_input_view.id().request_focus();
+    text_input_view
+}
+
+fn can_read_memory(
+    capabilities: &RwSignal<Option<DebuggerCapabilities>>,
+    var: &Variable,
+) -> bool {
+    var.memory_reference.is_some()
+        && capabilities.with_untracked(|capabilities| {
+            capabilities
+                .as_ref()
+                .and_then(|c| c.supports_read_memory_request)
+                .unwrap_or(false)
+        })
+}
+
+fn can_disassemble(
+    capabilities: &RwSignal<Option<DebuggerCapabilities>>,
+    frame: &StackFrame,
+) -> bool {
+    frame.instruction_pointer_reference.is_some()
+        && capabilities.with_untracked(|capabilities| {
+            capabilities
+                .as_ref()
+                .and_then(|c| c.supports_disassemble_request)
+                .unwrap_or(false)
+        })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn variable_secondary_click(
+    run_debug: RunDebugData,
     dap_id: DapId,
+    parent: Vec<usize>,
+    var: Variable,
+    can_set: bool,
+    can_read_memory: bool,
+    internal_command: Listener<InternalCommand>,
+) {
+    let mut menu = Menu::new("");
+
+    if can_set {
+        let run_debug = run_debug.clone();
+        let var = var.clone();
+        menu = menu.entry(MenuItem::new("Set Value").action(move || {
+            run_debug.start_variable_edit(dap_id, parent.clone(), &var);
+        }));
+    }
+
+    let value = var.value.clone();
+    menu = menu.entry(MenuItem::new("Copy Value").action(move || {
+        let mut clipboard = SystemClipboard::new();
+        clipboard.put_string(&value);
+    }));
+
+    if let Some(memory_reference) =
+        var.memory_reference.clone().filter(|_| can_read_memory)
+    {
+        let name = var.name.clone();
+        menu = menu.entry(MenuItem::new("View Memory").action(move || {
+            internal_command.send(InternalCommand::OpenMemory {
+                target: MemoryTarget {
+                    dap_id,
+                    name: name.clone(),
+                    memory_reference: memory_reference.clone(),
+                },
+            });
+        }));
+    }
+
+    if let Some(evaluate_name) = var.evaluate_name {
+        let expression = evaluate_name.clone();
+        menu = menu.entry(MenuItem::new("Copy as Expression").action(move || {
+            let mut clipboard = SystemClipboard::new();
+            clipboard.put_string(&expression);
+        }));
+
+        menu = menu.separator();
+        menu = menu.entry(MenuItem::new("Add to Watch").action(move || {
+            run_debug.add_watch(evaluate_name.clone());
+        }));
+    }
+
+    show_context_menu(menu, None);
+}
+
+fn watch_view(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let terminal = window_tab_data.terminal.clone();
+    let watches = terminal.debug.watches;
+    let config = window_tab_data.common.config;
+    container(
+        scroll(
+            dyn_stack(
+                move || watches.get(),
+                |expression| expression.clone(),
+                move |expression| {
+                    let terminal = terminal.clone();
+                    let run_debug = terminal.debug.clone();
+                    let value = {
+                        let expression = expression.clone();
+                        move || {
+                            let dap = terminal.get_active_dap(true)?;
+                            if !dap.stopped.get() {
+                                return None;
+                            }
+                            dap.watch_values
+                                .with(|values| values.get(&expression).cloned())
+                        }
+                    };
+                    let is_error = {
+                        let value = value.clone();
+                        move || matches!(value(), Some(Err(_)))
+                    };
+                    let remove_expression = expression.clone();
+                    stack((
+                        clickable_icon(
+                            || LapceIcons::CLOSE,
+                            move || {
+                                run_debug.remove_watch(&remove_expression);
+                            },
+                            || false,
+                            || false,
+                            || "Remove",
+                            config,
+                        )
+                        .on_event_stop(EventListener::PointerDown, |_| {}),
+                        text(expression),
+                        label(move || match value() {
+                            Some(Ok(value)) => format!(" = {value}"),
+                            Some(Err(err)) => format!(" = {err}"),
+                            None => String::new(),
+                        })
+                        .style(move |s| {
+                            s.text_ellipsis().apply_if(is_error(), |s| {
+                                s.color(config.get().color(LapceColor::LAPCE_ERROR))
+                            })
+                        }),
+                    ))
+                    .style(move |s| {
+                        s.items_center().padding_horiz(10.0).width_pct(100.0).hover(
+                            |s| {
+                                s.background(
+                                    config
+                                        .get()
+                                        .color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                                )
+                            },
+                        )
+                    })
+                },
+            )
+            .style(|s| s.flex_col().line_height(1.6).width_pct(100.0)),
+        )
+        .style(|s| s.absolute().size_pct(100.0, 100.0)),
+    )
+    .style(|s| s.size_pct(100.0, 100.0))
+}
+
+fn debug_thread_icons(
+    terminal: TerminalPanelData,
+    dap: &DapData,
     thread_id: ThreadId,
-    stack_trace: StackTraceData,
-    stopped: RwSignal<bool>,
+    stopped: bool,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let dap_id = dap.dap_id;
+    // Without support in the adapter, continuing a thread resumes all of them
+    let capabilities = dap.capabilities;
+    let supports_single_thread = move || {
+        capabilities.with(|c| {
+            c.as_ref()
+                .and_then(|c| c.supports_single_thread_execution_requests)
+                .unwrap_or(false)
+        })
+    };
+    stack((
+        {
+            let terminal = terminal.clone();
+            clickable_icon(
+                || LapceIcons::DEBUG_CONTINUE,
+                move || {
+                    terminal.common.proxy.dap_continue(dap_id, thread_id, true);
+                },
+                || false,
+                move || !stopped || !supports_single_thread(),
+                || "Continue Thread",
+                config,
+            )
+            .style(move |s| {
+                s.margin_right(4.0)
+                    .apply_if(!supports_single_thread(), |s| s.hide())
+            })
+        },
+        clickable_icon(
+            || LapceIcons::DEBUG_PAUSE,
+            move || {
+                terminal.common.proxy.dap_pause(dap_id, thread_id);
+            },
+            || false,
+            move || stopped,
+            || "Pause Thread",
+            config,
+        )
+        .style(|s| s.margin_right(4.0)),
+    ))
+}
+
+fn debug_stack_frames(
+    terminal: TerminalPanelData,
+    dap: DapData,
+    thread_id: ThreadId,
+    thread: DapThread,
     internal_command: Listener<InternalCommand>,
     config: ReadSignal<Arc<LapceConfig>>,
 ) -> impl View {
-    let expanded = stack_trace.expanded;
+    let stack_trace = dap
+        .stack_traces
+        .with_untracked(|stack_traces| stack_traces.get(&thread_id).cloned());
+    let expanded = stack_trace.as_ref().map(|stack_trace| stack_trace.expanded);
+    let is_selected = {
+        let selected = dap.thread_id;
+        move || selected.get() == Some(thread_id)
+    };
+    let is_hovered = create_rw_signal(false);
+    let thread_name = if thread.name.is_empty() {
+        format!("Thread {thread_id}")
+    } else {
+        format!("{} ({thread_id})", thread.name)
+    };
+    let state = if thread.stopped { "Paused" } else { "Running" };
+    let select_dap = dap.clone();
+    let frame_dap = dap.clone();
+    let frame_id = dap.frame_id;
+    let selected_thread = dap.thread_id;
     stack((
-        container(label(move || thread_id.to_string()))
-            .on_click_stop(move |_| {
+        stack((
+            label(move || thread_name.clone()).style(|s| {
+                s.flex_grow(1.0)
+                    .flex_basis(0.0)
+                    .min_width(0.0)
+                    .text_ellipsis()
+            }),
+            label(move || state.to_string()).style(move |s| {
+                s.margin_horiz(6.0)
+                    .color(config.get().color(LapceColor::EDITOR_DIM))
+                    .font_style(FontStyle::Italic)
+                    .apply_if(is_hovered.get(), |s| s.hide())
+            }),
+            debug_thread_icons(terminal, &dap, thread_id, thread.stopped, config)
+                .style(move |s| s.apply_if(!is_hovered.get(), |s| s.hide())),
+        ))
+        .on_click_stop(move |_| {
+            select_dap.select_thread(thread_id);
+            if let Some(expanded) = expanded {
                 expanded.update(|expanded| {
                     *expanded = !*expanded;
                 });
-            })
-            .style(move |s| {
-                s.padding_horiz(10.0).min_width_pct(100.0).hover(move |s| {
+            }
+        })
+        .on_event_stop(EventListener::PointerEnter, move |_| {
+            is_hovered.set(true);
+        })
+        .on_event_stop(EventListener::PointerLeave, move |_| {
+            is_hovered.set(false);
+        })
+        .style(move |s| {
+            let config = config.get();
+            s.items_center()
+                .padding_left(10.0)
+                .min_width_pct(100.0)
+                .apply_if(is_selected(), |s| {
+                    s.background(config.color(LapceColor::PANEL_CURRENT_BACKGROUND))
+                })
+                .hover(move |s| {
                     s.cursor(CursorStyle::Pointer).background(
-                        config.get().color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                        config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
                     )
                 })
-            }),
+        }),
         dyn_stack(
-            move || {
-                let expanded = stack_trace.expanded.get() && stopped.get();
-                if expanded {
+            move || match stack_trace.as_ref() {
+                Some(stack_trace) if stack_trace.expanded.get() => {
                     stack_trace.frames.get()
-                } else {
-                    im::Vector::new()
                 }
+                _ => im::Vector::new(),
             },
             |frame| frame.id,
             move |frame| {
+                let frame_dap = frame_dap.clone();
                 let full_path = frame.source.as_ref().and_then(|s| s.path.clone());
                 let line = frame.line.saturating_sub(1);
                 let col = frame.column.saturating_sub(1);
@@ -499,6 +889,14 @@ fn debug_stack_frames(
                     .to_string();
                 let has_source = !source_path.is_empty();
                 let source_path = format!("{source_path}:{}", frame.line);
+                let id = frame.id;
+                let can_disassemble =
+                    can_disassemble(&frame_dap.capabilities, &frame);
+                let secondary_click_dap = frame_dap.clone();
+                let is_selected_frame = move || {
+                    selected_thread.get() == Some(thread_id)
+                        && frame_id.get() == Some(id)
+                };
 
                 container(stack((
                     label(move || frame.name.clone()).style(move |s| {
@@ -534,10 +932,20 @@ fn debug_stack_frames(
                             },
                         });
                     }
-                    internal_command.send(InternalCommand::DapFrameScopes {
-                        dap_id,
-                        frame_id: frame.id,
-                    });
+                    frame_dap.select_frame(thread_id, id);
+                })
+                .on_secondary_click_stop(move |_| {
+                    if !can_disassemble {
+                        return;
+                    }
+                    let dap = secondary_click_dap.clone();
+                    let menu = Menu::new("").entry(
+                        MenuItem::new("Open Disassembly").action(move || {
+                            dap.select_frame(thread_id, id);
+                            internal_command.send(InternalCommand::OpenDisassembly);
+                        }),
+                    );
+                    show_context_menu(menu, None);
                 })
                 .style(move |s| {
                     let config = config.get();
@@ -547,6 +955,11 @@ fn debug_stack_frames(
                         .apply_if(!has_source, |s| {
                             s.color(config.color(LapceColor::EDITOR_DIM))
                         })
+                        .apply_if(is_selected_frame(), |s| {
+                            s.background(
+                                config.color(LapceColor::PANEL_CURRENT_BACKGROUND),
+                            )
+                        })
                         .hover(|s| {
                             s.background(
                                 config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
@@ -582,29 +995,36 @@ fn debug_stack_traces(
                         if process_stopped {
                             return Vec::new();
                         }
-                        let main_thread = dap.thread_id.get();
-                        let stack_traces = dap.stack_traces.get();
-                        let mut traces = stack_traces
+                        // track the stack traces so that the frames are
+                        // rebuilt when a thread stops again
+                        dap.stack_traces.track();
+                        let threads = dap.threads.get();
+                        threads
                             .into_iter()
-                            .map(|(thread_id, stack_trace)| {
-                                (dap.dap_id, dap.stopped, thread_id, stack_trace)
+                            .map(|(thread_id, thread)| {
+                                (dap.clone(), thread_id, thread)
                             })
-                            .collect::<Vec<_>>();
-                        traces.sort_by_key(|(_, _, id, _)| main_thread != Some(*id));
-                        traces
+                            .collect::<Vec<_>>()
                     } else {
                         Vec::new()
                     }
                 },
-                |(dap_id, stopped, thread_id, _)| {
-                    (*dap_id, *thread_id, stopped.get_untracked())
+                |(dap, thread_id, thread)| {
+                    (
+                        dap.dap_id,
+                        *thread_id,
+                        thread.clone(),
+                        dap.stack_traces.with_untracked(|stack_traces| {
+                            stack_traces.contains_key(thread_id)
+                        }),
+                    )
                 },
-                move |(dap_id, stopped, thread_id, stack_trace)| {
+                move |(dap, thread_id, thread)| {
                     debug_stack_frames(
-                        dap_id,
+                        terminal.clone(),
+                        dap,
                         thread_id,
-                        stack_trace,
-                        stopped,
+                        thread,
                         internal_command,
                         config,
                     )
@@ -756,3 +1176,55 @@ fn breakpoints_view(window_tab_data: Rc<WindowTabData>) -> impl View {
     )
     .style(|s| s.size_pct(100.0, 100.0))
 }
+
+#[cfg(test)]
+mod tests {
+    use floem::reactive::Scope;
+    use lapce_rpc::dap_types::{DapId, StackFrame, Variable};
+    use serde_json::json;
+
+    use super::{can_disassemble, can_read_memory};
+    use crate::debug::DapCapabilities;
+
+    #[test]
+    fn test_native_debugging_gated_by_capabilities() {
+        let capabilities = DapCapabilities::new(Scope::new());
+        let dap_id = DapId::next();
+        let var = Variable {
+            memory_reference: Some("0x1000".to_string()),
+            ..Default::default()
+        };
+        let frame: StackFrame = serde_json::from_value(json!({
+            "id": 1,
+            "name": "main",
+            "line": 1,
+            "column": 1,
+            "instructionPointerReference": "0x1000",
+        }))
+        .unwrap();
+        assert!(!can_read_memory(&capabilities.get(dap_id), &var));
+        assert!(!can_disassemble(&capabilities.get(dap_id), &frame));
+
+        capabilities.set(
+            dap_id,
+            serde_json::from_value(json!({
+                "supportsReadMemoryRequest": true,
+                "supportsDisassembleRequest": true,
+            }))
+            .unwrap(),
+        );
+        assert!(can_read_memory(&capabilities.get(dap_id), &var));
+        assert!(can_disassemble(&capabilities.get(dap_id), &frame));
+
+        // without a reference there's nothing to read or disassemble
+        assert!(!can_read_memory(
+            &capabilities.get(dap_id),
+            &Variable::default()
+        ));
+        let frame = StackFrame {
+            instruction_pointer_reference: None,
+            ..frame
+        };
+        assert!(!can_disassemble(&capabilities.get(dap_id), &frame));
+    }
+}
@@ -19,6 +19,9 @@ pub enum PanelKind {
     DocumentSymbol,
     References,
     Implementation,
+    Task,
+    Testing,
+    Output,
 }
 
 impl PanelKind {
@@ -35,6 +38,9 @@ pub fn svg_name(&self) -> &'static str {
             PanelKind::DocumentSymbol => LapceIcons::DOCUMENT_SYMBOL,
             PanelKind::References => LapceIcons::REFERENCES,
             PanelKind::Implementation => LapceIcons::IMPLEMENTATION,
+            PanelKind::Task => LapceIcons::START,
+            PanelKind::Testing => LapceIcons::RUN_ERRORS,
+            PanelKind::Output => LapceIcons::OUTPUT,
         }
     }
 
@@ -61,6 +67,9 @@ pub fn default_position(&self) -> PanelPosition {
             PanelKind::DocumentSymbol => PanelPosition::RightTop,
             PanelKind::References => PanelPosition::BottomLeft,
             PanelKind::Implementation => PanelPosition::BottomLeft,
+            PanelKind::Task => PanelPosition::LeftTop,
+            PanelKind::Testing => PanelPosition::LeftTop,
+            PanelKind::Output => PanelPosition::BottomLeft,
         }
     }
 }
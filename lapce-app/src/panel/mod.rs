@@ -5,11 +5,14 @@
 pub mod global_search_view;
 pub mod implementation_view;
 pub mod kind;
+pub mod output_view;
 pub mod plugin_view;
 pub mod position;
 pub mod problem_view;
 pub mod references_view;
 pub mod source_control_view;
 pub mod style;
+pub mod task_view;
 pub mod terminal_view;
+pub mod testing_view;
 pub mod view;
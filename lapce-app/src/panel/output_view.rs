@@ -0,0 +1,253 @@
+use std::{ops::Range, rc::Rc};
+
+use floem::{
+    View,
+    action::show_context_menu,
+    event::EventListener,
+    kurbo::{Point, Size},
+    menu::{Menu, MenuItem},
+    reactive::{SignalGet, SignalUpdate, SignalWith},
+    style::CursorStyle,
+    views::{
+        Decorators, VirtualVector, container, label, scroll, stack, svg,
+        virtual_stack,
+    },
+};
+use lapce_rpc::plugin::PluginOutputKind;
+use lsp_types::MessageType;
+
+use super::{kind::PanelKind, position::PanelPosition};
+use crate::{
+    app::clickable_icon,
+    config::{color::LapceColor, icon::LapceIcons},
+    output::{OutputData, OutputLine},
+    text_input::TextInputBuilder,
+    window_tab::{Focus, WindowTabData},
+};
+
+pub fn output_panel(
+    window_tab_data: Rc<WindowTabData>,
+    _position: PanelPosition,
+) -> impl View {
+    let output = window_tab_data.output.clone();
+
+    stack((toolbar(output.clone()), output_lines(output)))
+        .style(|s| s.absolute().size_pct(100.0, 100.0).flex_col())
+        .debug_name("Output Panel")
+}
+
+fn toolbar(output: OutputData) -> impl View {
+    let config = output.common.config;
+    let focus = output.common.focus;
+    let is_focused = move || focus.get() == Focus::Panel(PanelKind::Output);
+    let active = output.active;
+    let no_channel = move || active.with(|a| a.is_none());
+
+    stack((
+        channel_selector(output.clone()),
+        container(
+            TextInputBuilder::new()
+                .is_focused(is_focused)
+                .build_editor(output.filter_editor.clone())
+                .placeholder(|| "Filter".to_string())
+                .style(|s| s.width_pct(100.0)),
+        )
+        .on_event_cont(EventListener::PointerDown, move |_| {
+            focus.set(Focus::Panel(PanelKind::Output));
+        })
+        .style(move |s| {
+            s.flex_grow(1.0)
+                .flex_basis(0.0)
+                .min_width(0.0)
+                .margin_horiz(6.0)
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(config.get().color(LapceColor::LAPCE_BORDER))
+        }),
+        {
+            let output = output.clone();
+            let local_output = output.clone();
+            clickable_icon(
+                || LapceIcons::OUTPUT_TRACE,
+                move || output.toggle_trace(),
+                move || {
+                    local_output
+                        .active_channel()
+                        .map(|c| c.tracing.get())
+                        .unwrap_or(false)
+                },
+                no_channel,
+                || "Trace JSON-RPC Messages",
+                config,
+            )
+        },
+        {
+            let output = output.clone();
+            clickable_icon(
+                || LapceIcons::OUTPUT_CLEAR,
+                move || output.clear(),
+                || false,
+                no_channel,
+                || "Clear Output",
+                config,
+            )
+            .style(|s| s.margin_left(6.0))
+        },
+        clickable_icon(
+            || LapceIcons::OUTPUT_EXPORT,
+            move || output.export(),
+            || false,
+            no_channel,
+            || "Export Output",
+            config,
+        )
+        .style(|s| s.margin_left(6.0)),
+    ))
+    .style(|s| s.width_pct(100.0).items_center().padding(10.0))
+}
+
+fn channel_selector(output: OutputData) -> impl View {
+    let config = output.common.config;
+    let name = {
+        let output = output.clone();
+        move || {
+            output
+                .active
+                .with(|a| a.as_ref().map(|c| c.name.clone()))
+                .unwrap_or_else(|| "No Output".to_string())
+        }
+    };
+
+    stack((
+        label(name).style(|s| s.text_ellipsis().min_width(0.0)),
+        svg(move || config.get().ui_svg(LapceIcons::DROPDOWN_ARROW)).style(
+            move |s| {
+                let config = config.get();
+                let size = config.ui.icon_size() as f32;
+                s.size(size, size)
+                    .margin_left(4.0)
+                    .color(config.color(LapceColor::LAPCE_ICON_ACTIVE))
+            },
+        ),
+    ))
+    .on_click_stop(move |_| {
+        let mut menu = Menu::new("");
+        let channels = output
+            .channels
+            .with_untracked(|c| c.keys().cloned().collect::<Vec<_>>());
+        for channel in channels {
+            let active = output.active;
+            menu = menu.entry(
+                MenuItem::new(channel.name.clone())
+                    .action(move || active.set(Some(channel.clone()))),
+            );
+        }
+        show_context_menu(menu, None);
+    })
+    .style(move |s| {
+        s.items_center()
+            .max_width(240.0)
+            .padding_horiz(6.0)
+            .padding_vert(4.0)
+            .border_radius(6.0)
+            .cursor(CursorStyle::Pointer)
+            .hover(|s| {
+                s.background(
+                    config.get().color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                )
+            })
+    })
+}
+
+fn output_lines(output: OutputData) -> impl View {
+    let config = output.common.config;
+    let ui_line_height = output.common.ui_line_height;
+    let lines = {
+        let output = output.clone();
+        move || output.filtered_lines()
+    };
+    let line_count = {
+        let lines = lines.clone();
+        move || lines().len()
+    };
+
+    container(
+        scroll(
+            virtual_stack(
+                move || VectorItems(lines()),
+                |line| line.id,
+                move |line| line_view(&output, line),
+            )
+            .item_size_fixed(move || ui_line_height.get())
+            .style(|s| s.flex_col().min_width_pct(100.0)),
+        )
+        .ensure_visible(move || {
+            // follow the output as it comes in
+            let line_height = ui_line_height.get();
+            Size::new(1.0, line_height)
+                .to_rect()
+                .with_origin(Point::new(
+                    0.0,
+                    line_count().saturating_sub(1) as f64 * line_height,
+                ))
+        })
+        .style(|s| s.absolute().size_pct(100.0, 100.0)),
+    )
+    .style(move |s| {
+        let config = config.get();
+        s.flex_grow(1.0)
+            .flex_basis(0.0)
+            .width_pct(100.0)
+            .font_family(config.editor.font_family.clone())
+            .font_size(config.ui.font_size() as f32)
+    })
+}
+
+fn line_view(output: &OutputData, line: OutputLine) -> impl View {
+    let config = output.common.config;
+    let ui_line_height = output.common.ui_line_height;
+    let prefix = line.prefix();
+    let duration = line.duration.map(|d| format!(" ({d} ms)"));
+    let kind = line.kind.clone();
+
+    stack((
+        label(move || line.time.clone()).style(move |s| {
+            s.color(config.get().color(LapceColor::EDITOR_DIM))
+                .margin_right(8.0)
+        }),
+        label(move || prefix).style(move |s| {
+            let config = config.get();
+            let color = match kind {
+                PluginOutputKind::Stderr
+                | PluginOutputKind::Log(MessageType::ERROR) => {
+                    config.color(LapceColor::LAPCE_ERROR)
+                }
+                PluginOutputKind::Log(MessageType::WARNING) => {
+                    config.color(LapceColor::LAPCE_WARN)
+                }
+                _ => config.color(LapceColor::EDITOR_DIM),
+            };
+            s.color(color).margin_right(8.0)
+        }),
+        label(move || line.text.clone()),
+        label(move || duration.clone().unwrap_or_default())
+            .style(move |s| s.color(config.get().color(LapceColor::EDITOR_DIM))),
+    ))
+    .style(move |s| {
+        s.items_center()
+            .padding_horiz(10.0)
+            .height(ui_line_height.get() as f32)
+    })
+}
+
+struct VectorItems(im::Vector<OutputLine>);
+
+impl VirtualVector<OutputLine> for VectorItems {
+    fn total_len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn slice(&mut self, range: Range<usize>) -> impl Iterator<Item = OutputLine> {
+        self.0.slice(range).into_iter()
+    }
+}
@@ -26,7 +26,10 @@
     app::not_clickable_icon,
     command::InternalCommand,
     config::{color::LapceColor, icon::LapceIcons},
-    plugin::{AvailableVoltData, InstalledVoltData, PluginData, VoltIcon},
+    plugin::{
+        AvailableVoltData, InstalledVoltData, PluginData, VoltIcon, pinned_version,
+        volt_update,
+    },
     text_input::TextInputBuilder,
     window_tab::{Focus, WindowTabData},
 };
@@ -142,10 +145,29 @@ fn installed_view(plugin: PluginData) -> impl View {
                                 || workspace_disabled.with(|d| d.contains(&volt_id))
                             {
                                 "Disabled".to_string()
-                            } else if volt.meta.with(|m| {
-                                volt.latest.with(|i| i.version != m.version)
-                            }) {
-                                "Upgrade".to_string()
+                            } else if let Some((update, pinned)) =
+                                volt.meta.with(|m| {
+                                    volt.latest.with(|i| {
+                                        config.with(|c| {
+                                            let pinned = pinned_version(c, &volt_id);
+                                            volt_update(pinned, m, i)
+                                                .map(|u| (u, pinned.is_some()))
+                                        })
+                                    })
+                                })
+                            {
+                                if pinned {
+                                    format!("Install v{} (pinned)", update.version)
+                                } else {
+                                    "Upgrade".to_string()
+                                }
+                            } else if config
+                                .with(|c| pinned_version(c, &volt_id).is_some())
+                            {
+                                format!(
+                                    "v{} (pinned)",
+                                    volt.meta.with(|m| m.version.clone())
+                                )
                             } else {
                                 format!("v{}", volt.meta.with(|m| m.version.clone()))
                             }
@@ -0,0 +1,217 @@
+use std::rc::Rc;
+
+use floem::{
+    View,
+    action::show_context_menu,
+    event::EventListener,
+    menu::{Menu, MenuItem},
+    reactive::{SignalGet, SignalUpdate, SignalWith, create_rw_signal},
+    style::CursorStyle,
+    views::{Decorators, dyn_stack, label, scroll, stack, svg},
+};
+use lapce_rpc::task::{DetectedTask, TaskKind};
+
+use super::{data::PanelSection, position::PanelPosition, view::PanelBuilder};
+use crate::{
+    app::clickable_icon,
+    config::{color::LapceColor, icon::LapceIcons},
+    task::{TaskData, TaskStatus},
+    window_tab::WindowTabData,
+};
+
+pub fn task_panel(
+    window_tab_data: Rc<WindowTabData>,
+    position: PanelPosition,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    PanelBuilder::new(config, position)
+        .add(
+            "Detected Tasks",
+            task_list(window_tab_data.clone()),
+            window_tab_data.panel.section_open(PanelSection::Task),
+        )
+        .build()
+        .debug_name("Task Panel")
+}
+
+fn task_list(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let tasks = window_tab_data.tasks.clone();
+    let config = window_tab_data.common.config;
+    scroll({
+        let local_tasks = tasks.clone();
+        dyn_stack(
+            move || local_tasks.tasks.get(),
+            |task| task.label(),
+            move |task| task_item(tasks.clone(), task),
+        )
+        .style(|s| s.width_pct(100.0).flex_col())
+    })
+    .on_secondary_click_stop({
+        let tasks = window_tab_data.tasks.clone();
+        move |_| {
+            let tasks = tasks.clone();
+            show_context_menu(
+                Menu::new("").entry(
+                    MenuItem::new("Refresh Tasks").action(move || tasks.detect()),
+                ),
+                None,
+            );
+        }
+    })
+    .style(move |s| {
+        s.absolute()
+            .size_pct(100.0, 100.0)
+            .line_height(1.8)
+            .font_size(config.get().ui.font_size() as f32)
+    })
+}
+
+fn task_item(tasks: TaskData, task: DetectedTask) -> impl View {
+    let config = tasks.common.config;
+    let label_text = task.label();
+    let status = {
+        let tasks = tasks.clone();
+        let label_text = label_text.clone();
+        move || tasks.statuses.with(|s| s.get(&label_text).copied())
+    };
+    let is_hovered = create_rw_signal(false);
+
+    let status_icon = {
+        let status = status.clone();
+        move || match status() {
+            Some(TaskStatus::Exited(Some(0))) | None => LapceIcons::START,
+            Some(TaskStatus::Running) => LapceIcons::DEBUG_RESTART,
+            Some(TaskStatus::Exited(_)) => LapceIcons::RUN_ERRORS,
+        }
+    };
+    let status_text = {
+        let status = status.clone();
+        move || match status() {
+            None => String::new(),
+            Some(TaskStatus::Running) => "running".to_string(),
+            Some(TaskStatus::Exited(Some(code))) => format!("exit {code}"),
+            Some(TaskStatus::Exited(None)) => "stopped".to_string(),
+        }
+    };
+    let default_text = {
+        let tasks = tasks.clone();
+        let label_text = label_text.clone();
+        move || {
+            let is_build = tasks
+                .default_build
+                .with(|l| l.as_deref() == Some(label_text.as_str()));
+            let is_test = tasks
+                .default_test
+                .with(|l| l.as_deref() == Some(label_text.as_str()));
+            match (is_build, is_test) {
+                (true, true) => "default build, test".to_string(),
+                (true, false) => "default build".to_string(),
+                (false, true) => "default test".to_string(),
+                (false, false) => String::new(),
+            }
+        }
+    };
+
+    stack((
+        svg(move || config.get().ui_svg(status_icon())).style(move |s| {
+            let config = config.get();
+            let size = config.ui.icon_size() as f32;
+            let failed = matches!(
+                status(),
+                Some(TaskStatus::Exited(code)) if code != Some(0)
+            );
+            s.size(size, size)
+                .margin_vert(5.0)
+                .margin_horiz(10.0)
+                .min_width(size)
+                .color(if failed {
+                    config.color(LapceColor::LAPCE_ERROR)
+                } else {
+                    config.color(LapceColor::LAPCE_ICON_ACTIVE)
+                })
+        }),
+        label(move || label_text.clone()).style(|s| {
+            s.flex_grow(1.0)
+                .flex_basis(0.0)
+                .min_width(0.0)
+                .text_ellipsis()
+        }),
+        label(default_text).style(move |s| {
+            s.margin_left(6.0)
+                .color(config.get().color(LapceColor::EDITOR_DIM))
+        }),
+        label(status_text).style(move |s| {
+            s.margin_horiz(6.0)
+                .color(config.get().color(LapceColor::EDITOR_DIM))
+        }),
+        {
+            let tasks = tasks.clone();
+            let task = task.clone();
+            clickable_icon(
+                || LapceIcons::START,
+                move || tasks.run(&task),
+                || false,
+                || false,
+                || "Run Task",
+                config,
+            )
+            .style(move |s| {
+                s.margin_right(4.0)
+                    .apply_if(!is_hovered.get(), |s| s.hide())
+            })
+        },
+    ))
+    .on_double_click_stop({
+        let tasks = tasks.clone();
+        let task = task.clone();
+        move |_| {
+            tasks.run(&task);
+        }
+    })
+    .on_secondary_click_stop(move |_| {
+        task_menu(tasks.clone(), task.clone());
+    })
+    .on_event_stop(EventListener::PointerEnter, move |_| {
+        is_hovered.set(true);
+    })
+    .on_event_stop(EventListener::PointerLeave, move |_| {
+        is_hovered.set(false);
+    })
+    .style(move |s| {
+        let config = config.get();
+        s.padding_vert(6.0)
+            .width_pct(100.0)
+            .items_center()
+            .hover(|s| {
+                s.cursor(CursorStyle::Pointer).background(
+                    config
+                        .color(LapceColor::PANEL_HOVERED_BACKGROUND)
+                        .multiply_alpha(0.3),
+                )
+            })
+    })
+}
+
+fn task_menu(tasks: TaskData, task: DetectedTask) {
+    let mut menu = Menu::new("").entry({
+        let tasks = tasks.clone();
+        let task = task.clone();
+        MenuItem::new("Run Task").action(move || tasks.run(&task))
+    });
+    menu = menu.separator();
+    for (kind, title) in [
+        (TaskKind::Build, "Set as Default Build Task"),
+        (TaskKind::Test, "Set as Default Test Task"),
+    ] {
+        let tasks = tasks.clone();
+        let label = task.label();
+        menu = menu.entry(
+            MenuItem::new(title)
+                .action(move || tasks.set_default(kind, label.clone())),
+        );
+    }
+    menu = menu
+        .separator()
+        .entry(MenuItem::new("Refresh Tasks").action(move || tasks.detect()));
+    show_context_menu(menu, None);
+}
@@ -1,17 +1,18 @@
 use std::rc::Rc;
 
+use alacritty_terminal::index::Direction;
 use floem::{
-    View, ViewId,
+    AnyView, IntoView, View, ViewId,
     action::show_context_menu,
     event::{Event, EventListener, EventPropagation},
     kurbo::Size,
     menu::{Menu, MenuItem},
-    reactive::{SignalGet, SignalUpdate, SignalWith, create_rw_signal},
+    reactive::{RwSignal, SignalGet, SignalUpdate, SignalWith, create_rw_signal},
     style::CursorStyle,
     views::{
-        Decorators, container, dyn_stack, empty, label,
+        Decorators, container, dyn_container, dyn_stack, empty, label,
         scroll::{Thickness, VerticalScrollAsHorizontal, scroll},
-        stack, svg, tab,
+        stack, stack_from_iter, svg, tab,
     },
 };
 use lapce_rpc::terminal::TermId;
@@ -20,20 +21,31 @@
 use crate::{
     app::clickable_icon,
     command::{InternalCommand, LapceWorkbenchCommand},
-    config::{color::LapceColor, icon::LapceIcons},
+    config::{
+        color::LapceColor, icon::LapceIcons, terminal::TerminalSplitDirection,
+    },
     debug::RunDebugMode,
     listener::Listener,
     terminal::{
-        panel::TerminalPanelData, tab::TerminalTabData, view::terminal_view,
+        data::TerminalData,
+        panel::TerminalPanelData,
+        tab::{TerminalSplitNode, TerminalTabData},
+        view::terminal_view,
     },
-    window_tab::{Focus, WindowTabData},
+    text_input::TextInputBuilder,
+    window_tab::{DragContent, Focus, WindowTabData},
 };
 
 pub fn terminal_panel(window_tab_data: Rc<WindowTabData>) -> impl View {
     let focus = window_tab_data.common.focus;
     stack((
         terminal_tab_header(window_tab_data.clone()),
-        terminal_tab_content(window_tab_data.clone()),
+        stack((
+            terminal_tab_content(window_tab_data.clone()),
+            terminal_find_view(window_tab_data.terminal.clone()),
+            terminal_drop_view(window_tab_data.clone()),
+        ))
+        .style(|s| s.size_pct(100.0, 100.0)),
     ))
     .on_event_cont(EventListener::PointerDown, move |_| {
         if focus.get_untracked() != Focus::Panel(PanelKind::Terminal) {
@@ -55,6 +67,7 @@ fn terminal_tab_header(window_tab_data: Rc<WindowTabData>) -> impl View {
     let icon_width = create_rw_signal(0.0);
     let scroll_size = create_rw_signal(Size::ZERO);
     let workbench_command = window_tab_data.common.workbench_command;
+    let dragging = window_tab_data.common.dragging;
 
     stack((
         scroll(dyn_stack(
@@ -191,19 +204,34 @@ fn terminal_tab_header(window_tab_data: Rc<WindowTabData>) -> impl View {
                     }),
                 ))
                 .style(|s| s.cursor(CursorStyle::Pointer))
-                .on_event_cont(
-                    EventListener::PointerDown,
-                    move |_| {
-                        if tab_info.with_untracked(|tab| tab.active)
-                            != index.get_untracked()
-                        {
-                            tab_info.update(|tab| {
-                                tab.active = index.get_untracked();
-                            });
-                            local_terminal.update_debug_active_term();
-                        }
-                    },
-                )
+                .on_event_cont(EventListener::PointerDown, move |_| {
+                    if tab_info.with_untracked(|tab| tab.active)
+                        != index.get_untracked()
+                    {
+                        tab_info.update(|tab| {
+                            tab.active = index.get_untracked();
+                        });
+                        local_terminal.update_debug_active_term();
+                    }
+                })
+                .draggable()
+                .on_event_stop(EventListener::DragStart, move |_| {
+                    dragging.set(Some(DragContent::TerminalTab(terminal_tab_id)));
+                })
+                .on_event_stop(EventListener::DragEnd, move |_| {
+                    dragging.set(None);
+                })
+                .dragging_style(move |s| {
+                    let config = config.get();
+                    s.border(1.0)
+                        .border_radius(6.0)
+                        .border_color(config.color(LapceColor::LAPCE_BORDER))
+                        .background(
+                            config
+                                .color(LapceColor::PANEL_BACKGROUND)
+                                .multiply_alpha(0.7),
+                        )
+                })
             },
         ))
         .on_resize(move |rect| {
@@ -270,14 +298,33 @@ fn terminal_tab_split(
     terminal_tab_data: TerminalTabData,
     tab_index: usize,
 ) -> impl View {
-    let config = terminal_panel_data.common.config;
-    let internal_command = terminal_panel_data.common.internal_command;
-    let workspace = terminal_panel_data.workspace.clone();
-    let active = terminal_tab_data.active;
     let terminal_tab_scope = terminal_tab_data.scope;
+    let has_layout = terminal_tab_data.layout.with_untracked(|l| l.is_some());
+    let view = if has_layout {
+        terminal_layout_split(terminal_panel_data, terminal_tab_data, tab_index)
+            .into_any()
+    } else {
+        terminal_flat_split(terminal_panel_data, terminal_tab_data, tab_index)
+            .into_any()
+    };
+    container(view)
+        .on_cleanup(move || {
+            terminal_tab_scope.dispose();
+        })
+        .style(|s| s.size_pct(100.0, 100.0))
+}
+
+/// The terminals of the tab side by side
+fn terminal_flat_split(
+    terminal_panel_data: TerminalPanelData,
+    terminal_tab_data: TerminalTabData,
+    tab_index: usize,
+) -> impl View {
+    let config = terminal_panel_data.common.config;
+    let terminals = terminal_tab_data.terminals;
     dyn_stack(
         move || {
-            let terminals = terminal_tab_data.terminals.get();
+            let terminals = terminals.get();
             for (i, (index, _)) in terminals.iter().enumerate() {
                 if index.get_untracked() != i {
                     index.set(i);
@@ -287,67 +334,309 @@ fn terminal_tab_split(
         },
         |(_, terminal)| terminal.term_id,
         move |(index, terminal)| {
-            let terminal_panel_data = terminal_panel_data.clone();
-            let terminal_scope = terminal.scope;
-            container({
-                let terminal_view = terminal_view(
-                    terminal.term_id,
-                    terminal.raw.read_only(),
-                    terminal.mode.read_only(),
-                    terminal.run_debug.read_only(),
-                    terminal_panel_data,
-                    terminal.launch_error,
-                    internal_command,
-                    workspace.clone(),
-                );
-                let view_id = terminal_view.id();
-                let have_task = terminal.run_debug.get_untracked().is_some();
-                terminal_view
-                    .on_event_cont(EventListener::PointerDown, move |_| {
-                        active.set(index.get_untracked());
-                    })
-                    .on_secondary_click_stop(move |_| {
-                        if have_task {
-                            tab_secondary_click(
-                                internal_command,
-                                view_id,
-                                tab_index,
-                                index.get_untracked(),
-                                terminal.term_id,
-                            );
-                        }
-                    })
-                    .on_event(EventListener::PointerWheel, move |event| {
-                        if let Event::PointerWheel(pointer_event) = event {
-                            terminal.clone().wheel_scroll(pointer_event.delta.y);
-                            EventPropagation::Stop
-                        } else {
-                            EventPropagation::Continue
-                        }
-                    })
-                    .on_cleanup(move || {
-                        terminal_scope.dispose();
-                    })
-                    .style(|s| s.size_pct(100.0, 100.0))
-            })
+            container(terminal_pane(
+                terminal_panel_data.clone(),
+                terminal_tab_data.clone(),
+                tab_index,
+                index,
+                terminal,
+            ))
             .style(move |s| {
-                s.size_pct(100.0, 100.0).padding_horiz(10.0).apply_if(
-                    index.get() > 0,
-                    |s| {
-                        s.border_left(1.0).border_color(
-                            config.get().color(LapceColor::LAPCE_BORDER),
-                        )
-                    },
-                )
+                s.size_pct(100.0, 100.0).apply_if(index.get() > 0, |s| {
+                    s.border_left(1.0)
+                        .border_color(config.get().color(LapceColor::LAPCE_BORDER))
+                })
             })
         },
     )
-    .on_cleanup(move || {
-        terminal_tab_scope.dispose();
-    })
     .style(|s| s.size_pct(100.0, 100.0))
 }
 
+/// The terminals of a tab opened from a layout, in its splits
+fn terminal_layout_split(
+    terminal_panel_data: TerminalPanelData,
+    terminal_tab_data: TerminalTabData,
+    tab_index: usize,
+) -> impl View {
+    let terminals = terminal_tab_data.terminals;
+    let layout = terminal_tab_data.layout;
+    // the terminals change before the layout, which is what rebuilds the view
+    dyn_container(
+        move || {
+            terminals.with_untracked(|terminals| {
+                for (i, (index, _)) in terminals.iter().enumerate() {
+                    if index.get_untracked() != i {
+                        index.set(i);
+                    }
+                }
+            });
+            layout.get()
+        },
+        move |layout| match layout {
+            Some(node) => terminal_layout_node(
+                terminal_panel_data.clone(),
+                terminal_tab_data.clone(),
+                tab_index,
+                node,
+            ),
+            None => empty().into_any(),
+        },
+    )
+    .style(|s| s.size_pct(100.0, 100.0))
+}
+
+fn terminal_layout_node(
+    terminal_panel_data: TerminalPanelData,
+    terminal_tab_data: TerminalTabData,
+    tab_index: usize,
+    node: TerminalSplitNode,
+) -> AnyView {
+    let config = terminal_panel_data.common.config;
+    match node {
+        TerminalSplitNode::Terminal(term_id) => {
+            let terminal = terminal_tab_data.terminals.with_untracked(|terminals| {
+                terminals
+                    .iter()
+                    .find(|(_, t)| t.term_id == term_id)
+                    .cloned()
+            });
+            match terminal {
+                Some((index, terminal)) => terminal_pane(
+                    terminal_panel_data,
+                    terminal_tab_data,
+                    tab_index,
+                    index,
+                    terminal,
+                )
+                .into_any(),
+                None => empty().into_any(),
+            }
+        }
+        TerminalSplitNode::Split {
+            direction,
+            children,
+        } => {
+            let vertical = direction == TerminalSplitDirection::Vertical;
+            stack_from_iter(children.into_iter().enumerate().map(|(i, child)| {
+                container(terminal_layout_node(
+                    terminal_panel_data.clone(),
+                    terminal_tab_data.clone(),
+                    tab_index,
+                    child,
+                ))
+                .style(move |s| {
+                    let border = config.get().color(LapceColor::LAPCE_BORDER);
+                    s.flex_grow(1.0)
+                        .flex_basis(0.0)
+                        .min_size(0.0, 0.0)
+                        .apply_if(i > 0 && vertical, |s| {
+                            s.border_top(1.0).border_color(border)
+                        })
+                        .apply_if(i > 0 && !vertical, |s| {
+                            s.border_left(1.0).border_color(border)
+                        })
+                })
+            }))
+            .style(move |s| {
+                s.size_pct(100.0, 100.0)
+                    .apply_if(vertical, |s| s.flex_col())
+            })
+            .into_any()
+        }
+    }
+}
+
+/// A terminal of a tab, at `index` among its terminals
+fn terminal_pane(
+    terminal_panel_data: TerminalPanelData,
+    terminal_tab_data: TerminalTabData,
+    tab_index: usize,
+    index: RwSignal<usize>,
+    terminal: TerminalData,
+) -> impl View {
+    let internal_command = terminal_panel_data.common.internal_command;
+    let workspace = terminal_panel_data.workspace.clone();
+    let active = terminal_tab_data.active;
+    let terminals = terminal_tab_data.terminals;
+    let find_focus = terminal_panel_data.find.focus;
+    let terminal_scope = terminal.scope;
+    let term_id = terminal.term_id;
+    container({
+        let terminal_view = terminal_view(
+            terminal.term_id,
+            terminal.raw.read_only(),
+            terminal.mode.read_only(),
+            terminal.run_debug.read_only(),
+            terminal_panel_data,
+            terminal.launch_error,
+            internal_command,
+            workspace,
+        );
+        let view_id = terminal_view.id();
+        let have_task = terminal.run_debug.get_untracked().is_some();
+        terminal_view
+            .on_event_cont(EventListener::PointerDown, move |_| {
+                active.set(index.get_untracked());
+                find_focus.set(false);
+            })
+            .on_secondary_click_stop(move |_| {
+                if have_task {
+                    tab_secondary_click(
+                        internal_command,
+                        view_id,
+                        tab_index,
+                        index.get_untracked(),
+                        terminal.term_id,
+                    );
+                } else {
+                    show_context_menu(
+                        Menu::new("").entry(
+                            MenuItem::new("Move to Editor Area").action(move || {
+                                internal_command.send(
+                                    InternalCommand::MoveTerminalToEditor {
+                                        term_id,
+                                    },
+                                );
+                            }),
+                        ),
+                        None,
+                    );
+                }
+            })
+            .on_event(EventListener::PointerWheel, move |event| {
+                if let Event::PointerWheel(pointer_event) = event {
+                    terminal.clone().wheel_scroll(pointer_event.delta.y);
+                    EventPropagation::Stop
+                } else {
+                    EventPropagation::Continue
+                }
+            })
+            .on_cleanup(move || {
+                // the view of a layout is rebuilt when its splits change, so
+                // the terminal is only gone once it's out of the tab
+                let closed = terminals.with_untracked(|terminals| {
+                    terminals.iter().all(|(_, t)| t.term_id != term_id)
+                });
+                if closed {
+                    terminal_scope.dispose();
+                }
+            })
+            .style(|s| s.size_pct(100.0, 100.0))
+    })
+    .style(|s| {
+        // the terminal view has a gutter of its own on the left
+        s.size_pct(100.0, 100.0)
+            .padding_left(2.0)
+            .padding_right(10.0)
+    })
+}
+
+/// A terminal opened in an editor tab
+pub fn editor_terminal_view(
+    window_tab_data: Rc<WindowTabData>,
+    terminal: TerminalData,
+) -> impl View {
+    let internal_command = window_tab_data.common.internal_command;
+    let terminals = window_tab_data.main_split.terminals;
+    let terminal_scope = terminal.scope;
+    let term_id = terminal.term_id;
+    container(
+        terminal_view(
+            term_id,
+            terminal.raw.read_only(),
+            terminal.mode.read_only(),
+            terminal.run_debug.read_only(),
+            window_tab_data.terminal.clone(),
+            terminal.launch_error,
+            internal_command,
+            window_tab_data.workspace.clone(),
+        )
+        .on_secondary_click_stop(move |_| {
+            show_context_menu(
+                Menu::new("").entry(MenuItem::new("Move to Panel").action(
+                    move || {
+                        internal_command
+                            .send(InternalCommand::MoveTerminalToPanel { term_id });
+                    },
+                )),
+                None,
+            );
+        })
+        .on_event(EventListener::PointerWheel, move |event| {
+            if let Event::PointerWheel(pointer_event) = event {
+                terminal.clone().wheel_scroll(pointer_event.delta.y);
+                EventPropagation::Stop
+            } else {
+                EventPropagation::Continue
+            }
+        })
+        .on_cleanup(move || {
+            // the view is rebuilt when the terminal is moved to another editor
+            // tab, so it's only gone once it's out of the editor tabs
+            if terminals
+                .with_untracked(|terminals| !terminals.contains_key(&term_id))
+            {
+                terminal_scope.dispose();
+            }
+        })
+        .style(|s| s.size_pct(100.0, 100.0)),
+    )
+    .style(|s| {
+        s.size_pct(100.0, 100.0)
+            .padding_left(2.0)
+            .padding_right(10.0)
+    })
+}
+
+/// Where a terminal dragged from the editor tabs is dropped to move it back to
+/// the panel
+fn terminal_drop_view(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let config = window_tab_data.common.config;
+    let dragging = window_tab_data.common.dragging;
+    let internal_command = window_tab_data.common.internal_command;
+    let dragging_over = create_rw_signal(false);
+    empty()
+        .on_event(EventListener::DragEnter, move |_| {
+            if dragging
+                .with_untracked(|d| d.as_ref()?.editor_terminal())
+                .is_some()
+            {
+                dragging_over.set(true);
+                EventPropagation::Stop
+            } else {
+                EventPropagation::Continue
+            }
+        })
+        .on_event_stop(EventListener::DragLeave, move |_| {
+            dragging_over.set(false);
+        })
+        .on_event(EventListener::Drop, move |_| {
+            if let Some(term_id) =
+                dragging.with_untracked(|d| d.as_ref()?.editor_terminal())
+            {
+                dragging_over.set(false);
+                dragging.set(None);
+                internal_command
+                    .send(InternalCommand::MoveTerminalToPanel { term_id });
+                EventPropagation::Stop
+            } else {
+                EventPropagation::Continue
+            }
+        })
+        .style(move |s| {
+            let is_dragging_terminal =
+                dragging.with(|d| d.as_ref()?.editor_terminal()).is_some();
+            s.absolute()
+                .size_pct(100.0, 100.0)
+                .apply_if(!is_dragging_terminal, |s| s.pointer_events_none())
+                .apply_if(dragging_over.get(), |s| {
+                    s.background(
+                        config.get().color(LapceColor::EDITOR_DRAG_DROP_BACKGROUND),
+                    )
+                })
+        })
+}
+
 fn terminal_tab_content(window_tab_data: Rc<WindowTabData>) -> impl View {
     let terminal = window_tab_data.terminal.clone();
     tab(
@@ -361,6 +650,123 @@ fn terminal_tab_content(window_tab_data: Rc<WindowTabData>) -> impl View {
     .style(|s| s.size_pct(100.0, 100.0))
 }
 
+/// The find bar shown over the terminals, searching the active terminal
+fn terminal_find_view(terminal: TerminalPanelData) -> impl View {
+    let config = terminal.common.config;
+    let focus = terminal.common.focus;
+    let find = terminal.find.clone();
+    let visible = find.visible;
+    let find_focus = find.focus;
+    let case_sensitive = find.case_sensitive;
+    let is_regex = find.is_regex;
+    let previous = terminal.clone();
+    let next = terminal.clone();
+    let close = find.clone();
+
+    container(
+        stack((
+            stack((
+                TextInputBuilder::new()
+                    .is_focused(move || {
+                        focus.get() == Focus::Panel(PanelKind::Terminal)
+                            && visible.get()
+                            && find_focus.get()
+                    })
+                    .build_editor(find.editor.clone())
+                    .on_event_cont(EventListener::PointerDown, move |_| {
+                        find_focus.set(true);
+                    })
+                    .style(|s| s.width_pct(100.0)),
+                clickable_icon(
+                    || LapceIcons::SEARCH_CASE_SENSITIVE,
+                    move || {
+                        case_sensitive.update(|case_sensitive| {
+                            *case_sensitive = !*case_sensitive;
+                        });
+                    },
+                    move || case_sensitive.get(),
+                    || false,
+                    || "Case Sensitive",
+                    config,
+                )
+                .style(|s| s.padding_vert(4.0)),
+                clickable_icon(
+                    || LapceIcons::SEARCH_REGEX,
+                    move || {
+                        is_regex.update(|is_regex| {
+                            *is_regex = !*is_regex;
+                        });
+                    },
+                    move || is_regex.get(),
+                    || false,
+                    || "Use Regex",
+                    config,
+                )
+                .style(|s| s.padding_horiz(6.0)),
+            ))
+            .style(move |s| {
+                let config = config.get();
+                s.width(200.0)
+                    .items_center()
+                    .border(1.0)
+                    .border_radius(6.0)
+                    .border_color(config.color(LapceColor::LAPCE_BORDER))
+                    .background(config.color(LapceColor::EDITOR_BACKGROUND))
+            }),
+            clickable_icon(
+                || LapceIcons::SEARCH_BACKWARD,
+                move || {
+                    previous.find_next(Direction::Left);
+                },
+                move || false,
+                || false,
+                || "Previous Match",
+                config,
+            )
+            .style(|s| s.padding_left(6.0)),
+            clickable_icon(
+                || LapceIcons::SEARCH_FORWARD,
+                move || {
+                    next.find_next(Direction::Right);
+                },
+                move || false,
+                || false,
+                || "Next Match",
+                config,
+            )
+            .style(|s| s.padding_left(6.0)),
+            clickable_icon(
+                || LapceIcons::CLOSE,
+                move || {
+                    close.close();
+                },
+                move || false,
+                || false,
+                || "Close",
+                config,
+            )
+            .style(|s| s.padding_horiz(6.0)),
+        ))
+        .style(move |s| {
+            let config = config.get();
+            s.items_center()
+                .margin_right(20.0)
+                .background(config.color(LapceColor::PANEL_BACKGROUND))
+                .border_radius(6.0)
+                .border(1.0)
+                .border_color(config.color(LapceColor::LAPCE_BORDER))
+                .padding_vert(4.0)
+                .cursor(CursorStyle::Default)
+        }),
+    )
+    .style(move |s| {
+        s.absolute()
+            .width_pct(100.0)
+            .justify_end()
+            .apply_if(!visible.get(), |s| s.hide())
+    })
+}
+
 fn tab_secondary_click(
     internal_command: Listener<InternalCommand>,
     view_id: ViewId,
@@ -0,0 +1,448 @@
+use std::{path::PathBuf, rc::Rc};
+
+use floem::{
+    View,
+    action::show_context_menu,
+    event::EventListener,
+    menu::{Menu, MenuItem},
+    prelude::SignalTrack,
+    reactive::{
+        SignalGet, SignalUpdate, SignalWith, create_effect, create_rw_signal,
+    },
+    style::CursorStyle,
+    views::{Decorators, dyn_stack, label, scroll, stack, svg},
+};
+use lapce_rpc::testing::{TestItem, TestState};
+
+use super::{
+    data::PanelSection, kind::PanelKind, position::PanelPosition, view::PanelBuilder,
+};
+use crate::{
+    app::clickable_icon,
+    config::{color::LapceColor, icon::LapceIcons},
+    testing::{TestStatus, TestingData, format_duration},
+    window_tab::WindowTabData,
+};
+
+/// The tests are listed by file, or by package or runner when the file of a
+/// test isn't known.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct TestGroup {
+    name: String,
+    path: Option<PathBuf>,
+}
+
+fn test_group(testing: &TestingData, test: &TestItem) -> TestGroup {
+    let name = match (&test.path, &test.package) {
+        (Some(path), _) => testing
+            .common
+            .workspace
+            .path
+            .as_ref()
+            .and_then(|workspace| path.strip_prefix(workspace).ok())
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string(),
+        (None, Some(package)) => package.clone(),
+        (None, None) => test.runner.to_string(),
+    };
+    TestGroup {
+        name,
+        path: test.path.clone(),
+    }
+}
+
+pub fn testing_panel(
+    window_tab_data: Rc<WindowTabData>,
+    position: PanelPosition,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let testing = window_tab_data.testing.clone();
+    {
+        // discovering builds the tests, so it waits for the panel to be shown
+        let testing = testing.clone();
+        let panel = window_tab_data.panel.clone();
+        create_effect(move |_| {
+            panel.styles.track();
+            panel.panels.track();
+            if !testing.discovered.get_untracked()
+                && panel.is_panel_visible(&PanelKind::Testing)
+            {
+                testing.discover();
+            }
+        });
+    }
+
+    PanelBuilder::new(config, position)
+        .add(
+            "Tests",
+            stack((toolbar(testing.clone()), test_list(testing)))
+                .style(|s| s.flex_col().size_pct(100.0, 100.0)),
+            window_tab_data.panel.section_open(PanelSection::Tests),
+        )
+        .build()
+        .debug_name("Testing Panel")
+}
+
+fn toolbar(testing: TestingData) -> impl View {
+    let config = testing.common.config;
+    let busy = {
+        let testing = testing.clone();
+        move || testing.is_running() || testing.discovering.get()
+    };
+    let summary = {
+        let testing = testing.clone();
+        move || {
+            if testing.discovering.get() {
+                return "Discovering tests...".to_string();
+            }
+            if let Some(error) = testing.error.get() {
+                return error.lines().next().unwrap_or_default().to_string();
+            }
+            let (passed, failed, skipped) = testing.results.with(|results| {
+                results
+                    .values()
+                    .fold((0, 0, 0), |(p, f, s), r| match r.state {
+                        TestState::Passed => (p + 1, f, s),
+                        TestState::Failed => (p, f + 1, s),
+                        TestState::Skipped => (p, f, s + 1),
+                    })
+            });
+            if passed + failed + skipped == 0 {
+                let count = testing.tests.with(|tests| tests.len());
+                format!("{count} tests")
+            } else {
+                format!("{passed} passed, {failed} failed, {skipped} skipped")
+            }
+        }
+    };
+
+    stack((
+        {
+            let testing = testing.clone();
+            let busy = busy.clone();
+            clickable_icon(
+                || LapceIcons::START,
+                move || testing.run_all(),
+                || false,
+                busy,
+                || "Run All Tests",
+                config,
+            )
+            .style(|s| s.margin_horiz(6.0))
+        },
+        {
+            let testing = testing.clone();
+            let busy = busy.clone();
+            clickable_icon(
+                || LapceIcons::RUN_ERRORS,
+                move || testing.run_failed(),
+                || false,
+                busy,
+                || "Run Failed Tests",
+                config,
+            )
+            .style(|s| s.margin_right(6.0))
+        },
+        {
+            let testing = testing.clone();
+            clickable_icon(
+                || LapceIcons::DEBUG_RESTART,
+                move || testing.discover(),
+                || false,
+                busy,
+                || "Discover Tests",
+                config,
+            )
+            .style(|s| s.margin_right(6.0))
+        },
+        label(summary).style(move |s| {
+            let config = config.get();
+            s.flex_grow(1.0)
+                .flex_basis(0.0)
+                .min_width(0.0)
+                .text_ellipsis()
+                .color(if testing.error.with(|e| e.is_some()) {
+                    config.color(LapceColor::LAPCE_ERROR)
+                } else {
+                    config.color(LapceColor::EDITOR_DIM)
+                })
+        }),
+    ))
+    .style(|s| s.width_pct(100.0).items_center().padding_vert(4.0))
+}
+
+fn test_list(testing: TestingData) -> impl View {
+    let config = testing.common.config;
+    scroll({
+        let local_testing = testing.clone();
+        dyn_stack(
+            move || {
+                let mut groups: Vec<TestGroup> = Vec::new();
+                local_testing.tests.with(|tests| {
+                    for test in tests {
+                        let group = test_group(&local_testing, test);
+                        if !groups.contains(&group) {
+                            groups.push(group);
+                        }
+                    }
+                });
+                groups
+            },
+            |group| group.clone(),
+            move |group| group_view(testing.clone(), group),
+        )
+        .style(|s| s.width_pct(100.0).flex_col())
+    })
+    .style(move |s| {
+        s.flex_grow(1.0)
+            .flex_basis(0.0)
+            .width_pct(100.0)
+            .line_height(1.8)
+            .font_size(config.get().ui.font_size() as f32)
+    })
+}
+
+fn group_view(testing: TestingData, group: TestGroup) -> impl View {
+    let config = testing.common.config;
+    let is_hovered = create_rw_signal(false);
+    let tests = {
+        let testing = testing.clone();
+        let group = group.clone();
+        move || {
+            testing.tests.with(|tests| {
+                tests
+                    .iter()
+                    .filter(|test| test_group(&testing, test) == group)
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+        }
+    };
+
+    stack((
+        stack((
+            label({
+                let name = group.name.clone();
+                move || name.clone()
+            })
+            .style(|s| {
+                s.flex_grow(1.0)
+                    .flex_basis(0.0)
+                    .min_width(0.0)
+                    .text_ellipsis()
+                    .padding_left(10.0)
+            }),
+            {
+                let testing = testing.clone();
+                let tests = tests.clone();
+                clickable_icon(
+                    || LapceIcons::START,
+                    move || testing.run(tests()),
+                    || false,
+                    || false,
+                    || "Run Tests in File",
+                    config,
+                )
+                .style(move |s| {
+                    s.margin_right(4.0)
+                        .apply_if(!is_hovered.get(), |s| s.hide())
+                })
+            },
+        ))
+        .on_event_stop(EventListener::PointerEnter, move |_| {
+            is_hovered.set(true);
+        })
+        .on_event_stop(EventListener::PointerLeave, move |_| {
+            is_hovered.set(false);
+        })
+        .on_secondary_click_stop({
+            let testing = testing.clone();
+            move |_| {
+                let Some(path) = group.path.clone() else {
+                    return;
+                };
+                let testing = testing.clone();
+                show_context_menu(
+                    Menu::new("").entry(
+                        MenuItem::new("Run Tests in File")
+                            .action(move || testing.run_file(&path)),
+                    ),
+                    None,
+                );
+            }
+        })
+        .style(move |s| {
+            s.width_pct(100.0)
+                .items_center()
+                .padding_vert(2.0)
+                .color(config.get().color(LapceColor::PANEL_FOREGROUND_DIM))
+        }),
+        dyn_stack(
+            tests,
+            |test| test.id.clone(),
+            move |test| test_view(testing.clone(), test),
+        )
+        .style(|s| s.width_pct(100.0).flex_col()),
+    ))
+    .style(|s| s.width_pct(100.0).flex_col())
+}
+
+fn test_view(testing: TestingData, test: TestItem) -> impl View {
+    let config = testing.common.config;
+    let is_hovered = create_rw_signal(false);
+    let status = {
+        let testing = testing.clone();
+        let id = test.id.clone();
+        move || testing.status(&id)
+    };
+    let result = {
+        let testing = testing.clone();
+        let id = test.id.clone();
+        move || testing.results.with(|results| results.get(&id).cloned())
+    };
+
+    stack((
+        stack((
+            svg({
+                let status = status.clone();
+                move || {
+                    let icon = match status() {
+                        Some(TestStatus::Running) => LapceIcons::DEBUG_RESTART,
+                        _ => LapceIcons::TEST_STATUS,
+                    };
+                    config.get().ui_svg(icon)
+                }
+            })
+            .style({
+                let status = status.clone();
+                move |s| {
+                    let config = config.get();
+                    let size = config.ui.icon_size() as f32;
+                    let color = match status() {
+                        Some(TestStatus::Done(TestState::Passed)) => {
+                            config.color(LapceColor::SOURCE_CONTROL_ADDED)
+                        }
+                        Some(TestStatus::Done(TestState::Failed)) => {
+                            config.color(LapceColor::LAPCE_ERROR)
+                        }
+                        Some(TestStatus::Running) => {
+                            config.color(LapceColor::LAPCE_ICON_ACTIVE)
+                        }
+                        _ => config.color(LapceColor::EDITOR_DIM),
+                    };
+                    s.size(size, size)
+                        .min_width(size)
+                        .margin_left(20.0)
+                        .margin_right(6.0)
+                        .color(color)
+                }
+            }),
+            label({
+                let name = test.name.clone();
+                move || name.clone()
+            })
+            .style(|s| {
+                s.flex_grow(1.0)
+                    .flex_basis(0.0)
+                    .min_width(0.0)
+                    .text_ellipsis()
+            }),
+            label({
+                let result = result.clone();
+                move || {
+                    result()
+                        .and_then(|r| r.duration)
+                        .map(format_duration)
+                        .unwrap_or_default()
+                }
+            })
+            .style(move |s| {
+                s.margin_horiz(6.0)
+                    .color(config.get().color(LapceColor::EDITOR_DIM))
+            }),
+            {
+                let testing = testing.clone();
+                let test = test.clone();
+                clickable_icon(
+                    || LapceIcons::START,
+                    move || testing.run(vec![test.clone()]),
+                    || false,
+                    || false,
+                    || "Run Test",
+                    config,
+                )
+                .style(move |s| {
+                    s.margin_right(4.0)
+                        .apply_if(!is_hovered.get(), |s| s.hide())
+                })
+            },
+        ))
+        .style(|s| s.width_pct(100.0).items_center()),
+        // the first line of the failure, the rest being shown at the test
+        label({
+            let result = result.clone();
+            move || {
+                result()
+                    .filter(|r| r.state == TestState::Failed)
+                    .and_then(|r| r.message)
+                    .and_then(|m| {
+                        m.lines()
+                            .find(|line| !line.trim().is_empty())
+                            .map(|line| line.trim().to_string())
+                    })
+                    .unwrap_or_default()
+            }
+        })
+        .style(move |s| {
+            let failed = result()
+                .map(|r| r.state == TestState::Failed && r.message.is_some())
+                .unwrap_or(false);
+            s.padding_left(20.0 + config.get().ui.icon_size() as f32 + 6.0)
+                .padding_right(10.0)
+                .width_pct(100.0)
+                .min_width(0.0)
+                .text_ellipsis()
+                .color(config.get().color(LapceColor::LAPCE_ERROR))
+                .apply_if(!failed, |s| s.hide())
+        }),
+    ))
+    .on_click_stop({
+        let testing = testing.clone();
+        let test = test.clone();
+        move |_| {
+            testing.go_to_test(&test);
+        }
+    })
+    .on_secondary_click_stop(move |_| {
+        let menu = Menu::new("")
+            .entry({
+                let testing = testing.clone();
+                let test = test.clone();
+                MenuItem::new("Run Test")
+                    .action(move || testing.run(vec![test.clone()]))
+            })
+            .entry({
+                let testing = testing.clone();
+                let test = test.clone();
+                MenuItem::new("Go to Test").action(move || testing.go_to_test(&test))
+            });
+        show_context_menu(menu, None);
+    })
+    .on_event_stop(EventListener::PointerEnter, move |_| {
+        is_hovered.set(true);
+    })
+    .on_event_stop(EventListener::PointerLeave, move |_| {
+        is_hovered.set(false);
+    })
+    .style(move |s| {
+        let config = config.get();
+        s.width_pct(100.0).flex_col().padding_vert(2.0).hover(|s| {
+            s.cursor(CursorStyle::Pointer).background(
+                config
+                    .color(LapceColor::PANEL_HOVERED_BACKGROUND)
+                    .multiply_alpha(0.3),
+            )
+        })
+    })
+}
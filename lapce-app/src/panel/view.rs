@@ -20,11 +20,14 @@
     debug_view::debug_panel,
     global_search_view::global_search_panel,
     kind::PanelKind,
+    output_view::output_panel,
     plugin_view::plugin_panel,
     position::{PanelContainerPosition, PanelPosition},
     problem_view::problem_panel,
     source_control_view::source_control_panel,
+    task_view::task_panel,
     terminal_view::terminal_panel,
+    testing_view::testing_panel,
 };
 use crate::{
     app::{clickable_icon, clickable_icon_base},
@@ -507,6 +510,15 @@ fn panel_view(
                     implementation_panel(window_tab_data.clone(), position)
                         .into_any()
                 }
+                PanelKind::Task => {
+                    task_panel(window_tab_data.clone(), position).into_any()
+                }
+                PanelKind::Testing => {
+                    testing_panel(window_tab_data.clone(), position).into_any()
+                }
+                PanelKind::Output => {
+                    output_panel(window_tab_data.clone(), position).into_any()
+                }
             };
             view.style(|s| s.size_pct(100.0, 100.0))
         },
@@ -563,6 +575,9 @@ fn panel_picker(
                 PanelKind::DocumentSymbol => "Document Symbol",
                 PanelKind::References => "References",
                 PanelKind::Implementation => "Implementation",
+                PanelKind::Task => "Tasks",
+                PanelKind::Testing => "Testing",
+                PanelKind::Output => "Output",
             };
             let icon = p.svg_name();
             let is_active = {
@@ -1,13 +1,15 @@
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
     rc::Rc,
-    sync::{Arc, atomic::AtomicU64},
+    sync::{Arc, Mutex, atomic::AtomicU64},
+    time::{Duration, Instant},
 };
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use floem::{
     IntoView, View,
-    action::show_context_menu,
+    action::{exec_after, show_context_menu},
     ext_event::create_ext_action,
     keyboard::Modifiers,
     kurbo::Rect,
@@ -23,25 +25,47 @@
     },
 };
 use indexmap::IndexMap;
-use lapce_core::{command::EditCommand, directory::Directory, mode::Mode};
-use lapce_proxy::plugin::{download_volt, volt_icon, wasi::find_all_volts};
+use lapce_core::{
+    command::EditCommand,
+    directory::Directory,
+    language::LapceLanguage,
+    mode::{Mode, Modes},
+};
+use lapce_proxy::plugin::{
+    download_volt,
+    registry::{
+        DEFAULT_REGISTRY, Registry, VoltsInfo, rollback_volt_dir, volt_files,
+    },
+    volt_icon,
+    wasi::{find_all_volts, volt_languages, volt_permitted},
+};
 use lapce_rpc::{
+    RpcError,
     core::{CoreNotification, CoreRpcHandler},
-    plugin::{VoltID, VoltInfo, VoltMetadata},
+    plugin::{
+        PluginCommand, PluginId, PluginStatusItem, VoltID, VoltInfo, VoltMetadata,
+        VoltPermissions,
+    },
+    proxy::ProxyResponse,
 };
-use lsp_types::MessageType;
-use serde::{Deserialize, Serialize};
+use lsp_types::{MessageType, ShowMessageParams};
 use sha2::{Digest, Sha256};
 
 use crate::{
-    command::{CommandExecuted, CommandKind},
+    alert::AlertButton,
+    command::{CommandExecuted, CommandKind, InternalCommand, VoltCommand},
     config::{LapceConfig, color::LapceColor},
     db::LapceDb,
     editor::EditorData,
-    keypress::{KeyPressFocus, condition::Condition},
+    keypress::{
+        KeyPressFocus,
+        condition::Condition,
+        keymap::{KeyMap, KeyMapPress},
+    },
     main_split::Editors,
     markdown::{MarkdownContent, parse_markdown},
     panel::plugin_view::VOLT_DEFAULT_PNG,
+    snippet::snippet_completion_items,
     web_link::web_link,
     window_tab::CommonData,
 };
@@ -70,11 +94,12 @@ pub fn from_bytes(buf: &[u8]) -> Result<Self> {
     }
 }
 
-#[derive(Deserialize, Serialize)]
-pub struct VoltsInfo {
-    pub plugins: Vec<VoltInfo>,
-    pub total: usize,
-}
+/// The checksums of the files of an unpacked volt folder
+const VOLT_CHECKSUMS: &str = "checksums.sha256";
+
+/// When the installed volts were last checked for updates, by any window tab,
+/// so that the windows don't each check and tell about the same updates
+static LAST_UPDATE_CHECK: Mutex<Option<Instant>> = Mutex::new(None);
 
 #[derive(Clone)]
 pub struct InstalledVoltData {
@@ -106,6 +131,15 @@ pub struct PluginData {
     pub all: RwSignal<im::HashMap<VoltID, AvailableVoltData>>,
     pub disabled: RwSignal<HashSet<VoltID>>,
     pub workspace_disabled: RwSignal<HashSet<VoltID>>,
+    /// The permissions the user has granted to each volt
+    pub permissions: RwSignal<HashMap<VoltID, VoltPermissions>>,
+    /// The permissions the user refused each volt, which they aren't asked
+    /// about again unless the volt asks for something else
+    denied_permissions: RwSignal<HashMap<VoltID, VoltPermissions>>,
+    /// The status bar items of the volts, by volt and item id
+    pub status_items: RwSignal<IndexMap<(VoltID, String), PluginStatusItem>>,
+    /// The updates the user has already been told about
+    notified_updates: RwSignal<HashSet<(VoltID, String)>>,
     pub common: Rc<CommonData>,
 }
 
@@ -145,6 +179,7 @@ fn run_command(
                     .run_command(command, count, mods);
             }
             CommandKind::MotionMode(_) => {}
+            CommandKind::Plugin(_) => {}
         }
         CommandExecuted::No
     }
@@ -159,6 +194,7 @@ pub fn new(
         cx: Scope,
         disabled: HashSet<VoltID>,
         workspace_disabled: HashSet<VoltID>,
+        permissions: HashMap<VoltID, VoltPermissions>,
         editors: Editors,
         common: Rc<CommonData>,
         core_rpc: CoreRpcHandler,
@@ -173,6 +209,14 @@ pub fn new(
         };
         let disabled = cx.create_rw_signal(disabled);
         let workspace_disabled = cx.create_rw_signal(workspace_disabled);
+        let permissions = cx.create_rw_signal(permissions);
+        let db: Arc<LapceDb> = use_context().unwrap();
+        let denied_permissions = cx.create_rw_signal(
+            db.get_denied_volt_permissions()
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+        );
 
         let plugin = Self {
             installed,
@@ -180,10 +224,15 @@ pub fn new(
             all: cx.create_rw_signal(im::HashMap::new()),
             disabled,
             workspace_disabled,
+            permissions,
+            denied_permissions,
+            status_items: cx.create_rw_signal(IndexMap::new()),
+            notified_updates: cx.create_rw_signal(HashSet::new()),
             common,
         };
 
         plugin.load_available_volts("", 0, core_rpc.clone());
+        plugin.schedule_update_check(core_rpc.clone());
 
         {
             let plugin = plugin.clone();
@@ -282,22 +331,22 @@ pub fn volt_installed(&self, volt: &VoltMetadata, icon: &Option<Vec<u8>>) {
                     .and_then(|icon| VoltIcon::from_bytes(icon).ok()),
             );
         }
+        self.load_volt_languages_and_snippets(volt);
 
         let latest = volt_data.latest;
         if !is_latest {
-            let url = format!(
-                "https://plugins.lapce.dev/api/v1/plugins/{}/{}/latest",
-                volt.author, volt.name
-            );
+            let registries = self.registries();
+            let author = volt.author.clone();
+            let name = volt.name.clone();
             let send = create_ext_action(self.common.scope, move |info| {
                 if let Some(info) = info {
                     latest.set(info);
                 }
             });
             std::thread::spawn(move || {
-                let info: Option<VoltInfo> = lapce_proxy::get_url(url, None)
-                    .ok()
-                    .and_then(|r| r.json().ok());
+                let info = registries
+                    .iter()
+                    .find_map(|registry| registry.latest(&author, &name).ok());
                 send(info);
             });
         }
@@ -308,6 +357,12 @@ pub fn volt_removed(&self, volt: &VoltInfo) {
         self.installed.update(|installed| {
             installed.swap_remove(&id);
         });
+        self.remove_volt_contributions(&id);
+        // The languages of the volt stay until Lapce is restarted, as open
+        // documents can be of them
+        self.common.completion.update(|completion| {
+            completion.set_volt_snippets(id.clone(), Vec::new());
+        });
 
         if self.disabled.with_untracked(|d| d.contains(&id)) {
             self.disabled.update(|d| {
@@ -332,6 +387,301 @@ pub fn volt_removed(&self, volt: &VoltInfo) {
                     .collect(),
             );
         }
+
+        if self.permissions.with_untracked(|p| p.contains_key(&id)) {
+            self.permissions.update(|p| {
+                p.remove(&id);
+            });
+            self.save_volt_permissions();
+        }
+
+        if self
+            .denied_permissions
+            .with_untracked(|p| p.contains_key(&id))
+        {
+            self.denied_permissions.update(|p| {
+                p.remove(&id);
+            });
+            self.save_denied_volt_permissions();
+        }
+    }
+
+    /// Whether the user has granted everything the volt asks for
+    pub fn volt_permitted(&self, volt: &VoltMetadata) -> bool {
+        self.permissions
+            .with_untracked(|p| volt_permitted(volt, p.get(&volt.id())))
+    }
+
+    /// Ask the user to approve the permissions of a volt that the proxy
+    /// refused to start, either after installing or upgrading it or because
+    /// its grants were revoked. The user isn't asked again for what they
+    /// refused before.
+    pub fn volt_permissions_required(&self, volt: VoltMetadata) {
+        let denied = self
+            .denied_permissions
+            .with_untracked(|p| p.get(&volt.id()) == Some(&volt.permissions()));
+        if denied {
+            return;
+        }
+        self.ask_volt_permissions(volt);
+    }
+
+    fn ask_volt_permissions(&self, volt: VoltMetadata) {
+        let missing = self.permissions.with_untracked(|p| {
+            p.get(&volt.id())
+                .cloned()
+                .unwrap_or_default()
+                .missing(&volt.permissions())
+        });
+        let internal_command = self.common.internal_command;
+        let allow_plugin = self.clone();
+        let allow_volt = volt.clone();
+        let plugin = self.clone();
+        internal_command.send(InternalCommand::ShowAlert {
+            title: format!("{} requests additional permissions", volt.display_name),
+            msg: format!(
+                "{}\n\nThe plugin won't be started until they are allowed.",
+                permission_descriptions(&missing).join("\n")
+            ),
+            buttons: vec![
+                AlertButton {
+                    text: "Allow".to_string(),
+                    action: Rc::new(move || {
+                        internal_command.send(InternalCommand::HideAlert);
+                        allow_plugin.grant_volt_permissions(allow_volt.clone());
+                    }),
+                },
+                AlertButton {
+                    text: "Don't Allow".to_string(),
+                    action: Rc::new(move || {
+                        internal_command.send(InternalCommand::HideAlert);
+                        plugin.deny_volt_permissions(&volt);
+                    }),
+                },
+            ],
+        });
+    }
+
+    /// Don't ask about what the volt currently asks for again, until the user
+    /// grants it from the menu of the volt
+    fn deny_volt_permissions(&self, volt: &VoltMetadata) {
+        self.denied_permissions.update(|p| {
+            p.insert(volt.id(), volt.permissions());
+        });
+        self.save_denied_volt_permissions();
+    }
+
+    /// Grant the volt everything it currently asks for, replacing what it was
+    /// granted before.
+    pub fn grant_volt_permissions(&self, volt: VoltMetadata) {
+        let permissions = volt.permissions();
+        self.permissions.update(|p| {
+            p.insert(volt.id(), permissions.clone());
+        });
+        self.save_volt_permissions();
+        if self
+            .denied_permissions
+            .try_update(|p| p.remove(&volt.id()))
+            .flatten()
+            .is_some()
+        {
+            self.save_denied_volt_permissions();
+        }
+        self.common
+            .proxy
+            .grant_volt_permissions(volt.id(), permissions);
+        self.load_volt_languages_and_snippets(&volt);
+        if !self.plugin_disabled(&volt.id()) {
+            self.common.proxy.reload_volt(volt);
+        }
+    }
+
+    pub fn revoke_volt_permissions(&self, volt: VoltInfo) {
+        self.permissions.update(|p| {
+            p.remove(&volt.id());
+        });
+        self.save_volt_permissions();
+        self.remove_volt_contributions(&volt.id());
+        self.reload_volt_languages_and_snippets(&volt.id());
+        self.common.proxy.revoke_volt_permissions(volt);
+    }
+
+    /// The volt crashed, what it registered is gone with it and is registered
+    /// again by the volt if it's restarted
+    pub fn volt_crashed(&self, volt: &VoltID) {
+        self.remove_volt_contributions(volt);
+    }
+
+    pub fn volt_command_registered(
+        &self,
+        plugin_id: PluginId,
+        volt: VoltID,
+        command: PluginCommand,
+    ) {
+        let keymap = command.key.as_ref().map(|key| KeyMap {
+            key: KeyMapPress::parse(key),
+            modes: Modes::empty(),
+            when: command.when.clone(),
+            command: command.command.clone(),
+        });
+        let command = VoltCommand {
+            plugin_id,
+            volt,
+            command: command.command,
+            title: command.title,
+        };
+        let config = self.common.config.get_untracked();
+        self.common.keypress.update(|keypress| {
+            keypress.add_plugin_command(command, keymap, &config);
+        });
+    }
+
+    pub fn volt_command_unregistered(&self, volt: &VoltID, command: &str) {
+        let config = self.common.config.get_untracked();
+        self.common.keypress.update(|keypress| {
+            keypress.remove_plugin_commands(
+                |c| &c.volt == volt && c.command == command,
+                &config,
+            );
+        });
+    }
+
+    pub fn status_item_set(&self, volt: VoltID, item: PluginStatusItem) {
+        self.status_items.update(|items| {
+            items.insert((volt, item.id.clone()), item);
+        });
+    }
+
+    pub fn status_item_removed(&self, volt: VoltID, id: String) {
+        self.status_items.update(|items| {
+            items.shift_remove(&(volt, id));
+        });
+    }
+
+    /// Add the languages of the volt to the ones documents can be of, and its
+    /// snippets to the completion items of their language
+    fn load_volt_languages_and_snippets(&self, volt: &VoltMetadata) {
+        let id = volt.id();
+        let granted = self.permissions.with_untracked(|p| p.get(&id).cloned());
+        if self.plugin_disabled(&id) || !volt_permitted(volt, granted.as_ref()) {
+            self.common.completion.update(|completion| {
+                completion.set_volt_snippets(id, Vec::new());
+            });
+            return;
+        }
+
+        let mut languages_changed = false;
+        for language in volt_languages(volt, granted.as_ref()) {
+            languages_changed |= LapceLanguage::register_volt_language(&language);
+        }
+        if languages_changed {
+            self.common
+                .internal_command
+                .send(InternalCommand::VoltLanguagesChanged);
+        }
+
+        let mut snippets = Vec::new();
+        for file in volt.snippets.iter().flatten() {
+            let Some(language) = LapceLanguage::from_name(&file.language) else {
+                tracing::error!(
+                    "unknown language {} of the snippets of {}",
+                    file.language,
+                    volt.id()
+                );
+                continue;
+            };
+            let items = std::fs::read_to_string(&file.path)
+                .map_err(anyhow::Error::from)
+                .and_then(|contents| snippet_completion_items(&contents));
+            match items {
+                Ok(items) => {
+                    snippets.extend(items.into_iter().map(|item| (language, item)));
+                }
+                Err(err) => tracing::error!("{:?}", err),
+            }
+        }
+        self.common.completion.update(|completion| {
+            completion.set_volt_snippets(id, snippets);
+        });
+    }
+
+    /// Load the languages and snippets of the installed volt again, after it
+    /// was enabled or disabled, or its permissions changed
+    fn reload_volt_languages_and_snippets(&self, id: &VoltID) {
+        let volt = self
+            .installed
+            .with_untracked(|installed| installed.get(id).map(|v| v.meta))
+            .map(|meta| meta.get_untracked());
+        if let Some(volt) = volt {
+            self.load_volt_languages_and_snippets(&volt);
+        }
+    }
+
+    /// Remove the commands and status bar items of a volt that was stopped
+    fn remove_volt_contributions(&self, volt: &VoltID) {
+        let config = self.common.config.get_untracked();
+        self.common.keypress.update(|keypress| {
+            keypress.remove_plugin_commands(|c| &c.volt == volt, &config);
+        });
+        if self
+            .status_items
+            .with_untracked(|items| items.keys().any(|(v, _)| v == volt))
+        {
+            self.status_items.update(|items| {
+                items.retain(|(v, _), _| v != volt);
+            });
+        }
+    }
+
+    /// Have the volt that registered the command run it
+    pub fn run_volt_command(&self, command: &VoltCommand) {
+        self.common
+            .proxy
+            .execute_plugin_command(command.plugin_id, command.command.clone());
+    }
+
+    /// Run a command by its id, which is either a command of Lapce or one
+    /// registered by a volt
+    pub fn run_command_by_id(&self, command: &str) {
+        let cmd = self
+            .common
+            .keypress
+            .with_untracked(|keypress| keypress.commands.get(command).cloned());
+        match cmd {
+            Some(cmd) => self.common.lapce_command.send(cmd),
+            None => tracing::error!("command {command} not found"),
+        }
+    }
+
+    fn save_volt_permissions(&self) {
+        let db: Arc<LapceDb> = use_context().unwrap();
+        db.save_volt_permissions(
+            self.permissions.get_untracked().into_iter().collect(),
+        );
+    }
+
+    fn save_denied_volt_permissions(&self) {
+        let db: Arc<LapceDb> = use_context().unwrap();
+        db.save_denied_volt_permissions(
+            self.denied_permissions
+                .get_untracked()
+                .into_iter()
+                .collect(),
+        );
+    }
+
+    /// The registries from the `plugin-registries` setting, in order
+    fn registries(&self) -> Vec<Registry> {
+        let config = self.common.config.get_untracked();
+        if config.core.plugin_registries.is_empty() {
+            return vec![Registry::new(DEFAULT_REGISTRY)];
+        }
+        config
+            .core
+            .plugin_registries
+            .iter()
+            .map(|registry| Registry::new(registry))
+            .collect()
     }
 
     fn load_available_volts(
@@ -372,7 +722,10 @@ fn load_available_volts(
                                 {
                                     let volt = volt.clone();
                                     std::thread::spawn(move || {
-                                        let result = Self::load_icon(&volt);
+                                        let result = Self::load_icon(
+                                            &Registry::of(&volt),
+                                            &volt,
+                                        );
                                         send(result);
                                     });
                                 }
@@ -404,24 +757,22 @@ fn load_available_volts(
                 }
             });
 
+        let registries = self.registries();
         let query = query.to_string();
         std::thread::spawn(move || {
-            let volts = Self::query_volts(&query, offset);
+            let volts = Self::query_volts(&registries, &query, offset);
             send(volts);
         });
     }
 
-    fn load_icon(volt: &VoltInfo) -> Result<VoltIcon> {
-        let url = format!(
-            "https://plugins.lapce.dev/api/v1/plugins/{}/{}/{}/icon?id={}",
-            volt.author, volt.name, volt.version, volt.updated_at_ts
-        );
-
-        let cache_file_path = Directory::cache_directory().map(|cache_dir| {
-            let mut hasher = Sha256::new();
-            hasher.update(url.as_bytes());
-            let filename = format!("{:x}", hasher.finalize());
-            cache_dir.join(filename)
+    fn load_icon(registry: &Registry, volt: &VoltInfo) -> Result<VoltIcon> {
+        let cache_file_path = registry.icon_url(volt).and_then(|url| {
+            Directory::cache_directory().map(|cache_dir| {
+                let mut hasher = Sha256::new();
+                hasher.update(url.as_bytes());
+                let filename = format!("{:x}", hasher.finalize());
+                cache_dir.join(filename)
+            })
         });
 
         let cache_content =
@@ -430,11 +781,7 @@ fn load_icon(volt: &VoltInfo) -> Result<VoltIcon> {
         let content = match cache_content {
             Some(content) => content,
             None => {
-                let resp = lapce_proxy::get_url(&url, None)?;
-                if !resp.status().is_success() {
-                    return Err(anyhow::anyhow!("can't download icon"));
-                }
-                let buf = resp.bytes()?.to_vec();
+                let buf = registry.icon(volt)?;
 
                 if let Some(path) = cache_file_path.as_ref() {
                     if let Err(err) = std::fs::write(path, &buf) {
@@ -453,26 +800,50 @@ fn download_readme(
         volt: &VoltInfo,
         config: &LapceConfig,
     ) -> Result<Vec<MarkdownContent>> {
-        let url = format!(
-            "https://plugins.lapce.dev/api/v1/plugins/{}/{}/{}/readme",
-            volt.author, volt.name, volt.version
-        );
-        let resp = lapce_proxy::get_url(&url, None)?;
-        if resp.status() != 200 {
+        let Some(text) = Registry::of(volt).readme(volt)? else {
             let text = parse_markdown("Plugin doesn't have a README", 2.0, config);
             return Ok(text);
-        }
-        let text = resp.text()?;
+        };
         let text = parse_markdown(&text, 2.0, config);
         Ok(text)
     }
 
-    fn query_volts(query: &str, offset: usize) -> Result<VoltsInfo> {
-        let url = format!(
-            "https://plugins.lapce.dev/api/v1/plugins?q={query}&offset={offset}"
-        );
-        let plugins: VoltsInfo = lapce_proxy::get_url(url, None)?.json()?;
-        Ok(plugins)
+    /// Search all the registries, listing the results of each registry after
+    /// the ones before it, so the offset is spread over them in order.
+    fn query_volts(
+        registries: &[Registry],
+        query: &str,
+        offset: usize,
+    ) -> Result<VoltsInfo> {
+        let mut offset = offset;
+        let mut volts = VoltsInfo {
+            plugins: Vec::new(),
+            total: 0,
+        };
+        // Whether the registries so far have no more results after this page,
+        // otherwise the results of the next ones have to wait
+        let mut exhausted = true;
+        let mut error = None;
+        for registry in registries {
+            match registry.query(query, offset) {
+                Ok(info) => {
+                    if exhausted {
+                        exhausted = offset + info.plugins.len() >= info.total;
+                        volts.plugins.extend(info.plugins);
+                    }
+                    offset = offset.saturating_sub(info.total);
+                    volts.total += info.total;
+                }
+                Err(err) => {
+                    tracing::error!("{:?}", err);
+                    error.get_or_insert(err);
+                }
+            }
+        }
+        match error {
+            Some(err) if volts.total == 0 => Err(err),
+            _ => Ok(volts),
+        }
     }
 
     fn all_loaded(&self) -> bool {
@@ -522,6 +893,62 @@ pub fn install_volt(&self, info: VoltInfo) {
         }
     }
 
+    /// Install a packaged `.volt` archive or an unpacked volt folder, after
+    /// checking it against its checksum. The volt is sent to the proxy, which
+    /// installs it like the volts installed from a registry.
+    pub fn install_volt_from_path(&self, path: PathBuf, core_rpc: CoreRpcHandler) {
+        let plugin = self.clone();
+        let send = create_ext_action(
+            self.common.scope,
+            move |result: Result<(VoltMetadata, Option<Vec<u8>>)>| match result {
+                Ok((meta, icon)) => {
+                    if meta.wasm.is_some() && !plugin.plugin_disabled(&meta.id()) {
+                        plugin.reload_volt(meta.clone());
+                    }
+                    plugin.volt_installed(&meta, &icon);
+                }
+                Err(err) => {
+                    tracing::error!("{:?}", err);
+                    core_rpc.notification(CoreNotification::ShowMessage {
+                        title: "Install Plugin".to_string(),
+                        message: lsp_types::ShowMessageParams {
+                            typ: MessageType::ERROR,
+                            message: err.to_string(),
+                        },
+                    });
+                }
+            },
+        );
+        let proxy = self.common.proxy.clone();
+        std::thread::spawn(move || {
+            let archive = verify_volt_checksum(&path).and_then(|_| {
+                if path.is_dir() {
+                    volt_archive(&path)
+                } else {
+                    Ok(std::fs::read(&path)?)
+                }
+            });
+            match archive {
+                Ok(archive) => {
+                    proxy.install_volt_archive(
+                        archive,
+                        move |result: Result<ProxyResponse, RpcError>| {
+                            send(match result {
+                                Ok(ProxyResponse::InstallVoltArchiveResponse {
+                                    volt,
+                                    icon,
+                                }) => Ok((volt, icon)),
+                                Ok(_) => Err(anyhow!("unexpected response")),
+                                Err(err) => Err(anyhow!(err.message)),
+                            });
+                        },
+                    );
+                }
+                Err(err) => send(Err(err)),
+            }
+        });
+    }
+
     pub fn plugin_disabled(&self, id: &VoltID) -> bool {
         self.disabled.with_untracked(|d| d.contains(id))
             || self.workspace_disabled.with_untracked(|d| d.contains(id))
@@ -532,6 +959,7 @@ pub fn enable_volt(&self, volt: VoltInfo) {
         self.disabled.update(|d| {
             d.remove(&id);
         });
+        self.reload_volt_languages_and_snippets(&id);
         if !self.plugin_disabled(&id) {
             self.common.proxy.enable_volt(volt);
         }
@@ -541,9 +969,11 @@ pub fn enable_volt(&self, volt: VoltInfo) {
 
     pub fn disable_volt(&self, volt: VoltInfo) {
         let id = volt.id();
+        self.remove_volt_contributions(&id);
         self.disabled.update(|d| {
-            d.insert(id);
+            d.insert(id.clone());
         });
+        self.reload_volt_languages_and_snippets(&id);
         self.common.proxy.disable_volt(volt);
         let db: Arc<LapceDb> = use_context().unwrap();
         db.save_disabled_volts(self.disabled.get_untracked().into_iter().collect());
@@ -554,6 +984,7 @@ pub fn enable_volt_for_ws(&self, volt: VoltInfo) {
         self.workspace_disabled.update(|d| {
             d.remove(&id);
         });
+        self.reload_volt_languages_and_snippets(&id);
         if !self.plugin_disabled(&id) {
             self.common.proxy.enable_volt(volt);
         }
@@ -566,9 +997,11 @@ pub fn enable_volt_for_ws(&self, volt: VoltInfo) {
 
     pub fn disable_volt_for_ws(&self, volt: VoltInfo) {
         let id = volt.id();
+        self.remove_volt_contributions(&id);
         self.workspace_disabled.update(|d| {
-            d.insert(id);
+            d.insert(id.clone());
         });
+        self.reload_volt_languages_and_snippets(&id);
         self.common.proxy.disable_volt(volt);
         let db: Arc<LapceDb> = use_context().unwrap();
         db.save_workspace_disabled_volts(
@@ -607,21 +1040,246 @@ pub fn reload_volt(&self, volt: VoltMetadata) {
         self.common.proxy.reload_volt(volt);
     }
 
+    /// Go back to the version of the volt that was installed before the
+    /// current one
+    pub fn rollback_volt(&self, volt: VoltMetadata) {
+        if volt.wasm.is_some() {
+            self.common.proxy.rollback_volt(volt);
+            return;
+        }
+
+        let plugin = self.clone();
+        let send = create_ext_action(
+            self.common.scope,
+            move |result: Result<(VoltMetadata, Option<Vec<u8>>)>| match result {
+                Ok((meta, icon)) => {
+                    plugin.volt_installed(&meta, &icon);
+                }
+                Err(err) => {
+                    tracing::error!("{:?}", err);
+                }
+            },
+        );
+        std::thread::spawn(move || {
+            let rollback = || -> Result<(VoltMetadata, Option<Vec<u8>>)> {
+                let dir =
+                    volt.dir.as_ref().ok_or_else(|| anyhow!("don't have dir"))?;
+                let meta = rollback_volt_dir(dir)?;
+                let icon = volt_icon(&meta);
+                Ok((meta, icon))
+            };
+            send(rollback());
+        });
+    }
+
+    /// The version the volt is pinned to in the `pinned-plugins` setting
+    pub fn pinned_version(&self, id: &VoltID) -> Option<String> {
+        let config = self.common.config.get_untracked();
+        pinned_version(&config, id).map(|version| version.to_string())
+    }
+
+    /// Pin the volt to the version, or unpin it, in the user's settings
+    pub fn pin_volt(&self, id: &VoltID, version: Option<&str>) {
+        let config = self.common.config.get_untracked();
+        let volt = id.to_string();
+        let mut pins: Vec<String> = config
+            .core
+            .pinned_plugins
+            .iter()
+            .filter(|pin| pin.rsplit_once('@').is_none_or(|(v, _)| v != volt))
+            .cloned()
+            .collect();
+        if let Some(version) = version {
+            pins.push(format!("{volt}@{version}"));
+        }
+        LapceConfig::update_file(
+            "core",
+            "pinned-plugins",
+            toml_edit::Value::Array(pins.into_iter().collect()),
+        );
+    }
+
+    /// The installed volts that have an update, with the version to install
+    pub fn volt_updates(&self) -> Vec<(VoltMetadata, VoltInfo)> {
+        let config = self.common.config.get_untracked();
+        self.installed.with_untracked(|installed| {
+            installed
+                .values()
+                .filter_map(|volt| {
+                    let meta = volt.meta.get_untracked();
+                    let pinned = pinned_version(&config, &meta.id());
+                    let update = volt.latest.with_untracked(|latest| {
+                        volt_update(pinned, &meta, latest)
+                    })?;
+                    Some((meta, update))
+                })
+                .collect()
+        })
+    }
+
+    /// Install all the updates of the installed volts, returning the names
+    /// of the volts that are updated
+    pub fn update_all_volts(&self) -> Vec<String> {
+        self.volt_updates()
+            .into_iter()
+            .map(|(meta, update)| {
+                self.install_volt(update);
+                meta.display_name
+            })
+            .collect()
+    }
+
+    /// Look up the latest version of each installed volt in the registries,
+    /// then install the updates if `auto-update-plugins` is on, or tell the
+    /// user about them otherwise
+    pub fn check_volt_updates(&self, core_rpc: CoreRpcHandler) {
+        *LAST_UPDATE_CHECK.lock().unwrap() = Some(Instant::now());
+
+        let volts: Vec<VoltID> = self
+            .installed
+            .with_untracked(|installed| installed.keys().cloned().collect());
+        let registries = self.registries();
+        let plugin = self.clone();
+        let send = create_ext_action(
+            self.common.scope,
+            move |latest: Vec<(VoltID, VoltInfo)>| {
+                plugin.installed.with_untracked(|installed| {
+                    for (id, info) in latest {
+                        if let Some(volt) = installed.get(&id) {
+                            volt.latest.set(info);
+                        }
+                    }
+                });
+                plugin.volt_updates_checked(&core_rpc);
+            },
+        );
+        std::thread::spawn(move || {
+            let latest = volts
+                .into_iter()
+                .filter_map(|id| {
+                    let info = registries.iter().find_map(|registry| {
+                        registry.latest(&id.author, &id.name).ok()
+                    })?;
+                    Some((id, info))
+                })
+                .collect();
+            send(latest);
+        });
+    }
+
+    fn volt_updates_checked(&self, core_rpc: &CoreRpcHandler) {
+        if self.common.config.get_untracked().core.auto_update_plugins {
+            let updated = self.update_all_volts();
+            if !updated.is_empty() {
+                show_update_message(
+                    core_rpc,
+                    format!("Updating {}", updated.join(", ")),
+                );
+            }
+            return;
+        }
+
+        // Only the updates found since the last check are worth a message
+        let new: Vec<String> = self
+            .volt_updates()
+            .into_iter()
+            .filter(|(meta, update)| {
+                self.notified_updates.try_update(|notified| {
+                    notified.insert((meta.id(), update.version.clone()))
+                }) == Some(true)
+            })
+            .map(|(meta, update)| {
+                format!("{} v{}", meta.display_name, update.version)
+            })
+            .collect();
+        if !new.is_empty() {
+            show_update_message(
+                core_rpc,
+                format!(
+                    "Updates are available for {}. Run \"Update All Plugins\" to \
+                 install them.",
+                    new.join(", ")
+                ),
+            );
+        }
+    }
+
+    /// Check for updates every `plugin-update-check-interval` minutes, which
+    /// is looked at every minute so that changes to it apply right away, until
+    /// the window tab is closed
+    fn schedule_update_check(&self, core_rpc: CoreRpcHandler) {
+        let plugin = self.clone();
+        exec_after(Duration::from_secs(60), move |_| {
+            // the signals are gone with the scope of the closed window tab
+            if plugin.notified_updates.try_with_untracked(|_| ()).is_none() {
+                return;
+            }
+            let interval = plugin
+                .common
+                .config
+                .get_untracked()
+                .core
+                .plugin_update_check_interval;
+            let due = interval > 0
+                && LAST_UPDATE_CHECK.lock().unwrap().is_none_or(|last| {
+                    last.elapsed() >= Duration::from_secs(interval * 60)
+                });
+            if due {
+                plugin.check_volt_updates(core_rpc.clone());
+            }
+            plugin.schedule_update_check(core_rpc);
+        });
+    }
+
     pub fn plugin_controls(&self, meta: VoltMetadata, latest: VoltInfo) -> Menu {
         let volt_id = meta.id();
+        let pinned = self.pinned_version(&volt_id);
         let mut menu = Menu::new("");
-        if meta.version != latest.version {
-            menu = menu
-                .entry(MenuItem::new("Upgrade Plugin").action({
-                    let plugin = self.clone();
-                    let info = latest.clone();
-                    move || {
-                        plugin.install_volt(info.clone());
-                    }
-                }))
-                .separator();
+        if let Some(update) = volt_update(pinned.as_deref(), &meta, &latest) {
+            let title = if pinned.is_some() {
+                format!("Install Pinned Version v{}", update.version)
+            } else {
+                "Upgrade Plugin".to_string()
+            };
+            menu = menu.entry(MenuItem::new(title).action({
+                let plugin = self.clone();
+                move || {
+                    plugin.install_volt(update.clone());
+                }
+            }));
         }
+        if let Some(previous) = meta.previous.as_ref() {
+            menu = menu.entry(
+                MenuItem::new(format!("Roll Back to v{}", previous.version)).action(
+                    {
+                        let plugin = self.clone();
+                        let meta = meta.clone();
+                        move || {
+                            plugin.rollback_volt(meta.clone());
+                        }
+                    },
+                ),
+            );
+        }
+        menu = menu.entry(match pinned {
+            Some(version) => MenuItem::new(format!("Unpin v{version}")).action({
+                let plugin = self.clone();
+                let volt_id = volt_id.clone();
+                move || {
+                    plugin.pin_volt(&volt_id, None);
+                }
+            }),
+            None => MenuItem::new(format!("Pin to v{}", meta.version)).action({
+                let plugin = self.clone();
+                let volt_id = volt_id.clone();
+                let version = meta.version.clone();
+                move || {
+                    plugin.pin_volt(&volt_id, Some(&version));
+                }
+            }),
+        });
         menu = menu
+            .separator()
             .entry(MenuItem::new("Reload Plugin").action({
                 let plugin = self.clone();
                 let meta = meta.clone();
@@ -688,6 +1346,32 @@ pub fn plugin_controls(&self, meta: VoltMetadata, latest: VoltInfo) -> Menu {
                     }),
             )
             .separator()
+            .entry(
+                MenuItem::new("Grant Permissions")
+                    .enabled(!self.volt_permitted(&meta))
+                    .action({
+                        let plugin = self.clone();
+                        let meta = meta.clone();
+                        move || {
+                            plugin.ask_volt_permissions(meta.clone());
+                        }
+                    }),
+            )
+            .entry(
+                MenuItem::new("Revoke Permissions")
+                    .enabled(
+                        self.permissions
+                            .with_untracked(|p| p.contains_key(&volt_id)),
+                    )
+                    .action({
+                        let plugin = self.clone();
+                        let volt = meta.info();
+                        move || {
+                            plugin.revoke_volt_permissions(volt.clone());
+                        }
+                    }),
+            )
+            .separator()
             .entry(MenuItem::new("Uninstall").action({
                 let plugin = self.clone();
                 move || {
@@ -698,22 +1382,169 @@ pub fn plugin_controls(&self, meta: VoltMetadata, latest: VoltInfo) -> Menu {
     }
 }
 
+fn show_update_message(core_rpc: &CoreRpcHandler, message: String) {
+    core_rpc.show_message(
+        "Plugin Updates".to_string(),
+        ShowMessageParams {
+            typ: MessageType::INFO,
+            message,
+        },
+    );
+}
+
+/// The version the volt is pinned to in the `pinned-plugins` setting, where
+/// pins are written as `<author>.<name>@<version>`
+pub fn pinned_version<'a>(config: &'a LapceConfig, id: &VoltID) -> Option<&'a str> {
+    let id = id.to_string();
+    config.core.pinned_plugins.iter().find_map(|pin| {
+        let (volt, version) = pin.rsplit_once('@')?;
+        (volt == id).then_some(version)
+    })
+}
+
+/// The version an installed volt should be updated to, which is its pinned
+/// version if it has one and the latest version otherwise
+pub fn volt_update(
+    pinned: Option<&str>,
+    installed: &VoltMetadata,
+    latest: &VoltInfo,
+) -> Option<VoltInfo> {
+    let version = pinned.unwrap_or(&latest.version);
+    if installed.version == version {
+        return None;
+    }
+    Some(VoltInfo {
+        version: version.to_string(),
+        ..latest.clone()
+    })
+}
+
+/// Check a `.volt` archive against the `<archive>.sha256` file next to it, or
+/// an unpacked volt folder against the `checksums.sha256` file inside it, which
+/// has to list every file of the volt. Both are in the format of `sha256sum`.
+fn verify_volt_checksum(path: &Path) -> Result<()> {
+    fn sha256(path: &Path) -> Result<String> {
+        let mut hasher = Sha256::new();
+        hasher.update(std::fs::read(path)?);
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// The checksums of a `sha256sum` file, by file name
+    fn read_checksums(path: &Path) -> Result<HashMap<String, String>> {
+        let content = std::fs::read_to_string(path).map_err(|_| {
+            anyhow!("checksum file {} can't be read", path.display())
+        })?;
+        Ok(content
+            .lines()
+            .filter_map(|line| {
+                let (checksum, file) =
+                    line.trim().split_once(char::is_whitespace)?;
+                let file = file.trim_start().trim_start_matches('*');
+                Some((file.to_string(), checksum.to_lowercase()))
+            })
+            .collect())
+    }
+
+    if path.is_dir() {
+        let mut checksums = read_checksums(&path.join(VOLT_CHECKSUMS))?;
+        for file in volt_files(path)? {
+            if file == Path::new(VOLT_CHECKSUMS) {
+                continue;
+            }
+            let name = file.to_string_lossy().replace('\\', "/");
+            let expected = checksums
+                .remove(&name)
+                .ok_or_else(|| anyhow!("{name} isn't in {VOLT_CHECKSUMS}"))?;
+            if sha256(&path.join(&file))? != expected {
+                return Err(anyhow!("checksum of {name} doesn't match"));
+            }
+        }
+        if let Some(name) = checksums.keys().next() {
+            return Err(anyhow!("{name} is in {VOLT_CHECKSUMS} but is missing"));
+        }
+    } else {
+        let mut checksum_path = path.as_os_str().to_owned();
+        checksum_path.push(".sha256");
+        let checksums = read_checksums(Path::new(&checksum_path))?;
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        // The file name is optional when the file only has one checksum
+        let expected = checksums
+            .get(&name)
+            .or_else(|| checksums.values().next().filter(|_| checksums.len() == 1))
+            .ok_or_else(|| anyhow!("no checksum for {name}"))?;
+        if sha256(path)? != *expected {
+            return Err(anyhow!("checksum of {name} doesn't match"));
+        }
+    }
+    Ok(())
+}
+
+/// Pack an unpacked volt folder into a gzipped tar archive
+fn volt_archive(path: &Path) -> Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+        Vec::new(),
+        flate2::Compression::default(),
+    ));
+    for file in volt_files(path)? {
+        builder.append_path_with_name(path.join(&file), &file)?;
+    }
+    Ok(builder.into_inner()?.finish()?)
+}
+
+/// Human readable descriptions of each permission, for showing to the user
+fn permission_descriptions(permissions: &VoltPermissions) -> Vec<String> {
+    permissions
+        .network
+        .iter()
+        .map(|host| format!("Access the network at {host}"))
+        .chain(
+            permissions
+                .executables
+                .iter()
+                .map(|program| format!("Run {program}")),
+        )
+        .chain(
+            permissions
+                .env
+                .iter()
+                .map(|var| format!("Read the environment variable {var}")),
+        )
+        .chain(
+            permissions
+                .filesystem
+                .iter()
+                .map(|dir| format!("Access files in {dir}")),
+        )
+        .collect()
+}
+
 pub fn plugin_info_view(plugin: PluginData, volt: VoltID) -> impl View {
     let config = plugin.common.config;
     let header_rect = create_rw_signal(Rect::ZERO);
     let scroll_width: RwSignal<f64> = create_rw_signal(0.0);
     let internal_command = plugin.common.internal_command;
     let local_plugin = plugin.clone();
+    let volt_id = volt.clone();
     let plugin_info = create_memo(move |_| {
         plugin
             .installed
             .with(|volts| {
                 volts.get(&volt).map(|v| {
+                    let meta = v.meta.get();
+                    // A pinned volt is up to date at its pinned version
+                    let latest = v.latest.with(|latest| {
+                        config.with(|config| {
+                            volt_update(pinned_version(config, &volt), &meta, latest)
+                        })
+                    });
                     (
-                        Some(v.meta.get()),
-                        v.meta.get().info(),
+                        Some(meta.clone()),
+                        meta.info(),
                         v.icon.get(),
-                        Some(v.latest.get()),
+                        Some(latest.unwrap_or_else(|| meta.info())),
                         None,
                     )
                 })
@@ -738,6 +1569,7 @@ pub fn plugin_info_view(plugin: PluginData, volt: VoltID) -> impl View {
             .as_ref()
             .and_then(|(_, _, _, _, installing)| *installing);
         let local_version_info = version_info.clone();
+        let volt_id = volt_id.clone();
         let control = {
             move |version_info: Option<(String, Option<String>)>| match version_info
                 .as_ref()
@@ -746,6 +1578,11 @@ pub fn plugin_info_view(plugin: PluginData, volt: VoltID) -> impl View {
                     None => (false, false),
                 }) {
                 Some((true, true)) => "Installed ▼",
+                Some((true, false))
+                    if config.with(|c| pinned_version(c, &volt_id).is_some()) =>
+                {
+                    "Install Pinned ▼"
+                }
                 Some((true, false)) => "Upgrade ▼",
                 _ => {
                     if installing.map(|i| i.get()).unwrap_or(false) {
@@ -910,6 +1747,49 @@ pub fn plugin_info_view(plugin: PluginData, volt: VoltID) -> impl View {
                             .style(move |s| {
                                 s.color(config.get().color(LapceColor::EDITOR_DIM))
                             }),
+                            {
+                                let meta = plugin_info
+                                    .as_ref()
+                                    .and_then(|(meta, _, _, _, _)| meta.clone());
+                                let installed = meta.is_some();
+                                let plugin = local_plugin.clone();
+                                label(move || {
+                                    let Some(meta) = meta.as_ref() else {
+                                        return String::new();
+                                    };
+                                    let requested = meta.permissions();
+                                    if requested.is_empty() {
+                                        return "Permissions: none".to_string();
+                                    }
+                                    let permitted = plugin.permissions.with(|p| {
+                                        volt_permitted(meta, p.get(&meta.id()))
+                                    });
+                                    let status = if permitted {
+                                        "granted"
+                                    } else {
+                                        "not granted"
+                                    };
+                                    format!(
+                                        "Permissions ({status}): {}",
+                                        permission_descriptions(&requested)
+                                            .join(", ")
+                                    )
+                                })
+                                .style(move |s| {
+                                    let scroll_width = scroll_width.get();
+                                    s.max_width(
+                                        scroll_width.clamp(
+                                            200.0 + 60.0 * 2.0 + 200.0,
+                                            800.0,
+                                        ) - 60.0 * 2.0
+                                            - 200.0,
+                                    )
+                                    .color(
+                                        config.get().color(LapceColor::EDITOR_DIM),
+                                    )
+                                    .apply_if(!installed, |s| s.hide())
+                                })
+                            },
                             version_view(local_plugin.clone(), plugin_info.clone()),
                         ))
                         .style(|s| s.flex_col().line_height(1.6)),
@@ -935,9 +1815,23 @@ pub fn plugin_info_view(plugin: PluginData, volt: VoltID) -> impl View {
                         let info = plugin_info
                             .as_ref()
                             .map(|(_, info, _, _, _)| info.to_owned());
+                        // What changed since the version the volt was updated
+                        // from goes above the readme
+                        let changes = plugin_info
+                            .as_ref()
+                            .and_then(|(meta, _, _, _, _)| {
+                                meta.as_ref()?.previous.clone()
+                            })
+                            .and_then(|previous| {
+                                Some(format!(
+                                    "# Changes since v{}\n\n{}\n\n---\n",
+                                    previous.version, previous.changes?
+                                ))
+                            });
                         create_effect(move |_| {
                             let config = config.get();
                             let info = info.clone();
+                            let changes = changes.clone();
                             if let Some(info) = info {
                                 let cx = Scope::current();
                                 let send = create_ext_action(cx, move |result| {
@@ -947,7 +1841,16 @@ pub fn plugin_info_view(plugin: PluginData, volt: VoltID) -> impl View {
                                 });
                                 std::thread::spawn(move || {
                                     let result =
-                                        PluginData::download_readme(&info, &config);
+                                        PluginData::download_readme(&info, &config)
+                                            .map(|readme| match changes {
+                                                Some(changes) => parse_markdown(
+                                                    &changes, 2.0, &config,
+                                                )
+                                                .into_iter()
+                                                .chain(readme)
+                                                .collect(),
+                                                None => readme,
+                                            });
                                     send(result);
                                 });
                             }
@@ -1021,3 +1924,71 @@ pub fn plugin_info_view(plugin: PluginData, volt: VoltID) -> impl View {
     .style(|s| s.absolute().size_full())
     .debug_name("Plugin Info")
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use sha2::{Digest, Sha256};
+
+    use super::{VOLT_CHECKSUMS, verify_volt_checksum};
+
+    fn sha256(content: &str) -> String {
+        format!("{:x}", Sha256::digest(content.as_bytes()))
+    }
+
+    /// A volt folder with a `volt.toml` and a `README.md`, and a checksums
+    /// file with the lines
+    fn volt_dir(checksums: &[String]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("volt.toml"), "name = \"test\"").unwrap();
+        fs::write(dir.path().join("README.md"), "# Test").unwrap();
+        fs::write(dir.path().join(VOLT_CHECKSUMS), checksums.join("\n")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_verify_volt_folder_checksum() {
+        let toml = format!("{}  volt.toml", sha256("name = \"test\""));
+        let readme = format!("{} *README.md", sha256("# Test"));
+
+        let dir = volt_dir(&[toml.clone(), readme.clone()]);
+        assert!(verify_volt_checksum(dir.path()).is_ok());
+
+        let bad = format!("{}  README.md", sha256("# Other"));
+        let dir = volt_dir(&[toml.clone(), bad]);
+        assert!(verify_volt_checksum(dir.path()).is_err());
+
+        // A file that isn't listed
+        let dir = volt_dir(&[toml.clone()]);
+        assert!(verify_volt_checksum(dir.path()).is_err());
+
+        // A file that is listed but is missing
+        let missing = format!("{}  main.wasm", sha256(""));
+        let dir = volt_dir(&[toml, readme, missing]);
+        assert!(verify_volt_checksum(dir.path()).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("volt.toml"), "").unwrap();
+        assert!(verify_volt_checksum(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_verify_volt_archive_checksum() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("test.volt");
+        let checksum = dir.path().join("test.volt.sha256");
+        fs::write(&archive, "archive").unwrap();
+
+        assert!(verify_volt_checksum(&archive).is_err());
+
+        fs::write(&checksum, format!("{}  test.volt", sha256("archive"))).unwrap();
+        assert!(verify_volt_checksum(&archive).is_ok());
+
+        fs::write(&checksum, sha256("archive").to_uppercase()).unwrap();
+        assert!(verify_volt_checksum(&archive).is_ok());
+
+        fs::write(&checksum, format!("{}  test.volt", sha256("other"))).unwrap();
+        assert!(verify_volt_checksum(&archive).is_err());
+    }
+}
@@ -9,7 +9,7 @@
 use lapce_proxy::dispatch::Dispatcher;
 use lapce_rpc::{
     core::{CoreHandler, CoreNotification, CoreRpcHandler},
-    plugin::VoltID,
+    plugin::{LanguageServerConfig, LanguageServerPolicy, VoltID, VoltPermissions},
     proxy::{ProxyRpcHandler, ProxyStatus},
     terminal::TermId,
 };
@@ -48,8 +48,11 @@ pub fn shutdown(&self) {
 pub fn new_proxy(
     workspace: Arc<LapceWorkspace>,
     disabled_volts: Vec<VoltID>,
+    volt_permissions: Vec<(VoltID, VoltPermissions)>,
     extra_plugin_paths: Vec<PathBuf>,
     plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
+    language_servers: HashMap<String, LanguageServerConfig>,
+    language_server_policies: HashMap<String, LanguageServerPolicy>,
     term_tx: Sender<(TermId, TermEvent)>,
 ) -> ProxyData {
     let proxy_rpc = ProxyRpcHandler::new();
@@ -67,8 +70,11 @@ pub fn new_proxy(
                 proxy_rpc.initialize(
                     workspace.path.clone(),
                     disabled_volts,
+                    volt_permissions,
                     extra_plugin_paths,
                     plugin_configurations,
+                    language_servers,
+                    language_server_policies,
                     1,
                     1,
                 );
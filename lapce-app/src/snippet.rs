@@ -1,9 +1,13 @@
 use core::fmt;
-use std::{fmt::Display, str::FromStr};
+use std::{collections::BTreeMap, fmt::Display, str::FromStr};
 
 use anyhow::Error;
+use lsp_types::{
+    CompletionItem, CompletionItemKind, Documentation, InsertTextFormat,
+};
 use once_cell::sync::Lazy;
 use regex::Regex;
+use serde::Deserialize;
 
 #[derive(Debug, PartialEq)]
 pub enum SnippetElement {
@@ -280,6 +284,59 @@ pub fn elements_tabs(
     }
 }
 
+/// A snippet of a snippet file of a volt. The files have the format of VS
+/// Code's snippet files, which are an object of the snippets by their name.
+#[derive(Deserialize)]
+struct SnippetDefinition {
+    prefix: OneOrMany,
+    body: OneOrMany,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            OneOrMany::One(s) => vec![s],
+            OneOrMany::Many(v) => v,
+        }
+    }
+}
+
+/// The completion items of the snippets of a snippet file, one for each
+/// prefix of a snippet
+pub fn snippet_completion_items(
+    contents: &str,
+) -> anyhow::Result<Vec<CompletionItem>> {
+    let snippets: BTreeMap<String, SnippetDefinition> =
+        serde_json::from_str(contents)?;
+    let mut items = Vec::new();
+    for (name, snippet) in snippets {
+        // The lines of a body in a list are joined
+        let body = snippet.body.into_vec().join("\n");
+        let text = Snippet::from_str(&body)?.text();
+        let detail = snippet.description.unwrap_or(name);
+        for prefix in snippet.prefix.into_vec() {
+            items.push(CompletionItem {
+                label: prefix,
+                kind: Some(CompletionItemKind::SNIPPET),
+                detail: Some(detail.clone()),
+                documentation: Some(Documentation::String(text.clone())),
+                insert_text: Some(body.clone()),
+                insert_text_format: Some(InsertTextFormat::SNIPPET),
+                ..Default::default()
+            });
+        }
+    }
+    Ok(items)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -856,4 +913,37 @@ fn test_extract_text() {
             Snippet::extract_text(s, end + 1, &['$', '{', '}', '\\'], &[])
         );
     }
+
+    #[test]
+    fn test_snippet_completion_items() {
+        let contents = r##"{
+            "Print": {
+                "prefix": ["print", "pr"],
+                "body": ["println!(\"$1\");", "$0"],
+                "description": "Print a line"
+            },
+            "Test": {
+                "prefix": "test",
+                "body": "#[test]\nfn ${1:name}() {}"
+            }
+        }"##;
+        let items = snippet_completion_items(contents).unwrap();
+        let labels: Vec<_> = items.iter().map(|i| i.label.as_str()).collect();
+        assert_eq!(labels, vec!["print", "pr", "test"]);
+
+        let print = &items[0];
+        assert_eq!(print.kind, Some(CompletionItemKind::SNIPPET));
+        assert_eq!(print.insert_text_format, Some(InsertTextFormat::SNIPPET));
+        assert_eq!(print.insert_text.as_deref(), Some("println!(\"$1\");\n$0"));
+        assert_eq!(print.detail.as_deref(), Some("Print a line"));
+
+        // The name is shown when there's no description
+        assert_eq!(items[2].detail.as_deref(), Some("Test"));
+        assert_eq!(
+            items[2].documentation,
+            Some(Documentation::String("#[test]\nfn name() {}".to_string()))
+        );
+
+        assert!(snippet_completion_items("[]").is_err());
+    }
 }
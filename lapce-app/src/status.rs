@@ -14,16 +14,18 @@
 };
 use indexmap::IndexMap;
 use lapce_core::mode::{Mode, VisualMode};
+use lapce_rpc::plugin::PluginStatusItem;
 use lsp_types::{DiagnosticSeverity, ProgressToken};
 
 use crate::{
-    app::clickable_icon,
+    app::{clickable_icon, tooltip_label},
     command::LapceWorkbenchCommand,
     config::{LapceConfig, color::LapceColor, icon::LapceIcons},
     editor::EditorData,
     listener::Listener,
     palette::kind::PaletteKind,
     panel::{kind::PanelKind, position::PanelContainerPosition},
+    plugin::PluginData,
     source_control::SourceControlData,
     window_tab::{WindowTabData, WorkProgress},
 };
@@ -40,6 +42,7 @@ pub fn status(
     let editor = window_tab_data.main_split.active_editor;
     let panel = window_tab_data.panel.clone();
     let palette = window_tab_data.palette.clone();
+    let plugin = window_tab_data.plugin.clone();
     let diagnostic_count = create_memo(move |_| {
         let mut errors = 0;
         let mut warnings = 0;
@@ -226,6 +229,7 @@ pub fn status(
                 })
             },
             progress_view(config, progresses),
+            plugin_status_items(config, plugin),
         ))
         .style(|s| {
             s.height_pct(100.0)
@@ -437,6 +441,56 @@ fn progress_view(
     .style(move |s| s.flex_row().height_pct(100.0).min_width(0.0))
 }
 
+fn plugin_status_items(
+    config: ReadSignal<Arc<LapceConfig>>,
+    plugin: PluginData,
+) -> impl View {
+    let status_items = plugin.status_items;
+    dyn_stack(
+        move || status_items.get(),
+        |(key, item)| (key.clone(), item.clone()),
+        move |(_, item)| {
+            let plugin = plugin.clone();
+            let PluginStatusItem {
+                text,
+                tooltip,
+                command,
+                ..
+            } = item;
+            let clickable = command.is_some();
+            let tooltip = tooltip.unwrap_or_else(|| text.clone());
+            tooltip_label(
+                config,
+                label(move || text.clone()).style(move |s| {
+                    s.height_pct(100.0)
+                        .min_width(0.0)
+                        .padding_horiz(10.0)
+                        .text_ellipsis()
+                        .selectable(false)
+                        .items_center()
+                        .color(config.get().color(LapceColor::STATUS_FOREGROUND))
+                        .apply_if(clickable, |s| {
+                            s.hover(|s| {
+                                s.cursor(CursorStyle::Pointer).background(
+                                    config
+                                        .get()
+                                        .color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                                )
+                            })
+                        })
+                }),
+                move || tooltip.clone(),
+            )
+            .on_click_stop(move |_| {
+                if let Some(command) = command.as_ref() {
+                    plugin.run_command_by_id(command);
+                }
+            })
+        },
+    )
+    .style(move |s| s.flex_row().height_pct(100.0).min_width(0.0))
+}
+
 fn status_text<S: std::fmt::Display + 'static>(
     config: ReadSignal<Arc<LapceConfig>>,
     editor: Memo<Option<EditorData>>,
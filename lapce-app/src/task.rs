@@ -0,0 +1,157 @@
+use std::rc::Rc;
+
+use floem::{
+    ext_event::create_ext_action,
+    reactive::{RwSignal, Scope, SignalGet, SignalUpdate, SignalWith},
+};
+use lapce_rpc::{
+    dap_types::{ConfigSource, ProblemMatcher, RunDebugConfig},
+    proxy::ProxyResponse,
+    task::{DetectedTask, TaskKind, TaskSource},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{command::InternalCommand, debug::RunDebugMode, window_tab::CommonData};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskStatus {
+    Running,
+    Exited(Option<i32>),
+}
+
+/// The default tasks of the workspace, which are saved with it
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TaskInfo {
+    pub default_build: Option<String>,
+    pub default_test: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct TaskData {
+    pub tasks: RwSignal<im::Vector<DetectedTask>>,
+    /// The status of the tasks that have been run, by label
+    pub statuses: RwSignal<im::HashMap<String, TaskStatus>>,
+    pub default_build: RwSignal<Option<String>>,
+    pub default_test: RwSignal<Option<String>>,
+    pub common: Rc<CommonData>,
+}
+
+impl TaskData {
+    pub fn new(cx: Scope, info: Option<&TaskInfo>, common: Rc<CommonData>) -> Self {
+        let info = info.cloned().unwrap_or_default();
+        Self {
+            tasks: cx.create_rw_signal(im::Vector::new()),
+            statuses: cx.create_rw_signal(im::HashMap::new()),
+            default_build: cx.create_rw_signal(info.default_build),
+            default_test: cx.create_rw_signal(info.default_test),
+            common,
+        }
+    }
+
+    pub fn detect(&self) {
+        if self.common.workspace.path.is_none() {
+            return;
+        }
+        let tasks = self.tasks;
+        let send =
+            create_ext_action(self.common.scope, move |result| match result {
+                Ok(ProxyResponse::DetectTasksResponse { tasks: detected }) => {
+                    tasks.set(detected.into());
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    tracing::error!("{:?}", err);
+                }
+            });
+        self.common.proxy.detect_tasks(move |result| {
+            send(result);
+        });
+    }
+
+    /// The run config the task is run with, in a terminal named after it
+    pub fn config(task: &DetectedTask) -> RunDebugConfig {
+        // the output of cargo is turned into problems, like the code lens runs
+        let problem_matchers = (task.source == TaskSource::Cargo
+            && task.kind != TaskKind::Run)
+            .then(|| vec![ProblemMatcher::Preset("cargo".to_string())]);
+        RunDebugConfig {
+            ty: None,
+            name: task.label(),
+            program: task.program.clone(),
+            args: Some(task.args.clone()),
+            cwd: Some(task.cwd.to_string_lossy().to_string()),
+            env: None,
+            prelaunch: None,
+            post_debug: None,
+            depends_on: None,
+            ready_pattern: None,
+            problem_matchers,
+            debug_command: None,
+            dap_id: Default::default(),
+            tracing_output: false,
+            config_source: ConfigSource::Task,
+        }
+    }
+
+    pub fn run(&self, task: &DetectedTask) {
+        self.common
+            .internal_command
+            .send(InternalCommand::RunAndDebug {
+                mode: RunDebugMode::Run,
+                config: Self::config(task),
+            });
+    }
+
+    pub fn started(&self, label: &str) {
+        self.statuses.update(|statuses| {
+            statuses.insert(label.to_string(), TaskStatus::Running);
+        });
+    }
+
+    pub fn exited(&self, label: &str, exit_code: Option<i32>) {
+        self.statuses.update(|statuses| {
+            statuses.insert(label.to_string(), TaskStatus::Exited(exit_code));
+        });
+    }
+
+    pub fn default_task(&self, kind: TaskKind) -> Option<String> {
+        match kind {
+            TaskKind::Build => self.default_build.get_untracked(),
+            TaskKind::Test => self.default_test.get_untracked(),
+            _ => None,
+        }
+    }
+
+    /// Run the default task of the kind, or the first one of that kind when
+    /// there's no default.
+    pub fn run_default(&self, kind: TaskKind) {
+        let default = self.default_task(kind);
+        let task = self.tasks.with_untracked(|tasks| {
+            default
+                .and_then(|label| tasks.iter().find(|t| t.label() == label))
+                .or_else(|| tasks.iter().find(|t| t.kind == kind))
+                .cloned()
+        });
+        match task {
+            Some(task) => self.run(&task),
+            None => {
+                tracing::error!("no {kind:?} task found in the workspace");
+            }
+        }
+    }
+
+    pub fn set_default(&self, kind: TaskKind, label: String) {
+        match kind {
+            TaskKind::Build => self.default_build.set(Some(label)),
+            TaskKind::Test => self.default_test.set(Some(label)),
+            _ => {}
+        }
+    }
+
+    pub fn info(&self) -> TaskInfo {
+        TaskInfo {
+            default_build: self.default_build.get_untracked(),
+            default_test: self.default_test.get_untracked(),
+        }
+    }
+}
@@ -3,6 +3,7 @@
 use alacritty_terminal::{
     Term,
     grid::{Dimensions, Scroll},
+    index::Direction,
     selection::{Selection, SelectionType},
     term::{TermMode, test::TermSize},
     vi_mode::ViMotion,
@@ -24,20 +25,40 @@
     terminal::{TermId, TerminalProfile},
 };
 use parking_lot::RwLock;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use url::Url;
 
 use super::{
-    event::TermEvent,
+    event::{TermEvent, TermNotification},
+    problem::ProblemMatchers,
     raw::{EventProxy, RawTerminal},
+    repl::repl_input,
 };
 use crate::{
     command::{CommandExecuted, CommandKind, InternalCommand},
     debug::{RunDebugMode, RunDebugProcess},
-    keypress::{KeyPressFocus, condition::Condition},
+    keypress::{
+        EventRef, KeyPressData, KeyPressFocus, KeyPressHandle, condition::Condition,
+    },
     window_tab::CommonData,
     workspace::LapceWorkspace,
 };
 
+/// The number of lines of the scrollback that are saved with a terminal
+const SCROLLBACK_SNAPSHOT_LINES: usize = 1000;
+
+/// A terminal saved with the workspace, to be started again in the same
+/// place when the window is reloaded.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TerminalInfo {
+    pub profile: Option<TerminalProfile>,
+    pub title: String,
+    pub cwd: Option<PathBuf>,
+    /// The text of the last lines of the terminal
+    pub scrollback: String,
+}
+
 #[derive(Clone, Debug)]
 pub struct TerminalData {
     pub scope: Scope,
@@ -49,6 +70,8 @@ pub struct TerminalData {
     pub visual_mode: RwSignal<VisualMode>,
     pub raw: RwSignal<Arc<RwLock<RawTerminal>>>,
     pub run_debug: RwSignal<Option<RunDebugProcess>>,
+    /// The profile the terminal was started with
+    pub profile: Option<TerminalProfile>,
     pub common: Rc<CommonData>,
 }
 
@@ -260,27 +283,24 @@ fn run_command(
                         },
                     );
                 }
+                FocusCommand::Search => {
+                    self.common
+                        .internal_command
+                        .send(InternalCommand::ShowTerminalFind);
+                }
                 FocusCommand::SearchForward => {
-                    // if let Some(search_string) = self.find.search_string.as_ref() {
-                    //     let mut raw = self.terminal.raw.lock();
-                    //     let term = &mut raw.term;
-                    //     self.terminal.search_next(
-                    //         term,
-                    //         search_string,
-                    //         Direction::Right,
-                    //     );
-                    // }
+                    self.common.internal_command.send(
+                        InternalCommand::TerminalFindNext {
+                            direction: Direction::Right,
+                        },
+                    );
                 }
                 FocusCommand::SearchBackward => {
-                    // if let Some(search_string) = self.find.search_string.as_ref() {
-                    //     let mut raw = self.terminal.raw.lock();
-                    //     let term = &mut raw.term;
-                    //     self.terminal.search_next(
-                    //         term,
-                    //         search_string,
-                    //         Direction::Left,
-                    //     );
-                    // }
+                    self.common.internal_command.send(
+                        InternalCommand::TerminalFindNext {
+                            direction: Direction::Left,
+                        },
+                    );
                 }
                 _ => return CommandExecuted::No,
             },
@@ -289,6 +309,17 @@ fn run_command(
         CommandExecuted::Yes
     }
 
+    /// Run the code in the program of the terminal, as if it was pasted
+    pub fn send_code(&self, code: &str) {
+        let raw = self.raw.get_untracked();
+        let bracketed_paste =
+            raw.read().term.mode().contains(TermMode::BRACKETED_PASTE);
+        if let Some(input) = repl_input(code, bracketed_paste) {
+            self.common.proxy.terminal_write(self.term_id, input);
+            raw.write().term.scroll_display(Scroll::Bottom);
+        }
+    }
+
     fn receive_char(&self, c: &str) {
         if self.mode.get_untracked() == Mode::Terminal {
             self.common
@@ -319,6 +350,45 @@ pub fn new_run_debug(
         run_debug: Option<RunDebugProcess>,
         profile: Option<TerminalProfile>,
         common: Rc<CommonData>,
+    ) -> Self {
+        Self::new_with_snapshot(cx, workspace, run_debug, profile, None, common)
+    }
+
+    /// Start a saved terminal again, in its last working directory and below
+    /// the text it had.
+    pub fn restore(
+        cx: Scope,
+        workspace: Arc<LapceWorkspace>,
+        info: TerminalInfo,
+        common: Rc<CommonData>,
+    ) -> Self {
+        let mut profile = info.profile;
+        if let Some(cwd) = info.cwd {
+            let profile = profile.get_or_insert_with(|| TerminalProfile {
+                name: info.title.clone(),
+                ..Default::default()
+            });
+            profile.workdir = Url::from_file_path(cwd).ok();
+        }
+        let terminal = Self::new_with_snapshot(
+            cx,
+            workspace,
+            None,
+            profile,
+            Some(&info.scrollback),
+            common,
+        );
+        terminal.title.set(info.title);
+        terminal
+    }
+
+    fn new_with_snapshot(
+        cx: Scope,
+        workspace: Arc<LapceWorkspace>,
+        run_debug: Option<RunDebugProcess>,
+        profile: Option<TerminalProfile>,
+        snapshot: Option<&str>,
+        common: Rc<CommonData>,
     ) -> Self {
         let cx = cx.create_child();
         let term_id = TermId::next();
@@ -335,7 +405,8 @@ pub fn new_run_debug(
             &workspace,
             term_id,
             run_debug.as_ref(),
-            profile,
+            profile.clone(),
+            snapshot,
             common.clone(),
             launch_error,
         );
@@ -354,16 +425,57 @@ pub fn new_run_debug(
             run_debug,
             mode,
             visual_mode,
+            profile,
             common,
             launch_error,
         }
     }
 
+    /// The same terminal with its signals in another scope, for moving it
+    /// between the panel and the editor tabs. The process keeps running, and
+    /// the scope of the original is disposed once it's closed where it was.
+    pub fn moved_to(&self, cx: Scope) -> Self {
+        let cx = cx.create_child();
+        Self {
+            scope: cx,
+            term_id: self.term_id,
+            workspace: self.workspace.clone(),
+            title: cx.create_rw_signal(self.title.get_untracked()),
+            launch_error: cx.create_rw_signal(self.launch_error.get_untracked()),
+            mode: cx.create_rw_signal(self.mode.get_untracked()),
+            visual_mode: cx.create_rw_signal(self.visual_mode.get_untracked()),
+            raw: cx.create_rw_signal(self.raw.get_untracked()),
+            run_debug: cx.create_rw_signal(self.run_debug.get_untracked()),
+            profile: self.profile.clone(),
+            common: self.common.clone(),
+        }
+    }
+
+    /// The terminal to save with the workspace, unless it's running a run
+    /// config, which isn't started again.
+    pub fn info(&self) -> Option<TerminalInfo> {
+        if self
+            .run_debug
+            .with_untracked(|run_debug| run_debug.is_some())
+        {
+            return None;
+        }
+        let raw = self.raw.get_untracked();
+        let raw = raw.read();
+        Some(TerminalInfo {
+            profile: self.profile.clone(),
+            title: self.title.get_untracked(),
+            cwd: raw.cwd.clone(),
+            scrollback: raw.text_snapshot(SCROLLBACK_SNAPSHOT_LINES),
+        })
+    }
+
     fn new_raw_terminal(
         workspace: &LapceWorkspace,
         term_id: TermId,
         run_debug: Option<&RunDebugProcess>,
         profile: Option<TerminalProfile>,
+        snapshot: Option<&str>,
         common: Rc<CommonData>,
         launch_error: RwSignal<Option<String>>,
     ) -> Arc<RwLock<RawTerminal>> {
@@ -372,6 +484,58 @@ fn new_raw_terminal(
             common.proxy.clone(),
             common.term_notification_tx.clone(),
         )));
+        if let Some(snapshot) = snapshot.filter(|s| !s.is_empty()) {
+            raw.write().restore_snapshot(snapshot);
+        }
+
+        if let Some(run_debug) = run_debug.filter(|run_debug| {
+            !(run_debug.is_prelaunch && run_debug.config.prelaunch.is_some())
+        }) {
+            if let Some(pattern) = run_debug.config.ready_pattern.as_ref() {
+                match Regex::new(pattern) {
+                    Ok(pattern) => raw.write().watch_ready_pattern(pattern),
+                    Err(err) => launch_error.set(Some(format!(
+                        "Invalid ready pattern in run debug definition {}: {err}",
+                        run_debug.config.name
+                    ))),
+                }
+            }
+        }
+
+        let problem_matchers = match run_debug {
+            Some(run_debug) => run_debug
+                .config
+                .problem_matchers
+                .as_ref()
+                .map(|matchers| (run_debug.config.name.clone(), matchers)),
+            None => profile.as_ref().and_then(|profile| {
+                profile
+                    .problem_matchers
+                    .as_ref()
+                    .map(|matchers| (profile.name.clone(), matchers))
+            }),
+        };
+        if let Some((source, matchers)) = problem_matchers {
+            match ProblemMatchers::new(source.clone(), matchers) {
+                Ok(matchers) => raw.write().watch_problems(matchers),
+                Err(err) => launch_error.set(Some(format!(
+                    "Invalid problem matchers for {source}: {err}"
+                ))),
+            }
+            // the problems of the previous run are cleared when the task starts
+            // again, and not when it moves on from the prelaunch
+            let task_start = run_debug.is_none_or(|run_debug| {
+                run_debug.is_prelaunch || run_debug.config.prelaunch.is_none()
+            });
+            if task_start {
+                if let Err(err) = common
+                    .term_notification_tx
+                    .send(TermNotification::ClearProblems { source })
+                {
+                    tracing::error!("{:?}", err);
+                }
+            }
+        }
 
         let mut profile = profile.unwrap_or_default();
 
@@ -415,6 +579,15 @@ fn new_raw_terminal(
             profile.arguments = run_debug.args;
         }
 
+        // tasks run their own program rather than an interactive shell
+        profile.shell_integration = run_debug.is_none()
+            && common.config.get_untracked().terminal.shell_integration;
+
+        raw.write().cwd = profile
+            .workdir
+            .as_ref()
+            .and_then(|workdir| workdir.to_file_path().ok());
+
         {
             let raw = raw.clone();
             if let Err(err) =
@@ -428,6 +601,27 @@ fn new_raw_terminal(
         raw
     }
 
+    /// Run the command of the key, or else type it in the terminal
+    pub fn key_down<'a>(
+        &self,
+        event: impl Into<EventRef<'a>> + Copy,
+        keypress: &KeyPressData,
+    ) -> KeyPressHandle {
+        let handle = keypress.key_down(event, self);
+        if !handle.handled && self.get_mode() == Mode::Terminal {
+            if let EventRef::Keyboard(key_event) = event.into() {
+                if self.send_keypress(key_event) {
+                    return KeyPressHandle {
+                        handled: true,
+                        keymatch: handle.keymatch,
+                        keypress: handle.keypress,
+                    };
+                }
+            }
+        }
+        handle
+    }
+
     pub fn send_keypress(&self, key: &KeyEvent) -> bool {
         if let Some(command) = Self::resolve_key_event(key) {
             self.receive_char(command);
@@ -730,6 +924,7 @@ pub fn new_process(&self, run_debug: Option<RunDebugProcess>) {
             self.term_id,
             run_debug.as_ref(),
             None,
+            None,
             self.common.clone(),
             self.launch_error,
         );
@@ -1,5 +1,6 @@
 use std::{
     collections::HashMap,
+    path::PathBuf,
     sync::{
         Arc,
         mpsc::{Receiver, Sender},
@@ -8,14 +9,28 @@
 };
 
 use lapce_rpc::terminal::TermId;
+use lsp_types::Diagnostic;
 use parking_lot::RwLock;
 
 use super::raw::RawTerminal;
 
 /// The notifications for terminals to send back to main thread
 pub enum TermNotification {
-    SetTitle { term_id: TermId, title: String },
+    SetTitle {
+        term_id: TermId,
+        title: String,
+    },
     RequestPaint,
+    Ready {
+        term_id: TermId,
+    },
+    Problems {
+        source: String,
+        problems: Vec<(PathBuf, Diagnostic)>,
+    },
+    ClearProblems {
+        source: String,
+    },
 }
 
 pub enum TermEvent {
@@ -0,0 +1,148 @@
+use std::rc::Rc;
+
+use alacritty_terminal::index::Direction;
+use floem::{
+    keyboard::Modifiers,
+    reactive::{Memo, RwSignal, Scope, SignalGet, SignalUpdate, SignalWith},
+};
+use lapce_core::{
+    command::{EditCommand, FocusCommand},
+    mode::Mode,
+    selection::Selection,
+};
+use lapce_xi_rope::Rope;
+
+use crate::{
+    command::{CommandExecuted, CommandKind, InternalCommand},
+    editor::EditorData,
+    keypress::{KeyPressFocus, condition::Condition},
+    main_split::Editors,
+    window_tab::CommonData,
+};
+
+/// The find bar of the terminal panel, which searches the scrollback of the
+/// active terminal.
+#[derive(Clone)]
+pub struct TerminalFindData {
+    pub visible: RwSignal<bool>,
+    pub focus: RwSignal<bool>,
+    pub case_sensitive: RwSignal<bool>,
+    pub is_regex: RwSignal<bool>,
+    pub editor: EditorData,
+    /// The regex handed to the terminal search, `None` when there's nothing
+    /// to search for.
+    pub pattern: Memo<Option<String>>,
+    pub common: Rc<CommonData>,
+}
+
+impl KeyPressFocus for TerminalFindData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        matches!(condition, Condition::SearchFocus | Condition::PanelFocus)
+    }
+
+    fn run_command(
+        &self,
+        command: &crate::command::LapceCommand,
+        count: Option<usize>,
+        mods: Modifiers,
+    ) -> CommandExecuted {
+        match &command.kind {
+            CommandKind::Focus(FocusCommand::SearchForward)
+            | CommandKind::Edit(EditCommand::InsertNewLine) => {
+                self.find_next(Direction::Right);
+            }
+            CommandKind::Focus(FocusCommand::SearchBackward) => {
+                self.find_next(Direction::Left);
+            }
+            CommandKind::Focus(
+                FocusCommand::ClearSearch | FocusCommand::ModalClose,
+            ) => {
+                self.close();
+            }
+            CommandKind::Edit(_)
+            | CommandKind::Move(_)
+            | CommandKind::MultiSelection(_) => {
+                return self.editor.run_command(command, count, mods);
+            }
+            _ => return CommandExecuted::No,
+        }
+        CommandExecuted::Yes
+    }
+
+    fn receive_char(&self, c: &str) {
+        self.editor.receive_char(c);
+    }
+}
+
+impl TerminalFindData {
+    pub fn new(cx: Scope, editors: Editors, common: Rc<CommonData>) -> Self {
+        let visible = cx.create_rw_signal(false);
+        let case_sensitive = cx.create_rw_signal(false);
+        let is_regex = cx.create_rw_signal(false);
+        let editor = editors.make_local(cx, common.clone());
+
+        let pattern = {
+            let doc = editor.doc_signal();
+            cx.create_memo(move |_| {
+                if !visible.get() {
+                    return None;
+                }
+                let text = doc.get().buffer.with(|buffer| buffer.to_string());
+                if text.is_empty() {
+                    return None;
+                }
+                let text = if is_regex.get() {
+                    text
+                } else {
+                    regex::escape(&text)
+                };
+                // The terminal search picks the case sensitivity from the
+                // pattern, so it's always set explicitly
+                let flags = if case_sensitive.get() { "-i" } else { "i" };
+                Some(format!("(?{flags}){text}"))
+            })
+        };
+
+        Self {
+            visible,
+            focus: cx.create_rw_signal(false),
+            case_sensitive,
+            is_regex,
+            editor,
+            pattern,
+            common,
+        }
+    }
+
+    /// Show the find bar, searching for `pattern` if there is one.
+    pub fn open(&self, pattern: Option<String>) {
+        if let Some(pattern) = pattern {
+            self.editor.doc().reload(Rope::from(pattern), true);
+        }
+        let pattern_len = self
+            .editor
+            .doc()
+            .buffer
+            .with_untracked(|buffer| buffer.len());
+        self.editor
+            .cursor()
+            .update(|cursor| cursor.set_insert(Selection::region(0, pattern_len)));
+        self.visible.set(true);
+        self.focus.set(true);
+    }
+
+    pub fn close(&self) {
+        self.visible.set(false);
+        self.focus.set(false);
+    }
+
+    pub fn find_next(&self, direction: Direction) {
+        self.common
+            .internal_command
+            .send(InternalCommand::TerminalFindNext { direction });
+    }
+}
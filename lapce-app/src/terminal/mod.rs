@@ -1,6 +1,10 @@
 pub mod data;
 pub mod event;
+pub mod find;
 pub mod panel;
+pub mod problem;
 pub mod raw;
+pub mod repl;
+pub mod shell;
 pub mod tab;
 pub mod view;
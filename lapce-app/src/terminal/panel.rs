@@ -1,26 +1,35 @@
 use std::{collections::HashMap, path::PathBuf, rc::Rc, sync::Arc};
 
+use alacritty_terminal::{index::Direction, term::search::RegexSearch};
 use floem::{
     ext_event::create_ext_action,
     reactive::{Memo, RwSignal, Scope, SignalGet, SignalUpdate, SignalWith},
+    views::editor::{core::register::Clipboard, text::SystemClipboard},
 };
-use lapce_core::mode::Mode;
+use lapce_core::language::LapceLanguage;
 use lapce_rpc::{
     dap_types::{
-        self, DapId, RunDebugConfig, StackFrame, Stopped, ThreadId, Variable,
+        self, ConfigSource, DapId, RunDebugConfig, StackFrame, SteppingGranularity,
+        Stopped, Thread, ThreadId, Variable,
     },
     proxy::ProxyResponse,
     terminal::{TermId, TerminalProfile},
 };
+use serde::{Deserialize, Serialize};
 
-use super::{data::TerminalData, tab::TerminalTabData};
+use super::{
+    data::TerminalData,
+    find::TerminalFindData,
+    tab::{TerminalSplitInfo, TerminalTabData},
+};
 use crate::{
+    command::InternalCommand,
     debug::{
         DapData, DapVariable, RunDebugConfigs, RunDebugData, RunDebugMode,
-        RunDebugProcess, ScopeOrVar,
+        RunDebugProcess, RunDebugVariables, ScopeOrVar,
     },
     id::TerminalTabId,
-    keypress::{EventRef, KeyPressData, KeyPressFocus, KeyPressHandle},
+    keypress::{EventRef, KeyPressData, KeyPressHandle},
     main_split::MainSplitData,
     panel::kind::PanelKind,
     window_tab::{CommonData, Focus},
@@ -32,13 +41,23 @@ pub struct TerminalTabInfo {
     pub tabs: im::Vector<(RwSignal<usize>, TerminalTabData)>,
 }
 
+/// The terminal tabs saved with the workspace
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TerminalPanelInfo {
+    pub active: usize,
+    pub tabs: Vec<TerminalSplitInfo>,
+}
+
 #[derive(Clone)]
 pub struct TerminalPanelData {
     pub cx: Scope,
     pub workspace: Arc<LapceWorkspace>,
     pub tab_info: RwSignal<TerminalTabInfo>,
     pub debug: RunDebugData,
+    pub find: TerminalFindData,
     pub breakline: Memo<Option<(usize, PathBuf)>>,
+    /// The terminal that code from the editors is sent to
+    pub repl_term: RwSignal<Option<TermId>>,
     pub common: Rc<CommonData>,
     pub main_split: MainSplitData,
 }
@@ -47,20 +66,49 @@ impl TerminalPanelData {
     pub fn new(
         workspace: Arc<LapceWorkspace>,
         profile: Option<TerminalProfile>,
+        info: Option<TerminalPanelInfo>,
         common: Rc<CommonData>,
         main_split: MainSplitData,
     ) -> Self {
-        let terminal_tab =
-            TerminalTabData::new(workspace.clone(), profile, common.clone());
+        let tab_info = match info.filter(|info| !info.tabs.is_empty()) {
+            Some(info) => {
+                let tabs = info
+                    .tabs
+                    .into_iter()
+                    .map(|info| {
+                        let terminal_tab = TerminalTabData::restore(
+                            workspace.clone(),
+                            info,
+                            common.clone(),
+                        );
+                        (terminal_tab.scope.create_rw_signal(0), terminal_tab)
+                    })
+                    .collect::<im::Vector<_>>();
+                let active = info.active.min(tabs.len() - 1);
+                TerminalTabInfo { active, tabs }
+            }
+            None => {
+                let terminal_tab =
+                    TerminalTabData::new(workspace.clone(), profile, common.clone());
+                let tabs = im::vector![(
+                    terminal_tab.scope.create_rw_signal(0),
+                    terminal_tab
+                )];
+                TerminalTabInfo { active: 0, tabs }
+            }
+        };
 
         let cx = common.scope;
-
-        let tabs =
-            im::vector![(terminal_tab.scope.create_rw_signal(0), terminal_tab)];
-        let tab_info = TerminalTabInfo { active: 0, tabs };
         let tab_info = cx.create_rw_signal(tab_info);
 
-        let debug = RunDebugData::new(cx, common.breakpoints);
+        let debug = RunDebugData::new(
+            cx,
+            common.breakpoints,
+            main_split.editors,
+            common.clone(),
+        );
+
+        let find = TerminalFindData::new(cx, main_split.editors, common.clone());
 
         let breakline = {
             let active_term = debug.active_term;
@@ -109,7 +157,9 @@ pub fn new(
             workspace,
             tab_info,
             debug,
+            find,
             breakline,
+            repl_term: cx.create_rw_signal(None),
             common,
             main_split,
         }
@@ -144,26 +194,148 @@ pub fn key_down<'a>(
             self.new_tab(None);
         }
 
+        if self.find.visible.get_untracked() && self.find.focus.get_untracked() {
+            return Some(keypress.key_down(event, &self.find));
+        }
+
         let tab = self.active_tab(false);
-        let terminal = tab.and_then(|tab| tab.active_terminal(false));
-        if let Some(terminal) = terminal {
-            let handle = keypress.key_down(event, &terminal);
-            let mode = terminal.get_mode();
-
-            if !handle.handled && mode == Mode::Terminal {
-                if let EventRef::Keyboard(key_event) = event.into() {
-                    if terminal.send_keypress(key_event) {
-                        return Some(KeyPressHandle {
-                            handled: true,
-                            keymatch: handle.keymatch,
-                            keypress: handle.keypress,
-                        });
+        let terminal = tab.and_then(|tab| tab.active_terminal(false))?;
+        Some(terminal.key_down(event, keypress))
+    }
+
+    /// Open the find bar for the active terminal, searching for its selection.
+    pub fn show_find(&self) {
+        let pattern = self
+            .active_tab(false)
+            .and_then(|tab| tab.active_terminal(false))
+            .and_then(|terminal| {
+                terminal
+                    .raw
+                    .get_untracked()
+                    .read()
+                    .term
+                    .selection_to_string()
+            })
+            .filter(|s| !s.is_empty() && !s.contains('\n'));
+        self.find.open(pattern);
+    }
+
+    /// Jump to the next match of the find bar in the active terminal.
+    pub fn find_next(&self, direction: Direction) {
+        let Some(pattern) = self.find.pattern.get_untracked() else {
+            return;
+        };
+        let Ok(mut search) = RegexSearch::new(&pattern) else {
+            return;
+        };
+        let Some(terminal) = self
+            .active_tab(false)
+            .and_then(|tab| tab.active_terminal(false))
+        else {
+            return;
+        };
+        terminal
+            .raw
+            .get_untracked()
+            .write()
+            .search_next(&mut search, direction);
+        self.common.view_id.get_untracked().request_paint();
+    }
+
+    /// The terminal tabs to save with the workspace, leaving out the
+    /// terminals of run configs.
+    pub fn panel_info(&self) -> TerminalPanelInfo {
+        self.tab_info.with_untracked(|info| {
+            let mut tabs = Vec::new();
+            let mut active = 0;
+            for (i, (_, tab)) in info.tabs.iter().enumerate() {
+                if let Some(split) = tab.split_info() {
+                    if i <= info.active {
+                        active = tabs.len();
                     }
+                    tabs.push(split);
                 }
             }
-            Some(handle)
-        } else {
-            None
+            TerminalPanelInfo { active, tabs }
+        })
+    }
+
+    /// Scroll the active terminal to the previous or next prompt marked by
+    /// the shell integration.
+    pub fn scroll_to_prompt(&self, direction: Direction) {
+        let Some(terminal) = self
+            .active_tab(false)
+            .and_then(|tab| tab.active_terminal(false))
+        else {
+            return;
+        };
+        terminal
+            .raw
+            .get_untracked()
+            .write()
+            .scroll_to_prompt(direction);
+        self.common.view_id.get_untracked().request_paint();
+    }
+
+    /// Copy the output of the last command that finished in the active
+    /// terminal.
+    pub fn copy_last_command_output(&self) {
+        let output = self
+            .active_tab(false)
+            .and_then(|tab| tab.active_terminal(false))
+            .and_then(|terminal| {
+                terminal.raw.get_untracked().read().last_command_output()
+            });
+        if let Some(output) = output {
+            let mut clipboard = SystemClipboard::new();
+            clipboard.put_string(output);
+        }
+    }
+
+    /// Send the code to the terminal chosen for it, or the last one it was
+    /// sent to. If there's none, the REPL of the language is started, or else
+    /// it goes to the active terminal.
+    pub fn send_to_repl(&self, code: &str, language: LapceLanguage) {
+        let terminal = self
+            .repl_term
+            .get_untracked()
+            .and_then(|term_id| self.get_terminal(&term_id))
+            .or_else(|| {
+                let profile = self
+                    .common
+                    .config
+                    .get_untracked()
+                    .terminal
+                    .repl_profile(language)?;
+                self.new_tab_run_debug(None, Some(profile))
+                    .active_terminal(false)
+            })
+            .or_else(|| {
+                if self.tab_info.with_untracked(|info| info.tabs.is_empty()) {
+                    self.new_tab(
+                        self.common
+                            .config
+                            .get_untracked()
+                            .terminal
+                            .get_default_profile(),
+                    );
+                }
+                self.active_tab(false)?.active_terminal(false)
+            });
+        if let Some(terminal) = terminal {
+            self.repl_term.set(Some(terminal.term_id));
+            terminal.send_code(code);
+        }
+    }
+
+    /// Send the code of the editors to the active terminal from now on
+    pub fn set_repl_terminal(&self) {
+        let term_id = self
+            .active_tab(false)
+            .and_then(|tab| tab.active_terminal(false))
+            .map(|terminal| terminal.term_id);
+        if term_id.is_some() {
+            self.repl_term.set(term_id);
         }
     }
 
@@ -184,7 +356,12 @@ pub fn new_tab_run_debug(
             profile,
             self.common.clone(),
         );
+        self.insert_tab(&terminal_tab);
+        terminal_tab
+    }
 
+    /// Add the tab after the active one and make it active
+    fn insert_tab(&self, terminal_tab: &TerminalTabData) {
         self.tab_info.update(|info| {
             info.tabs.insert(
                 if info.tabs.is_empty() {
@@ -197,8 +374,86 @@ pub fn new_tab_run_debug(
             let new_active = (info.active + 1).min(info.tabs.len() - 1);
             info.active = new_active;
         });
+    }
 
-        terminal_tab
+    /// Move the terminal to the active editor tab, where it keeps running.
+    /// Terminals of run configs stay in the panel with their debugging.
+    pub fn move_to_editor(&self, term_id: TermId) -> bool {
+        let Some((_, _, _, terminal)) = self.get_terminal_in_tab(&term_id) else {
+            return false;
+        };
+        if terminal.run_debug.with_untracked(|r| r.is_some()) {
+            return false;
+        }
+        self.main_split.open_terminal(&terminal);
+        self.close_terminal(&term_id);
+        terminal.scope.dispose();
+        true
+    }
+
+    /// Move all the terminals of the tab to the active editor tab
+    pub fn move_tab_to_editor(&self, terminal_tab_id: TerminalTabId) -> bool {
+        let Some(terminals) = self.tab_info.with_untracked(|info| {
+            info.tabs
+                .iter()
+                .find(|(_, tab)| tab.terminal_tab_id == terminal_tab_id)
+                .map(|(_, tab)| tab.terminals.get_untracked())
+        }) else {
+            return false;
+        };
+        if terminals
+            .iter()
+            .any(|(_, t)| t.run_debug.with_untracked(|r| r.is_some()))
+        {
+            return false;
+        }
+        for (_, terminal) in terminals {
+            self.move_to_editor(terminal.term_id);
+        }
+        true
+    }
+
+    /// Move the terminal from the editor tabs to a new tab of the panel
+    pub fn move_from_editor(&self, term_id: TermId) {
+        let cx = self.common.scope.create_child();
+        let Some(terminal) = self.main_split.take_terminal(term_id, cx) else {
+            cx.dispose();
+            return;
+        };
+        let terminal_tab = TerminalTabData::from_terminal(cx, terminal);
+        self.insert_tab(&terminal_tab);
+        self.update_debug_active_term();
+    }
+
+    /// Open the tabs of the layout from the settings, after the active tab.
+    pub fn open_layout(&self, name: &str) {
+        let Some(tabs) = self
+            .common
+            .config
+            .with_untracked(|config| config.terminal.layouts.get(name).cloned())
+        else {
+            return;
+        };
+        for pane in &tabs {
+            let terminal_tab = TerminalTabData::from_layout(
+                self.workspace.clone(),
+                pane,
+                self.common.clone(),
+            );
+            self.tab_info.update(|info| {
+                let index = if info.tabs.is_empty() {
+                    0
+                } else {
+                    (info.active + 1).min(info.tabs.len())
+                };
+                info.tabs.insert(
+                    index,
+                    (terminal_tab.scope.create_rw_signal(0), terminal_tab),
+                );
+                info.active = index;
+            });
+        }
+        self.update_debug_active_term();
     }
 
     pub fn next_tab(&self) {
@@ -255,6 +510,7 @@ pub fn close_tab(&self, terminal_tab_id: Option<TerminalTabId>) {
             .flatten()
         {
             for (_, data) in close_tab {
+                self.task_closed(&data);
                 data.stop();
             }
         }
@@ -267,8 +523,9 @@ pub fn set_title(&self, term_id: &TermId, title: &str) {
         }
     }
 
+    /// The terminal in the panel or in the editor tabs
     pub fn get_terminal(&self, term_id: &TermId) -> Option<TerminalData> {
-        self.tab_info.with_untracked(|info| {
+        let terminal = self.tab_info.with_untracked(|info| {
             for (_, tab) in &info.tabs {
                 let terminal = tab.terminals.with_untracked(|terminals| {
                     terminals
@@ -281,7 +538,8 @@ pub fn get_terminal(&self, term_id: &TermId) -> Option<TerminalData> {
                 }
             }
             None
-        })
+        });
+        terminal.or_else(|| self.main_split.get_terminal(term_id))
     }
 
     fn get_terminal_in_tab(
@@ -314,9 +572,15 @@ pub fn split(&self, term_id: TermId) {
                 self.common.clone(),
             );
             let i = terminal_data.scope.create_rw_signal(0);
+            let new_term_id = terminal_data.term_id;
             tab.terminals.update(|terminals| {
                 terminals.insert(index + 1, (i, terminal_data));
             });
+            tab.layout.update(|layout| {
+                if let Some(layout) = layout {
+                    layout.insert_after(term_id, new_term_id);
+                }
+            });
         }
     }
 
@@ -345,16 +609,25 @@ pub fn split_exchange(&self, term_id: TermId) {
         if let Some((_, tab, index, _)) = self.get_terminal_in_tab(&term_id) {
             let max = tab.terminals.with_untracked(|t| t.len() - 1);
             if index < max {
+                let next = tab
+                    .terminals
+                    .with_untracked(|terminals| terminals[index + 1].1.term_id);
                 tab.terminals.update(|terminals| {
                     terminals.swap(index, index + 1);
                 });
+                tab.layout.update(|layout| {
+                    if let Some(layout) = layout {
+                        layout.swap(term_id, next);
+                    }
+                });
                 self.update_debug_active_term();
             }
         }
     }
 
     pub fn close_terminal(&self, term_id: &TermId) {
-        if let Some((_, tab, index, _)) = self.get_terminal_in_tab(term_id) {
+        if let Some((_, tab, index, terminal)) = self.get_terminal_in_tab(term_id) {
+            self.task_closed(&terminal);
             let active = tab.active.get_untracked();
             let len = tab
                 .terminals
@@ -363,6 +636,11 @@ pub fn close_terminal(&self, term_id: &TermId) {
                     terminals.len()
                 })
                 .unwrap();
+            tab.layout.update(|layout| {
+                if let Some(layout) = layout {
+                    layout.remove(*term_id);
+                }
+            });
             if len == 0 {
                 self.close_tab(Some(tab.terminal_tab_id));
             } else {
@@ -372,6 +650,28 @@ pub fn close_terminal(&self, term_id: &TermId) {
                     self.update_debug_active_term();
                 }
             }
+        } else {
+            self.main_split.close_terminal(*term_id);
+        }
+    }
+
+    /// A task whose terminal is closed while it runs won't report its exit.
+    fn task_closed(&self, terminal: &TerminalData) {
+        let name = terminal.run_debug.with_untracked(|run_debug| {
+            run_debug
+                .as_ref()
+                .filter(|r| {
+                    r.config.config_source == ConfigSource::Task && !r.stopped
+                })
+                .map(|r| r.config.name.clone())
+        });
+        if let Some(name) = name {
+            self.common
+                .internal_command
+                .send(InternalCommand::TaskExited {
+                    name,
+                    exit_code: None,
+                });
         }
     }
 
@@ -406,10 +706,15 @@ pub fn terminal_stopped(&self, term_id: &TermId, exit_code: Option<i32>) {
                         }
                     })
                     .unwrap();
+                if was_prelaunch == Some(false) {
+                    if let Some(dap) = self.get_dap(*term_id, false) {
+                        dap.clear_inline_values();
+                    }
+                }
                 let exit_code = exit_code.unwrap_or(0);
-                if was_prelaunch == Some(true) && exit_code == 0 {
-                    let run_debug = terminal.run_debug.get_untracked();
-                    if let Some(run_debug) = run_debug {
+                let run_debug = terminal.run_debug.get_untracked();
+                if let Some(run_debug) = run_debug {
+                    if was_prelaunch == Some(true) && exit_code == 0 {
                         if run_debug.mode == RunDebugMode::Debug {
                             self.common.proxy.dap_start(
                                 run_debug.config,
@@ -418,6 +723,10 @@ pub fn terminal_stopped(&self, term_id: &TermId, exit_code: Option<i32>) {
                         } else {
                             terminal.new_process(Some(run_debug));
                         }
+                    } else if was_prelaunch == Some(true) {
+                        self.run_debug_failed(&run_debug.config.name);
+                    } else if was_prelaunch == Some(false) {
+                        self.run_debug_exited(&run_debug, exit_code);
                     }
                 }
             } else {
@@ -426,6 +735,135 @@ pub fn terminal_stopped(&self, term_id: &TermId, exit_code: Option<i32>) {
         }
     }
 
+    /// Start the configs waiting on the one that exited if it succeeded, and
+    /// run its post debug task.
+    fn run_debug_exited(&self, run_debug: &RunDebugProcess, exit_code: i32) {
+        if run_debug.config.config_source == ConfigSource::Task {
+            self.common
+                .internal_command
+                .send(InternalCommand::TaskExited {
+                    name: run_debug.config.name.clone(),
+                    exit_code: Some(exit_code),
+                });
+        }
+
+        if !run_debug.ready {
+            if exit_code == 0 && run_debug.config.ready_pattern.is_none() {
+                self.start_waiting_on(&run_debug.config.name);
+            } else {
+                self.run_debug_failed(&run_debug.config.name);
+            }
+        }
+
+        if run_debug.mode == RunDebugMode::Debug {
+            if let Some(post_debug) = run_debug.config.post_debug.as_ref() {
+                let config = RunDebugConfig {
+                    ty: None,
+                    name: format!("{} (post-debug)", run_debug.config.name),
+                    program: post_debug.program.clone(),
+                    args: post_debug.args.clone(),
+                    cwd: run_debug.config.cwd.clone(),
+                    env: run_debug.config.env.clone(),
+                    prelaunch: None,
+                    post_debug: None,
+                    depends_on: None,
+                    ready_pattern: None,
+                    problem_matchers: None,
+                    debug_command: None,
+                    dap_id: Default::default(),
+                    tracing_output: false,
+                    config_source: ConfigSource::RunInTerminal,
+                };
+                self.common
+                    .internal_command
+                    .send(InternalCommand::RunAndDebug {
+                        mode: RunDebugMode::Run,
+                        config,
+                    });
+            }
+        }
+    }
+
+    /// The output of the terminal matched the ready pattern of its config.
+    pub fn run_debug_ready(&self, term_id: &TermId) {
+        let Some(terminal) = self.get_terminal(term_id) else {
+            return;
+        };
+        let name = terminal
+            .run_debug
+            .try_update(|run_debug| {
+                let run_debug = run_debug.as_mut()?;
+                run_debug.ready = true;
+                Some(run_debug.config.name.clone())
+            })
+            .flatten();
+        if let Some(name) = name {
+            self.start_waiting_on(&name);
+        }
+    }
+
+    /// Whether the config with the given name is ready, `None` if it isn't
+    /// running.
+    pub fn is_run_debug_ready(&self, name: &str) -> Option<bool> {
+        self.run_debug_process(false)
+            .into_iter()
+            .find(|(_, process)| !process.stopped && process.config.name == name)
+            .map(|(_, process)| process.ready)
+    }
+
+    fn start_waiting_on(&self, name: &str) {
+        let ready = self
+            .debug
+            .pending
+            .try_update(|pending| {
+                for pending in pending.iter_mut() {
+                    pending.waiting_on.remove(name);
+                }
+                let (ready, waiting): (Vec<_>, Vec<_>) = std::mem::take(pending)
+                    .into_iter()
+                    .partition(|pending| pending.waiting_on.is_empty());
+                *pending = waiting;
+                ready
+            })
+            .unwrap_or_default();
+        for pending in ready {
+            self.common
+                .internal_command
+                .send(InternalCommand::StartRunDebug {
+                    mode: pending.mode,
+                    config: pending.config,
+                    compound: pending.compound,
+                });
+        }
+    }
+
+    /// Drop the configs waiting on the one that failed, and the ones waiting on
+    /// them.
+    fn run_debug_failed(&self, name: &str) {
+        let mut failed = vec![name.to_string()];
+        while let Some(name) = failed.pop() {
+            let dropped = self
+                .debug
+                .pending
+                .try_update(|pending| {
+                    let (dropped, waiting): (Vec<_>, Vec<_>) =
+                        std::mem::take(pending)
+                            .into_iter()
+                            .partition(|pending| pending.waiting_on.contains(&name));
+                    *pending = waiting;
+                    dropped
+                })
+                .unwrap_or_default();
+            for pending in dropped {
+                tracing::error!(
+                    "{} wasn't started because {name} failed",
+                    pending.config.name
+                );
+                failed.push(pending.config.name);
+            }
+        }
+    }
+
     pub fn get_stopped_run_debug_terminal(
         &self,
         mode: &RunDebugMode,
@@ -470,12 +908,15 @@ pub fn restart_run_debug(&self, term_id: TermId) -> Option<bool> {
             self.get_terminal_in_tab(&term_id)?;
         let mut run_debug = terminal.run_debug.get_untracked()?;
         if run_debug.config.config_source.from_palette() {
-            if let Some(new_config) =
+            if let Some(mut new_config) =
                 self.get_run_config_by_name(&run_debug.config.name)
             {
+                self.run_debug_variables(self.debug.inputs.get_untracked())
+                    .substitute_config(&mut new_config);
                 run_debug.config = new_config;
             }
         }
+        run_debug.ready = false;
         let mut is_debug = false;
         let new_term_id = match run_debug.mode {
             RunDebugMode::Run => {
@@ -517,23 +958,43 @@ pub fn restart_run_debug(&self, term_id: TermId) -> Option<bool> {
     }
 
     fn get_run_config_by_name(&self, name: &str) -> Option<RunDebugConfig> {
-        if let Some(workspace) = self.common.workspace.path.as_deref() {
-            let run_toml = workspace.join(".lapce").join("run.toml");
-            let (doc, new_doc) = self.main_split.get_doc(run_toml.clone(), None);
-            if !new_doc {
-                let content = doc.buffer.with_untracked(|b| b.to_string());
-                match toml::from_str::<RunDebugConfigs>(&content) {
-                    Ok(configs) => {
-                        return configs.configs.into_iter().find(|x| x.name == name);
-                    }
-                    Err(err) => {
-                        // todo show message window
-                        tracing::error!("deser fail {:?}", err);
-                    }
-                }
+        self.run_debug_configs()?
+            .configs
+            .into_iter()
+            .find(|x| x.name == name)
+    }
+
+    /// The configs in the run.toml of the workspace, if it's open.
+    pub fn run_debug_configs(&self) -> Option<RunDebugConfigs> {
+        let workspace = self.common.workspace.path.as_deref()?;
+        let run_toml = workspace.join(".lapce").join("run.toml");
+        let (doc, new_doc) = self.main_split.get_doc(run_toml.clone(), None);
+        if new_doc {
+            return None;
+        }
+        let content = doc.buffer.with_untracked(|b| b.to_string());
+        match toml::from_str::<RunDebugConfigs>(&content) {
+            Ok(configs) => Some(configs),
+            Err(err) => {
+                // todo show message window
+                tracing::error!("deser fail {:?}", err);
+                None
             }
         }
-        None
+    }
+
+    pub fn run_debug_variables(
+        &self,
+        inputs: HashMap<String, String>,
+    ) -> RunDebugVariables {
+        let file =
+            self.main_split
+                .active_editor
+                .get_untracked()
+                .and_then(|editor| {
+                    editor.doc().content.with_untracked(|c| c.path().cloned())
+                });
+        RunDebugVariables { file, inputs }
     }
 
     pub fn focus_terminal(&self, term_id: TermId) {
@@ -572,24 +1033,48 @@ pub fn update_debug_active_term(&self) {
         }
     }
 
+    /// Stop the process of the terminal, and the others of the compound it
+    /// was started as part of.
     pub fn stop_run_debug(&self, term_id: TermId) -> Option<()> {
         let terminal = self.get_terminal(&term_id)?;
         let run_debug = terminal.run_debug.get_untracked()?;
 
+        if let Some(compound) = run_debug.compound.as_ref() {
+            self.debug.pending.update(|pending| {
+                pending
+                    .retain(|pending| pending.compound.as_ref() != Some(compound));
+            });
+            for (other_term_id, process) in self.run_debug_process(false) {
+                if other_term_id != term_id
+                    && !process.stopped
+                    && process.compound.as_ref() == Some(compound)
+                {
+                    self.stop_process(other_term_id, &process);
+                }
+            }
+        }
+        self.stop_process(term_id, &run_debug);
+
+        self.focus_terminal(term_id);
+        Some(())
+    }
+
+    fn stop_process(&self, term_id: TermId, run_debug: &RunDebugProcess) {
         match run_debug.mode {
             RunDebugMode::Run => {
                 self.common.proxy.terminal_close(term_id);
             }
             RunDebugMode::Debug => {
                 let dap_id = run_debug.config.dap_id;
-                let daps = self.debug.daps.get_untracked();
-                let dap = daps.get(&dap_id)?;
-                self.common.proxy.dap_stop(dap.dap_id);
+                if self
+                    .debug
+                    .daps
+                    .with_untracked(|daps| daps.contains_key(&dap_id))
+                {
+                    self.common.proxy.dap_stop(dap_id);
+                }
             }
         }
-
-        self.focus_terminal(term_id);
-        Some(())
     }
 
     pub fn run_debug_process(
@@ -641,14 +1126,28 @@ pub fn set_process_id(&self, term_id: &TermId, process_id: Option<u32>) {
         }
     }
 
-    pub fn dap_continued(&self, dap_id: &DapId) {
+    pub fn dap_continued(
+        &self,
+        dap_id: &DapId,
+        thread_id: Option<ThreadId>,
+        all_threads_continued: bool,
+    ) {
         let dap = self
             .debug
             .daps
             .with_untracked(|daps| daps.get(dap_id).cloned());
         if let Some(dap) = dap {
-            dap.thread_id.set(None);
-            dap.stopped.set(false);
+            dap.continued(thread_id, all_threads_continued);
+        }
+    }
+
+    pub fn dap_threads(&self, dap_id: &DapId, threads: &[Thread]) {
+        let dap = self
+            .debug
+            .daps
+            .with_untracked(|daps| daps.get(dap_id).cloned());
+        if let Some(dap) = dap {
+            dap.set_threads(threads);
         }
     }
 
@@ -656,6 +1155,7 @@ pub fn dap_stopped(
         &self,
         dap_id: &DapId,
         stopped: &Stopped,
+        threads: &[Thread],
         stack_frames: &HashMap<ThreadId, Vec<StackFrame>>,
         variables: &[(dap_types::Scope, Vec<Variable>)],
     ) {
@@ -664,7 +1164,7 @@ pub fn dap_stopped(
             .daps
             .with_untracked(|daps| daps.get(dap_id).cloned());
         if let Some(dap) = dap {
-            dap.stopped(self.cx, stopped, stack_frames, variables);
+            dap.stopped(self.cx, stopped, threads, stack_frames, variables);
         }
         floem::action::focus_window();
     }
@@ -676,10 +1176,10 @@ pub fn dap_continue(&self, term_id: TermId) -> Option<()> {
             .with_untracked(|r| r.as_ref().map(|r| r.config.dap_id))?;
         let thread_id = self.debug.daps.with_untracked(|daps| {
             daps.get(&dap_id)
-                .and_then(|dap| dap.thread_id.get_untracked())
+                .map(|dap| dap.selected_thread())
+                .unwrap_or_default()
         });
-        let thread_id = thread_id.unwrap_or_default();
-        self.common.proxy.dap_continue(dap_id, thread_id);
+        self.common.proxy.dap_continue(dap_id, thread_id, false);
         Some(())
     }
 
@@ -690,52 +1190,70 @@ pub fn dap_pause(&self, term_id: TermId) -> Option<()> {
             .with_untracked(|r| r.as_ref().map(|r| r.config.dap_id))?;
         let thread_id = self.debug.daps.with_untracked(|daps| {
             daps.get(&dap_id)
-                .and_then(|dap| dap.thread_id.get_untracked())
+                .map(|dap| dap.selected_thread())
+                .unwrap_or_default()
         });
-        let thread_id = thread_id.unwrap_or_default();
         self.common.proxy.dap_pause(dap_id, thread_id);
         Some(())
     }
 
-    pub fn dap_step_over(&self, term_id: TermId) -> Option<()> {
+    pub fn dap_step_over(
+        &self,
+        term_id: TermId,
+        granularity: Option<SteppingGranularity>,
+    ) -> Option<()> {
         let terminal = self.get_terminal(&term_id)?;
         let dap_id = terminal
             .run_debug
             .with_untracked(|r| r.as_ref().map(|r| r.config.dap_id))?;
         let thread_id = self.debug.daps.with_untracked(|daps| {
             daps.get(&dap_id)
-                .and_then(|dap| dap.thread_id.get_untracked())
+                .map(|dap| dap.selected_thread())
+                .unwrap_or_default()
         });
-        let thread_id = thread_id.unwrap_or_default();
-        self.common.proxy.dap_step_over(dap_id, thread_id);
+        self.common
+            .proxy
+            .dap_step_over(dap_id, thread_id, false, granularity);
         Some(())
     }
 
-    pub fn dap_step_into(&self, term_id: TermId) -> Option<()> {
+    pub fn dap_step_into(
+        &self,
+        term_id: TermId,
+        granularity: Option<SteppingGranularity>,
+    ) -> Option<()> {
         let terminal = self.get_terminal(&term_id)?;
         let dap_id = terminal
             .run_debug
             .with_untracked(|r| r.as_ref().map(|r| r.config.dap_id))?;
         let thread_id = self.debug.daps.with_untracked(|daps| {
             daps.get(&dap_id)
-                .and_then(|dap| dap.thread_id.get_untracked())
+                .map(|dap| dap.selected_thread())
+                .unwrap_or_default()
         });
-        let thread_id = thread_id.unwrap_or_default();
-        self.common.proxy.dap_step_into(dap_id, thread_id);
+        self.common
+            .proxy
+            .dap_step_into(dap_id, thread_id, false, granularity);
         Some(())
     }
 
-    pub fn dap_step_out(&self, term_id: TermId) -> Option<()> {
+    pub fn dap_step_out(
+        &self,
+        term_id: TermId,
+        granularity: Option<SteppingGranularity>,
+    ) -> Option<()> {
         let terminal = self.get_terminal(&term_id)?;
         let dap_id = terminal
             .run_debug
             .with_untracked(|r| r.as_ref().map(|r| r.config.dap_id))?;
         let thread_id = self.debug.daps.with_untracked(|daps| {
             daps.get(&dap_id)
-                .and_then(|dap| dap.thread_id.get_untracked())
+                .map(|dap| dap.selected_thread())
+                .unwrap_or_default()
         });
-        let thread_id = thread_id.unwrap_or_default();
-        self.common.proxy.dap_step_out(dap_id, thread_id);
+        self.common
+            .proxy
+            .dap_step_out(dap_id, thread_id, false, granularity);
         Some(())
     }
 
@@ -771,7 +1289,10 @@ pub fn get_dap(&self, term_id: TermId, tracked: bool) -> Option<DapData> {
 
     pub fn dap_frame_scopes(&self, dap_id: DapId, frame_id: usize) {
         if let Some(dap) = self.debug.daps.get_untracked().get(&dap_id) {
+            dap.frame_id.set(Some(frame_id));
+            dap.evaluate_watches();
             let variables = dap.variables;
+            let dap = dap.clone();
             let send = create_ext_action(self.common.scope, move |result| {
                 if let Ok(ProxyResponse::DapGetScopesResponse { scopes }) = result {
                     variables.update(|dap_var| {
@@ -807,6 +1328,7 @@ pub fn dap_frame_scopes(&self, dap_id: DapId, frame_id: usize) {
                             .map(|v| v.children_expanded_count + 1)
                             .sum::<usize>();
                     });
+                    dap.update_inline_values();
                 }
             });
 
@@ -0,0 +1,235 @@
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+use lapce_rpc::dap_types::{ProblemMatcher, ProblemPattern};
+use lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range};
+use regex::{Captures, Regex};
+
+const CARGO: &str = r"^(?P<severity>error|warning)(?:\[\w+\])?: (?P<message>.+)\n\s*--> (?P<file>.+?):(?P<line>\d+):(?P<column>\d+)$";
+const TSC: &str = r"^(?P<file>[^\s(][^(]*)\((?P<line>\d+),(?P<column>\d+)\): (?P<severity>error|warning|info) (?P<message>.+)$";
+const GCC: &str = r"^(?P<file>[^\s:][^:]*):(?P<line>\d+):(?P<column>\d+): (?:fatal )?(?P<severity>error|warning|note): (?P<message>.+)$";
+const GO: &str =
+    r"^(?P<file>[^\s:]+\.go):(?P<line>\d+):(?P<column>\d+): (?P<message>.+)$";
+
+struct CompiledPattern {
+    regex: Regex,
+    /// The number of output lines the regex is matched against
+    lines: usize,
+    severity: DiagnosticSeverity,
+}
+
+/// Parses the output of a terminal into diagnostics, with the problem
+/// matchers of its run config or profile.
+pub struct ProblemMatchers {
+    /// The source of the diagnostics, to tell them apart from those of the
+    /// language servers
+    pub source: String,
+    patterns: Vec<CompiledPattern>,
+    /// The most recent lines of output, for the patterns spanning lines
+    lines: VecDeque<String>,
+    max_lines: usize,
+}
+
+impl ProblemMatchers {
+    pub fn new(source: String, matchers: &[ProblemMatcher]) -> Result<Self> {
+        let patterns = matchers
+            .iter()
+            .map(|matcher| match matcher {
+                ProblemMatcher::Preset(name) => {
+                    let regexp = match name.as_str() {
+                        "cargo" => CARGO,
+                        "tsc" => TSC,
+                        "gcc" => GCC,
+                        "go" => GO,
+                        _ => return Err(anyhow!("unknown problem matcher {name}")),
+                    };
+                    compile(&ProblemPattern {
+                        regexp: regexp.to_string(),
+                        severity: None,
+                    })
+                }
+                ProblemMatcher::Pattern(pattern) => compile(pattern),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let max_lines = patterns.iter().map(|p| p.lines).max().unwrap_or(1);
+        Ok(Self {
+            source,
+            patterns,
+            lines: VecDeque::with_capacity(max_lines),
+            max_lines,
+        })
+    }
+
+    /// Match a complete line of the output, returning the problems it ends.
+    /// Relative paths are resolved against `cwd`.
+    pub fn match_line(
+        &mut self,
+        line: &str,
+        cwd: Option<&Path>,
+    ) -> Vec<(PathBuf, Diagnostic)> {
+        if self.lines.len() == self.max_lines {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line.to_string());
+
+        let mut problems = Vec::new();
+        for pattern in &self.patterns {
+            if pattern.lines > self.lines.len() {
+                continue;
+            }
+            let text = self
+                .lines
+                .range(self.lines.len() - pattern.lines..)
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join("\n");
+            for captures in pattern.regex.captures_iter(&text) {
+                if let Some(problem) = self.problem(&captures, pattern.severity, cwd)
+                {
+                    problems.push(problem);
+                }
+            }
+        }
+        problems
+    }
+
+    fn problem(
+        &self,
+        captures: &Captures,
+        severity: DiagnosticSeverity,
+        cwd: Option<&Path>,
+    ) -> Option<(PathBuf, Diagnostic)> {
+        let number = |name: &str| {
+            captures
+                .name(name)
+                .and_then(|m| m.as_str().parse::<u32>().ok())
+        };
+        let file = PathBuf::from(captures.name("file")?.as_str().trim());
+        let path = match cwd {
+            Some(cwd) if file.is_relative() => cwd.join(file),
+            _ => file,
+        };
+        let line = number("line")?.saturating_sub(1);
+        let column = number("column").unwrap_or(1).saturating_sub(1);
+        let start = Position::new(line, column);
+        let end = match number("end_line") {
+            Some(end_line) => Position::new(
+                end_line.saturating_sub(1),
+                number("end_column").unwrap_or(1).saturating_sub(1),
+            ),
+            None => Position::new(
+                line,
+                number("end_column")
+                    .map(|c| c.saturating_sub(1))
+                    .unwrap_or(column + 1),
+            ),
+        };
+        let severity = captures
+            .name("severity")
+            .and_then(|s| parse_severity(s.as_str()))
+            .unwrap_or(severity);
+        let message = captures
+            .name("message")
+            .map(|m| m.as_str().trim().to_string())
+            .unwrap_or_default();
+        let diagnostic = Diagnostic {
+            range: Range::new(start, end),
+            severity: Some(severity),
+            source: Some(self.source.clone()),
+            message,
+            ..Default::default()
+        };
+        Some((path, diagnostic))
+    }
+}
+
+fn compile(pattern: &ProblemPattern) -> Result<CompiledPattern> {
+    let regex = Regex::new(&pattern.regexp)?;
+    let severity = pattern
+        .severity
+        .as_deref()
+        .and_then(parse_severity)
+        .unwrap_or(DiagnosticSeverity::ERROR);
+    Ok(CompiledPattern {
+        regex,
+        lines: pattern.regexp.matches("\\n").count() + 1,
+        severity,
+    })
+}
+
+fn parse_severity(severity: &str) -> Option<DiagnosticSeverity> {
+    match severity.to_lowercase().as_str() {
+        "error" | "fatal" => Some(DiagnosticSeverity::ERROR),
+        "warning" | "warn" => Some(DiagnosticSeverity::WARNING),
+        "info" | "information" | "note" => Some(DiagnosticSeverity::INFORMATION),
+        "hint" => Some(DiagnosticSeverity::HINT),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use lapce_rpc::dap_types::{ProblemMatcher, ProblemPattern};
+    use lsp_types::{DiagnosticSeverity, Position};
+
+    use super::ProblemMatchers;
+
+    #[test]
+    fn test_cargo_problem_matcher() {
+        let mut matchers = ProblemMatchers::new(
+            "build".to_string(),
+            &[ProblemMatcher::Preset("cargo".to_string())],
+        )
+        .unwrap();
+        let cwd = PathBuf::from("/project");
+        assert!(
+            matchers
+                .match_line(
+                    "error[E0425]: cannot find value `x`",
+                    Some(cwd.as_path())
+                )
+                .is_empty()
+        );
+        let problems =
+            matchers.match_line(" --> src/main.rs:2:5", Some(cwd.as_path()));
+        assert_eq!(problems.len(), 1);
+        let (path, diagnostic) = &problems[0];
+        assert_eq!(path, &PathBuf::from("/project/src/main.rs"));
+        assert_eq!(diagnostic.range.start, Position::new(1, 4));
+        assert_eq!(diagnostic.severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(diagnostic.message, "cannot find value `x`");
+        assert_eq!(diagnostic.source.as_deref(), Some("build"));
+    }
+
+    #[test]
+    fn test_custom_problem_matcher() {
+        let mut matchers = ProblemMatchers::new(
+            "lint".to_string(),
+            &[ProblemMatcher::Pattern(ProblemPattern {
+                regexp: r"^(?P<file>\S+) line (?P<line>\d+): (?P<message>.+)$"
+                    .to_string(),
+                severity: Some("warning".to_string()),
+            })],
+        )
+        .unwrap();
+        let problems = matchers.match_line("/a.sh line 3: unused variable", None);
+        assert_eq!(problems.len(), 1);
+        let (path, diagnostic) = &problems[0];
+        assert_eq!(path, &PathBuf::from("/a.sh"));
+        assert_eq!(diagnostic.range.start, Position::new(2, 0));
+        assert_eq!(diagnostic.severity, Some(DiagnosticSeverity::WARNING));
+
+        assert!(
+            ProblemMatchers::new(
+                "lint".to_string(),
+                &[ProblemMatcher::Preset("unknown".to_string())],
+            )
+            .is_err()
+        );
+    }
+}
@@ -1,20 +1,50 @@
-use std::sync::mpsc::Sender;
+use std::{collections::VecDeque, path::PathBuf, str::Chars, sync::mpsc::Sender};
 
 use alacritty_terminal::{
     Term,
     event::EventListener,
-    grid::Dimensions,
-    index::{Column, Direction, Line, Point},
+    grid::{Dimensions, Scroll},
+    index::{Boundary, Column, Direction, Line, Point, Side},
+    selection::{Selection, SelectionType},
     term::{
+        TermMode,
         cell::{Flags, LineLength},
         search::{Match, RegexIter, RegexSearch},
         test::TermSize,
     },
     vte::ansi,
 };
+use lapce_core::directory::Directory;
 use lapce_rpc::{proxy::ProxyRpcHandler, terminal::TermId};
+use regex::Regex;
 
-use super::event::TermNotification;
+use super::{
+    event::TermNotification,
+    problem::ProblemMatchers,
+    shell::{OscParser, ShellMark},
+};
+
+/// The number of commands kept for the gutter and the prompt navigation
+const MAX_SHELL_COMMANDS: usize = 1000;
+
+/// A command run at a prompt, as marked by the shell integration. The lines
+/// are counted from the top of the scrollback, so that they stay put as the
+/// output grows.
+#[derive(Clone, Debug)]
+pub struct ShellCommand {
+    pub prompt_line: usize,
+    /// Where the output of the command starts, once it's entered
+    pub output_line: Option<usize>,
+    /// Where the output of the command stops, once it's finished
+    pub end: Option<(usize, Column)>,
+    pub exit_code: Option<i32>,
+}
+
+impl ShellCommand {
+    pub fn is_running(&self) -> bool {
+        self.output_line.is_some() && self.end.is_none()
+    }
+}
 
 pub struct EventProxy {
     term_id: TermId,
@@ -55,6 +85,22 @@ pub struct RawTerminal {
     pub parser: ansi::Processor,
     pub term: Term<EventProxy>,
     pub scroll_delta: f64,
+    /// The working directory of the terminal's process
+    pub cwd: Option<PathBuf>,
+    term_id: TermId,
+    term_notification_tx: Sender<TermNotification>,
+    /// The pattern that tells the run config of the terminal is ready
+    ready_pattern: Option<Regex>,
+    problem_matchers: Option<ProblemMatchers>,
+    /// The text of the line being output, while watching for the ready pattern
+    /// or problems
+    output_line: String,
+    shell_parser: OscParser,
+    /// The commands found by the shell integration, oldest first
+    pub commands: VecDeque<ShellCommand>,
+    /// The lines dropped from the top of the full scrollback, which the lines
+    /// of the commands count as well
+    evicted_lines: usize,
 }
 
 impl RawTerminal {
@@ -70,7 +116,7 @@ pub fn new(
         let event_proxy = EventProxy {
             term_id,
             proxy,
-            term_notification_tx,
+            term_notification_tx: term_notification_tx.clone(),
         };
 
         let size = TermSize::new(50, 30);
@@ -81,15 +127,357 @@ pub fn new(
             parser,
             term,
             scroll_delta: 0.0,
+            cwd: None,
+            term_id,
+            term_notification_tx,
+            ready_pattern: None,
+            problem_matchers: None,
+            output_line: String::new(),
+            shell_parser: OscParser::default(),
+            commands: VecDeque::new(),
+            evicted_lines: 0,
         }
     }
 
+    /// Notify once a line of the output matches the pattern.
+    pub fn watch_ready_pattern(&mut self, pattern: Regex) {
+        self.ready_pattern = Some(pattern);
+        self.output_line.clear();
+    }
+
+    /// Parse the output into diagnostics with the problem matchers.
+    pub fn watch_problems(&mut self, matchers: ProblemMatchers) {
+        self.problem_matchers = Some(matchers);
+        self.output_line.clear();
+    }
+
     pub fn update_content(&mut self, content: Vec<u8>) {
+        if self.ready_pattern.is_some() || self.problem_matchers.is_some() {
+            self.match_output(&content);
+        }
+        let history_size = self.term.grid().history_size();
         for byte in content {
+            let grid = self.term.grid();
+            let (point, input_needs_wrap) =
+                (grid.cursor.point, grid.cursor.input_needs_wrap);
+            let history = grid.history_size();
             self.parser.advance(&mut self.term, byte);
+            if history > 0
+                && self.term.grid().history_size() == history
+                && self.scrolled_up(byte, point, input_needs_wrap)
+            {
+                self.evicted_lines += 1;
+            }
+            if let Some(mark) = self.shell_parser.advance(byte) {
+                self.shell_mark(mark);
+            }
+        }
+        // the scrollback was cleared, taking the commands with it
+        if self.term.grid().history_size() < history_size {
+            self.commands.clear();
+        }
+    }
+
+    /// Whether the byte scrolled the screen up a line, from where the cursor was
+    /// before it. Once the scrollback is full that drops its top line, which
+    /// the grid doesn't tell about, so it's a line feed or a wrap of the output
+    /// at the bottom of the screen.
+    fn scrolled_up(&self, byte: u8, point: Point, input_needs_wrap: bool) -> bool {
+        let grid = self.term.grid();
+        let bottom = self.term.bottommost_line();
+        if point.line != bottom || grid.cursor.point.line != bottom {
+            return false;
+        }
+        match byte {
+            b'\n' | b'\x0b' | b'\x0c' => true,
+            // the last byte of a char printed past the end of the line
+            byte if byte >= 0x20 && byte != 0x7f => {
+                input_needs_wrap
+                    && grid.cursor.point.column < point.column
+                    && grid[Line(bottom.0 - 1)][self.term.last_column()]
+                        .flags
+                        .contains(Flags::WRAPLINE)
+            }
+            _ => false,
+        }
+    }
+
+    fn shell_mark(&mut self, mark: ShellMark) {
+        let cursor = self.term.grid().cursor.point;
+        let line = self.absolute_line(cursor.line);
+        match mark {
+            ShellMark::PromptStart => {
+                if self.commands.len() == MAX_SHELL_COMMANDS {
+                    self.commands.pop_front();
+                }
+                self.commands.push_back(ShellCommand {
+                    prompt_line: line,
+                    output_line: None,
+                    end: None,
+                    exit_code: None,
+                });
+            }
+            ShellMark::CommandStart => {}
+            ShellMark::OutputStart => {
+                if let Some(command) = self.commands.back_mut() {
+                    if command.output_line.is_none() {
+                        command.output_line = Some(line);
+                    }
+                }
+            }
+            ShellMark::CommandEnd(exit_code) => {
+                // the shells report an end for the empty prompts as well
+                if let Some(command) =
+                    self.commands.back_mut().filter(|c| c.is_running())
+                {
+                    command.end = Some((line, cursor.column));
+                    command.exit_code = exit_code;
+                }
+            }
+            ShellMark::Cwd(cwd) => {
+                if self.cwd.as_ref() != Some(&cwd) {
+                    let home = Directory::home_dir();
+                    let title = match home
+                        .as_ref()
+                        .and_then(|home| cwd.strip_prefix(home).ok())
+                    {
+                        Some(path) if path.as_os_str().is_empty() => "~".to_string(),
+                        Some(path) => format!(
+                            "~{}{}",
+                            std::path::MAIN_SEPARATOR,
+                            path.display()
+                        ),
+                        None => cwd.display().to_string(),
+                    };
+                    if let Err(err) =
+                        self.term_notification_tx.send(TermNotification::SetTitle {
+                            term_id: self.term_id,
+                            title,
+                        })
+                    {
+                        tracing::error!("{:?}", err);
+                    }
+                }
+                self.cwd = Some(cwd);
+            }
         }
     }
 
+    /// The line counted from the first line of the output, including the lines
+    /// dropped from the scrollback since
+    pub fn absolute_line(&self, line: Line) -> usize {
+        self.evicted_lines
+            + (self.term.grid().history_size() as i32 + line.0).max(0) as usize
+    }
+
+    /// The line in the grid of a line counted from the first line of the
+    /// output, if it's still there
+    pub fn grid_line(&self, line: usize) -> Option<Line> {
+        let line = line.checked_sub(self.evicted_lines)? as i32
+            - self.term.grid().history_size() as i32;
+        (line < self.term.screen_lines() as i32).then_some(Line(line))
+    }
+
+    /// The index of the char at the point in the text from `start` to it, as
+    /// it's returned by `bounds_to_string`, where a wide char is a single char
+    /// over two cells.
+    pub fn char_index(&self, start: Point, point: Point) -> usize {
+        let grid = self.term.grid();
+        let spacer = Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER;
+        let mut index = 0;
+        // where the last char before the point starts
+        let mut last_char = 0;
+        let mut current = start;
+        while current < point {
+            let cell = &grid[current];
+            if !cell.flags.intersects(spacer) {
+                last_char = index;
+                index += 1 + cell.zerowidth().map_or(0, |chars| chars.len());
+            }
+            current = current.add(&self.term, Boundary::None, 1);
+        }
+        // the second half of a wide char is the char itself
+        if grid[point].flags.contains(Flags::WIDE_CHAR_SPACER) {
+            last_char
+        } else {
+            index
+        }
+    }
+
+    /// Scroll the previous or next prompt to the top of the viewport.
+    pub fn scroll_to_prompt(&mut self, direction: Direction) {
+        let top =
+            self.absolute_line(Line(-(self.term.grid().display_offset() as i32)));
+        let prompt = match direction {
+            Direction::Left => self
+                .commands
+                .iter()
+                .rev()
+                .map(|c| c.prompt_line)
+                .find(|line| *line < top),
+            Direction::Right => self
+                .commands
+                .iter()
+                .map(|c| c.prompt_line)
+                .find(|line| *line > top),
+        };
+        match prompt.and_then(|line| self.grid_line(line)) {
+            Some(line) => {
+                let offset = self.term.grid().display_offset() as i32;
+                self.term.scroll_display(Scroll::Delta(-line.0 - offset));
+            }
+            None if direction == Direction::Right => {
+                self.term.scroll_display(Scroll::Bottom);
+            }
+            None => {}
+        }
+    }
+
+    /// The output of the last command that finished, as far as it's still in
+    /// the scrollback.
+    pub fn last_command_output(&self) -> Option<String> {
+        let command = self.commands.iter().rev().find(|c| c.end.is_some())?;
+        let output_line = command.output_line?;
+        let (end_line, end_column) = command.end?;
+        if (end_line, end_column) <= (output_line, Column(0)) {
+            return Some(String::new());
+        }
+        let start = self
+            .grid_line(output_line)
+            .unwrap_or(self.term.topmost_line());
+        let end = Point::new(self.grid_line(end_line)?, end_column).sub(
+            &self.term,
+            Boundary::None,
+            1,
+        );
+        let output = self
+            .term
+            .bounds_to_string(Point::new(start, Column(0)), end);
+        Some(output.trim_end().to_string())
+    }
+
+    /// The text of the last `lines` lines, to be restored in a new terminal.
+    pub fn text_snapshot(&self, lines: usize) -> String {
+        let end = self.term.bottommost_line();
+        let start = Line(end.0 + 1 - lines as i32).max(self.term.topmost_line());
+        let text = self.term.bounds_to_string(
+            Point::new(start, Column(0)),
+            Point::new(end, self.term.last_column()),
+        );
+        text.trim_end().to_string()
+    }
+
+    /// Print the text of a snapshot, before the output of the process.
+    pub fn restore_snapshot(&mut self, snapshot: &str) {
+        let text = format!("{}\r\n", snapshot.replace('\n', "\r\n"));
+        for byte in text.bytes() {
+            self.parser.advance(&mut self.term, byte);
+        }
+    }
+
+    /// Match the lines of the output against the ready pattern and the
+    /// problem matchers.
+    fn match_output(&mut self, content: &[u8]) {
+        let text = String::from_utf8_lossy(content);
+        let mut chars = text.chars();
+        let mut ready = false;
+        let mut problems = Vec::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '\x1b' => skip_escape_sequence(&mut chars),
+                '\n' => {
+                    ready = ready
+                        || self.ready_pattern.as_ref().is_some_and(|pattern| {
+                            pattern.is_match(&self.output_line)
+                        });
+                    if let Some(matchers) = self.problem_matchers.as_mut() {
+                        problems.extend(
+                            matchers
+                                .match_line(&self.output_line, self.cwd.as_deref()),
+                        );
+                    }
+                    self.output_line.clear();
+                }
+                '\r' => {}
+                c => self.output_line.push(c),
+            }
+        }
+        // the line could be a prompt that doesn't end until there's input
+        ready = ready
+            || self
+                .ready_pattern
+                .as_ref()
+                .is_some_and(|pattern| pattern.is_match(&self.output_line));
+        if ready {
+            self.ready_pattern = None;
+            if self.problem_matchers.is_none() {
+                self.output_line = String::new();
+            }
+            if let Err(err) =
+                self.term_notification_tx.send(TermNotification::Ready {
+                    term_id: self.term_id,
+                })
+            {
+                tracing::error!("{:?}", err);
+            }
+        }
+        if let Some(matchers) = self.problem_matchers.as_ref() {
+            if !problems.is_empty() {
+                if let Err(err) =
+                    self.term_notification_tx.send(TermNotification::Problems {
+                        source: matchers.source.clone(),
+                        problems,
+                    })
+                {
+                    tracing::error!("{:?}", err);
+                }
+            }
+        }
+    }
+
+    /// Select the next match of the search after the current selection, or
+    /// from the viewport if there's nothing selected, and scroll it into view.
+    pub fn search_next(
+        &mut self,
+        search: &mut RegexSearch,
+        direction: Direction,
+    ) -> Option<Match> {
+        let term = &self.term;
+        let selection = term.selection.as_ref().and_then(|s| s.to_range(term));
+        let display_offset = term.grid().display_offset() as i32;
+        let (origin, side) = match direction {
+            Direction::Right => {
+                let origin = selection
+                    .map(|s| s.end.add(term, Boundary::None, 1))
+                    .unwrap_or_else(|| Point::new(Line(-display_offset), Column(0)));
+                (origin, Side::Left)
+            }
+            Direction::Left => {
+                let origin = selection
+                    .map(|s| s.start.sub(term, Boundary::None, 1))
+                    .unwrap_or_else(|| {
+                        Point::new(
+                            Line(term.screen_lines() as i32 - 1 - display_offset),
+                            term.last_column(),
+                        )
+                    });
+                (origin, Side::Right)
+            }
+        };
+
+        let term = &mut self.term;
+        let m = term.search_next(search, origin, direction, side, None)?;
+        let mut selection =
+            Selection::new(SelectionType::Simple, *m.start(), Side::Left);
+        selection.update(*m.end(), Side::Right);
+        term.selection = Some(selection);
+        if term.mode().contains(TermMode::VI) {
+            term.vi_mode_cursor.point = *m.start();
+        }
+        term.scroll_to_point(*m.start());
+        Some(m)
+    }
+
     pub fn output(&self, line_num: usize) -> Vec<String> {
         let grid = self.term.grid();
         let mut lines = Vec::with_capacity(5);
@@ -130,6 +518,33 @@ pub fn output(&self, line_num: usize) -> Vec<String> {
     }
 }
 
+/// Skip the rest of an escape sequence, so that only the printed text is left.
+fn skip_escape_sequence(chars: &mut Chars) {
+    match chars.next() {
+        // CSI, ended by a byte in the range 0x40..=0x7e
+        Some('[') => {
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        }
+        // OSC, ended by BEL or ST
+        Some(']') => {
+            while let Some(c) = chars.next() {
+                if c == '\x07' {
+                    break;
+                }
+                if c == '\x1b' {
+                    chars.next();
+                    break;
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
 pub fn visible_regex_match_iter<'a, EventProxy>(
     term: &'a Term<EventProxy>,
     regex: &'a mut RegexSearch,
@@ -147,3 +562,42 @@ pub fn visible_regex_match_iter<'a, EventProxy>(
 }
 /// todo:should be improved
 pub const MAX_SEARCH_LINES: usize = 100;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+
+    use alacritty_terminal::{index::Line, term::Config};
+    use lapce_rpc::{proxy::ProxyRpcHandler, terminal::TermId};
+
+    use super::RawTerminal;
+
+    #[test]
+    fn test_commands_past_full_scrollback() {
+        let (tx, _rx) = channel();
+        let mut raw = RawTerminal::new(TermId::next(), ProxyRpcHandler::new(), tx);
+        raw.term.set_options(Config {
+            scrolling_history: 5,
+            ..Default::default()
+        });
+
+        raw.update_content(b"\x1b]133;A\x07$ \x1b]133;C\x07".to_vec());
+        let mut output = Vec::new();
+        for i in 0..100 {
+            output.extend(format!("line {i}\r\n").into_bytes());
+        }
+        // a line that wraps three times at the bottom of the screen
+        output.extend(vec![b'x'; 160]);
+        output.extend(b"\r\n\x1b]133;D;0\x07\x1b]133;A\x07$ \x1b]133;C\x07");
+        raw.update_content(output);
+
+        assert_eq!(raw.term.grid().history_size(), 5);
+        assert_eq!(raw.commands.len(), 2);
+        // the first prompt is long gone from the scrollback
+        assert_eq!(raw.grid_line(raw.commands[0].prompt_line), None);
+        let cursor = raw.term.grid().cursor.point.line;
+        assert_eq!(raw.grid_line(raw.commands[1].prompt_line), Some(cursor));
+        assert_eq!(raw.absolute_line(cursor), raw.commands[1].prompt_line);
+        assert_eq!(raw.absolute_line(Line(0)), 75);
+    }
+}
@@ -0,0 +1,72 @@
+/// The code of an editor that is sent to a terminal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplUnit {
+    /// The selection, or the line of the cursor if nothing is selected
+    Selection,
+    Line,
+    /// The top level block around the cursor, such as a function
+    Block,
+}
+
+/// The input that runs the code in the program of a terminal, pasted so that
+/// it isn't run line by line if the program supports bracketed paste. Code of
+/// more than one line ends with an empty line, which ends an indented block
+/// in REPLs like python's.
+pub fn repl_input(code: &str, bracketed_paste: bool) -> Option<String> {
+    let code = code.trim_end();
+    let code = code.trim_start_matches(['\r', '\n']);
+    if code.is_empty() {
+        return None;
+    }
+    let multiline = code.contains('\n');
+    // a stray escape would start a control sequence in the terminal
+    let code = code.replace("\r\n", "\n").replace('\x1b', "");
+    let mut input = if bracketed_paste {
+        format!("\x1b[200~{code}\x1b[201~")
+    } else {
+        code.replace('\n', "\r")
+    };
+    input.push('\r');
+    if multiline {
+        input.push('\r');
+    }
+    Some(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::repl_input;
+
+    #[test]
+    fn test_single_line() {
+        assert_eq!(
+            repl_input("print(1)\n", false).as_deref(),
+            Some("print(1)\r")
+        );
+        assert_eq!(repl_input("  \n", false), None);
+    }
+
+    #[test]
+    fn test_multiple_lines() {
+        assert_eq!(
+            repl_input("def f():\r\n    return 1\n", false).as_deref(),
+            Some("def f():\r    return 1\r\r")
+        );
+    }
+
+    #[test]
+    fn test_escape_removed() {
+        assert_eq!(
+            repl_input("x = 1\x1b[2J\n", false).as_deref(),
+            Some("x = 1[2J\r")
+        );
+    }
+
+    #[test]
+    fn test_bracketed_paste() {
+        assert_eq!(
+            repl_input("for i in x:\n    print(i)\x1b", true).as_deref(),
+            Some("\x1b[200~for i in x:\n    print(i)\x1b[201~\r\r")
+        );
+    }
+}
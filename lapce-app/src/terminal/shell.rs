@@ -0,0 +1,168 @@
+use std::path::PathBuf;
+
+/// The longest OSC sequence that's kept, the others being ignored.
+const MAX_OSC_LEN: usize = 4096;
+
+/// A mark sent by the shell integration scripts, telling where the prompts
+/// and the commands are in the output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShellMark {
+    /// OSC 133;A, the prompt is about to be printed
+    PromptStart,
+    /// OSC 133;B, the prompt is printed and the command is being typed
+    CommandStart,
+    /// OSC 133;C, the command was entered and its output follows
+    OutputStart,
+    /// OSC 133;D, the command finished, with its exit code if it's known
+    CommandEnd(Option<i32>),
+    /// OSC 7, the working directory of the shell
+    Cwd(PathBuf),
+}
+
+#[derive(Default)]
+enum State {
+    #[default]
+    Ground,
+    Escape,
+    Osc,
+    OscEscape,
+}
+
+/// Picks the shell integration marks out of the terminal output, alongside
+/// the parser of the terminal, which ignores them.
+#[derive(Default)]
+pub struct OscParser {
+    state: State,
+    osc: Vec<u8>,
+}
+
+impl OscParser {
+    pub fn advance(&mut self, byte: u8) -> Option<ShellMark> {
+        match self.state {
+            State::Ground => {
+                if byte == 0x1b {
+                    self.state = State::Escape;
+                }
+            }
+            State::Escape => {
+                self.state = match byte {
+                    b']' => {
+                        self.osc.clear();
+                        State::Osc
+                    }
+                    0x1b => State::Escape,
+                    _ => State::Ground,
+                };
+            }
+            State::Osc => match byte {
+                // BEL
+                0x07 => {
+                    self.state = State::Ground;
+                    return parse_osc(&self.osc);
+                }
+                0x1b => self.state = State::OscEscape,
+                // CAN and SUB abort the sequence
+                0x18 | 0x1a => self.state = State::Ground,
+                _ => {
+                    if self.osc.len() < MAX_OSC_LEN {
+                        self.osc.push(byte);
+                    }
+                }
+            },
+            State::OscEscape => {
+                // ST
+                if byte == b'\\' {
+                    self.state = State::Ground;
+                    return parse_osc(&self.osc);
+                }
+                // any other escape aborts the sequence and starts a new one
+                self.state = State::Escape;
+                return self.advance(byte);
+            }
+        }
+        None
+    }
+}
+
+fn parse_osc(osc: &[u8]) -> Option<ShellMark> {
+    if osc.len() >= MAX_OSC_LEN {
+        return None;
+    }
+    let osc = std::str::from_utf8(osc).ok()?;
+    let (ps, params) = osc.split_once(';').unwrap_or((osc, ""));
+    match ps {
+        // 633 is the VS Code flavour of 133, which some shell setups emit
+        "133" | "633" => {
+            let mut params = params.split(';');
+            match params.next()? {
+                "A" => Some(ShellMark::PromptStart),
+                "B" => Some(ShellMark::CommandStart),
+                "C" => Some(ShellMark::OutputStart),
+                "D" => Some(ShellMark::CommandEnd(
+                    params.next().and_then(|code| code.parse().ok()),
+                )),
+                "P" if ps == "633" => {
+                    let cwd = params.next()?.strip_prefix("Cwd=")?;
+                    let cwd = cwd.replace("\\x3b", ";").replace("\\\\", "\\");
+                    Some(ShellMark::Cwd(PathBuf::from(cwd)))
+                }
+                _ => None,
+            }
+        }
+        "7" => {
+            let mut url = url::Url::parse(params).ok()?;
+            if url.scheme() != "file" {
+                return None;
+            }
+            // the host is the machine's name, which the path doesn't need
+            url.set_host(None).ok()?;
+            url.to_file_path().ok().map(ShellMark::Cwd)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{OscParser, ShellMark};
+
+    fn marks(output: &[u8]) -> Vec<ShellMark> {
+        let mut parser = OscParser::default();
+        output
+            .iter()
+            .filter_map(|byte| parser.advance(*byte))
+            .collect()
+    }
+
+    #[test]
+    fn test_prompt_marks() {
+        assert_eq!(
+            marks(b"\x1b]133;A\x07$ \x1b]133;B\x1b\\ls\r\n\x1b]133;C\x07a b\r\n\x1b]133;D;1\x07"),
+            vec![
+                ShellMark::PromptStart,
+                ShellMark::CommandStart,
+                ShellMark::OutputStart,
+                ShellMark::CommandEnd(Some(1)),
+            ]
+        );
+        assert_eq!(
+            marks(b"\x1b]133;D\x07\x1b]0;title\x07\x1b[1m"),
+            vec![ShellMark::CommandEnd(None)]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_cwd_marks() {
+        assert_eq!(
+            marks(b"\x1b]7;file://host/home/user/my%20dir\x07"),
+            vec![ShellMark::Cwd(PathBuf::from("/home/user/my dir"))]
+        );
+        assert_eq!(
+            marks(b"\x1b]633;P;Cwd=/tmp/a\\x3bb\x07"),
+            vec![ShellMark::Cwd(PathBuf::from("/tmp/a;b"))]
+        );
+    }
+}
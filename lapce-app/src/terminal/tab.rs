@@ -1,20 +1,161 @@
 use std::{rc::Rc, sync::Arc};
 
 use floem::reactive::{RwSignal, Scope, SignalGet, SignalWith};
-use lapce_rpc::terminal::TerminalProfile;
+use lapce_rpc::terminal::{TermId, TerminalProfile};
+use serde::{Deserialize, Serialize};
 
-use super::data::TerminalData;
+use super::data::{TerminalData, TerminalInfo};
 use crate::{
-    debug::RunDebugProcess, id::TerminalTabId, window_tab::CommonData,
+    config::terminal::{TerminalLayoutPane, TerminalSplitDirection},
+    debug::RunDebugProcess,
+    id::TerminalTabId,
+    window_tab::CommonData,
     workspace::LapceWorkspace,
 };
 
+/// The terminals of a tab, saved with the workspace
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TerminalSplitInfo {
+    pub active: usize,
+    pub terminals: Vec<TerminalInfo>,
+    /// The arrangement of the terminals, by their index in `terminals`, if
+    /// they aren't side by side
+    #[serde(default)]
+    pub layout: Option<TerminalSplitNode<usize>>,
+}
+
+/// How the terminals of a tab opened from a layout are arranged, with the
+/// terminals in the same order as in the tab. It's saved with the terminals
+/// by their index in the saved tab.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TerminalSplitNode<T = TermId> {
+    Terminal(T),
+    Split {
+        direction: TerminalSplitDirection,
+        children: Vec<TerminalSplitNode<T>>,
+    },
+}
+
+impl<T: Copy> TerminalSplitNode<T> {
+    /// The same arrangement of other terminals, leaving out the ones there's
+    /// none for
+    pub fn filter_map<U>(
+        &self,
+        f: &impl Fn(T) -> Option<U>,
+    ) -> Option<TerminalSplitNode<U>> {
+        match self {
+            TerminalSplitNode::Terminal(terminal) => {
+                f(*terminal).map(TerminalSplitNode::Terminal)
+            }
+            TerminalSplitNode::Split {
+                direction,
+                children,
+            } => {
+                let mut children = children
+                    .iter()
+                    .filter_map(|child| child.filter_map(f))
+                    .collect::<Vec<_>>();
+                match children.len() {
+                    0 => None,
+                    1 => children.pop(),
+                    _ => Some(TerminalSplitNode::Split {
+                        direction: *direction,
+                        children,
+                    }),
+                }
+            }
+        }
+    }
+
+    fn terminals(&self, terminals: &mut Vec<T>) {
+        match self {
+            TerminalSplitNode::Terminal(terminal) => terminals.push(*terminal),
+            TerminalSplitNode::Split { children, .. } => {
+                for child in children {
+                    child.terminals(terminals);
+                }
+            }
+        }
+    }
+}
+
+impl TerminalSplitNode {
+    /// Put the new terminal next to the other one, in the same split
+    pub fn insert_after(&mut self, term_id: TermId, new_term_id: TermId) -> bool {
+        match self {
+            TerminalSplitNode::Terminal(id) => {
+                if *id != term_id {
+                    return false;
+                }
+                *self = TerminalSplitNode::Split {
+                    direction: TerminalSplitDirection::Horizontal,
+                    children: vec![
+                        TerminalSplitNode::Terminal(term_id),
+                        TerminalSplitNode::Terminal(new_term_id),
+                    ],
+                };
+                true
+            }
+            TerminalSplitNode::Split { children, .. } => {
+                let leaf = TerminalSplitNode::Terminal(term_id);
+                if let Some(i) = children.iter().position(|c| c == &leaf) {
+                    children.insert(i + 1, TerminalSplitNode::Terminal(new_term_id));
+                    return true;
+                }
+                children
+                    .iter_mut()
+                    .any(|child| child.insert_after(term_id, new_term_id))
+            }
+        }
+    }
+
+    /// Take the terminal out, a split left with one pane becoming that pane
+    pub fn remove(&mut self, term_id: TermId) -> bool {
+        let TerminalSplitNode::Split { children, .. } = self else {
+            return false;
+        };
+        let leaf = TerminalSplitNode::Terminal(term_id);
+        let removed = if let Some(i) = children.iter().position(|c| c == &leaf) {
+            children.remove(i);
+            true
+        } else {
+            children.iter_mut().any(|child| child.remove(term_id))
+        };
+        if removed && children.len() == 1 {
+            let child = children.remove(0);
+            *self = child;
+        }
+        removed
+    }
+
+    /// Exchange the places of the two terminals
+    pub fn swap(&mut self, a: TermId, b: TermId) {
+        match self {
+            TerminalSplitNode::Terminal(id) => {
+                if *id == a {
+                    *id = b;
+                } else if *id == b {
+                    *id = a;
+                }
+            }
+            TerminalSplitNode::Split { children, .. } => {
+                for child in children {
+                    child.swap(a, b);
+                }
+            }
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct TerminalTabData {
     pub scope: Scope,
     pub terminal_tab_id: TerminalTabId,
     pub active: RwSignal<usize>,
     pub terminals: RwSignal<im::Vector<(RwSignal<usize>, TerminalData)>>,
+    /// The arrangement of the terminals if the tab was opened from a layout,
+    /// otherwise they are side by side.
+    pub layout: RwSignal<Option<TerminalSplitNode>>,
 }
 
 impl TerminalTabData {
@@ -45,9 +186,126 @@ pub fn new_run_debug(
             terminal_tab_id,
             active,
             terminals,
+            layout: cx.create_rw_signal(None),
+        }
+    }
+
+    /// Start the terminals of a tab of a layout, arranged as it describes.
+    pub fn from_layout(
+        workspace: Arc<LapceWorkspace>,
+        pane: &TerminalLayoutPane,
+        common: Rc<CommonData>,
+    ) -> Self {
+        let cx = common.scope.create_child();
+        let mut terminals = im::Vector::new();
+        let layout = layout_node(cx, &workspace, pane, &common, &mut terminals)
+            .unwrap_or_else(|| {
+                let terminal =
+                    TerminalData::new(cx, workspace.clone(), None, common.clone());
+                let node = TerminalSplitNode::Terminal(terminal.term_id);
+                terminals.push_back((cx.create_rw_signal(0), terminal));
+                node
+            });
+        Self {
+            scope: cx,
+            terminal_tab_id: TerminalTabId::next(),
+            active: cx.create_rw_signal(0),
+            terminals: cx.create_rw_signal(terminals),
+            layout: cx.create_rw_signal(Some(layout)),
+        }
+    }
+
+    /// A tab of a terminal that is already running, whose signals are in a
+    /// child of the scope.
+    pub fn from_terminal(cx: Scope, terminal: TerminalData) -> Self {
+        Self {
+            scope: cx,
+            terminal_tab_id: TerminalTabId::next(),
+            active: cx.create_rw_signal(0),
+            terminals: cx
+                .create_rw_signal(im::vector![(cx.create_rw_signal(0), terminal)]),
+            layout: cx.create_rw_signal(None),
         }
     }
 
+    /// Start the saved terminals of a tab again. There must be at least one.
+    pub fn restore(
+        workspace: Arc<LapceWorkspace>,
+        info: TerminalSplitInfo,
+        common: Rc<CommonData>,
+    ) -> Self {
+        let cx = common.scope.create_child();
+        let terminals = info
+            .terminals
+            .into_iter()
+            .map(|info| {
+                let terminal_data = TerminalData::restore(
+                    cx,
+                    workspace.clone(),
+                    info,
+                    common.clone(),
+                );
+                (cx.create_rw_signal(0), terminal_data)
+            })
+            .collect::<im::Vector<_>>();
+        let active = info.active.min(terminals.len().saturating_sub(1));
+        let term_ids = terminals
+            .iter()
+            .map(|(_, terminal)| terminal.term_id)
+            .collect::<Vec<_>>();
+        let layout = info.layout.and_then(|layout| {
+            let node = layout.filter_map(&|i| term_ids.get(i).copied())?;
+            // a layout that doesn't show each terminal once isn't of this tab
+            let mut shown = Vec::new();
+            node.terminals(&mut shown);
+            (shown.len() == term_ids.len()
+                && term_ids.iter().all(|id| shown.contains(id)))
+            .then_some(node)
+        });
+        let terminals = cx.create_rw_signal(terminals);
+        let active = cx.create_rw_signal(active);
+        let terminal_tab_id = TerminalTabId::next();
+        Self {
+            scope: cx,
+            terminal_tab_id,
+            active,
+            terminals,
+            layout: cx.create_rw_signal(layout),
+        }
+    }
+
+    /// The terminals of the tab to save with the workspace, if there are any
+    /// that aren't running a run config.
+    pub fn split_info(&self) -> Option<TerminalSplitInfo> {
+        let active = self.active.get_untracked();
+        let (terminals, active, term_ids) =
+            self.terminals.with_untracked(|terminals| {
+                let mut saved = Vec::new();
+                let mut saved_active = 0;
+                let mut term_ids = Vec::new();
+                for (i, (_, terminal)) in terminals.iter().enumerate() {
+                    if let Some(info) = terminal.info() {
+                        if i <= active {
+                            saved_active = saved.len();
+                        }
+                        saved.push(info);
+                        term_ids.push(terminal.term_id);
+                    }
+                }
+                (saved, saved_active, term_ids)
+            });
+        let layout = self.layout.with_untracked(|layout| {
+            layout
+                .as_ref()?
+                .filter_map(&|term_id| term_ids.iter().position(|id| *id == term_id))
+        });
+        (!terminals.is_empty()).then_some(TerminalSplitInfo {
+            active,
+            terminals,
+            layout,
+        })
+    }
+
     pub fn active_terminal(&self, tracked: bool) -> Option<TerminalData> {
         let active = if tracked {
             self.active.get()
@@ -66,3 +324,201 @@ pub fn active_terminal(&self, tracked: bool) -> Option<TerminalData> {
         }
     }
 }
+
+/// Start the terminals of the pane of a layout, or none if it's an empty split
+fn layout_node(
+    cx: Scope,
+    workspace: &Arc<LapceWorkspace>,
+    pane: &TerminalLayoutPane,
+    common: &Rc<CommonData>,
+    terminals: &mut im::Vector<(RwSignal<usize>, TerminalData)>,
+) -> Option<TerminalSplitNode> {
+    match pane {
+        TerminalLayoutPane::Terminal(terminal) => {
+            let profile = common
+                .config
+                .get_untracked()
+                .terminal
+                .layout_profile(terminal, workspace.path.as_deref());
+            let terminal = TerminalData::new(
+                cx,
+                workspace.clone(),
+                Some(profile),
+                common.clone(),
+            );
+            let node = TerminalSplitNode::Terminal(terminal.term_id);
+            let index = cx.create_rw_signal(terminals.len());
+            terminals.push_back((index, terminal));
+            Some(node)
+        }
+        TerminalLayoutPane::Split(split) => {
+            let mut children = split
+                .panes
+                .iter()
+                .filter_map(|pane| {
+                    layout_node(cx, workspace, pane, common, terminals)
+                })
+                .collect::<Vec<_>>();
+            match children.len() {
+                0 => None,
+                1 => children.pop(),
+                _ => Some(TerminalSplitNode::Split {
+                    direction: split.direction,
+                    children,
+                }),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lapce_rpc::terminal::TermId;
+
+    use super::TerminalSplitNode;
+    use crate::config::terminal::TerminalSplitDirection;
+
+    fn split(
+        direction: TerminalSplitDirection,
+        children: Vec<TerminalSplitNode>,
+    ) -> TerminalSplitNode {
+        TerminalSplitNode::Split {
+            direction,
+            children,
+        }
+    }
+
+    #[test]
+    fn insert_next_to_terminal() {
+        let (a, b, c) = (TermId::next(), TermId::next(), TermId::next());
+        let mut node = TerminalSplitNode::Terminal(a);
+        assert!(node.insert_after(a, b));
+        assert_eq!(
+            node,
+            split(
+                TerminalSplitDirection::Horizontal,
+                vec![
+                    TerminalSplitNode::Terminal(a),
+                    TerminalSplitNode::Terminal(b)
+                ]
+            )
+        );
+
+        assert!(node.insert_after(a, c));
+        assert!(!node.insert_after(TermId::next(), c));
+        assert_eq!(
+            node,
+            split(
+                TerminalSplitDirection::Horizontal,
+                vec![
+                    TerminalSplitNode::Terminal(a),
+                    TerminalSplitNode::Terminal(c),
+                    TerminalSplitNode::Terminal(b),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn remove_collapses_splits() {
+        let (a, b, c) = (TermId::next(), TermId::next(), TermId::next());
+        let mut node = split(
+            TerminalSplitDirection::Horizontal,
+            vec![
+                TerminalSplitNode::Terminal(a),
+                split(
+                    TerminalSplitDirection::Vertical,
+                    vec![
+                        TerminalSplitNode::Terminal(b),
+                        TerminalSplitNode::Terminal(c),
+                    ],
+                ),
+            ],
+        );
+
+        assert!(node.remove(b));
+        assert_eq!(
+            node,
+            split(
+                TerminalSplitDirection::Horizontal,
+                vec![
+                    TerminalSplitNode::Terminal(a),
+                    TerminalSplitNode::Terminal(c)
+                ]
+            )
+        );
+        assert!(node.remove(a));
+        assert_eq!(node, TerminalSplitNode::Terminal(c));
+        assert!(!node.remove(c));
+    }
+
+    #[test]
+    fn swap_terminals() {
+        let (a, b) = (TermId::next(), TermId::next());
+        let mut node = split(
+            TerminalSplitDirection::Vertical,
+            vec![
+                TerminalSplitNode::Terminal(a),
+                TerminalSplitNode::Terminal(b),
+            ],
+        );
+        node.swap(a, b);
+        assert_eq!(
+            node,
+            split(
+                TerminalSplitDirection::Vertical,
+                vec![
+                    TerminalSplitNode::Terminal(b),
+                    TerminalSplitNode::Terminal(a)
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn save_and_restore_arrangement() {
+        let (a, b, c) = (TermId::next(), TermId::next(), TermId::next());
+        let node = split(
+            TerminalSplitDirection::Horizontal,
+            vec![
+                TerminalSplitNode::Terminal(a),
+                split(
+                    TerminalSplitDirection::Vertical,
+                    vec![
+                        TerminalSplitNode::Terminal(b),
+                        TerminalSplitNode::Terminal(c),
+                    ],
+                ),
+            ],
+        );
+
+        // the terminal of a run config isn't saved
+        let saved_ids = [a, b];
+        let saved = node
+            .filter_map(&|term_id| saved_ids.iter().position(|id| *id == term_id))
+            .unwrap();
+        assert_eq!(
+            saved,
+            TerminalSplitNode::Split {
+                direction: TerminalSplitDirection::Horizontal,
+                children: vec![
+                    TerminalSplitNode::Terminal(0),
+                    TerminalSplitNode::Terminal(1)
+                ],
+            }
+        );
+
+        let (d, e) = (TermId::next(), TermId::next());
+        let restored = saved.filter_map(&|i| [d, e].get(i).copied());
+        assert_eq!(
+            restored,
+            Some(split(
+                TerminalSplitDirection::Horizontal,
+                vec![
+                    TerminalSplitNode::Terminal(d),
+                    TerminalSplitNode::Terminal(e)
+                ]
+            ))
+        );
+    }
+}
@@ -1,37 +1,48 @@
-use std::{sync::Arc, time::SystemTime};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
 
 use alacritty_terminal::{
     grid::Dimensions,
     index::Side,
     selection::{Selection, SelectionType},
-    term::{RenderableContent, cell::Flags, test::TermSize},
+    term::{RenderableContent, cell::Flags, search::RegexSearch, test::TermSize},
 };
 use floem::{
     Renderer, View, ViewId,
     context::{EventCx, PaintCx},
     event::{Event, EventPropagation},
-    kurbo::Stroke,
+    ext_event::create_ext_action,
+    kurbo::{Circle, Stroke},
     peniko::{
         Color,
         kurbo::{Point, Rect, Size},
     },
     pointer::PointerInputEvent,
     prelude::SignalTrack,
-    reactive::{ReadSignal, RwSignal, SignalGet, SignalWith, create_effect},
+    reactive::{Memo, ReadSignal, RwSignal, SignalGet, SignalWith, create_effect},
     text::{Attrs, AttrsList, FamilyOwned, TextLayout, Weight},
     views::editor::{core::register::Clipboard, text::SystemClipboard},
 };
 use lapce_core::mode::Mode;
-use lapce_rpc::{proxy::ProxyRpcHandler, terminal::TermId};
+use lapce_rpc::{
+    proxy::{ProxyResponse, ProxyRpcHandler},
+    terminal::TermId,
+};
 use lsp_types::Position;
 use parking_lot::RwLock;
 use regex::Regex;
 use unicode_width::UnicodeWidthChar;
 
-use super::{panel::TerminalPanelData, raw::RawTerminal};
+use super::{
+    panel::TerminalPanelData,
+    raw::{RawTerminal, visible_regex_match_iter},
+};
 use crate::{
     command::InternalCommand,
-    config::{LapceConfig, color::LapceColor},
+    config::{LapceConfig, color::LapceColor, terminal::URL_PATTERN},
     debug::RunDebugProcess,
     editor::location::{EditorLocation, EditorPosition},
     listener::Listener,
@@ -42,6 +53,8 @@
 
 /// Threshold used for double_click/triple_click.
 const CLICK_THRESHOLD: u128 = 400;
+/// The width on the left of the terminal content for the command markers
+const GUTTER_WIDTH: f64 = 8.0;
 
 enum TerminalViewState {
     Config,
@@ -70,7 +83,14 @@ pub struct TerminalView {
     launch_error: RwSignal<Option<String>>,
     internal_command: Listener<InternalCommand>,
     workspace: Arc<LapceWorkspace>,
-    hyper_regs: Vec<Regex>,
+    /// The regexes of the file locations that can be clicked
+    link_matchers: Vec<Regex>,
+    url_reg: Regex,
+    find_pattern: Memo<Option<String>>,
+    /// The search built from the find pattern, kept until the pattern changes
+    find_search: Option<(String, Option<RegexSearch>)>,
+    /// The width reserved for the command markers, once there are any
+    gutter_width: f64,
     previous_mouse_action: MouseAction,
     current_mouse_action: MouseAction,
 }
@@ -104,6 +124,12 @@ pub fn terminal_view(
         id.update_state(TerminalViewState::Config);
     });
 
+    let find_pattern = terminal_panel_data.find.pattern;
+    create_effect(move |_| {
+        find_pattern.track();
+        id.request_paint();
+    });
+
     let proxy = terminal_panel_data.common.proxy.clone();
 
     create_effect(move |last| {
@@ -116,6 +142,10 @@ pub fn terminal_view(
                 let terminal = tab.active_terminal(true);
                 is_focused = terminal.map(|t| t.term_id) == Some(term_id);
             }
+        } else if let Focus::Workbench = focus {
+            // a terminal in the editor tabs
+            is_focused = terminal_panel_data.main_split.active_terminal(true)
+                == Some(term_id);
         }
 
         if last != Some(is_focused) {
@@ -125,8 +155,8 @@ pub fn terminal_view(
         is_focused
     });
 
-    // for rust
-    let reg = regex::Regex::new("[\\w\\\\/-]+\\.(rs)?(toml)?:\\d+(:\\d+)?").unwrap();
+    let link_matchers = config.get_untracked().terminal.link_matchers();
+    let url_reg = Regex::new(URL_PATTERN).unwrap();
 
     TerminalView {
         id,
@@ -141,7 +171,11 @@ pub fn terminal_view(
         launch_error,
         internal_command,
         workspace,
-        hyper_regs: vec![reg],
+        link_matchers,
+        url_reg,
+        find_pattern,
+        find_search: None,
+        gutter_width: 0.0,
         previous_mouse_action: Default::default(),
         current_mouse_action: Default::default(),
     }
@@ -165,52 +199,111 @@ fn terminal_size(&self) -> (usize, usize) {
         let config = self.config.get_untracked();
         let line_height = config.terminal_line_height() as f64;
         let char_width = self.char_size().width;
-        let width = (self.size.width / char_width).floor() as usize;
+        let width =
+            ((self.size.width - self.gutter_width) / char_width).floor() as usize;
         let height = (self.size.height / line_height).floor() as usize;
         (width.max(1), height.max(1))
     }
 
+    /// The gutter only takes from the width of the terminal once the shell
+    /// integration has marked a command
+    fn gutter_width(&self) -> f64 {
+        if self.raw.read().commands.is_empty() {
+            0.0
+        } else {
+            GUTTER_WIDTH
+        }
+    }
+
+    fn resize(&self) {
+        let (width, height) = self.terminal_size();
+        let term_size = TermSize::new(width, height);
+        self.raw.write().term.resize(term_size);
+        self.proxy.terminal_resize(self.term_id, width, height);
+    }
+
     fn click(&self, pos: Point) -> Option<()> {
         let raw = self.raw.read();
         let position = self.get_terminal_point(pos);
-        let start_point = raw.term.semantic_search_left(position);
-        let end_point = raw.term.semantic_search_right(position);
-        let mut selection =
-            Selection::new(SelectionType::Simple, start_point, Side::Left);
-        selection.update(end_point, Side::Right);
-        selection.include_all();
-        if let Some(selection) = selection.to_range(&raw.term) {
-            let content = raw.term.bounds_to_string(selection.start, selection.end);
-            if let Some(match_str) =
-                self.hyper_regs.iter().find_map(|x| x.find(&content))
-            {
-                let hyperlink = match_str.as_str();
-                let content: Vec<&str> = hyperlink.split(':').collect();
-                let (file, line, col) = (
-                    content.first()?,
-                    content.get(1).and_then(|x: &&str| x.parse::<u32>().ok())?,
-                    content
-                        .get(2)
-                        .and_then(|x: &&str| x.parse::<u32>().ok())
-                        .unwrap_or(0),
-                );
-                let parent_path = self.workspace.path.as_ref()?;
-                self.internal_command.send(InternalCommand::JumpToLocation {
+        let start = raw.term.line_search_left(position);
+        let end = raw.term.line_search_right(position);
+        let content = raw.term.bounds_to_string(start, end);
+        let index = raw.char_index(start, position);
+        let offset = content.char_indices().nth(index).map(|(i, _)| i)?;
+
+        if let Some(url) = self
+            .url_reg
+            .find_iter(&content)
+            .find(|m| m.range().contains(&offset))
+        {
+            let uri = url
+                .as_str()
+                .trim_end_matches(['.', ',', ';', ':', '\'', ')', ']'])
+                .to_string();
+            self.internal_command
+                .send(InternalCommand::OpenWebUri { uri });
+            return Some(());
+        }
+
+        let captures = self.link_matchers.iter().find_map(|reg| {
+            reg.captures_iter(&content).find(|captures| {
+                captures.get(0).is_some_and(|m| m.range().contains(&offset))
+            })
+        })?;
+        let path = PathBuf::from(captures.name("path")?.as_str());
+        let paths = self.link_path_candidates(raw.cwd.as_deref(), &path);
+        let line = captures
+            .name("line")
+            .and_then(|line| line.as_str().parse::<u32>().ok());
+        let column = captures
+            .name("column")
+            .and_then(|column| column.as_str().parse::<u32>().ok())
+            .unwrap_or(0);
+
+        // the files are on the proxy's side, which is another machine for a
+        // remote workspace
+        let internal_command = self.internal_command;
+        let send = create_ext_action(
+            internal_command.scope(),
+            move |path: Option<PathBuf>| {
+                let Some(path) = path else {
+                    return;
+                };
+                internal_command.send(InternalCommand::JumpToLocation {
                     location: EditorLocation {
-                        path: parent_path.join(file),
-                        position: Some(EditorPosition::Position(Position::new(
-                            line.saturating_sub(1),
-                            col.saturating_sub(1),
-                        ))),
+                        path,
+                        position: line.map(|line| {
+                            EditorPosition::Position(Position::new(
+                                line.saturating_sub(1),
+                                column.saturating_sub(1),
+                            ))
+                        }),
                         scroll_offset: None,
                         ignore_unconfirmed: false,
                         same_editor_tab: false,
                     },
                 });
-                return Some(());
-            }
+            },
+        );
+        self.proxy
+            .find_existing_path(paths, move |result| match result {
+                Ok(ProxyResponse::FindExistingPathResponse { path }) => send(path),
+                Ok(_) => {}
+                Err(err) => tracing::error!("{:?}", err),
+            });
+        Some(())
+    }
+
+    /// Relative paths in the output are relative to the terminal's working
+    /// directory, or failing that the workspace.
+    fn link_path_candidates(&self, cwd: Option<&Path>, path: &Path) -> Vec<PathBuf> {
+        if path.is_absolute() {
+            return vec![path.to_path_buf()];
         }
-        None
+        cwd.into_iter()
+            .chain(self.workspace.path.as_deref())
+            .map(|dir| dir.join(path))
+            .collect()
     }
 
     fn update_mouse_action_by_down(&mut self, mouse: &PointerInputEvent) {
@@ -313,7 +406,8 @@ fn update_mouse_action_by_up(&mut self, mouse: &PointerInputEvent) {
 
     fn get_terminal_point(&self, pos: Point) -> alacritty_terminal::index::Point {
         let raw = self.raw.read();
-        let col = (pos.x / self.char_size().width) as usize;
+        let col =
+            ((pos.x - self.gutter_width).max(0.0) / self.char_size().width) as usize;
         let line_no = pos.y as i32
             / (self.config.get().terminal_line_height() as i32)
             - raw.term.grid().display_offset() as i32;
@@ -355,7 +449,7 @@ fn paint_content(
             let cell = item.cell;
             let inverse = cell.flags.contains(Flags::INVERSE);
 
-            let x = point.column.0 as f64 * char_width;
+            let x = self.gutter_width + point.column.0 as f64 * char_width;
             let y =
                 (point.line.0 as f64 + content.display_offset as f64) * line_height;
             let char_y = y + (line_height - char_size.height) / 2.0;
@@ -437,7 +531,10 @@ fn paint_line_content(
                 line_height,
             )
             .to_rect()
-            .with_origin(Point::new(*start as f64 * char_width, line_content.y));
+            .with_origin(Point::new(
+                self.gutter_width + *start as f64 * char_width,
+                line_content.y,
+            ));
             cx.fill(&rect, bg, 0.0);
         }
 
@@ -445,7 +542,10 @@ fn paint_line_content(
             let rect =
                 Size::new(char_width * (end.saturating_sub(*start) as f64), 1.0)
                     .to_rect()
-                    .with_origin(Point::new(*start as f64 * char_width, y - 1.0));
+                    .with_origin(Point::new(
+                        self.gutter_width + *start as f64 * char_width,
+                        y - 1.0,
+                    ));
             cx.fill(&rect, fg, 0.0);
         }
 
@@ -485,6 +585,53 @@ fn paint_line_content(
     }
 }
 
+impl TerminalView {
+    /// Mark the prompts of the commands in the gutter with how they went.
+    fn paint_command_markers(
+        &self,
+        cx: &mut PaintCx,
+        raw: &RawTerminal,
+        display_offset: usize,
+        line_height: f64,
+        config: &LapceConfig,
+    ) {
+        let screen_lines = raw.term.screen_lines() as i32;
+        let radius = (GUTTER_WIDTH / 2.0 - 1.5).min(line_height / 4.0);
+        for command in &raw.commands {
+            if command.output_line.is_none() {
+                continue;
+            }
+            let Some(line) = raw.grid_line(command.prompt_line) else {
+                continue;
+            };
+            let line = line.0 + display_offset as i32;
+            if line < 0 || line >= screen_lines {
+                continue;
+            }
+            let center = Point::new(
+                GUTTER_WIDTH / 2.0,
+                line as f64 * line_height + line_height / 2.0,
+            );
+            let circle = Circle::new(center, radius);
+            match (command.is_running(), command.exit_code) {
+                (true, _) | (false, None) => {
+                    cx.stroke(
+                        &circle,
+                        config.color(LapceColor::EDITOR_DIM),
+                        &Stroke::new(1.0),
+                    );
+                }
+                (false, Some(0)) => {
+                    cx.fill(&circle, config.color(LapceColor::TERMINAL_GREEN), 0.0);
+                }
+                (false, Some(_)) => {
+                    cx.fill(&circle, config.color(LapceColor::TERMINAL_RED), 0.0);
+                }
+            }
+        }
+    }
+}
+
 impl Drop for TerminalView {
     fn drop(&mut self) {
         self.proxy.terminal_close(self.term_id);
@@ -586,7 +733,10 @@ fn update(
     ) {
         if let Ok(state) = state.downcast() {
             match *state {
-                TerminalViewState::Config => {}
+                TerminalViewState::Config => {
+                    self.link_matchers =
+                        self.config.get_untracked().terminal.link_matchers();
+                }
                 TerminalViewState::Focus(is_focused) => {
                     self.is_focused = is_focused;
                 }
@@ -615,12 +765,11 @@ fn compute_layout(
         if size.is_zero_area() {
             return None;
         }
-        if size != self.size {
+        let gutter_width = self.gutter_width();
+        if size != self.size || gutter_width != self.gutter_width {
             self.size = size;
-            let (width, height) = self.terminal_size();
-            let term_size = TermSize::new(width, height);
-            self.raw.write().term.resize(term_size);
-            self.proxy.terminal_resize(self.term_id, width, height);
+            self.gutter_width = gutter_width;
+            self.resize();
         }
 
         None
@@ -655,13 +804,23 @@ fn paint(&mut self, cx: &mut floem::context::PaintCx) {
             return;
         }
 
+        let pattern = self.find_pattern.get_untracked();
+        if self.find_search.as_ref().map(|(p, _)| p) != pattern.as_ref() {
+            self.find_search = pattern.map(|pattern| {
+                let search = RegexSearch::new(&pattern).ok();
+                (pattern, search)
+            });
+        }
+
+        // the terminal is resized for the gutter in the layout
+        if self.gutter_width() != self.gutter_width {
+            self.id.request_layout();
+        }
+
         let raw = self.raw.read();
         let term = &raw.term;
         let content = term.renderable_content();
 
-        // let mut search = RegexSearch::new("[\\w\\\\?]+\\.rs:\\d+:\\d+").unwrap();
-        // self.hyper_matches = visible_regex_match_iter(term, &mut search).collect();
-
         if let Some(selection) = content.selection.as_ref() {
             let start_line = selection.start.line.0 + content.display_offset as i32;
             let start_line = if start_line < 0 {
@@ -686,8 +845,8 @@ fn paint(&mut self, cx: &mut floem::context::PaintCx) {
                 } else {
                     term.last_column().0
                 };
-                let x0 = left_col as f64 * char_width;
-                let x1 = right_col as f64 * char_width;
+                let x0 = self.gutter_width + left_col as f64 * char_width;
+                let x1 = self.gutter_width + right_col as f64 * char_width;
                 let y0 = line as f64 * line_height;
                 let y1 = y0 + line_height;
                 cx.fill(
@@ -707,64 +866,37 @@ fn paint(&mut self, cx: &mut floem::context::PaintCx) {
             );
         }
 
+        let display_offset = content.display_offset;
         self.paint_content(cx, content, line_height, char_size, &config);
-        // if data.find.visual {
-        //     if let Some(search_string) = data.find.search_string.as_ref() {
-        //         if let Ok(dfas) = RegexSearch::new(&regex::escape(search_string)) {
-        //             let mut start = alacritty_terminal::index::Point::new(
-        //                 alacritty_terminal::index::Line(
-        //                     -(content.display_offset as i32),
-        //                 ),
-        //                 alacritty_terminal::index::Column(0),
-        //             );
-        //             let end_line = (start.line + term.screen_lines())
-        //                 .min(term.bottommost_line());
-        //             let mut max_lines = (end_line.0 - start.line.0) as usize;
-
-        //             while let Some(m) = term.search_next(
-        //                 &dfas,
-        //                 start,
-        //                 Direction::Right,
-        //                 Side::Left,
-        //                 Some(max_lines),
-        //             ) {
-        //                 let match_start = m.start();
-        //                 if match_start.line.0 < start.line.0
-        //                     || (match_start.line.0 == start.line.0
-        //                         && match_start.column.0 < start.column.0)
-        //                 {
-        //                     break;
-        //                 }
-        //                 let x = match_start.column.0 as f64 * char_width;
-        //                 let y = (match_start.line.0 as f64
-        //                     + content.display_offset as f64)
-        //                     * line_height;
-        //                 let rect = Rect::ZERO
-        //                     .with_origin(Point::new(x, y))
-        //                     .with_size(Size::new(
-        //                         (m.end().column.0 - m.start().column.0
-        //                             + term.grid()[*m.end()].c.width().unwrap_or(1))
-        //                             as f64
-        //                             * char_width,
-        //                         line_height,
-        //                     ));
-        //                 cx.stroke(
-        //                     &rect,
-        //                     config.get_color(LapceColor::TERMINAL_FOREGROUND),
-        //                     1.0,
-        //                 );
-        //                 start = *m.end();
-        //                 if start.column.0 < term.last_column() {
-        //                     start.column.0 += 1;
-        //                 } else if start.line.0 < term.bottommost_line() {
-        //                     start.column.0 = 0;
-        //                     start.line.0 += 1;
-        //                 }
-        //                 max_lines = (end_line.0 - start.line.0) as usize;
-        //             }
-        //         }
-        //     }
-        // }
+        self.paint_command_markers(cx, &raw, display_offset, line_height, &config);
+        if let Some((_, Some(search))) = self.find_search.as_mut() {
+            let color = config.color(LapceColor::TERMINAL_FOREGROUND);
+            let display_offset = term.grid().display_offset() as i32;
+            for m in visible_regex_match_iter(term, search) {
+                let (start, end) = (m.start(), m.end());
+                let end_width = term.grid()[*end].c.width().unwrap_or(1);
+                for line in start.line.0..=end.line.0 {
+                    let left_col = if line == start.line.0 {
+                        start.column.0
+                    } else {
+                        0
+                    };
+                    let right_col = if line == end.line.0 {
+                        end.column.0 + end_width
+                    } else {
+                        term.columns()
+                    };
+                    let y0 = (line + display_offset) as f64 * line_height;
+                    let rect = Rect::new(
+                        self.gutter_width + left_col as f64 * char_width,
+                        y0,
+                        self.gutter_width + right_col as f64 * char_width,
+                        y0 + line_height,
+                    );
+                    cx.stroke(&rect, color, &Stroke::new(1.0));
+                }
+            }
+        }
     }
 }
 
@@ -0,0 +1,272 @@
+use std::{path::Path, rc::Rc};
+
+use floem::{
+    ext_event::create_ext_action,
+    reactive::{RwSignal, Scope, SignalGet, SignalUpdate, SignalWith},
+};
+use lapce_rpc::{
+    proxy::ProxyResponse,
+    testing::{TestItem, TestResult, TestRunner, TestState},
+};
+use lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range};
+
+use crate::{
+    command::InternalCommand,
+    editor::location::{EditorLocation, EditorPosition},
+    main_split::MainSplitData,
+    window_tab::CommonData,
+};
+
+/// The source of the diagnostics of the failed tests
+const DIAGNOSTIC_SOURCE: &str = "Tests";
+/// The lines of the output of a failed test that are shown at its location
+const MESSAGE_LINES: usize = 12;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestStatus {
+    Running,
+    Done(TestState),
+}
+
+#[derive(Clone)]
+pub struct TestingData {
+    pub tests: RwSignal<im::Vector<TestItem>>,
+    /// The result of the last run of each test, by id
+    pub results: RwSignal<im::HashMap<String, TestResult>>,
+    /// The ids of the tests being run
+    pub running: RwSignal<im::HashSet<String>>,
+    pub discovering: RwSignal<bool>,
+    pub discovered: RwSignal<bool>,
+    /// Why the last discovery or run failed, e.g. a compile error
+    pub error: RwSignal<Option<String>>,
+    pub main_split: MainSplitData,
+    pub common: Rc<CommonData>,
+}
+
+impl TestingData {
+    pub fn new(
+        cx: Scope,
+        main_split: MainSplitData,
+        common: Rc<CommonData>,
+    ) -> Self {
+        Self {
+            tests: cx.create_rw_signal(im::Vector::new()),
+            results: cx.create_rw_signal(im::HashMap::new()),
+            running: cx.create_rw_signal(im::HashSet::new()),
+            discovering: cx.create_rw_signal(false),
+            discovered: cx.create_rw_signal(false),
+            error: cx.create_rw_signal(None),
+            main_split,
+            common,
+        }
+    }
+
+    pub fn discover(&self) {
+        if self.common.workspace.path.is_none() || self.discovering.get_untracked() {
+            return;
+        }
+        self.discovering.set(true);
+        let data = self.clone();
+        let send = create_ext_action(self.common.scope, move |result| {
+            data.discovering.set(false);
+            data.discovered.set(true);
+            match result {
+                Ok(ProxyResponse::DiscoverTestsResponse { tests }) => {
+                    data.tests.set(tests.into());
+                    data.update_diagnostics();
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    tracing::error!("{:?}", err);
+                }
+            }
+        });
+        self.common.proxy.discover_tests(move |result| {
+            send(result);
+        });
+    }
+
+    pub fn status(&self, id: &str) -> Option<TestStatus> {
+        if self.running.with(|running| running.contains(id)) {
+            return Some(TestStatus::Running);
+        }
+        self.results
+            .with(|results| results.get(id).map(|r| TestStatus::Done(r.state)))
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.with(|running| !running.is_empty())
+    }
+
+    /// Run the tests, with one run for each of their runners
+    pub fn run(&self, tests: Vec<TestItem>) {
+        for runner in [TestRunner::Cargo, TestRunner::Pytest, TestRunner::Go] {
+            let ids = tests
+                .iter()
+                .filter(|t| t.runner == runner)
+                .map(|t| t.id.clone())
+                .collect::<Vec<_>>();
+            if !ids.is_empty() {
+                self.run_ids(runner, ids.clone(), ids);
+            }
+        }
+    }
+
+    pub fn run_all(&self) {
+        let tests = self.tests.get_untracked();
+        for runner in [TestRunner::Cargo, TestRunner::Pytest, TestRunner::Go] {
+            let ids = tests
+                .iter()
+                .filter(|t| t.runner == runner)
+                .map(|t| t.id.clone())
+                .collect::<Vec<_>>();
+            if !ids.is_empty() {
+                // the runner finds the tests by itself, including the new ones
+                self.run_ids(runner, Vec::new(), ids);
+            }
+        }
+    }
+
+    pub fn run_failed(&self) {
+        let failed = self.results.with_untracked(|results| {
+            self.tests.with_untracked(|tests| {
+                tests
+                    .iter()
+                    .filter(|t| {
+                        results.get(&t.id).map(|r| r.state)
+                            == Some(TestState::Failed)
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+        });
+        self.run(failed);
+    }
+
+    pub fn run_file(&self, path: &Path) {
+        let tests = self.tests.with_untracked(|tests| {
+            tests
+                .iter()
+                .filter(|t| t.path.as_deref() == Some(path))
+                .cloned()
+                .collect::<Vec<_>>()
+        });
+        self.run(tests);
+    }
+
+    /// Run the tests of the ids with the runner, or all of its tests if there
+    /// are none, showing the `running` ones as running until it's done.
+    fn run_ids(&self, runner: TestRunner, ids: Vec<String>, running: Vec<String>) {
+        self.error.set(None);
+        self.running.update(|r| r.extend(running.iter().cloned()));
+        let data = self.clone();
+        let send = create_ext_action(self.common.scope, move |result| {
+            data.running.update(|r| {
+                for id in &running {
+                    r.remove(id);
+                }
+            });
+            match result {
+                Ok(ProxyResponse::RunTestsResponse { results }) => {
+                    data.results.update(|all| {
+                        for result in results {
+                            all.insert(result.id.clone(), result);
+                        }
+                    });
+                    data.update_diagnostics();
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    tracing::error!("{:?}", err);
+                    data.error.set(Some(err.message));
+                }
+            }
+        });
+        self.common.proxy.run_tests(runner, ids, move |result| {
+            send(result);
+        });
+    }
+
+    pub fn go_to_test(&self, test: &TestItem) {
+        let Some(path) = test.path.clone() else {
+            return;
+        };
+        self.common
+            .internal_command
+            .send(InternalCommand::JumpToLocation {
+                location: EditorLocation {
+                    path,
+                    position: test
+                        .line
+                        .map(|line| EditorPosition::Line(line as usize)),
+                    scroll_offset: None,
+                    ignore_unconfirmed: false,
+                    same_editor_tab: false,
+                },
+            });
+    }
+
+    /// Show the output of the failed tests at their locations
+    fn update_diagnostics(&self) {
+        let failures = self.results.with_untracked(|results| {
+            self.tests.with_untracked(|tests| {
+                tests
+                    .iter()
+                    .filter_map(|test| {
+                        let result = results.get(&test.id)?;
+                        if result.state != TestState::Failed {
+                            return None;
+                        }
+                        let path = test.path.clone()?;
+                        let position = Position::new(test.line.unwrap_or(0), 0);
+                        let message = failure_message(test, result);
+                        Some((
+                            path,
+                            Diagnostic {
+                                range: Range::new(position, position),
+                                severity: Some(DiagnosticSeverity::ERROR),
+                                source: Some(DIAGNOSTIC_SOURCE.to_string()),
+                                message,
+                                ..Default::default()
+                            },
+                        ))
+                    })
+                    .collect::<Vec<_>>()
+            })
+        });
+        self.main_split.clear_task_diagnostics(DIAGNOSTIC_SOURCE);
+        self.main_split
+            .add_task_diagnostics(DIAGNOSTIC_SOURCE, &failures);
+    }
+}
+
+/// The first lines of the output of the failed test, or just that it failed
+/// if it had none.
+pub fn failure_message(test: &TestItem, result: &TestResult) -> String {
+    let output = result
+        .message
+        .as_deref()
+        .map(|message| {
+            message
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .take(MESSAGE_LINES)
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_default();
+    if output.is_empty() {
+        format!("{} failed", test.name)
+    } else {
+        format!("{} failed\n{output}", test.name)
+    }
+}
+
+/// A duration in seconds, as e.g. `12ms` or `1.5s`
+pub fn format_duration(seconds: f64) -> String {
+    if seconds < 1.0 {
+        format!("{}ms", (seconds * 1000.0).round() as u64)
+    } else {
+        format!("{seconds:.1}s")
+    }
+}
@@ -10,7 +10,7 @@
     time::Instant,
 };
 
-use alacritty_terminal::vte::ansi::Handler;
+use alacritty_terminal::{index::Direction, vte::ansi::Handler};
 use floem::{
     ViewId,
     action::{TimerToken, open_file, remove_overlay},
@@ -29,10 +29,10 @@
 };
 use im::HashMap;
 use indexmap::IndexMap;
-use itertools::Itertools;
 use lapce_core::{
-    command::FocusCommand, cursor::CursorAffinity, directory::Directory, meta,
-    mode::Mode, register::Register,
+    command::FocusCommand, cursor::CursorAffinity, directory::Directory,
+    language::LapceLanguage, meta, mode::Mode, register::Register,
+    syntax::highlight::reset_highlight_configs,
 };
 use lapce_rpc::{
     RpcError,
@@ -42,11 +42,12 @@
     plugin::PluginId,
     proxy::{ProxyResponse, ProxyRpcHandler, ProxyStatus},
     source_control::FileDiff,
+    task::TaskKind,
     terminal::TermId,
 };
 use lsp_types::{
-    CodeActionOrCommand, CodeLens, Diagnostic, ProgressParams, ProgressToken,
-    ShowMessageParams,
+    CodeActionOrCommand, CodeLens, Diagnostic, MessageType, ProgressParams,
+    ProgressToken, ShowMessageParams,
 };
 use serde_json::Value;
 use tracing::{Level, debug, error, event};
@@ -62,7 +63,10 @@
     completion::{CompletionData, CompletionStatus},
     config::LapceConfig,
     db::LapceDb,
-    debug::{DapData, LapceBreakpoint, RunDebugMode, RunDebugProcess},
+    debug::{
+        DapData, DebugInlineValues, LapceBreakpoint, PendingRunDebug, RunDebugInput,
+        RunDebugLaunch, RunDebugMode, RunDebugProcess, config_input_ids,
+    },
     doc::DocContent,
     editor::location::{EditorLocation, EditorPosition},
     editor_tab::EditorTabChild,
@@ -70,12 +74,13 @@
     find::Find,
     global_search::GlobalSearchData,
     hover::HoverData,
-    id::WindowTabId,
+    id::{TerminalTabId, WindowTabId},
     inline_completion::InlineCompletionData,
     keypress::{EventRef, KeyPressData, KeyPressFocus, condition::Condition},
     listener::Listener,
     lsp::path_from_url,
     main_split::{MainSplitData, SplitData, SplitDirection, SplitMoveDirection},
+    output::OutputData,
     palette::{DEFAULT_RUN_TOML, PaletteData, PaletteStatus, kind::PaletteKind},
     panel::{
         call_hierarchy_view::{CallHierarchyData, CallHierarchyItemData},
@@ -87,10 +92,13 @@
     proxy::{ProxyData, new_proxy},
     rename::RenameData,
     source_control::SourceControlData,
+    task::TaskData,
     terminal::{
         event::{TermEvent, TermNotification, terminal_update_process},
-        panel::TerminalPanelData,
+        panel::{TerminalPanelData, TerminalPanelInfo},
+        repl::ReplUnit,
     },
+    testing::TestingData,
     tracing::*,
     window::WindowCommonData,
     workspace::{LapceWorkspace, LapceWorkspaceType, WorkspaceInfo},
@@ -110,12 +118,23 @@ pub enum Focus {
 pub enum DragContent {
     Panel(PanelKind),
     EditorTab(EditorTabChild),
+    TerminalTab(TerminalTabId),
 }
 
 impl DragContent {
     pub fn is_panel(&self) -> bool {
         matches!(self, DragContent::Panel(_))
     }
+
+    /// The terminal of the editor tab being dragged, if it's one
+    pub fn editor_terminal(&self) -> Option<TermId> {
+        match self {
+            DragContent::EditorTab(EditorTabChild::Terminal(_, term_id)) => {
+                Some(*term_id)
+            }
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -152,6 +171,7 @@ pub struct CommonData {
     pub proxy_status: RwSignal<Option<ProxyStatus>>,
     pub mouse_hover_timer: RwSignal<TimerToken>,
     pub breakpoints: RwSignal<BTreeMap<PathBuf, BTreeMap<usize, LapceBreakpoint>>>,
+    pub debug_inline_values: RwSignal<Option<DebugInlineValues>>,
     // the current focused view which will receive keyboard events
     pub keyboard_focus: RwSignal<Option<ViewId>>,
     pub window_common: Rc<WindowCommonData>,
@@ -175,6 +195,9 @@ pub struct WindowTabData {
     pub file_explorer: FileExplorerData,
     pub panel: PanelData,
     pub terminal: TerminalPanelData,
+    pub tasks: TaskData,
+    pub testing: TestingData,
+    pub output: OutputData,
     pub plugin: PluginData,
     pub code_action: RwSignal<CodeActionData>,
     pub code_lens: RwSignal<Option<ViewId>>,
@@ -231,6 +254,9 @@ fn run_command(
             CommandKind::Workbench(cmd) => {
                 self.run_workbench_command(cmd.clone(), None);
             }
+            CommandKind::Plugin(cmd) => {
+                self.plugin.run_volt_command(cmd);
+            }
             CommandKind::Focus(cmd) => {
                 if self.common.focus.get_untracked() == Focus::Workbench {
                     match cmd {
@@ -295,6 +321,7 @@ pub fn new(
             .unwrap_or_default();
         let mut all_disabled_volts = disabled_volts.clone();
         all_disabled_volts.extend(workspace_disabled_volts.clone());
+        let volt_permissions = db.get_volt_permissions().unwrap_or_default();
 
         let workspace_info = if workspace.path.is_some() {
             db.get_workspace_info(&workspace).ok()
@@ -332,8 +359,11 @@ pub fn new(
         let proxy = new_proxy(
             workspace.clone(),
             all_disabled_volts,
+            volt_permissions.clone(),
             window_common.extra_plugin_paths.as_ref().clone(),
             config.plugins.clone(),
+            config.lsp.clone(),
+            config.lsp_policy.clone(),
             term_tx.clone(),
         );
         let (config, set_config) = cx.create_signal(Arc::new(config));
@@ -387,6 +417,7 @@ pub fn new(
             mouse_hover_timer: cx.create_rw_signal(TimerToken::INVALID),
             window_origin: cx.create_rw_signal(Point::ZERO),
             breakpoints: cx.create_rw_signal(BTreeMap::new()),
+            debug_inline_values: cx.create_rw_signal(None),
             keyboard_focus: cx.create_rw_signal(None),
             window_common: window_common.clone(),
         });
@@ -422,12 +453,21 @@ pub fn new(
             });
         }
 
+        let tasks = TaskData::new(
+            cx,
+            workspace_info.as_ref().map(|info| &info.tasks),
+            common.clone(),
+        );
+        tasks.detect();
+        let testing = TestingData::new(cx, main_split.clone(), common.clone());
+
         let palette = PaletteData::new(
             cx,
             workspace.clone(),
             main_split.clone(),
             keypress.read_only(),
             source_control.clone(),
+            tasks.clone(),
             common.clone(),
         );
 
@@ -484,9 +524,15 @@ pub fn new(
                 )
             });
 
+        let terminal_config = common.config.get_untracked();
         let terminal = TerminalPanelData::new(
             workspace.clone(),
-            common.config.get_untracked().terminal.get_default_profile(),
+            terminal_config.terminal.get_default_profile(),
+            terminal_config
+                .terminal
+                .restore_sessions
+                .then(|| db.get_terminals(&workspace).ok())
+                .flatten(),
             common.clone(),
             main_split.clone(),
         );
@@ -510,12 +556,14 @@ pub fn new(
         }
 
         let rename = RenameData::new(cx, main_split.editors, common.clone());
+        let output = OutputData::new(cx, main_split.editors, common.clone());
         let global_search = GlobalSearchData::new(cx, main_split.clone());
 
         let plugin = PluginData::new(
             cx,
             HashSet::from_iter(disabled_volts),
             HashSet::from_iter(workspace_disabled_volts),
+            volt_permissions.into_iter().collect(),
             main_split.editors,
             common.clone(),
             proxy.core_rpc.clone(),
@@ -524,6 +572,7 @@ pub fn new(
         {
             let notification = create_signal_from_channel(term_notification_rx);
             let terminal = terminal.clone();
+            let main_split = main_split.clone();
             cx.create_effect(move |_| {
                 notification.with(|notification| {
                     if let Some(notification) = notification.as_ref() {
@@ -534,6 +583,15 @@ pub fn new(
                             TermNotification::RequestPaint => {
                                 view_id.get_untracked().request_paint();
                             }
+                            TermNotification::Ready { term_id } => {
+                                terminal.run_debug_ready(term_id);
+                            }
+                            TermNotification::Problems { source, problems } => {
+                                main_split.add_task_diagnostics(source, problems);
+                            }
+                            TermNotification::ClearProblems { source } => {
+                                main_split.clear_task_diagnostics(source);
+                            }
                         }
                     }
                 });
@@ -550,6 +608,9 @@ pub fn new(
             palette,
             main_split,
             terminal,
+            tasks,
+            testing,
+            output,
             panel,
             file_explorer,
             code_action,
@@ -626,6 +687,26 @@ pub fn new(
             });
         }
 
+        {
+            // the inline values are part of the phantom text of the doc they
+            // belong to, so both the old and the new doc need to be laid out
+            // again
+            let docs = window_tab_data.main_split.docs;
+            let debug_inline_values = window_tab_data.common.debug_inline_values;
+            cx.create_effect(move |prev_path: Option<Option<PathBuf>>| {
+                let path = debug_inline_values
+                    .with(|values| values.as_ref().map(|v| v.path.clone()));
+                docs.with_untracked(|docs| {
+                    for path in prev_path.iter().flatten().chain(path.iter()) {
+                        if let Some(doc) = docs.get(path) {
+                            doc.clear_text_cache();
+                        }
+                    }
+                });
+                path
+            });
+        }
+
         window_tab_data
     }
 
@@ -648,6 +729,21 @@ pub fn reload_config(&self) {
             keypress.update_keymaps(&config);
         });
 
+        if self.common.config.with_untracked(|c| c.lsp != config.lsp) {
+            self.common
+                .proxy
+                .update_language_servers(config.lsp.clone());
+        }
+        if self
+            .common
+            .config
+            .with_untracked(|c| c.lsp_policy != config.lsp_policy)
+        {
+            self.common
+                .proxy
+                .update_language_server_policies(config.lsp_policy.clone());
+        }
+
         let mut change_plugins = Vec::new();
         for (key, configs) in self.common.config.get_untracked().plugins.iter() {
             if config
@@ -710,6 +806,9 @@ pub fn run_lapce_command(&self, cmd: LapceCommand) {
             }
             CommandKind::MotionMode(_) => {}
             CommandKind::MultiSelection(_) => {}
+            CommandKind::Plugin(command) => {
+                self.plugin.run_volt_command(&command);
+            }
         }
     }
 
@@ -910,6 +1009,40 @@ pub fn run_workbench_command(
                     open_uri(&dir);
                 }
             }
+            InstallPluginFromFile | InstallPluginFromFolder => {
+                let options = if matches!(cmd, InstallPluginFromFolder) {
+                    FileDialogOptions::new()
+                        .title("Choose a plugin folder")
+                        .select_directories()
+                } else {
+                    FileDialogOptions::new().title("Choose a plugin archive")
+                };
+                let plugin = self.plugin.clone();
+                let core_rpc = self.proxy.core_rpc.clone();
+                open_file(options, move |file| {
+                    if let Some(path) = file.and_then(|mut file| file.path.pop()) {
+                        plugin.install_volt_from_path(path, core_rpc.clone());
+                    }
+                });
+            }
+            CheckPluginUpdates => {
+                self.plugin.check_volt_updates(self.proxy.core_rpc.clone());
+            }
+            UpdateAllPlugins => {
+                let updated = self.plugin.update_all_volts();
+                let message = if updated.is_empty() {
+                    "All plugins are up to date".to_string()
+                } else {
+                    format!("Updating {}", updated.join(", "))
+                };
+                self.show_message(
+                    "Plugin Updates",
+                    &ShowMessageParams {
+                        typ: MessageType::INFO,
+                        message,
+                    },
+                );
+            }
             OpenGrammarsDirectory => {
                 if let Some(dir) = Directory::grammars_directory() {
                     open_uri(&dir);
@@ -1049,6 +1182,36 @@ pub fn run_workbench_command(
                 }
                 self.common.focus.set(Focus::Panel(PanelKind::Terminal));
             }
+            NewTerminalInEditor => {
+                self.main_split.new_terminal(
+                    self.common
+                        .config
+                        .get_untracked()
+                        .terminal
+                        .get_default_profile(),
+                );
+                self.common.focus.set(Focus::Workbench);
+            }
+            MoveTerminalToEditor => {
+                if let Some(terminal) = self
+                    .terminal
+                    .active_tab(false)
+                    .and_then(|tab| tab.active_terminal(false))
+                {
+                    self.common.internal_command.send(
+                        InternalCommand::MoveTerminalToEditor {
+                            term_id: terminal.term_id,
+                        },
+                    );
+                }
+            }
+            MoveTerminalToPanel => {
+                if let Some(term_id) = self.main_split.active_terminal(false) {
+                    self.common
+                        .internal_command
+                        .send(InternalCommand::MoveTerminalToPanel { term_id });
+                }
+            }
             CloseTerminalTab => {
                 self.terminal.close_tab(None);
                 if self
@@ -1081,6 +1244,15 @@ pub fn run_workbench_command(
                 }
                 self.common.focus.set(Focus::Panel(PanelKind::Terminal));
             }
+            TerminalPreviousPrompt => {
+                self.terminal.scroll_to_prompt(Direction::Left);
+            }
+            TerminalNextPrompt => {
+                self.terminal.scroll_to_prompt(Direction::Right);
+            }
+            TerminalCopyLastCommandOutput => {
+                self.terminal.copy_last_command_output();
+            }
 
             // ==== Remote ====
             ConnectSshHost => {
@@ -1161,6 +1333,26 @@ pub fn run_workbench_command(
                     self.terminal.stop_run_debug(term_id);
                 }
             }
+            RunDefaultBuildTask => {
+                self.tasks.run_default(TaskKind::Build);
+            }
+            RunDefaultTestTask => {
+                self.tasks.run_default(TaskKind::Test);
+            }
+            RefreshTasks => {
+                self.tasks.detect();
+            }
+            TestingDiscover => {
+                self.testing.discover();
+            }
+            TestingRunAll => {
+                self.show_panel(PanelKind::Testing);
+                self.testing.run_all();
+            }
+            TestingRunFailed => {
+                self.show_panel(PanelKind::Testing);
+                self.testing.run_failed();
+            }
 
             // ==== UI ====
             ZoomIn => {
@@ -1267,6 +1459,15 @@ pub fn run_workbench_command(
             ToggleSearchFocus => {
                 self.toggle_panel_focus(PanelKind::Search);
             }
+            ToggleTaskFocus => {
+                self.toggle_panel_focus(PanelKind::Task);
+            }
+            ToggleTestingFocus => {
+                self.toggle_panel_focus(PanelKind::Testing);
+            }
+            ToggleOutputFocus => {
+                self.toggle_panel_focus(PanelKind::Output);
+            }
             ToggleTerminalVisual => {
                 self.toggle_panel_visual(PanelKind::Terminal);
             }
@@ -1288,6 +1489,9 @@ pub fn run_workbench_command(
             ToggleSearchVisual => {
                 self.toggle_panel_visual(PanelKind::Search);
             }
+            ToggleOutputVisual => {
+                self.toggle_panel_visual(PanelKind::Output);
+            }
             FocusEditor => {
                 self.common.focus.set(Focus::Workbench);
             }
@@ -1529,6 +1733,10 @@ pub fn run_workbench_command(
                             cwd: None,
                             env: None,
                             prelaunch: None,
+                            post_debug: None,
+                            depends_on: None,
+                            ready_pattern: None,
+                            problem_matchers: None,
                             debug_command: None,
                             dap_id: Default::default(),
                             tracing_output: false,
@@ -1540,6 +1748,18 @@ pub fn run_workbench_command(
                     }
                 }
             }
+            SendSelectionToTerminal => {
+                self.send_to_terminal(ReplUnit::Selection);
+            }
+            SendLineToTerminal => {
+                self.send_to_terminal(ReplUnit::Line);
+            }
+            SendBlockToTerminal => {
+                self.send_to_terminal(ReplUnit::Block);
+            }
+            UseTerminalForSentCode => {
+                self.terminal.set_repl_terminal();
+            }
             GoToLocation => {
                 if let Some(editor_data) =
                     self.main_split.active_editor.get_untracked()
@@ -1839,11 +2059,10 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
             InternalCommand::ShowCodeActions {
                 offset,
                 mouse_click,
-                plugin_id,
                 code_actions,
             } => {
                 let mut code_action = self.code_action.get_untracked();
-                code_action.show(plugin_id, code_actions, offset, mouse_click);
+                code_action.show(code_actions, offset, mouse_click);
                 self.code_action.set(code_action);
             }
             InternalCommand::RunCodeAction { plugin_id, action } => {
@@ -1863,6 +2082,13 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
             InternalCommand::NewTerminal { profile } => {
                 self.terminal.new_tab(profile);
             }
+            InternalCommand::OpenTerminalLayout { name } => {
+                self.terminal.open_layout(&name);
+                if !self.panel.is_panel_visible(&PanelKind::Terminal) {
+                    self.panel.show_panel(&PanelKind::Terminal);
+                }
+                self.common.focus.set(Focus::Panel(PanelKind::Terminal));
+            }
             InternalCommand::SplitTerminal { term_id } => {
                 self.terminal.split(term_id);
             }
@@ -1872,11 +2098,59 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
             InternalCommand::SplitTerminalPrevious { term_id } => {
                 self.terminal.split_previous(term_id);
             }
+            InternalCommand::ShowTerminalFind => {
+                self.terminal.show_find();
+            }
+            InternalCommand::TerminalFindNext { direction } => {
+                self.terminal.find_next(direction);
+            }
             InternalCommand::SplitTerminalExchange { term_id } => {
                 self.terminal.split_exchange(term_id);
             }
+            InternalCommand::MoveTerminalToEditor { term_id } => {
+                if self.terminal.move_to_editor(term_id) {
+                    self.terminal_moved_to_editor();
+                }
+            }
+            InternalCommand::MoveTerminalTabToEditor {
+                terminal_tab_id,
+                editor_tab_id,
+            } => {
+                self.main_split.active_editor_tab.set(Some(editor_tab_id));
+                if self.terminal.move_tab_to_editor(terminal_tab_id) {
+                    self.terminal_moved_to_editor();
+                }
+            }
+            InternalCommand::MoveTerminalToPanel { term_id } => {
+                self.terminal.move_from_editor(term_id);
+                if !self.panel.is_panel_visible(&PanelKind::Terminal) {
+                    self.panel.show_panel(&PanelKind::Terminal);
+                }
+                self.common.focus.set(Focus::Panel(PanelKind::Terminal));
+            }
             InternalCommand::RunAndDebug { mode, config } => {
-                self.run_and_debug(cx, &mode, &config);
+                if config.config_source == ConfigSource::Task {
+                    self.tasks.started(&config.name);
+                }
+                self.launch_run_debug(
+                    cx,
+                    RunDebugLaunch {
+                        mode,
+                        configs: vec![config],
+                        compound: None,
+                        inputs: Default::default(),
+                    },
+                );
+            }
+            InternalCommand::RunAndDebugLaunch { launch } => {
+                self.launch_run_debug(cx, launch);
+            }
+            InternalCommand::StartRunDebug {
+                mode,
+                config,
+                compound,
+            } => {
+                self.run_and_debug(cx, &mode, &config, compound);
             }
             InternalCommand::StartRename {
                 path,
@@ -1993,6 +2267,13 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
             InternalCommand::OpenVoltView { volt_id } => {
                 self.main_split.open_volt_view(volt_id);
             }
+            InternalCommand::OpenDisassembly => {
+                self.main_split.open_disassembly();
+            }
+            InternalCommand::OpenMemory { target } => {
+                self.terminal.debug.memory.open(target);
+                self.main_split.open_memory();
+            }
             InternalCommand::ResetBlinkCursor => {
                 // All the editors share the blinking information and logic, so we can just reset
                 // one of them.
@@ -2070,6 +2351,30 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
             InternalCommand::CallHierarchyIncoming { item_id } => {
                 self.call_hierarchy_incoming(item_id);
             }
+            InternalCommand::TaskExited { name, exit_code } => {
+                self.tasks.exited(&name, exit_code);
+            }
+            InternalCommand::VoltLanguagesChanged => {
+                reset_highlight_configs();
+                for (_, doc) in self.main_split.docs.get_untracked() {
+                    let language = doc.syntax().with_untracked(|s| s.language);
+                    // The documents opened before the volts were loaded are
+                    // detected again
+                    if !matches!(
+                        language,
+                        LapceLanguage::PlainText | LapceLanguage::Volt(_)
+                    ) {
+                        continue;
+                    }
+                    let Some(path) =
+                        doc.content.with_untracked(|c| c.path().cloned())
+                    else {
+                        continue;
+                    };
+                    doc.set_language(LapceLanguage::from_path(&path));
+                    doc.trigger_syntax_change(None);
+                }
+            }
         }
     }
 
@@ -2128,26 +2433,15 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
             }
             CoreNotification::PublishDiagnostics { diagnostics } => {
                 let path = path_from_url(&diagnostics.uri);
-                let diagnostics: im::Vector<Diagnostic> = diagnostics
-                    .diagnostics
-                    .clone()
-                    .into_iter()
-                    .sorted_by_key(|d| d.range.start)
-                    .collect();
+                let diagnostics: im::Vector<Diagnostic> =
+                    diagnostics.diagnostics.clone().into_iter().collect();
 
-                self.main_split
-                    .get_diagnostic_data(&path)
-                    .diagnostics
-                    .set(diagnostics);
+                let diagnostic_data = self.main_split.get_diagnostic_data(&path);
+                diagnostic_data.lsp_diagnostics.set(diagnostics);
+                diagnostic_data.merge();
 
                 // inform the document about the diagnostics
-                if let Some(doc) = self
-                    .main_split
-                    .docs
-                    .with_untracked(|docs| docs.get(&path).cloned())
-                {
-                    doc.init_diagnostics();
-                }
+                self.main_split.init_diagnostics(&path);
             }
             CoreNotification::ServerStatus { params } => {
                 if params.is_ok() {
@@ -2188,7 +2482,7 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                 self.terminal.launch_failed(term_id, error);
             }
             CoreNotification::RunInTerminal { config } => {
-                self.run_in_terminal(cx, &RunDebugMode::Debug, config, true);
+                self.run_in_terminal(cx, &RunDebugMode::Debug, config, true, None);
             }
             CoreNotification::TerminalProcessId {
                 term_id,
@@ -2199,18 +2493,44 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
             CoreNotification::DapStopped {
                 dap_id,
                 stopped,
+                threads,
                 stack_frames,
                 variables,
             } => {
                 self.show_panel(PanelKind::Debug);
-                self.terminal
-                    .dap_stopped(dap_id, stopped, stack_frames, variables);
+                self.terminal.dap_stopped(
+                    dap_id,
+                    stopped,
+                    threads,
+                    stack_frames,
+                    variables,
+                );
             }
             CoreNotification::OpenPaths { paths } => {
                 self.open_paths(paths);
             }
-            CoreNotification::DapContinued { dap_id } => {
-                self.terminal.dap_continued(dap_id);
+            CoreNotification::DapContinued {
+                dap_id,
+                thread_id,
+                all_threads_continued,
+            } => {
+                self.terminal.dap_continued(
+                    dap_id,
+                    *thread_id,
+                    *all_threads_continued,
+                );
+            }
+            CoreNotification::DapThreads { dap_id, threads } => {
+                self.terminal.dap_threads(dap_id, threads);
+            }
+            CoreNotification::DapCapabilities {
+                dap_id,
+                capabilities,
+            } => {
+                self.terminal
+                    .debug
+                    .capabilities
+                    .set(*dap_id, capabilities.clone());
             }
             CoreNotification::DapBreakpointsResp {
                 path, breakpoints, ..
@@ -2259,6 +2579,55 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
             CoreNotification::VoltRemoved { volt, .. } => {
                 self.plugin.volt_removed(volt);
             }
+            CoreNotification::VoltPermissionsRequired { volt } => {
+                self.plugin.volt_permissions_required(volt.clone());
+            }
+            CoreNotification::VoltCrashed {
+                volt,
+                name,
+                error,
+                restart_in,
+            } => {
+                self.plugin.volt_crashed(volt);
+                let message = match restart_in {
+                    Some(secs) => format!(
+                        "{name} crashed and will be restarted in {secs}s: {error}"
+                    ),
+                    None => format!(
+                        "{name} crashed too often and won't be restarted: {error}"
+                    ),
+                };
+                self.show_message(
+                    "Plugin Crashed",
+                    &ShowMessageParams {
+                        typ: MessageType::ERROR,
+                        message,
+                    },
+                );
+            }
+            CoreNotification::PluginCommandRegistered {
+                plugin_id,
+                volt,
+                command,
+            } => {
+                self.plugin.volt_command_registered(
+                    *plugin_id,
+                    volt.clone(),
+                    command.clone(),
+                );
+            }
+            CoreNotification::PluginCommandUnregistered { volt, command } => {
+                self.plugin.volt_command_unregistered(volt, command);
+            }
+            CoreNotification::PluginStatusItemSet { volt, item } => {
+                self.plugin.status_item_set(volt.clone(), item.clone());
+            }
+            CoreNotification::PluginStatusItemRemoved { volt, id } => {
+                self.plugin.status_item_removed(volt.clone(), id.clone());
+            }
+            CoreNotification::PluginOutput { channel, line } => {
+                self.output.add_line(channel.clone(), line.clone());
+            }
             CoreNotification::WorkDoneProgress { progress } => {
                 self.update_progress(progress);
             }
@@ -2294,7 +2663,6 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                 }
             }
             CoreNotification::LogMessage { message, target } => {
-                use lsp_types::MessageType;
                 use tracing_log::log::{Level, log};
                 match message.typ {
                     MessageType::ERROR => {
@@ -2322,6 +2690,20 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
         }
     }
 
+    /// Focus the terminal moved to the editor tabs, hiding the panel if it
+    /// was the last one there.
+    fn terminal_moved_to_editor(&self) {
+        if self
+            .terminal
+            .tab_info
+            .with_untracked(|info| info.tabs.is_empty())
+            && self.panel.is_panel_visible(&PanelKind::Terminal)
+        {
+            self.panel.hide_panel(&PanelKind::Terminal);
+        }
+        self.common.focus.set(Focus::Workbench);
+    }
+
     pub fn key_down<'a>(&self, event: impl Into<EventRef<'a>> + Copy) -> bool {
         if self.alert_data.active.get_untracked() {
             return false;
@@ -2349,6 +2731,9 @@ pub fn key_down<'a>(&self, event: impl Into<EventRef<'a>> + Copy) -> bool {
             Focus::Panel(PanelKind::SourceControl) => {
                 Some(keypress.key_down(event, &self.source_control))
             }
+            Focus::Panel(PanelKind::Output) => {
+                Some(keypress.key_down(event, &self.output))
+            }
             _ => None,
         };
 
@@ -2390,9 +2775,20 @@ pub fn workspace_info(&self) -> WorkspaceInfo {
                     (path, breakpoints.into_values().collect::<Vec<_>>())
                 })
                 .collect(),
+            tasks: self.tasks.info(),
         }
     }
 
+    /// The terminals to save with the workspace, if they're to be restored
+    pub fn terminal_info(&self) -> Option<TerminalPanelInfo> {
+        self.common
+            .config
+            .get_untracked()
+            .terminal
+            .restore_sessions
+            .then(|| self.terminal.panel_info())
+    }
+
     pub fn hover_origin(&self) -> Option<Point> {
         if !self.common.hover.active.get_untracked() {
             return None;
@@ -2630,14 +3026,17 @@ fn toggle_panel_focus(&self, kind: PanelKind) {
             | PanelKind::CallHierarchy
             | PanelKind::DocumentSymbol
             | PanelKind::References
-            | PanelKind::Implementation => {
+            | PanelKind::Implementation
+            | PanelKind::Task
+            | PanelKind::Testing => {
                 // Some panels don't accept focus (yet). Fall back to visibility check
                 // in those cases.
                 self.panel.is_panel_visible(&kind)
             }
-            PanelKind::Terminal | PanelKind::SourceControl | PanelKind::Search => {
-                self.is_panel_focused(kind)
-            }
+            PanelKind::Terminal
+            | PanelKind::SourceControl
+            | PanelKind::Search
+            | PanelKind::Output => self.is_panel_focused(kind),
         };
         if should_hide {
             self.hide_panel(kind);
@@ -2695,6 +3094,20 @@ fn hide_panel(&self, kind: PanelKind) {
         self.common.focus.set(Focus::Workbench);
     }
 
+    /// Send the code around the cursor of the active editor to a terminal,
+    /// leaving the focus in the editor.
+    fn send_to_terminal(&self, unit: ReplUnit) {
+        let Some(editor) = self.main_split.active_editor.get_untracked() else {
+            return;
+        };
+        let code = editor.repl_code(unit);
+        let language = editor.doc().syntax().with_untracked(|s| s.language);
+        self.terminal.send_to_repl(&code, language);
+        if !self.panel.is_panel_visible(&PanelKind::Terminal) {
+            self.panel.show_panel(&PanelKind::Terminal);
+        }
+    }
+
     pub fn show_panel(&self, kind: PanelKind) {
         if kind == PanelKind::Terminal
             && self
@@ -2725,20 +3138,108 @@ pub fn show_panel(&self, kind: PanelKind) {
         self.common.focus.set(Focus::Panel(kind));
     }
 
+    /// Start the configs of the launch once the configs they depend on are
+    /// ready, starting those too if they aren't running. The inputs the
+    /// configs use are asked for first.
+    fn launch_run_debug(&self, cx: Scope, mut launch: RunDebugLaunch) {
+        let run_configs = self.terminal.run_debug_configs().unwrap_or_default();
+        let dependencies = run_configs.dependencies(&launch.configs);
+
+        let missing_input = launch
+            .configs
+            .iter()
+            .chain(dependencies.iter())
+            .flat_map(config_input_ids)
+            .find(|id| !launch.inputs.contains_key(id));
+        if let Some(id) = missing_input {
+            let mut input =
+                run_configs.input(&id).cloned().unwrap_or(RunDebugInput {
+                    id: id.clone(),
+                    description: None,
+                    default: None,
+                    options: None,
+                });
+            if let Some(last) = self
+                .terminal
+                .debug
+                .inputs
+                .with_untracked(|inputs| inputs.get(&id).cloned())
+            {
+                input.default = Some(last);
+            }
+            self.palette.run_debug_input(launch, input);
+            return;
+        }
+
+        self.terminal
+            .debug
+            .inputs
+            .update(|inputs| inputs.extend(launch.inputs.clone()));
+        let variables = self
+            .terminal
+            .run_debug_variables(std::mem::take(&mut launch.inputs));
+
+        let dependencies = dependencies
+            .into_iter()
+            .filter(|config| {
+                self.terminal.is_run_debug_ready(&config.name).is_none()
+            })
+            .map(|config| (RunDebugMode::Run, config));
+        let configs = launch.configs.into_iter().map(|config| {
+            let mode = if config.ty.is_some() {
+                launch.mode
+            } else {
+                RunDebugMode::Run
+            };
+            (mode, config)
+        });
+        for (mode, mut config) in dependencies.chain(configs).collect::<Vec<_>>() {
+            variables.substitute_config(&mut config);
+            let waiting_on: HashSet<String> = config
+                .depends_on
+                .iter()
+                .flatten()
+                .filter(|name| {
+                    run_configs.config(name).is_some()
+                        && self.terminal.is_run_debug_ready(name) != Some(true)
+                })
+                .cloned()
+                .collect();
+            if waiting_on.is_empty() {
+                self.run_and_debug(cx, &mode, &config, launch.compound.clone());
+            } else {
+                self.terminal.debug.pending.update(|pending| {
+                    pending.push(PendingRunDebug {
+                        mode,
+                        config,
+                        compound: launch.compound.clone(),
+                        waiting_on,
+                    });
+                });
+            }
+        }
+    }
+
     fn run_and_debug(
         &self,
         cx: Scope,
         mode: &RunDebugMode,
         config: &RunDebugConfig,
+        compound: Option<String>,
     ) {
         debug!("{:?}", config);
         match mode {
             RunDebugMode::Run => {
-                self.run_in_terminal(cx, mode, config, false);
+                self.run_in_terminal(cx, mode, config, false, compound);
             }
             RunDebugMode::Debug => {
+                if let Some(compound) = compound.clone() {
+                    self.terminal.debug.dap_compounds.update(|compounds| {
+                        compounds.insert(config.dap_id, compound);
+                    });
+                }
                 if config.prelaunch.is_some() {
-                    self.run_in_terminal(cx, mode, config, false);
+                    self.run_in_terminal(cx, mode, config, false, compound);
                 } else {
                     self.common.proxy.dap_start(
                         config.clone(),
@@ -2758,9 +3259,16 @@ fn run_in_terminal(
         mode: &RunDebugMode,
         config: &RunDebugConfig,
         from_dap: bool,
+        compound: Option<String>,
     ) {
         // if not from dap, then run prelaunch first
         let is_prelaunch = !from_dap;
+        let compound = compound.or_else(|| {
+            self.terminal
+                .debug
+                .dap_compounds
+                .with_untracked(|compounds| compounds.get(&config.dap_id).cloned())
+        });
         let term_id = if let Some(terminal) =
             self.terminal.get_stopped_run_debug_terminal(mode, config)
         {
@@ -2770,6 +3278,8 @@ fn run_in_terminal(
                 stopped: false,
                 created: Instant::now(),
                 is_prelaunch,
+                compound: compound.clone(),
+                ready: false,
             }));
 
             terminal.term_id
@@ -2781,6 +3291,8 @@ fn run_in_terminal(
                     stopped: false,
                     created: Instant::now(),
                     is_prelaunch,
+                    compound,
+                    ready: false,
                 }),
                 None,
             );
@@ -2793,7 +3305,14 @@ fn run_in_terminal(
         self.terminal.debug.daps.update(|daps| {
             daps.insert(
                 config.dap_id,
-                DapData::new(cx, config.dap_id, term_id, self.common.clone()),
+                DapData::new(
+                    cx,
+                    config.dap_id,
+                    term_id,
+                    self.terminal.debug.watches,
+                    self.terminal.debug.capabilities.get(config.dap_id),
+                    self.common.clone(),
+                ),
             );
         });
 
@@ -2912,11 +3431,13 @@ pub fn show_code_lens(
             .send(InternalCommand::ShowCodeActions {
                 offset,
                 mouse_click,
-                plugin_id,
                 code_actions: lens
                     .into_iter()
                     .filter_map(|lens| {
-                        Some(CodeActionOrCommand::Command(lens.command?))
+                        Some((
+                            plugin_id,
+                            CodeActionOrCommand::Command(lens.command?),
+                        ))
                     })
                     .collect(),
             });
@@ -2,7 +2,10 @@
 
 use serde::{Deserialize, Serialize};
 
-use crate::{debug::LapceBreakpoint, main_split::SplitInfo, panel::data::PanelInfo};
+use crate::{
+    debug::LapceBreakpoint, main_split::SplitInfo, panel::data::PanelInfo,
+    task::TaskInfo,
+};
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Hash)]
 pub struct SshHost {
@@ -156,4 +159,6 @@ pub struct WorkspaceInfo {
     pub split: SplitInfo,
     pub panel: PanelInfo,
     pub breakpoints: HashMap<PathBuf, Vec<LapceBreakpoint>>,
+    #[serde(default)]
+    pub tasks: TaskInfo,
 }
@@ -175,6 +175,23 @@ pub fn queries_directory() -> Option<PathBuf> {
         }
     }
 
+    /// Get the path to the scripts that the terminal loads into the shells
+    /// for the shell integration
+    pub fn shell_integration_directory() -> Option<PathBuf> {
+        if let Some(dir) = Self::data_local_directory() {
+            let dir = dir.join("shell-integration");
+            if !dir.exists() {
+                if let Err(err) = std::fs::create_dir(&dir) {
+                    tracing::error!("{:?}", err);
+                }
+            }
+
+            Some(dir)
+        } else {
+            None
+        }
+    }
+
     pub fn grammars_directory() -> Option<PathBuf> {
         if let Some(dir) = Self::data_local_directory() {
             let dir = dir.join("grammars");
@@ -1,14 +1,20 @@
 use std::{
     collections::{HashMap, HashSet, hash_map::Entry},
     fmt::Write,
-    path::Path,
+    path::{Path, PathBuf},
     str::FromStr,
+    sync::RwLock,
 };
 
-use lapce_rpc::style::{LineStyle, Style};
+use lapce_rpc::{
+    plugin::VoltLanguage,
+    style::{LineStyle, Style},
+};
 use once_cell::sync::Lazy;
 use regex::Regex;
-use strum_macros::{AsRefStr, Display, EnumMessage, EnumString, IntoStaticStr};
+use strum_macros::{
+    AsRefStr, Display, EnumDiscriminants, EnumMessage, EnumString, IntoStaticStr,
+};
 use tracing::{Level, event};
 use tree_sitter::{Point, TreeCursor};
 
@@ -167,8 +173,9 @@ struct CommentProperties {
     multi_line_prefix: Option<&'static str>,
 }
 
-/// NOTE: Keep the enum variants "fieldless" so they can cast to usize as array
-/// indices into the LANGUAGES array.  See method `LapceLanguage::properties`.
+/// NOTE: Keep the enum variants other than `Volt` "fieldless" so that their
+/// discriminants can cast to usize as array indices into the LANGUAGES array.
+/// See method `LapceLanguage::properties`.
 ///
 /// Do not assign values to the variants because the number of variants and
 /// number of elements in the LANGUAGES array change as different features
@@ -187,8 +194,10 @@ struct CommentProperties {
     IntoStaticStr,
     EnumString,
     EnumMessage,
+    EnumDiscriminants,
     Default,
 )]
+#[strum_discriminants(vis(pub(crate)))]
 #[strum(ascii_case_insensitive)]
 #[remain::sorted]
 pub enum LapceLanguage {
@@ -446,6 +455,12 @@ pub enum LapceLanguage {
     Yaml,
     #[strum(message = "Zig")]
     Zig,
+
+    // Do not move
+    /// A language added by a volt, by its index in `VOLT_LANGUAGES`
+    #[remain::unsorted]
+    #[strum(disabled)]
+    Volt(usize),
 }
 
 /// NOTE: Elements in the array must be in the same order as the enum variants of
@@ -1691,6 +1706,39 @@ pub enum LapceLanguage {
     },
 ];
 
+/// The languages added by volts, which are never removed so that the
+/// `LapceLanguage::Volt` of an open document stays valid
+static VOLT_LANGUAGES: Lazy<RwLock<Vec<&'static VoltLanguageProperties>>> =
+    Lazy::new(Default::default);
+
+struct VoltLanguageProperties {
+    /// The definition in the volt, to tell if it changed when the volt is
+    /// loaded again
+    definition: VoltLanguage,
+    name: &'static str,
+    language_id: &'static str,
+    syntax: SyntaxProperties,
+    grammar: Option<PathBuf>,
+    grammar_fn: String,
+    queries: Option<PathBuf>,
+}
+
+fn volt_language(i: usize) -> &'static VoltLanguageProperties {
+    VOLT_LANGUAGES.read().unwrap()[i]
+}
+
+fn volt_languages() -> Vec<&'static VoltLanguageProperties> {
+    VOLT_LANGUAGES.read().unwrap().clone()
+}
+
+fn leak_str(s: &str) -> &'static str {
+    Box::leak(s.to_string().into_boxed_str())
+}
+
+fn leak_strs(strs: &[String]) -> &'static [&'static str] {
+    Box::leak(strs.iter().map(|s| leak_str(s)).collect())
+}
+
 impl LapceLanguage {
     const HIGHLIGHTS_INJECTIONS_FILE_NAME: &'static str = "injections.scm";
     const HIGHLIGHTS_QUERIES_FILE_NAME: &'static str = "highlights.scm";
@@ -1704,30 +1752,41 @@ pub fn from_path_raw(path: &Path) -> Option<LapceLanguage> {
         let extension = path
             .extension()
             .and_then(|s| s.to_str().map(|s| s.to_lowercase()));
+        let matches = |properties: &SyntaxProperties| {
+            properties.files.iter().any(|f| Some(*f) == filename)
+                || properties
+                    .extensions
+                    .iter()
+                    .any(|e| Some(*e) == extension.as_deref())
+        };
         // NOTE: This is a linear search.  It is assumed that this function
         // isn't called in any tight loop.
-        for properties in LANGUAGES {
-            if properties.files.iter().any(|f| Some(*f) == filename) {
-                return Some(properties.id);
-            }
-            if properties
-                .extensions
-                .iter()
-                .any(|e| Some(*e) == extension.as_deref())
-            {
-                return Some(properties.id);
-            }
+        if let Some(properties) = LANGUAGES.iter().find(|p| matches(p)) {
+            return Some(properties.id);
         }
-
-        None
+        // The built-in languages come first, so that a volt can't take over
+        // their files
+        volt_languages()
+            .into_iter()
+            .find(|l| matches(&l.syntax))
+            .map(|l| l.syntax.id)
     }
 
     pub fn from_name(name: &str) -> Option<LapceLanguage> {
         match LapceLanguage::from_str(name.to_lowercase().as_str()) {
             Ok(v) => Some(v),
             Err(e) => {
-                event!(Level::DEBUG, "failed parsing `{name}` LapceLanguage: {e}");
-                None
+                let volt_language = volt_languages()
+                    .into_iter()
+                    .find(|l| l.name.eq_ignore_ascii_case(name))
+                    .map(|l| l.syntax.id);
+                if volt_language.is_none() {
+                    event!(
+                        Level::DEBUG,
+                        "failed parsing `{name}` LapceLanguage: {e}"
+                    );
+                }
+                volt_language
             }
         }
     }
@@ -1740,14 +1799,78 @@ pub fn languages() -> Vec<&'static str> {
                 langs.push(lang)
             }
         }
+        langs.extend(volt_languages().into_iter().map(|l| l.name));
         langs
     }
 
-    // NOTE: Instead of using `&LANGUAGES[*self as usize]` directly, the
-    // `debug_assertion` gives better feedback should something has gone wrong
-    // badly.
-    fn properties(&self) -> &SyntaxProperties {
-        let i = *self as usize;
+    /// Add a language of a volt, in place of the one of the same name a volt
+    /// added before. Returns whether the languages changed.
+    pub fn register_volt_language(language: &VoltLanguage) -> bool {
+        let mut languages = VOLT_LANGUAGES.write().unwrap();
+        let i = languages
+            .iter()
+            .position(|l| l.name.eq_ignore_ascii_case(&language.name));
+        if i.is_some_and(|i| languages[i].definition == *language) {
+            return false;
+        }
+        let i = i.unwrap_or(languages.len());
+
+        let extensions = language
+            .extensions
+            .iter()
+            .map(|e| e.to_lowercase())
+            .collect::<Vec<_>>();
+        let properties = VoltLanguageProperties {
+            definition: language.clone(),
+            name: leak_str(&language.name),
+            language_id: leak_str(&language.name.to_lowercase()),
+            syntax: SyntaxProperties {
+                id: LapceLanguage::Volt(i),
+                comment: match language.comment.as_deref() {
+                    Some(comment) => comment_properties!(leak_str(comment)),
+                    None => comment_properties!(),
+                },
+                indent: language
+                    .indent
+                    .as_deref()
+                    .map(leak_str)
+                    .unwrap_or(Indent::space(4)),
+                files: leak_strs(&language.files),
+                extensions: leak_strs(&extensions),
+                tree_sitter: TreeSitterProperties::DEFAULT,
+            },
+            grammar: language.grammar.as_ref().map(PathBuf::from),
+            grammar_fn: language
+                .grammar_fn
+                .clone()
+                .unwrap_or_else(|| language.name.to_lowercase()),
+            queries: language.queries.as_ref().map(PathBuf::from),
+        };
+        let properties = Box::leak(Box::new(properties));
+        if i < languages.len() {
+            languages[i] = properties;
+        } else {
+            languages.push(properties);
+        }
+        true
+    }
+
+    /// The language id sent to language servers, for a language added by a
+    /// volt
+    pub fn volt_language_id(&self) -> Option<&'static str> {
+        match self {
+            LapceLanguage::Volt(i) => Some(volt_language(*i).language_id),
+            _ => None,
+        }
+    }
+
+    // NOTE: Instead of using `&LANGUAGES[i]` directly, the `debug_assertion`
+    // gives better feedback should something has gone wrong badly.
+    fn properties(&self) -> &'static SyntaxProperties {
+        if let LapceLanguage::Volt(i) = self {
+            return &volt_language(*i).syntax;
+        }
+        let i = LapceLanguageDiscriminants::from(*self) as usize;
         let l = &LANGUAGES[i];
         debug_assert!(
             l.id == *self,
@@ -1759,6 +1882,9 @@ fn properties(&self) -> &SyntaxProperties {
     }
 
     pub fn name(&self) -> &'static str {
+        if let LapceLanguage::Volt(i) = self {
+            return volt_language(*i).name;
+        }
         strum::EnumMessage::get_message(self).unwrap_or(self.into())
     }
 
@@ -1778,6 +1904,20 @@ pub fn indent_unit(&self) -> &str {
     }
 
     fn get_grammar(&self) -> Option<tree_sitter::Language> {
+        if let LapceLanguage::Volt(i) = self {
+            let language = volt_language(*i);
+            // The languages are only registered with a grammar that's in the
+            // `executables` permission the user granted the volt
+            let grammar = language.grammar.as_ref()?;
+            return match load_grammar_library(grammar, &language.grammar_fn) {
+                Ok(grammar) => Some(grammar),
+                Err(err) => {
+                    tracing::error!("{} {:?}", language.name, err);
+                    None
+                }
+            };
+        }
+
         let grammar_name = self.grammar_name();
         let grammar_fn_name = self.grammar_fn_name();
 
@@ -1823,6 +1963,27 @@ fn grammar_fn_name(&self) -> String {
     }
 
     fn get_grammar_query(&self) -> (String, String) {
+        if let LapceLanguage::Volt(i) = self {
+            let Some(queries) = volt_language(*i).queries.as_ref() else {
+                return ("".to_string(), "".to_string());
+            };
+            // The queries of a volt can inherit from the ones of Lapce
+            let queries_dir =
+                Directory::queries_directory().unwrap_or_else(|| queries.clone());
+            return (
+                read_query_file(
+                    &queries.join(Self::HIGHLIGHTS_QUERIES_FILE_NAME),
+                    &queries_dir,
+                    Self::HIGHLIGHTS_QUERIES_FILE_NAME,
+                ),
+                read_query_file(
+                    &queries.join(Self::HIGHLIGHTS_INJECTIONS_FILE_NAME),
+                    &queries_dir,
+                    Self::HIGHLIGHTS_INJECTIONS_FILE_NAME,
+                ),
+            );
+        }
+
         let query_name = self.query_name();
 
         // Try reading highlights from user config dir
@@ -1895,7 +2056,14 @@ fn load_grammar(
     }
 
     event!(Level::DEBUG, "Loading grammar from user grammar dir");
-    let library = match unsafe { libloading::Library::new(&library_path) } {
+    load_grammar_library(&library_path, grammar_fn_name)
+}
+
+fn load_grammar_library(
+    library_path: &Path,
+    grammar_fn_name: &str,
+) -> Result<tree_sitter::Language, HighlightIssue> {
+    let library = match unsafe { libloading::Library::new(library_path) } {
         Ok(v) => v,
         Err(e) => {
             let err = format!("Failed to load '{}': '{e}'", library_path.display());
@@ -2031,11 +2199,15 @@ pub(crate) fn walk_tree_bracket_ast(
 }
 
 fn read_grammar_query(queries_dir: &Path, name: &str, kind: &str) -> String {
+    read_query_file(&queries_dir.join(name).join(kind), queries_dir, kind)
+}
+
+/// Read a query file, with the queries it inherits from in `queries_dir`
+fn read_query_file(file: &Path, queries_dir: &Path, kind: &str) -> String {
     static INHERITS_REGEX: Lazy<Regex> =
         Lazy::new(|| Regex::new(r";+\s*inherits\s*:?\s*([a-z_,()-]+)\s*").unwrap());
 
-    let file = queries_dir.join(name).join(kind);
-    let query = std::fs::read_to_string(&file).unwrap_or_else(|err| {
+    let query = std::fs::read_to_string(file).unwrap_or_else(|err| {
         tracing::event!(
             tracing::Level::WARN,
             "Failed to read queries at: {file:?}, {err}"
@@ -2064,6 +2236,8 @@ fn read_grammar_query(queries_dir: &Path, name: &str, kind: &str) -> String {
 mod tests {
     use std::path::PathBuf;
 
+    use lapce_rpc::plugin::VoltLanguage;
+
     use super::LapceLanguage;
 
     #[test]
@@ -2071,4 +2245,41 @@ fn test_lanaguage_from_path() {
         let l = LapceLanguage::from_path(&PathBuf::new().join("test.rs"));
         assert_eq!(l, LapceLanguage::Rust);
     }
+
+    #[test]
+    fn test_volt_language() {
+        // The languages of volts are global, so the test uses names no other
+        // test or built-in language has
+        let mut language = VoltLanguage {
+            name: "LapceTestVoltlang".to_string(),
+            extensions: vec!["LAPCE-TEST-VLT".to_string()],
+            files: vec!["LapceTestVoltfile".to_string()],
+            comment: Some("--".to_string()),
+            indent: None,
+            grammar: None,
+            grammar_fn: None,
+            queries: None,
+        };
+        assert!(LapceLanguage::register_volt_language(&language));
+        assert!(!LapceLanguage::register_volt_language(&language));
+
+        let l = LapceLanguage::from_path(&PathBuf::from("test.lapce-test-vlt"));
+        assert!(matches!(l, LapceLanguage::Volt(_)));
+        assert_eq!(l.name(), "LapceTestVoltlang");
+        assert_eq!(l.volt_language_id(), Some("lapcetestvoltlang"));
+        assert_eq!(l.comment_token(), "--");
+        assert_eq!(
+            LapceLanguage::from_path(&PathBuf::from("LapceTestVoltfile")),
+            l
+        );
+        assert_eq!(LapceLanguage::from_name("lapcetestvoltlang"), Some(l));
+        assert!(LapceLanguage::languages().contains(&"LapceTestVoltlang"));
+
+        // Loading the volt again with a change keeps the language
+        language.comment = Some("#".to_string());
+        assert!(LapceLanguage::register_volt_language(&language));
+        let l2 = LapceLanguage::from_path(&PathBuf::from("test.lapce-test-vlt"));
+        assert_eq!(l2, l);
+        assert_eq!(l2.comment_token(), "#");
+    }
 }
@@ -1150,6 +1150,16 @@ fn find_tag_in_children(&self, node: Node, tag: &str) -> Option<usize> {
         None
     }
 
+    /// The byte ranges of the identifiers that overlap `start..end`, in the
+    /// order they appear in the text.
+    pub fn identifiers(&self, start: usize, end: usize) -> Vec<(usize, usize)> {
+        self.layers
+            .as_ref()
+            .and_then(|layers| layers.try_tree())
+            .map(|tree| tree_identifiers(tree, start, end))
+            .unwrap_or_default()
+    }
+
     pub fn sticky_headers(&self, offset: usize) -> Option<Vec<usize>> {
         let tree = self.layers.as_ref()?.try_tree()?;
         let mut node = tree.root_node().descendant_for_byte_range(offset, offset)?;
@@ -1168,6 +1178,31 @@ pub fn sticky_headers(&self, offset: usize) -> Option<Vec<usize>> {
         Some(offsets)
     }
 
+    /// The byte range of the innermost sticky header node around the offset,
+    /// such as the function it's in.
+    pub fn sticky_header_range(&self, offset: usize) -> Option<(usize, usize)> {
+        let tree = self.layers.as_ref()?.try_tree()?;
+        let mut node = tree.root_node().descendant_for_byte_range(offset, offset)?;
+        let sticky_header_tags = self.language.sticky_header_tags();
+        loop {
+            if sticky_header_tags.iter().any(|t| *t == node.kind()) {
+                return Some((node.start_byte(), node.end_byte()));
+            }
+            node = node.parent()?;
+        }
+    }
+
+    /// The byte range of the top level node around the offset, such as the
+    /// function or the statement it's in.
+    pub fn top_level_range(&self, offset: usize) -> Option<(usize, usize)> {
+        let tree = self.layers.as_ref()?.try_tree()?;
+        let root = tree.root_node();
+        let mut cursor = root.walk();
+        root.named_children(&mut cursor)
+            .find(|node| node.start_byte() <= offset && offset <= node.end_byte())
+            .map(|node| (node.start_byte(), node.end_byte()))
+    }
+
     pub fn find_enclosing_parentheses(
         &self,
         offset: usize,
@@ -1241,10 +1276,58 @@ pub fn find_enclosing_pair(&self, offset: usize) -> Option<(usize, usize)> {
     }
 }
 
+fn tree_identifiers(tree: &Tree, start: usize, end: usize) -> Vec<(usize, usize)> {
+    let mut identifiers = Vec::new();
+    let mut cursor = tree.walk();
+    'outer: loop {
+        let node = cursor.node();
+        if node.start_byte() < end && node.end_byte() > start {
+            if node.child_count() == 0 {
+                if node.kind() == "identifier" {
+                    identifiers.push((node.start_byte(), node.end_byte()));
+                }
+            } else if cursor.goto_first_child() {
+                continue;
+            }
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                break 'outer;
+            }
+        }
+    }
+    identifiers
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_tree_identifiers() {
+        let text =
+            "fn add(a: u32, b: u32) -> u32 {\n    let sum = a + b;\n    sum\n}\n";
+        let mut parser = Parser::new();
+        parser.set_language(&tree_sitter_rust::language()).unwrap();
+        let tree = parser.parse(text, None).unwrap();
+        let names = |start: usize, end: usize| {
+            tree_identifiers(&tree, start, end)
+                .into_iter()
+                .map(|(start, end)| &text[start..end])
+                .collect::<Vec<_>>()
+        };
+
+        // the second line, without the types or keywords
+        let line = text.find("    let").unwrap();
+        let line_end = text[line..].find('\n').unwrap() + line;
+        assert_eq!(names(line, line_end), vec!["sum", "a", "b"]);
+        assert_eq!(
+            names(0, text.len()),
+            vec!["add", "a", "b", "sum", "a", "b", "sum"]
+        );
+        assert!(names(text.len(), text.len()).is_empty());
+    }
+
     #[test]
     fn test_lens() {
         let lens = Syntax::lens_from_normal_lines(5, 25, 2, &[4]);
@@ -10,7 +10,7 @@
 
 use anyhow::{Result, anyhow};
 use floem_editor_core::buffer::rope_text::CharIndicesJoin;
-use lapce_core::encoding::offset_utf8_to_utf16;
+use lapce_core::{encoding::offset_utf8_to_utf16, language::LapceLanguage};
 use lapce_rpc::buffer::BufferId;
 use lapce_xi_rope::{RopeDelta, interval::IntervalBounds, rope::Rope};
 use lsp_types::*;
@@ -284,7 +284,7 @@ pub fn language_id_from_path(path: &Path) -> Option<&'static str> {
                     "yml" | "yaml" => "yaml",
                     "zig" => "zig",
                     "vue" => "vue",
-                    _ => return None,
+                    _ => return volt_language_id(path),
                 },
             }
         }
@@ -295,12 +295,17 @@ pub fn language_id_from_path(path: &Path) -> Option<&'static str> {
             filename => match filename.to_lowercase().as_str() {
                 "dockerfile" => "dockerfile",
                 "makefile" | "gnumakefile" => "makefile",
-                _ => return None,
+                _ => return volt_language_id(path),
             },
         },
     })
 }
 
+/// The language id of a file of a language added by a volt
+fn volt_language_id(path: &Path) -> Option<&'static str> {
+    LapceLanguage::from_path_raw(path)?.volt_language_id()
+}
+
 fn get_document_content_changes(
     delta: &RopeDelta,
     buffer: &Buffer,
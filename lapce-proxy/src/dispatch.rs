@@ -44,13 +44,22 @@
 
 use crate::{
     buffer::{Buffer, get_mod_time, load_file},
-    plugin::{PluginCatalogRpcHandler, catalog::PluginCatalog},
+    plugin::{
+        PluginCatalogRpcHandler,
+        catalog::PluginCatalog,
+        registry::install_volt_archive,
+        volt_icon,
+        wasi::{load_volt, volt_watched_files},
+    },
+    task::detect_tasks,
     terminal::{Terminal, TerminalSender},
+    testing::{discover_tests, run_tests},
     watcher::{FileWatcher, Notify, WatchToken},
 };
 
 const OPEN_FILE_EVENT_TOKEN: WatchToken = WatchToken(1);
 const WORKSPACE_EVENT_TOKEN: WatchToken = WatchToken(2);
+const DEV_VOLT_EVENT_TOKEN: WatchToken = WatchToken(3);
 
 pub struct Dispatcher {
     workspace: Option<PathBuf>,
@@ -71,16 +80,32 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
             Initialize {
                 workspace,
                 disabled_volts,
+                volt_permissions,
                 extra_plugin_paths,
                 plugin_configurations,
+                language_servers,
+                language_server_policies,
                 window_id,
                 tab_id,
             } => {
                 self.window_id = window_id;
                 self.tab_id = tab_id;
                 self.workspace = workspace;
+                // The volts loaded with `--plugin-path` are being developed, so
+                // they're reloaded when they're rebuilt or their volt.toml changes
+                let dev_volts: Vec<(PathBuf, Vec<PathBuf>)> = extra_plugin_paths
+                    .iter()
+                    .filter_map(|path| match volt_watched_files(path) {
+                        Ok(files) => Some((path.clone(), files)),
+                        Err(err) => {
+                            tracing::error!("{:?}", err);
+                            None
+                        }
+                    })
+                    .collect();
                 self.file_watcher.notify(FileWatchNotifier::new(
                     self.workspace.clone(),
+                    dev_volts.clone(),
                     self.core_rpc.clone(),
                     self.proxy_rpc.clone(),
                 ));
@@ -88,6 +113,19 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     self.file_watcher
                         .watch(workspace, true, WORKSPACE_EVENT_TOKEN);
                 }
+                for file in dev_volts.into_iter().flat_map(|(_, files)| files) {
+                    // The folder is watched rather than the file, which a build
+                    // replaces. It has to exist, so the wasm has to have been
+                    // built once.
+                    if let Some(dir) = file.parent().map(|dir| dir.to_path_buf()) {
+                        self.file_watcher.watch_filtered(
+                            &dir,
+                            false,
+                            DEV_VOLT_EVENT_TOKEN,
+                            move |path| path == file,
+                        );
+                    }
+                }
 
                 let plugin_rpc = self.catalog_rpc.clone();
                 let workspace = self.workspace.clone();
@@ -95,8 +133,11 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     let mut plugin = PluginCatalog::new(
                         workspace,
                         disabled_volts,
+                        volt_permissions.into_iter().collect(),
                         extra_plugin_paths,
                         plugin_configurations,
+                        language_servers,
+                        language_server_policies,
                         plugin_rpc.clone(),
                     );
                     plugin_rpc.mainloop(&mut plugin);
@@ -182,6 +223,18 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     tracing::error!("{:?}", err);
                 }
             }
+            UpdateLanguageServers { servers } => {
+                if let Err(err) = self.catalog_rpc.update_language_servers(servers) {
+                    tracing::error!("{:?}", err);
+                }
+            }
+            UpdateLanguageServerPolicies { policies } => {
+                if let Err(err) =
+                    self.catalog_rpc.update_language_server_policies(policies)
+                {
+                    tracing::error!("{:?}", err);
+                }
+            }
             NewTerminal { term_id, profile } => {
                 let mut terminal = match Terminal::new(term_id, profile, 50, 10) {
                     Ok(terminal) => terminal,
@@ -258,8 +311,15 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     tracing::error!("{:?}", err);
                 }
             }
-            DapContinue { dap_id, thread_id } => {
-                if let Err(err) = self.catalog_rpc.dap_continue(dap_id, thread_id) {
+            DapContinue {
+                dap_id,
+                thread_id,
+                single_thread,
+            } => {
+                if let Err(err) =
+                    self.catalog_rpc
+                        .dap_continue(dap_id, thread_id, single_thread)
+                {
                     tracing::error!("{:?}", err);
                 }
             }
@@ -268,18 +328,48 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     tracing::error!("{:?}", err);
                 }
             }
-            DapStepOver { dap_id, thread_id } => {
-                if let Err(err) = self.catalog_rpc.dap_step_over(dap_id, thread_id) {
+            DapStepOver {
+                dap_id,
+                thread_id,
+                single_thread,
+                granularity,
+            } => {
+                if let Err(err) = self.catalog_rpc.dap_step_over(
+                    dap_id,
+                    thread_id,
+                    single_thread,
+                    granularity,
+                ) {
                     tracing::error!("{:?}", err);
                 }
             }
-            DapStepInto { dap_id, thread_id } => {
-                if let Err(err) = self.catalog_rpc.dap_step_into(dap_id, thread_id) {
+            DapStepInto {
+                dap_id,
+                thread_id,
+                single_thread,
+                granularity,
+            } => {
+                if let Err(err) = self.catalog_rpc.dap_step_into(
+                    dap_id,
+                    thread_id,
+                    single_thread,
+                    granularity,
+                ) {
                     tracing::error!("{:?}", err);
                 }
             }
-            DapStepOut { dap_id, thread_id } => {
-                if let Err(err) = self.catalog_rpc.dap_step_out(dap_id, thread_id) {
+            DapStepOut {
+                dap_id,
+                thread_id,
+                single_thread,
+                granularity,
+            } => {
+                if let Err(err) = self.catalog_rpc.dap_step_out(
+                    dap_id,
+                    thread_id,
+                    single_thread,
+                    granularity,
+                ) {
                     tracing::error!("{:?}", err);
                 }
             }
@@ -324,6 +414,11 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     tracing::error!("{:?}", err);
                 }
             }
+            RollbackVolt { volt } => {
+                if let Err(err) = self.catalog_rpc.rollback_volt(volt) {
+                    tracing::error!("{:?}", err);
+                }
+            }
             RemoveVolt { volt } => {
                 self.catalog_rpc.remove_volt(volt);
             }
@@ -335,6 +430,43 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     tracing::error!("{:?}", err);
                 }
             }
+            GrantVoltPermissions { volt, permissions } => {
+                if let Err(err) =
+                    self.catalog_rpc.grant_volt_permissions(volt, permissions)
+                {
+                    tracing::error!("{:?}", err);
+                }
+            }
+            RevokeVoltPermissions { volt } => {
+                if let Err(err) = self.catalog_rpc.revoke_volt_permissions(volt) {
+                    tracing::error!("{:?}", err);
+                }
+            }
+            SetPluginTrace { channel, enabled } => {
+                if let Err(err) = self.catalog_rpc.set_plugin_trace(channel, enabled)
+                {
+                    tracing::error!("{:?}", err);
+                }
+            }
+            ExecutePluginCommand { plugin_id, command } => {
+                let core_rpc = self.core_rpc.clone();
+                let title = command.clone();
+                self.catalog_rpc.execute_command(
+                    plugin_id,
+                    command,
+                    move |_, result| {
+                        if let Err(err) = result {
+                            core_rpc.show_message(
+                                title,
+                                ShowMessageParams {
+                                    typ: MessageType::ERROR,
+                                    message: err.message,
+                                },
+                            );
+                        }
+                    },
+                );
+            }
             GitCommit { message, diffs } => {
                 if let Some(workspace) = self.workspace.as_ref() {
                     match git_commit(workspace, &message, diffs) {
@@ -700,9 +832,15 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     &path,
                     position,
                     diagnostics,
-                    move |plugin_id, result| {
-                        let result = result.map(|resp| {
-                            ProxyResponse::GetCodeActionsResponse { plugin_id, resp }
+                    move |result| {
+                        let result = result.map(|resps| {
+                            let actions = resps
+                                .into_iter()
+                                .flat_map(|(plugin_id, resp)| {
+                                    resp.into_iter().map(move |a| (plugin_id, a))
+                                })
+                                .collect();
+                            ProxyResponse::GetCodeActionsResponse { actions }
                         });
                         proxy_rpc.handle_response(id, result);
                     },
@@ -806,6 +944,47 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     proxy_rpc.handle_response(id, result);
                 });
             }
+            DetectTasks {} => {
+                let workspace = self.workspace.clone();
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let tasks = workspace
+                        .map(|workspace| detect_tasks(&workspace))
+                        .unwrap_or_default();
+                    proxy_rpc.handle_response(
+                        id,
+                        Ok(ProxyResponse::DetectTasksResponse { tasks }),
+                    );
+                });
+            }
+            DiscoverTests {} => {
+                let workspace = self.workspace.clone();
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let tests = workspace
+                        .map(|workspace| discover_tests(&workspace))
+                        .unwrap_or_default();
+                    proxy_rpc.handle_response(
+                        id,
+                        Ok(ProxyResponse::DiscoverTestsResponse { tests }),
+                    );
+                });
+            }
+            RunTests { runner, ids } => {
+                let workspace = self.workspace.clone();
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = workspace
+                        .ok_or_else(|| anyhow!("no workspace set"))
+                        .and_then(|workspace| run_tests(&workspace, runner, &ids))
+                        .map(|results| ProxyResponse::RunTestsResponse { results })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
             GetOpenFilesContent {} => {
                 let items = self
                     .buffers
@@ -852,6 +1031,28 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     proxy_rpc.handle_response(id, result);
                 });
             }
+            InstallVoltArchive { archive } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = install_volt_archive(archive.as_slice())
+                        .map(|volt| {
+                            let icon = volt_icon(&volt);
+                            ProxyResponse::InstallVoltArchiveResponse { volt, icon }
+                        })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            FindExistingPath { paths } => {
+                let path = paths.into_iter().find(|path| path.exists());
+                self.proxy_rpc.handle_response(
+                    id,
+                    Ok(ProxyResponse::FindExistingPathResponse { path }),
+                );
+            }
             Save {
                 rev,
                 path,
@@ -1123,6 +1324,116 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                         );
                     });
             }
+            DapSetVariable {
+                dap_id,
+                reference,
+                name,
+                value,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.dap_set_variable(
+                    dap_id,
+                    reference,
+                    name,
+                    value,
+                    move |result| {
+                        proxy_rpc.handle_response(
+                            id,
+                            result.map(|resp| {
+                                ProxyResponse::DapSetVariableResponse { resp }
+                            }),
+                        );
+                    },
+                );
+            }
+            DapSetExpression {
+                dap_id,
+                frame_id,
+                expression,
+                value,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.dap_set_expression(
+                    dap_id,
+                    frame_id,
+                    expression,
+                    value,
+                    move |result| {
+                        proxy_rpc.handle_response(
+                            id,
+                            result.map(|resp| {
+                                ProxyResponse::DapSetExpressionResponse { resp }
+                            }),
+                        );
+                    },
+                );
+            }
+            DapEvaluate {
+                dap_id,
+                frame_id,
+                expression,
+                context,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.dap_evaluate(
+                    dap_id,
+                    frame_id,
+                    expression,
+                    context,
+                    move |result| {
+                        proxy_rpc.handle_response(
+                            id,
+                            result.map(|resp| ProxyResponse::DapEvaluateResponse {
+                                resp,
+                            }),
+                        );
+                    },
+                );
+            }
+            DapDisassemble {
+                dap_id,
+                memory_reference,
+                instruction_offset,
+                instruction_count,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.dap_disassemble(
+                    dap_id,
+                    memory_reference,
+                    instruction_offset,
+                    instruction_count,
+                    move |result| {
+                        proxy_rpc.handle_response(
+                            id,
+                            result.map(|resp| {
+                                ProxyResponse::DapDisassembleResponse { resp }
+                            }),
+                        );
+                    },
+                );
+            }
+            DapReadMemory {
+                dap_id,
+                memory_reference,
+                offset,
+                count,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.dap_read_memory(
+                    dap_id,
+                    memory_reference,
+                    offset,
+                    count,
+                    move |result| {
+                        proxy_rpc.handle_response(
+                            id,
+                            result.map(|resp| {
+                                ProxyResponse::DapReadMemoryResponse { resp }
+                            }),
+                        );
+                    },
+                );
+            }
             GetCodeLens { path } => {
                 let proxy_rpc = self.proxy_rpc.clone();
                 self.catalog_rpc
@@ -1245,6 +1556,10 @@ struct FileWatchNotifier {
     workspace: Option<PathBuf>,
     workspace_fs_change_handler: Arc<Mutex<Option<Sender<bool>>>>,
     last_diff: Arc<Mutex<DiffInfo>>,
+    /// The folder of each volt in development, with the files it's reloaded
+    /// on a change of
+    dev_volts: Vec<(PathBuf, Vec<PathBuf>)>,
+    dev_volt_change_handler: Arc<Mutex<Option<Sender<PathBuf>>>>,
 }
 
 impl Notify for FileWatchNotifier {
@@ -1256,6 +1571,7 @@ fn notify(&self, events: Vec<(WatchToken, notify::Event)>) {
 impl FileWatchNotifier {
     fn new(
         workspace: Option<PathBuf>,
+        dev_volts: Vec<(PathBuf, Vec<PathBuf>)>,
         core_rpc: CoreRpcHandler,
         proxy_rpc: ProxyRpcHandler,
     ) -> Self {
@@ -1265,6 +1581,8 @@ fn new(
             proxy_rpc,
             workspace_fs_change_handler: Arc::new(Mutex::new(None)),
             last_diff: Arc::new(Mutex::new(DiffInfo::default())),
+            dev_volts,
+            dev_volt_change_handler: Arc::new(Mutex::new(None)),
         };
 
         if let Some(workspace) = notifier.workspace.clone() {
@@ -1286,6 +1604,7 @@ fn handle_fs_events(&self, events: Vec<(WatchToken, notify::Event)>) {
             match token {
                 OPEN_FILE_EVENT_TOKEN => self.handle_open_file_fs_event(event),
                 WORKSPACE_EVENT_TOKEN => self.handle_workspace_fs_event(event),
+                DEV_VOLT_EVENT_TOKEN => self.handle_dev_volt_fs_event(event),
                 _ => {}
             }
         }
@@ -1369,6 +1688,54 @@ fn handle_workspace_fs_event(&self, event: notify::Event) {
         });
         *handler = Some(sender);
     }
+
+    fn handle_dev_volt_fs_event(&self, event: notify::Event) {
+        if !event.kind.is_create() && !event.kind.is_modify() {
+            return;
+        }
+        let changed: Vec<PathBuf> = self
+            .dev_volts
+            .iter()
+            .filter(|(_, files)| event.paths.iter().any(|p| files.contains(p)))
+            .map(|(dir, _)| dir.clone())
+            .collect();
+
+        let mut handler = self.dev_volt_change_handler.lock();
+        if let Some(sender) = handler.as_mut() {
+            for dir in changed {
+                if let Err(err) = sender.send(dir) {
+                    tracing::error!("{:?}", err);
+                }
+            }
+            return;
+        }
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        for dir in changed {
+            if let Err(err) = sender.send(dir) {
+                tracing::error!("{:?}", err);
+            }
+        }
+
+        let local_handler = self.dev_volt_change_handler.clone();
+        let proxy_rpc = self.proxy_rpc.clone();
+        thread::spawn(move || {
+            // a build writes the wasm more than once
+            thread::sleep(Duration::from_millis(500));
+
+            {
+                local_handler.lock().take();
+            }
+
+            let dirs: HashSet<PathBuf> = receiver.into_iter().collect();
+            for dir in dirs {
+                match load_volt(&dir) {
+                    Ok(meta) => proxy_rpc.reload_volt(meta),
+                    Err(err) => tracing::error!("{:?}", err),
+                }
+            }
+        });
+        *handler = Some(sender);
+    }
 }
 
 #[derive(Clone, Debug)]
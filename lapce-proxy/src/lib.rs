@@ -4,18 +4,21 @@
 pub mod cli;
 pub mod dispatch;
 pub mod plugin;
+pub mod task;
 pub mod terminal;
+pub mod testing;
 pub mod watcher;
 
 use std::{
     io::{BufReader, stdin, stdout},
+    path::PathBuf,
     process::exit,
     sync::Arc,
     thread,
 };
 
 use anyhow::{Result, anyhow};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use dispatch::Dispatcher;
 use lapce_core::{directory::Directory, meta};
 use lapce_rpc::{
@@ -30,10 +33,14 @@
 #[derive(Parser)]
 #[clap(name = "Lapce-proxy")]
 #[clap(version = meta::VERSION)]
+#[clap(args_conflicts_with_subcommands = true)]
 struct Cli {
     #[clap(short, long, action, hide = true)]
     proxy: bool,
 
+    #[clap(subcommand)]
+    command: Option<Command>,
+
     /// Paths to file(s) and/or folder(s) to open.
     /// When path is a file (that exists or not),
     /// it accepts `path:line:column` syntax
@@ -43,8 +50,45 @@ struct Cli {
     paths: Vec<PathObject>,
 }
 
+#[derive(Subcommand)]
+enum Command {
+    /// Create a new volt in a folder named after it. Lapce started with
+    /// `--plugin-path <folder>` reloads it each time it's built.
+    NewVolt {
+        /// The name of the volt
+        name: String,
+        /// The folder the folder of the volt is created in
+        #[clap(long, default_value = ".")]
+        path: PathBuf,
+        /// The author of the volt, the current user by default
+        #[clap(long)]
+        author: Option<String>,
+    },
+}
+
 pub fn mainloop() {
     let cli = Cli::parse();
+    if let Some(Command::NewVolt { name, path, author }) = cli.command {
+        let author = author
+            .or_else(|| std::env::var("USER").ok())
+            .or_else(|| std::env::var("USERNAME").ok())
+            .unwrap_or_else(|| "author".to_string());
+        match plugin::scaffold::scaffold_volt(&path, &name, &author) {
+            Ok(dir) => {
+                println!("created {}", dir.display());
+                println!(
+                    "build it with `cargo build --release` in it, and load it with \
+                     `lapce --plugin-path {}`",
+                    dir.display()
+                );
+                exit(0);
+            }
+            Err(e) => {
+                eprintln!("failed to create volt: {e}");
+                exit(1);
+            }
+        }
+    }
     if !cli.proxy {
         if let Err(e) = cli::try_open_in_existing_process(&cli.paths) {
             error!("failed to open path(s): {e}");
@@ -1,26 +1,36 @@
+#[cfg(test)]
+mod tests;
+
 use std::{
     borrow::Cow,
-    collections::HashMap,
+    collections::{HashMap, HashSet, VecDeque},
     path::PathBuf,
     sync::{
         Arc,
         atomic::{AtomicUsize, Ordering},
     },
     thread,
+    time::{Duration, Instant},
 };
 
+use lapce_core::language::LapceLanguage;
 use lapce_rpc::{
     RpcError,
     dap_types::{self, DapId, DapServer, SetBreakpointsResponse},
-    plugin::{PluginId, VoltID, VoltInfo, VoltMetadata},
+    plugin::{
+        LanguageServerConfig, LanguageServerPolicy, PluginId, PluginOutputChannel,
+        VoltID, VoltInfo, VoltMetadata, VoltPermissions,
+    },
     proxy::ProxyResponse,
     style::LineStyle,
 };
 use lapce_xi_rope::{Rope, RopeDelta};
 use lsp_types::{
-    DidOpenTextDocumentParams, MessageType, SemanticTokens, ShowMessageParams,
-    TextDocumentIdentifier, TextDocumentItem, VersionedTextDocumentIdentifier,
-    notification::DidOpenTextDocument, request::Request,
+    DidOpenTextDocumentParams, MessageType, SemanticTokens, SetTraceParams,
+    ShowMessageParams, TextDocumentIdentifier, TextDocumentItem, TraceValue,
+    VersionedTextDocumentIdentifier,
+    notification::{DidOpenTextDocument, SetTrace},
+    request::Request,
 };
 use parking_lot::Mutex;
 use psp_types::Notification;
@@ -29,13 +39,20 @@
 use super::{
     PluginCatalogNotification, PluginCatalogRpcHandler,
     dap::{DapClient, DapRpcHandler, DebuggerData},
+    lsp::{
+        LspClient, language_server_document_selector, language_server_handles,
+        language_server_uri, language_server_volt_id,
+    },
     psp::{ClonableCallback, PluginServerRpc, PluginServerRpcHandler, RpcCallback},
-    wasi::{load_all_volts, start_volt},
+    wasi::{load_all_volts, start_volt, volt_permitted, volt_restart_delay},
 };
 use crate::plugin::{
-    install_volt, psp::PluginHandlerNotification, wasi::enable_volt,
+    install_volt, psp::PluginHandlerNotification, rollback_volt, wasi::enable_volt,
 };
 
+/// How long a volt has to run without crashing for its crashes to be forgotten
+const VOLT_CRASH_RESET: Duration = Duration::from_secs(10 * 60);
+
 pub struct PluginCatalog {
     workspace: Option<PathBuf>,
     plugin_rpc: PluginCatalogRpcHandler,
@@ -44,15 +61,32 @@ pub struct PluginCatalog {
     debuggers: HashMap<String, DebuggerData>,
     plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
     unactivated_volts: HashMap<VoltID, VoltMetadata>,
+    /// Permissions the user has granted to each volt
+    volt_permissions: HashMap<VoltID, VoltPermissions>,
+    /// Language servers declared in the settings, by the name of their section
+    language_servers: HashMap<String, LanguageServerConfig>,
+    /// The language servers from the settings that have been started
+    started_language_servers: HashSet<String>,
+    /// How the results of the language servers are combined, by language
+    language_server_policies: HashMap<String, LanguageServerPolicy>,
     open_files: HashMap<PathBuf, String>,
+    /// The Output panel channels that have their JSON-RPC messages traced
+    traced_channels: HashSet<PluginOutputChannel>,
+    /// How many times in a row each volt crashed, and when it last did
+    volt_crashes: HashMap<VoltID, (u32, Instant)>,
+    /// The volts that crashed and are waiting to be restarted
+    restarting_volts: HashSet<VoltID>,
 }
 
 impl PluginCatalog {
     pub fn new(
         workspace: Option<PathBuf>,
         disabled_volts: Vec<VoltID>,
+        volt_permissions: HashMap<VoltID, VoltPermissions>,
         extra_plugin_paths: Vec<PathBuf>,
         plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
+        language_servers: HashMap<String, LanguageServerConfig>,
+        language_server_policies: HashMap<String, LanguageServerPolicy>,
         plugin_rpc: PluginCatalogRpcHandler,
     ) -> Self {
         let plugin = Self {
@@ -63,11 +97,24 @@ pub fn new(
             daps: HashMap::new(),
             debuggers: HashMap::new(),
             unactivated_volts: HashMap::new(),
+            volt_permissions,
+            language_servers,
+            started_language_servers: HashSet::new(),
+            language_server_policies,
             open_files: HashMap::new(),
+            traced_channels: HashSet::new(),
+            volt_crashes: HashMap::new(),
+            restarting_volts: HashSet::new(),
         };
 
+        let volt_permissions = plugin.volt_permissions.clone();
         thread::spawn(move || {
-            load_all_volts(plugin_rpc, &extra_plugin_paths, disabled_volts);
+            load_all_volts(
+                plugin_rpc,
+                &extra_plugin_paths,
+                disabled_volts,
+                volt_permissions,
+            );
         });
 
         plugin
@@ -109,6 +156,41 @@ pub fn handle_server_request(
             return;
         }
 
+        if let Some((chosen, fallback_on_empty)) =
+            self.policy_plugins(&method, language_id.as_deref())
+        {
+            // The servers chosen for the language answer first, and the others
+            // are only asked if they can't
+            if let Some(request_sent) = &request_sent {
+                request_sent.fetch_add(1, Ordering::Relaxed);
+            }
+            let others: Vec<(PluginId, PluginServerRpcHandler)> = self
+                .plugins
+                .iter()
+                .filter(|(id, _)| !chosen.contains(id))
+                .map(|(id, plugin)| (*id, plugin.clone()))
+                .collect();
+            let chosen = chosen
+                .into_iter()
+                .map(|id| (id, self.plugins[&id].clone()))
+                .collect();
+            policy_request(
+                chosen,
+                others,
+                PolicyRequest {
+                    method,
+                    params,
+                    language_id,
+                    path,
+                    check,
+                    fallback_on_empty,
+                    request_sent,
+                },
+                f,
+            );
+            return;
+        }
+
         if let Some(request_sent) = request_sent {
             // if there are no plugins installed the callback of the client is not called
             // so check if plugins list is empty
@@ -182,6 +264,7 @@ pub fn shutdown_volt(
         f: Box<dyn ClonableCallback<Value, RpcError>>,
     ) {
         let id = volt.id();
+        self.restarting_volts.remove(&id);
         for (plugin_id, plugin) in self.plugins.iter() {
             if plugin.volt_id == id {
                 let f = dyn_clone::clone_box(&*f);
@@ -201,18 +284,61 @@ pub fn shutdown_volt(
         }
     }
 
+    /// Stop the plugins of the volt, which are the volt and the language
+    /// servers it spawned
+    fn shutdown_volt_plugins(&mut self, volt_id: &VoltID) {
+        let ids: Vec<PluginId> = self
+            .plugins
+            .iter()
+            .filter(|(_, plugin)| &plugin.volt_id == volt_id)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in ids {
+            if let Some(plugin) = self.plugins.remove(&id) {
+                plugin.shutdown();
+            }
+        }
+    }
+
+    /// The plugins that are asked first for the method on documents of the
+    /// language because of the language server policy of the language, with
+    /// whether the others are asked when they have no result
+    fn policy_plugins(
+        &self,
+        method: &str,
+        language_id: Option<&str>,
+    ) -> Option<(Vec<PluginId>, bool)> {
+        let policy = self.language_server_policies.get(language_id?)?;
+        let (server, fallback_on_empty) = policy.server_for(method)?;
+        let chosen = policy_candidates(&self.plugins, server);
+        if chosen.is_empty() {
+            return None;
+        }
+        Some((chosen, fallback_on_empty))
+    }
+
     fn start_unactivated_volts(&mut self, to_be_activated: Vec<VoltID>) {
         for id in to_be_activated.iter() {
             let workspace = self.workspace.clone();
             if let Some(meta) = self.unactivated_volts.remove(id) {
+                if !volt_permitted(&meta, self.volt_permissions.get(id)) {
+                    self.plugin_rpc.core_rpc.volt_permissions_required(meta);
+                    continue;
+                }
                 let configurations =
                     self.plugin_configurations.get(&meta.name).cloned();
+                let granted =
+                    self.volt_permissions.get(id).cloned().unwrap_or_default();
                 tracing::debug!("{:?} {:?}", id, configurations);
                 let plugin_rpc = self.plugin_rpc.clone();
                 thread::spawn(move || {
-                    if let Err(err) =
-                        start_volt(workspace, configurations, plugin_rpc, meta)
-                    {
+                    if let Err(err) = start_volt(
+                        workspace,
+                        configurations,
+                        plugin_rpc,
+                        meta,
+                        granted,
+                    ) {
                         tracing::error!("{:?}", err);
                     }
                 });
@@ -220,6 +346,63 @@ fn start_unactivated_volts(&mut self, to_be_activated: Vec<VoltID>) {
         }
     }
 
+    /// Start the language servers from the settings that handle any of the
+    /// open files and haven't been started yet
+    fn start_language_servers(&mut self) {
+        let to_be_started: Vec<String> = self
+            .language_servers
+            .iter()
+            .filter(|(name, config)| {
+                !self.started_language_servers.contains(*name)
+                    && self.open_files.iter().any(|(path, language_id)| {
+                        language_server_handles(config, language_id, path)
+                    })
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in to_be_started {
+            let Some(config) = self.language_servers.get(&name).cloned() else {
+                continue;
+            };
+            self.started_language_servers.insert(name.clone());
+            let workspace = self.workspace.clone();
+            let plugin_rpc = self.plugin_rpc.clone();
+            thread::spawn(move || {
+                let core_rpc = plugin_rpc.core_rpc.clone();
+                let result =
+                    language_server_uri(&config.command, workspace.as_deref())
+                        .and_then(|server_uri| {
+                            LspClient::start(
+                                plugin_rpc,
+                                language_server_document_selector(&config),
+                                workspace,
+                                language_server_volt_id(&name),
+                                name.clone(),
+                                None,
+                                None,
+                                None,
+                                server_uri,
+                                config.args.clone(),
+                                config.initialization_options.clone(),
+                                Some(config),
+                            )
+                        });
+                if let Err(err) = result {
+                    tracing::error!("{:?}", err);
+                    core_rpc.show_message(
+                        name.clone(),
+                        ShowMessageParams {
+                            typ: MessageType::ERROR,
+                            message: format!(
+                                "Language server {name} failed to start: {err}"
+                            ),
+                        },
+                    );
+                }
+            });
+        }
+    }
+
     fn check_unactivated_volts(&mut self) {
         let to_be_activated: Vec<VoltID> = self
             .unactivated_volts
@@ -305,6 +488,7 @@ pub fn handle_did_open_text_document(&mut self, document: TextDocumentItem) {
             })
             .collect();
         self.start_unactivated_volts(to_be_activated);
+        self.start_language_servers();
 
         let path = document.uri.to_file_path().ok();
         for (_, plugin) in self.plugins.iter() {
@@ -473,12 +657,142 @@ pub fn dap_get_scopes(
         }
     }
 
+    pub fn dap_set_variable(
+        &self,
+        dap_id: DapId,
+        reference: usize,
+        name: String,
+        value: String,
+        f: Box<dyn RpcCallback<dap_types::SetVariableResponse, RpcError>>,
+    ) {
+        if let Some(dap) = self.daps.get(&dap_id) {
+            dap.set_variable_async(
+                reference,
+                name,
+                value,
+                |result: Result<dap_types::SetVariableResponse, RpcError>| {
+                    f.call(result)
+                },
+            );
+        } else {
+            f.call(Err(RpcError {
+                code: 0,
+                message: "plugin doesn't exist".to_string(),
+            }));
+        }
+    }
+
+    pub fn dap_set_expression(
+        &self,
+        dap_id: DapId,
+        frame_id: Option<usize>,
+        expression: String,
+        value: String,
+        f: Box<dyn RpcCallback<dap_types::SetExpressionResponse, RpcError>>,
+    ) {
+        if let Some(dap) = self.daps.get(&dap_id) {
+            dap.set_expression_async(
+                frame_id,
+                expression,
+                value,
+                |result: Result<dap_types::SetExpressionResponse, RpcError>| {
+                    f.call(result)
+                },
+            );
+        } else {
+            f.call(Err(RpcError {
+                code: 0,
+                message: "plugin doesn't exist".to_string(),
+            }));
+        }
+    }
+
+    pub fn dap_evaluate(
+        &self,
+        dap_id: DapId,
+        frame_id: Option<usize>,
+        expression: String,
+        context: Option<String>,
+        f: Box<dyn RpcCallback<dap_types::EvaluateResponse, RpcError>>,
+    ) {
+        if let Some(dap) = self.daps.get(&dap_id) {
+            dap.evaluate_async(
+                frame_id,
+                expression,
+                context,
+                |result: Result<dap_types::EvaluateResponse, RpcError>| {
+                    f.call(result)
+                },
+            );
+        } else {
+            f.call(Err(RpcError {
+                code: 0,
+                message: "plugin doesn't exist".to_string(),
+            }));
+        }
+    }
+
+    pub fn dap_disassemble(
+        &self,
+        dap_id: DapId,
+        memory_reference: String,
+        instruction_offset: i64,
+        instruction_count: usize,
+        f: Box<dyn RpcCallback<dap_types::DisassembleResponse, RpcError>>,
+    ) {
+        if let Some(dap) = self.daps.get(&dap_id) {
+            dap.disassemble_async(
+                memory_reference,
+                instruction_offset,
+                instruction_count,
+                |result: Result<dap_types::DisassembleResponse, RpcError>| {
+                    f.call(result)
+                },
+            );
+        } else {
+            f.call(Err(RpcError {
+                code: 0,
+                message: "plugin doesn't exist".to_string(),
+            }));
+        }
+    }
+
+    pub fn dap_read_memory(
+        &self,
+        dap_id: DapId,
+        memory_reference: String,
+        offset: i64,
+        count: usize,
+        f: Box<dyn RpcCallback<dap_types::ReadMemoryResponse, RpcError>>,
+    ) {
+        if let Some(dap) = self.daps.get(&dap_id) {
+            dap.read_memory_async(
+                memory_reference,
+                offset,
+                count,
+                |result: Result<dap_types::ReadMemoryResponse, RpcError>| {
+                    f.call(result)
+                },
+            );
+        } else {
+            f.call(Err(RpcError {
+                code: 0,
+                message: "plugin doesn't exist".to_string(),
+            }));
+        }
+    }
+
     pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
         use PluginCatalogNotification::*;
         match notification {
             UnactivatedVolts(volts) => {
                 tracing::debug!("UnactivatedVolts {:?}", volts);
                 for volt in volts {
+                    // The languages of the volt have their language id sent
+                    // to language servers
+                    for language in volt.languages.iter().flatten() {
+                        LapceLanguage::register_volt_language(language);
+                    }
                     let id = volt.id();
                     self.unactivated_volts.insert(id, volt);
                 }
@@ -488,7 +802,34 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
                 tracing::debug!("UpdatePluginConfigs {:?}", configs);
                 self.plugin_configurations = configs;
             }
+            UpdateLanguageServers(servers) => {
+                tracing::debug!("UpdateLanguageServers {:?}", servers);
+                // Restart the servers whose section changed, with the new one
+                let changed: Vec<String> = self
+                    .started_language_servers
+                    .iter()
+                    .filter(|name| {
+                        servers.get(*name) != self.language_servers.get(*name)
+                    })
+                    .cloned()
+                    .collect();
+                for name in changed {
+                    self.started_language_servers.remove(&name);
+                    self.shutdown_volt_plugins(&language_server_volt_id(&name));
+                }
+                self.language_servers = servers;
+                self.start_language_servers();
+            }
+            UpdateLanguageServerPolicies(policies) => {
+                tracing::debug!("UpdateLanguageServerPolicies {:?}", policies);
+                self.language_server_policies = policies;
+            }
             PluginServerLoaded(plugin) => {
+                // keep tracing a plugin that was restarted
+                if self.traced_channels.contains(plugin.output.channel()) {
+                    set_plugin_trace(&plugin, true);
+                }
+
                 // TODO: check if the server has did open registered
                 match self.plugin_rpc.proxy_rpc.get_open_files_content() {
                     Ok(ProxyResponse::GetOpenFilesContentResponse { items }) => {
@@ -532,44 +873,143 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
                 let workspace = self.workspace.clone();
                 let configurations =
                     self.plugin_configurations.get(&volt.name).cloned();
+                let granted = self.volt_permissions.get(&volt.id()).cloned();
                 let catalog_rpc = self.plugin_rpc.clone();
                 catalog_rpc.stop_volt(volt.clone());
                 thread::spawn(move || {
-                    if let Err(err) =
-                        install_volt(catalog_rpc, workspace, configurations, volt)
-                    {
+                    if let Err(err) = install_volt(
+                        catalog_rpc,
+                        workspace,
+                        configurations,
+                        granted,
+                        volt,
+                    ) {
+                        tracing::error!("{:?}", err);
+                    }
+                });
+            }
+            RollbackVolt(volt) => {
+                tracing::debug!("RollbackVolt {:?}", volt);
+                let workspace = self.workspace.clone();
+                let configurations =
+                    self.plugin_configurations.get(&volt.name).cloned();
+                let granted = self.volt_permissions.get(&volt.id()).cloned();
+                // Stopped here rather than with `stop_volt`, so that it's done
+                // before the previous version starts
+                let volt_id = volt.id();
+                self.restarting_volts.remove(&volt_id);
+                self.shutdown_volt_plugins(&volt_id);
+                self.unactivated_volts.remove(&volt_id);
+                let catalog_rpc = self.plugin_rpc.clone();
+                thread::spawn(move || {
+                    if let Err(err) = rollback_volt(
+                        catalog_rpc.clone(),
+                        workspace,
+                        configurations,
+                        granted,
+                        volt,
+                    ) {
                         tracing::error!("{:?}", err);
+                        catalog_rpc.core_rpc.show_message(
+                            "Roll Back Plugin".to_string(),
+                            ShowMessageParams {
+                                typ: MessageType::ERROR,
+                                message: err.to_string(),
+                            },
+                        );
                     }
                 });
             }
             ReloadVolt(volt) => {
                 tracing::debug!("ReloadVolt {:?}", volt);
                 let volt_id = volt.id();
-                let ids: Vec<PluginId> = self.plugins.keys().cloned().collect();
-                for id in ids {
-                    if self.plugins.get(&id).unwrap().volt_id == volt_id {
-                        let plugin = self.plugins.remove(&id).unwrap();
-                        plugin.shutdown();
-                    }
-                }
+                self.restarting_volts.remove(&volt_id);
+                // a new build of it gets restarted again if it crashes
+                self.volt_crashes.remove(&volt_id);
+                self.shutdown_volt_plugins(&volt_id);
                 if let Err(err) = self.plugin_rpc.unactivated_volts(vec![volt]) {
                     tracing::error!("{:?}", err);
                 }
             }
+            VoltCrashed(plugin_id, volt, error) => {
+                tracing::debug!("VoltCrashed {:?} {error}", volt.id());
+                // it was stopped on purpose in the meantime
+                if !self.plugins.contains_key(&plugin_id) {
+                    return;
+                }
+                // The language servers it spawned are stopped as well, it
+                // spawns them again when it's restarted
+                let volt_id = volt.id();
+                self.shutdown_volt_plugins(&volt_id);
+
+                let crashes = match self.volt_crashes.get(&volt_id) {
+                    Some((crashes, last)) if last.elapsed() < VOLT_CRASH_RESET => {
+                        crashes + 1
+                    }
+                    _ => 1,
+                };
+                self.volt_crashes
+                    .insert(volt_id.clone(), (crashes, Instant::now()));
+                let delay = volt_restart_delay(crashes);
+                self.plugin_rpc.core_rpc.volt_crashed(
+                    volt_id.clone(),
+                    volt.display_name.clone(),
+                    error,
+                    delay.map(|delay| delay.as_secs()),
+                );
+
+                if let Some(delay) = delay {
+                    self.restarting_volts.insert(volt_id);
+                    let plugin_rpc = self.plugin_rpc.clone();
+                    thread::spawn(move || {
+                        thread::sleep(delay);
+                        if let Err(err) = plugin_rpc.restart_volt(volt) {
+                            tracing::error!("{:?}", err);
+                        }
+                    });
+                }
+            }
+            RestartVolt(volt) => {
+                tracing::debug!("RestartVolt {:?}", volt.id());
+                let volt_id = volt.id();
+                // unless it was stopped, reloaded or enabled again since
+                if self.restarting_volts.remove(&volt_id) {
+                    self.unactivated_volts.insert(volt_id.clone(), volt);
+                    self.start_unactivated_volts(vec![volt_id]);
+                }
+            }
             StopVolt(volt) => {
                 tracing::debug!("StopVolt {:?}", volt);
                 let volt_id = volt.id();
-                let ids: Vec<PluginId> = self.plugins.keys().cloned().collect();
-                for id in ids {
-                    if self.plugins.get(&id).unwrap().volt_id == volt_id {
-                        let plugin = self.plugins.remove(&id).unwrap();
-                        plugin.shutdown();
+                self.restarting_volts.remove(&volt_id);
+                self.shutdown_volt_plugins(&volt_id);
+            }
+            GrantVoltPermissions(volt, permissions) => {
+                tracing::debug!("GrantVoltPermissions {:?}", volt);
+                self.volt_permissions.insert(volt, permissions);
+            }
+            RevokeVoltPermissions(volt) => {
+                tracing::debug!("RevokeVoltPermissions {:?}", volt);
+                self.volt_permissions.remove(&volt.id());
+                self.plugin_rpc.stop_volt(volt);
+            }
+            SetPluginTrace(channel, enabled) => {
+                tracing::debug!("SetPluginTrace {:?} {enabled}", channel);
+                for plugin in self.plugins.values() {
+                    if plugin.output.channel() == &channel {
+                        set_plugin_trace(plugin, enabled);
                     }
                 }
+                if enabled {
+                    self.traced_channels.insert(channel);
+                } else {
+                    self.traced_channels.remove(&channel);
+                }
             }
             EnableVolt(volt) => {
                 tracing::debug!("EnableVolt {:?}", volt);
                 let volt_id = volt.id();
+                self.restarting_volts.remove(&volt_id);
                 for (_, volt) in self.plugins.iter() {
                     if volt.volt_id == volt_id {
                         return;
@@ -649,12 +1089,27 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
                     }
                 }
             }
-            DapContinue { dap_id, thread_id } => {
+            DapContinue {
+                dap_id,
+                thread_id,
+                single_thread,
+            } => {
                 if let Some(dap) = self.daps.get(&dap_id).cloned() {
                     let plugin_rpc = self.plugin_rpc.clone();
                     thread::spawn(move || {
-                        if dap.continue_thread(thread_id).is_ok() {
-                            plugin_rpc.core_rpc.dap_continued(dap_id);
+                        if let Ok(resp) =
+                            dap.continue_thread(thread_id, single_thread)
+                        {
+                            // an omitted `allThreadsContinued` means all
+                            // threads were resumed, unless only the one was
+                            // asked for
+                            plugin_rpc.core_rpc.dap_continued(
+                                dap_id,
+                                Some(thread_id),
+                                resp.all_threads_continued.unwrap_or(
+                                    !(single_thread && dap.supports_single_thread()),
+                                ),
+                            );
                         }
                     });
                 }
@@ -668,19 +1123,34 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
                     });
                 }
             }
-            DapStepOver { dap_id, thread_id } => {
+            DapStepOver {
+                dap_id,
+                thread_id,
+                single_thread,
+                granularity,
+            } => {
                 if let Some(dap) = self.daps.get(&dap_id).cloned() {
-                    dap.next(thread_id);
+                    dap.next(thread_id, single_thread, granularity);
                 }
             }
-            DapStepInto { dap_id, thread_id } => {
+            DapStepInto {
+                dap_id,
+                thread_id,
+                single_thread,
+                granularity,
+            } => {
                 if let Some(dap) = self.daps.get(&dap_id).cloned() {
-                    dap.step_in(thread_id);
+                    dap.step_in(thread_id, single_thread, granularity);
                 }
             }
-            DapStepOut { dap_id, thread_id } => {
+            DapStepOut {
+                dap_id,
+                thread_id,
+                single_thread,
+                granularity,
+            } => {
                 if let Some(dap) = self.daps.get(&dap_id).cloned() {
-                    dap.step_out(thread_id);
+                    dap.step_out(thread_id, single_thread, granularity);
                 }
             }
             DapStop { dap_id } => {
@@ -754,3 +1224,115 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
         }
     }
 }
+
+/// Turn the trace of the JSON-RPC messages of a plugin on or off, for the plugin
+/// as well with `$/setTrace`
+fn set_plugin_trace(plugin: &PluginServerRpcHandler, enabled: bool) {
+    plugin.output.set_trace(enabled);
+    let value = if enabled {
+        TraceValue::Verbose
+    } else {
+        TraceValue::Off
+    };
+    plugin.server_notification(
+        SetTrace::METHOD,
+        SetTraceParams { value },
+        None,
+        None,
+        false,
+    );
+}
+
+/// The plugins of the server named in a language server policy, in the order
+/// they're asked. A volt and the language servers it spawned have the same
+/// name, the language servers go first, and then the ones started first.
+fn policy_candidates(
+    plugins: &HashMap<PluginId, PluginServerRpcHandler>,
+    server: &str,
+) -> Vec<PluginId> {
+    let mut chosen: Vec<(&PluginId, &PluginServerRpcHandler)> = plugins
+        .iter()
+        .filter(|(_, plugin)| plugin.volt_id.is_named(server))
+        .collect();
+    chosen.sort_by_key(|(id, plugin)| (plugin.spawned_by.is_none(), id.0));
+    chosen.into_iter().map(|(id, _)| *id).collect()
+}
+
+/// A request that's routed by the language server policy of its language
+struct PolicyRequest {
+    method: Cow<'static, str>,
+    params: Value,
+    language_id: Option<String>,
+    path: Option<PathBuf>,
+    check: bool,
+    /// Whether an empty result counts as no result
+    fallback_on_empty: bool,
+    request_sent: Option<Arc<AtomicUsize>>,
+}
+
+/// Ask the plugins chosen by a language server policy one at a time, until one
+/// of them has a result. The ones that don't handle the document or the method
+/// answer with an error. If none of them has a result, the other plugins are
+/// asked.
+fn policy_request(
+    mut chosen: VecDeque<(PluginId, PluginServerRpcHandler)>,
+    others: Vec<(PluginId, PluginServerRpcHandler)>,
+    request: PolicyRequest,
+    f: Box<dyn ClonableCallback<Value, RpcError>>,
+) {
+    let Some((plugin_id, plugin)) = chosen.pop_front() else {
+        return;
+    };
+    plugin.server_request_async(
+        request.method.clone(),
+        request.params.clone(),
+        request.language_id.clone(),
+        request.path.clone(),
+        request.check,
+        move |result| {
+            let has_result = match &result {
+                Ok(value) => !request.fallback_on_empty || !is_empty_result(value),
+                Err(_) => false,
+            };
+            if has_result || (chosen.is_empty() && others.is_empty()) {
+                f(plugin_id, result);
+                return;
+            }
+            if !chosen.is_empty() {
+                policy_request(chosen, others, request, f);
+                return;
+            }
+
+            if let Some(request_sent) = &request.request_sent {
+                request_sent.fetch_add(others.len(), Ordering::Relaxed);
+            }
+            for (other_id, other) in others {
+                let f = dyn_clone::clone_box(&*f);
+                other.server_request_async(
+                    request.method.clone(),
+                    request.params.clone(),
+                    request.language_id.clone(),
+                    request.path.clone(),
+                    request.check,
+                    move |result| {
+                        f(other_id, result);
+                    },
+                );
+            }
+            // the chosen servers count as one that failed
+            f(
+                plugin_id,
+                Err(result.err().unwrap_or_else(|| RpcError {
+                    code: 0,
+                    message: "no result".to_string(),
+                })),
+            );
+        },
+    );
+}
+
+/// Whether a response has nothing in it, like a hover that's `null` or an empty
+/// list of locations
+fn is_empty_result(value: &Value) -> bool {
+    value.is_null() || value.as_array().is_some_and(|a| a.is_empty())
+}
@@ -0,0 +1,194 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use crossbeam_channel::Receiver;
+use jsonrpc_lite::JsonRpc;
+use lapce_rpc::{
+    RpcError,
+    core::CoreRpcHandler,
+    plugin::{PluginId, VoltID},
+};
+use parking_lot::Mutex;
+use serde_json::{Value, json};
+
+use super::{PolicyRequest, policy_candidates, policy_request};
+use crate::plugin::{output::PluginOutput, psp::PluginServerRpcHandler};
+
+/// A plugin of the volt `lapce.<name>` that answers every request with
+/// `answer`, and records its id in `asked` when it's asked
+fn plugin(
+    id: u64,
+    name: &str,
+    spawned_by: Option<u64>,
+    answer: Result<Value, RpcError>,
+    asked: &Arc<Mutex<Vec<u64>>>,
+) -> (PluginId, PluginServerRpcHandler) {
+    let (io_tx, io_rx) = crossbeam_channel::unbounded();
+    let volt_id = VoltID {
+        author: "lapce".to_string(),
+        name: name.to_string(),
+    };
+    let output =
+        PluginOutput::new(CoreRpcHandler::new(), volt_id.clone(), name.to_string());
+    let rpc = PluginServerRpcHandler::new(
+        volt_id,
+        spawned_by.map(PluginId),
+        Some(PluginId(id)),
+        output,
+        io_tx,
+    );
+    let server_rpc = rpc.clone();
+    let asked = asked.clone();
+    thread::spawn(move || {
+        for msg in io_rx {
+            if let value @ JsonRpc::Request(_) = msg {
+                asked.lock().push(id);
+                server_rpc
+                    .handle_server_response(value.get_id().unwrap(), answer.clone());
+            }
+        }
+    });
+    (PluginId(id), rpc)
+}
+
+fn error() -> Result<Value, RpcError> {
+    Err(RpcError {
+        code: 0,
+        message: "not supported".to_string(),
+    })
+}
+
+/// Route a request to the chosen plugins, then the others, and collect the
+/// results the callback gets
+fn route(
+    chosen: Vec<(PluginId, PluginServerRpcHandler)>,
+    others: Vec<(PluginId, PluginServerRpcHandler)>,
+    fallback_on_empty: bool,
+    request_sent: &Arc<AtomicUsize>,
+) -> Receiver<(PluginId, Result<Value, RpcError>)> {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    request_sent.fetch_add(1, Ordering::Relaxed);
+    policy_request(
+        chosen.into_iter().collect(),
+        others,
+        PolicyRequest {
+            method: "textDocument/formatting".into(),
+            params: Value::Null,
+            language_id: Some("rust".to_string()),
+            path: None,
+            check: false,
+            fallback_on_empty,
+            request_sent: Some(request_sent.clone()),
+        },
+        Box::new(move |plugin_id, result| {
+            tx.send((plugin_id, result)).unwrap();
+        }),
+    );
+    rx
+}
+
+/// The results of the routed request, one for each request that was counted
+fn results(
+    rx: &Receiver<(PluginId, Result<Value, RpcError>)>,
+    request_sent: &AtomicUsize,
+) -> Vec<(PluginId, Result<Value, RpcError>)> {
+    let mut results = Vec::new();
+    while results.len() < request_sent.load(Ordering::Relaxed) {
+        results.push(rx.recv_timeout(Duration::from_secs(5)).unwrap());
+    }
+    results
+}
+
+#[test]
+fn test_policy_candidates() {
+    let asked = Arc::new(Mutex::new(Vec::new()));
+    let plugins: HashMap<PluginId, PluginServerRpcHandler> = [
+        plugin(1, "mydsl", None, Ok(Value::Null), &asked),
+        plugin(2, "other", None, Ok(Value::Null), &asked),
+        plugin(4, "mydsl", Some(1), Ok(Value::Null), &asked),
+        plugin(3, "mydsl", Some(1), Ok(Value::Null), &asked),
+    ]
+    .into_iter()
+    .collect();
+
+    assert_eq!(
+        policy_candidates(&plugins, "mydsl"),
+        vec![PluginId(3), PluginId(4), PluginId(1)]
+    );
+    assert_eq!(
+        policy_candidates(&plugins, "lapce.other"),
+        vec![PluginId(2)]
+    );
+    assert!(policy_candidates(&plugins, "missing").is_empty());
+}
+
+#[test]
+fn test_policy_request_tries_chosen_in_order() {
+    let asked = Arc::new(Mutex::new(Vec::new()));
+    let request_sent = Arc::new(AtomicUsize::new(0));
+    let rx = route(
+        vec![
+            plugin(1, "mydsl", Some(2), error(), &asked),
+            plugin(2, "mydsl", None, Ok(json!([1])), &asked),
+        ],
+        vec![plugin(3, "other", None, Ok(json!([3])), &asked)],
+        true,
+        &request_sent,
+    );
+
+    let results = results(&rx, &request_sent);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].0, PluginId(2));
+    assert_eq!(results[0].1.as_ref().unwrap(), &json!([1]));
+    assert_eq!(*asked.lock(), vec![1, 2]);
+}
+
+#[test]
+fn test_policy_request_falls_back_to_others() {
+    let asked = Arc::new(Mutex::new(Vec::new()));
+    let request_sent = Arc::new(AtomicUsize::new(0));
+    let rx = route(
+        vec![
+            plugin(1, "mydsl", Some(2), error(), &asked),
+            plugin(2, "mydsl", None, Ok(json!([])), &asked),
+        ],
+        vec![
+            plugin(3, "other", None, Ok(json!([3])), &asked),
+            plugin(4, "another", None, Ok(json!([4])), &asked),
+        ],
+        true,
+        &request_sent,
+    );
+
+    let mut results = results(&rx, &request_sent);
+    results.sort_by_key(|(id, _)| id.0);
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].0, PluginId(2));
+    assert!(results[0].1.is_err());
+    assert_eq!(results[1].1.as_ref().unwrap(), &json!([3]));
+    assert_eq!(results[2].1.as_ref().unwrap(), &json!([4]));
+}
+
+#[test]
+fn test_policy_request_empty_result() {
+    let asked = Arc::new(Mutex::new(Vec::new()));
+    let request_sent = Arc::new(AtomicUsize::new(0));
+    let rx = route(
+        vec![plugin(1, "mydsl", None, Ok(json!([])), &asked)],
+        vec![plugin(2, "other", None, Ok(json!([2])), &asked)],
+        false,
+        &request_sent,
+    );
+
+    let results = results(&rx, &request_sent);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].1.as_ref().unwrap(), &json!([]));
+    assert_eq!(*asked.lock(), vec![1]);
+}
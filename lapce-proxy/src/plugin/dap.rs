@@ -5,7 +5,7 @@
     process::{Child, Command, Stdio},
     sync::{
         Arc,
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
     },
     thread,
 };
@@ -17,14 +17,19 @@
     dap_types::{
         self, ConfigurationDone, Continue, ContinueArguments, ContinueResponse,
         DapEvent, DapId, DapPayload, DapRequest, DapResponse, DapServer,
-        DebuggerCapabilities, Disconnect, Initialize, Launch, Next, NextArguments,
-        Pause, PauseArguments, Request, RunDebugConfig, RunInTerminal,
-        RunInTerminalArguments, RunInTerminalResponse, Scope, Scopes,
-        ScopesArguments, ScopesResponse, SetBreakpoints, SetBreakpointsArguments,
-        SetBreakpointsResponse, Source, SourceBreakpoint, StackTrace,
-        StackTraceArguments, StackTraceResponse, StepIn, StepInArguments, StepOut,
-        StepOutArguments, Terminate, ThreadId, Threads, ThreadsResponse, Variable,
-        Variables, VariablesArguments, VariablesResponse,
+        DebuggerCapabilities, Disassemble, DisassembleArguments,
+        DisassembleResponse, Disconnect, Evaluate, EvaluateArguments,
+        EvaluateResponse, Initialize, Launch, Next, NextArguments, Pause,
+        PauseArguments, ReadMemory, ReadMemoryArguments, ReadMemoryResponse,
+        Request, RunDebugConfig, RunInTerminal, RunInTerminalArguments,
+        RunInTerminalResponse, Scope, Scopes, ScopesArguments, ScopesResponse,
+        SetBreakpoints, SetBreakpointsArguments, SetBreakpointsResponse,
+        SetExpression, SetExpressionArguments, SetExpressionResponse, SetVariable,
+        SetVariableArguments, SetVariableResponse, Source, SourceBreakpoint,
+        StackTrace, StackTraceArguments, StackTraceResponse, StepIn,
+        StepInArguments, StepOut, StepOutArguments, SteppingGranularity, Terminate,
+        ThreadId, Threads, ThreadsResponse, Variable, Variables, VariablesArguments,
+        VariablesResponse,
     },
     terminal::TermId,
 };
@@ -238,16 +243,11 @@ fn handle_host_event(&mut self, event: &DapEvent) -> Result<()> {
             DapEvent::Stopped(stopped) => {
                 let all_threads_stopped =
                     stopped.all_threads_stopped.unwrap_or_default();
-                let mut stack_frames = HashMap::new();
-                if all_threads_stopped {
-                    if let Ok(response) = self.dap_rpc.threads() {
-                        for thread in response.threads {
-                            if let Ok(frames) = self.dap_rpc.stack_trace(thread.id) {
-                                stack_frames.insert(thread.id, frames.stack_frames);
-                            }
-                        }
-                    }
-                }
+                let threads = self
+                    .dap_rpc
+                    .threads()
+                    .map(|response| response.threads)
+                    .unwrap_or_default();
 
                 let current_thread = if all_threads_stopped {
                     Some(stopped.thread_id.unwrap_or_default())
@@ -255,6 +255,20 @@ fn handle_host_event(&mut self, event: &DapEvent) -> Result<()> {
                     stopped.thread_id
                 };
 
+                // Only the threads that are actually stopped have a valid
+                // stack trace.
+                let stopped_threads: Vec<ThreadId> = if all_threads_stopped {
+                    threads.iter().map(|thread| thread.id).collect()
+                } else {
+                    current_thread.into_iter().collect()
+                };
+                let mut stack_frames = HashMap::new();
+                for thread_id in stopped_threads {
+                    if let Ok(frames) = self.dap_rpc.stack_trace(thread_id) {
+                        stack_frames.insert(thread_id, frames.stack_frames);
+                    }
+                }
+
                 let active_frame = current_thread
                     .and_then(|thread_id| stack_frames.get(&thread_id))
                     .and_then(|stack_frames| stack_frames.first());
@@ -273,26 +287,17 @@ fn handle_host_event(&mut self, event: &DapEvent) -> Result<()> {
                 self.plugin_rpc.core_rpc.dap_stopped(
                     self.config.dap_id,
                     stopped.clone(),
+                    threads,
                     stack_frames,
                     vars,
                 );
-
-                // if all_threads_stopped {
-                //     if let Ok(response) = self.dap_rpc.threads() {
-                //         for thread in response.threads {
-                //             self.fetch_stack_trace(thread.id);
-                //         }
-                //         self.select_thread_id(
-                //             stopped.thread_id.unwrap_or_default(),
-                //             false,
-                //         );
-                //     }
-                // } else if let Some(thread_id) = stopped.thread_id {
-                //     self.select_thread_id(thread_id, false);
-                // }
             }
-            DapEvent::Continued(_) => {
-                self.plugin_rpc.core_rpc.dap_continued(self.dap_rpc.dap_id);
+            DapEvent::Continued(continued) => {
+                self.plugin_rpc.core_rpc.dap_continued(
+                    self.dap_rpc.dap_id,
+                    Some(continued.thread_id),
+                    continued.all_threads_continued.unwrap_or(false),
+                );
             }
             DapEvent::Exited(_exited) => {}
             DapEvent::Terminated(_) => {
@@ -305,7 +310,15 @@ fn handle_host_event(&mut self, event: &DapEvent) -> Result<()> {
                     tracing::error!("{:?}", err);
                 }
             }
-            DapEvent::Thread { .. } => {}
+            DapEvent::Thread { .. } => {
+                let dap_rpc = self.dap_rpc.clone();
+                let core_rpc = self.plugin_rpc.core_rpc.clone();
+                thread::spawn(move || {
+                    if let Ok(response) = dap_rpc.threads() {
+                        core_rpc.dap_threads(dap_rpc.dap_id, response.threads);
+                    }
+                });
+            }
             DapEvent::Output(_) => {}
             DapEvent::Breakpoint { .. } => {}
             DapEvent::Module { .. } => {}
@@ -338,6 +351,14 @@ pub(crate) fn initialize(&mut self) -> Result<()> {
             .dap_rpc
             .request::<Initialize>(params)
             .map_err(|e| anyhow!(e.message))?;
+        self.plugin_rpc
+            .core_rpc
+            .dap_capabilities(self.config.dap_id, resp.clone());
+        self.dap_rpc.supports_single_thread.store(
+            resp.supports_single_thread_execution_requests
+                .unwrap_or(false),
+            Ordering::Relaxed,
+        );
         self.capabilities = Some(resp);
 
         Ok(())
@@ -441,6 +462,9 @@ pub struct DapRpcHandler {
     termain_process_rx: Receiver<(TermId, Option<u32>)>,
     seq_counter: Arc<AtomicU64>,
     server_pending: Arc<Mutex<HashMap<u64, ResponseHandler<DapResponse, RpcError>>>>,
+    /// Whether the adapter can resume or step one thread while the others stay
+    /// stopped
+    supports_single_thread: Arc<AtomicBool>,
 }
 
 impl DapRpcHandler {
@@ -459,9 +483,20 @@ fn new(dap_id: DapId) -> Self {
             termain_process_rx,
             seq_counter: Arc::new(AtomicU64::new(0)),
             server_pending: Arc::new(Mutex::new(HashMap::new())),
+            supports_single_thread: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    pub(crate) fn supports_single_thread(&self) -> bool {
+        self.supports_single_thread.load(Ordering::Relaxed)
+    }
+
+    /// The `singleThread` argument of the requests that resume or step a
+    /// thread, which is only sent when asked for and the adapter supports it
+    fn single_thread(&self, single_thread: bool) -> Option<bool> {
+        (single_thread && self.supports_single_thread()).then_some(true)
+    }
+
     pub fn mainloop(&self, dap_client: &mut DapClient) {
         for msg in &self.rpc_rx {
             match msg {
@@ -711,8 +746,17 @@ pub fn set_breakpoints(
         Ok(resp)
     }
 
-    pub fn continue_thread(&self, thread_id: ThreadId) -> Result<ContinueResponse> {
-        let params = ContinueArguments { thread_id };
+    /// Resume the thread, and the others too unless `single_thread` is set and
+    /// the adapter supports it
+    pub fn continue_thread(
+        &self,
+        thread_id: ThreadId,
+        single_thread: bool,
+    ) -> Result<ContinueResponse> {
+        let params = ContinueArguments {
+            thread_id,
+            single_thread: self.single_thread(single_thread),
+        };
         let resp = self
             .request::<Continue>(params)
             .map_err(|e| anyhow!(e.message))?;
@@ -794,31 +838,140 @@ pub fn variables_async(
         self.request_async::<Variables>(args, f);
     }
 
-    pub fn next(&self, thread_id: ThreadId) {
+    /// Step over the next line of the thread, resuming the others while it steps unless
+    /// `single_thread` is set and the adapter supports it
+    pub fn next(
+        &self,
+        thread_id: ThreadId,
+        single_thread: bool,
+        granularity: Option<SteppingGranularity>,
+    ) {
         let args = NextArguments {
             thread_id,
-            granularity: None,
+            single_thread: self.single_thread(single_thread),
+            granularity,
         };
 
         self.request_async::<Next>(args, move |_| {});
     }
 
-    pub fn step_in(&self, thread_id: ThreadId) {
+    /// Step into the call of the thread, resuming the others while it steps unless
+    /// `single_thread` is set and the adapter supports it
+    pub fn step_in(
+        &self,
+        thread_id: ThreadId,
+        single_thread: bool,
+        granularity: Option<SteppingGranularity>,
+    ) {
         let args = StepInArguments {
             thread_id,
+            single_thread: self.single_thread(single_thread),
             target_id: None,
-            granularity: None,
+            granularity,
         };
 
         self.request_async::<StepIn>(args, move |_| {});
     }
 
-    pub fn step_out(&self, thread_id: ThreadId) {
+    /// Step out of the function of the thread, resuming the others while it steps unless
+    /// `single_thread` is set and the adapter supports it
+    pub fn step_out(
+        &self,
+        thread_id: ThreadId,
+        single_thread: bool,
+        granularity: Option<SteppingGranularity>,
+    ) {
         let args = StepOutArguments {
             thread_id,
-            granularity: None,
+            single_thread: self.single_thread(single_thread),
+            granularity,
         };
 
         self.request_async::<StepOut>(args, move |_| {});
     }
+
+    pub fn set_variable_async(
+        &self,
+        variables_reference: usize,
+        name: String,
+        value: String,
+        f: impl RpcCallback<SetVariableResponse, RpcError> + 'static,
+    ) {
+        let args = SetVariableArguments {
+            variables_reference,
+            name,
+            value,
+            format: None,
+        };
+
+        self.request_async::<SetVariable>(args, f);
+    }
+
+    pub fn set_expression_async(
+        &self,
+        frame_id: Option<usize>,
+        expression: String,
+        value: String,
+        f: impl RpcCallback<SetExpressionResponse, RpcError> + 'static,
+    ) {
+        let args = SetExpressionArguments {
+            expression,
+            value,
+            frame_id,
+            format: None,
+        };
+
+        self.request_async::<SetExpression>(args, f);
+    }
+
+    pub fn evaluate_async(
+        &self,
+        frame_id: Option<usize>,
+        expression: String,
+        context: Option<String>,
+        f: impl RpcCallback<EvaluateResponse, RpcError> + 'static,
+    ) {
+        let args = EvaluateArguments {
+            expression,
+            frame_id,
+            context,
+            format: None,
+        };
+
+        self.request_async::<Evaluate>(args, f);
+    }
+
+    pub fn disassemble_async(
+        &self,
+        memory_reference: String,
+        instruction_offset: i64,
+        instruction_count: usize,
+        f: impl RpcCallback<DisassembleResponse, RpcError> + 'static,
+    ) {
+        let args = DisassembleArguments {
+            memory_reference,
+            offset: None,
+            instruction_offset: Some(instruction_offset),
+            instruction_count,
+            resolve_symbols: Some(true),
+        };
+
+        self.request_async::<Disassemble>(args, f);
+    }
+
+    pub fn read_memory_async(
+        &self,
+        memory_reference: String,
+        offset: i64,
+        count: usize,
+        f: impl RpcCallback<ReadMemoryResponse, RpcError> + 'static,
+    ) {
+        let args = ReadMemoryArguments {
+            memory_reference,
+            offset: Some(offset),
+            count,
+        };
+
+        self.request_async::<ReadMemory>(args, f);
+    }
 }
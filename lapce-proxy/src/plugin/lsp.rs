@@ -1,6 +1,10 @@
+#[cfg(test)]
+mod tests;
+
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 use std::{
+    collections::HashMap,
     io::{BufRead, BufReader, BufWriter, Write},
     path::{Path, PathBuf},
     process::{self, Child, Command, Stdio},
@@ -13,13 +17,13 @@
 use lapce_core::meta;
 use lapce_rpc::{
     RpcError,
-    plugin::{PluginId, VoltID},
+    plugin::{LanguageServerConfig, PluginId, VoltID, VoltPermissions},
     style::LineStyle,
 };
 use lapce_xi_rope::Rope;
 use lsp_types::{
-    notification::{Initialized, Notification},
-    request::{Initialize, Request},
+    notification::{DidChangeConfiguration, Initialized, Notification},
+    request::{Initialize, Request, WorkspaceConfiguration},
     *,
 };
 use parking_lot::Mutex;
@@ -27,6 +31,7 @@
 
 use super::{
     client_capabilities,
+    output::PluginOutput,
     psp::{
         PluginHandlerNotification, PluginHostHandler, PluginServerHandler,
         PluginServerRpcHandler, ResponseSender, RpcCallback,
@@ -65,6 +70,9 @@ pub struct LspClient {
     workspace: Option<PathBuf>,
     host: PluginHostHandler,
     options: Option<Value>,
+    /// The `[lsp.<name>]` section of a language server declared in the
+    /// settings, `None` for servers started by volts
+    config: Option<LanguageServerConfig>,
 }
 
 impl PluginServerHandler for LspClient {
@@ -106,6 +114,28 @@ fn handle_host_request(
         params: Params,
         resp: ResponseSender,
     ) {
+        if method == WorkspaceConfiguration::METHOD {
+            if let Some(config) = self.config.as_ref() {
+                match serde_json::to_value(params)
+                    .and_then(serde_json::from_value::<ConfigurationParams>)
+                {
+                    Ok(params) => {
+                        let settings: Vec<Value> = params
+                            .items
+                            .iter()
+                            .map(|item| {
+                                config.settings_section(item.section.as_deref())
+                            })
+                            .collect();
+                        resp.send(settings);
+                    }
+                    Err(err) => {
+                        resp.send_err(0, err.to_string());
+                    }
+                }
+                return;
+            }
+        }
         self.host.handle_request(id, method, params, resp);
     }
 
@@ -183,6 +213,7 @@ fn new(
         server_uri: Url,
         args: Vec<String>,
         options: Option<Value>,
+        config: Option<LanguageServerConfig>,
     ) -> Result<Self> {
         let server = match server_uri.scheme() {
             "file" => {
@@ -201,19 +232,35 @@ fn new(
             _ => return Err(anyhow!("uri not supported")),
         };
 
-        let mut process = Self::process(workspace.as_ref(), &server, &args)?;
+        let mut process = Self::process(
+            workspace.as_ref(),
+            &server,
+            &args,
+            config.as_ref().map(|c| &c.env),
+        )?;
         let stdin = process.stdin.take().unwrap();
         let stdout = process.stdout.take().unwrap();
         let stderr = process.stderr.take().unwrap();
 
         let mut writer = Box::new(BufWriter::new(stdin));
         let (io_tx, io_rx) = crossbeam_channel::unbounded();
+        let server_name = Path::new(&server)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| server.clone());
+        let output = PluginOutput::new(
+            plugin_rpc.core_rpc.clone(),
+            volt_id.clone(),
+            format!("{volt_display_name} ({server_name})"),
+        );
         let server_rpc = PluginServerRpcHandler::new(
             volt_id.clone(),
             spawned_by,
             plugin_id,
+            output.clone(),
             io_tx.clone(),
         );
+        let writer_output = output.clone();
         thread::spawn(move || {
             for msg in io_rx {
                 if msg
@@ -225,6 +272,7 @@ fn new(
                 }
                 if let Ok(msg) = serde_json::to_string(&msg) {
                     tracing::debug!("write to lsp: {}", msg);
+                    writer_output.trace_sent(&msg);
                     let msg =
                         format!("Content-Length: {}\r\n\r\n{}", msg.len(), msg);
                     if let Err(err) = writer.write(msg.as_bytes()) {
@@ -241,6 +289,7 @@ fn new(
         let core_rpc = plugin_rpc.core_rpc.clone();
         let volt_id_closure = volt_id.clone();
         let name = volt_display_name.clone();
+        let reader_output = output.clone();
         thread::spawn(move || {
             let mut reader = Box::new(BufReader::new(stdout));
             loop {
@@ -249,6 +298,7 @@ fn new(
                         if !message_str.contains("$/progress") {
                             tracing::debug!("read from lsp: {}", message_str);
                         }
+                        reader_output.trace_received(&message_str);
                         if let Some(resp) = handle_plugin_server_message(
                             &local_server_rpc,
                             &message_str,
@@ -260,6 +310,10 @@ fn new(
                         }
                     }
                     Err(_err) => {
+                        reader_output.log(
+                            MessageType::ERROR,
+                            &format!("lsp server {server} stopped!"),
+                        );
                         core_rpc.log(
                             lapce_rpc::core::LogLevel::Error,
                             format!("lsp server {server} stopped!"),
@@ -285,6 +339,7 @@ fn new(
                         if n == 0 {
                             return;
                         }
+                        output.stderr(&line);
                         core_rpc.log(
                             lapce_rpc::core::LogLevel::Trace,
                             line.trim_end().to_string(),
@@ -306,6 +361,9 @@ fn new(
             pwd,
             volt_id,
             volt_display_name,
+            // The language server is a native process already, so it doesn't
+            // get to run programs on top of that through the host.
+            VoltPermissions::default(),
             document_selector,
             plugin_rpc.core_rpc.clone(),
             server_rpc.clone(),
@@ -319,6 +377,7 @@ fn new(
             workspace,
             host,
             options,
+            config,
         })
     }
 
@@ -335,6 +394,7 @@ pub fn start(
         server_uri: Url,
         args: Vec<String>,
         options: Option<Value>,
+        config: Option<LanguageServerConfig>,
     ) -> Result<PluginId> {
         let mut lsp = Self::new(
             plugin_rpc,
@@ -348,6 +408,7 @@ pub fn start(
             server_uri,
             args,
             options,
+            config,
         )?;
         let plugin_id = lsp.server_rpc.plugin_id;
 
@@ -403,6 +464,17 @@ fn initialize(&mut self) {
                     None,
                     false,
                 );
+                if let Some(settings) =
+                    self.config.as_ref().and_then(|c| c.settings.clone())
+                {
+                    self.server_rpc.server_notification(
+                        DidChangeConfiguration::METHOD,
+                        DidChangeConfigurationParams { settings },
+                        None,
+                        None,
+                        false,
+                    );
+                }
                 if self
                     .plugin_rpc
                     .plugin_server_loaded(self.server_rpc.clone())
@@ -441,6 +513,7 @@ fn process(
         workspace: Option<&PathBuf>,
         server: &str,
         args: &[String],
+        env: Option<&HashMap<String, String>>,
     ) -> Result<Child> {
         let mut process = Command::new(server);
         if let Some(workspace) = workspace {
@@ -448,6 +521,9 @@ fn process(
         }
 
         process.args(args);
+        if let Some(env) = env {
+            process.envs(env);
+        }
 
         #[cfg(target_os = "windows")]
         let process = process.creation_flags(0x08000000);
@@ -460,6 +536,83 @@ fn process(
     }
 }
 
+/// Language servers declared in the settings are tracked as if they were
+/// volts by this author, with the name of their `[lsp.<name>]` section.
+const SETTINGS_LANGUAGE_SERVER_AUTHOR: &str = "settings";
+
+pub fn language_server_volt_id(name: &str) -> VoltID {
+    VoltID {
+        author: SETTINGS_LANGUAGE_SERVER_AUTHOR.to_string(),
+        name: name.to_string(),
+    }
+}
+
+/// The uri a language server from the settings is started with. Paths
+/// relative to the workspace are resolved against it, anything else is looked
+/// up in `PATH`.
+pub fn language_server_uri(command: &str, workspace: Option<&Path>) -> Result<Url> {
+    if command.is_empty() {
+        return Err(anyhow!("language server doesn't have a command"));
+    }
+    let path = Path::new(command);
+    let path = if path.is_absolute() {
+        Some(path.to_path_buf())
+    } else if path.components().count() > 1 {
+        workspace.map(|workspace| workspace.join(path))
+    } else {
+        None
+    };
+    match path {
+        Some(path) => Url::from_file_path(&path)
+            .map_err(|_| anyhow!("invalid language server path {}", path.display())),
+        None => Ok(Url::parse(&format!("urn:{command}"))?),
+    }
+}
+
+/// The documents a language server from the settings handles, by its
+/// `languages` and `files`
+pub fn language_server_document_selector(
+    config: &LanguageServerConfig,
+) -> DocumentSelector {
+    let languages = config
+        .languages
+        .iter()
+        .map(|language| (Some(language.clone()), None));
+    let files = config.files.iter().map(|glob| (None, Some(glob.clone())));
+    languages
+        .chain(files)
+        .map(|(language, pattern)| lsp_types::DocumentFilter {
+            language,
+            scheme: None,
+            pattern,
+        })
+        .collect()
+}
+
+/// Whether the language server from the settings should be started for the
+/// document
+pub fn language_server_handles(
+    config: &LanguageServerConfig,
+    language_id: &str,
+    path: &Path,
+) -> bool {
+    language_server_document_selector(config)
+        .iter()
+        .map(DocumentFilter::from_lsp_filter_loose)
+        .any(|filter| {
+            filter
+                .language_id
+                .as_ref()
+                .map(|id| id == language_id)
+                .unwrap_or(true)
+                && filter
+                    .pattern
+                    .as_ref()
+                    .map(|pattern| pattern.is_match(path))
+                    .unwrap_or(true)
+        })
+}
+
 pub struct DocumentFilter {
     /// The document must have this language id, if it exists
     pub language_id: Option<String>,
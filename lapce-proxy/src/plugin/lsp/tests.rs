@@ -0,0 +1,62 @@
+use std::path::Path;
+
+use lapce_rpc::plugin::LanguageServerConfig;
+
+use super::{language_server_handles, language_server_uri};
+
+#[test]
+fn test_language_server_uri() {
+    let workspace = std::env::temp_dir().join("workspace");
+    assert_eq!(
+        language_server_uri("mydsl-lsp", Some(&workspace))
+            .unwrap()
+            .path(),
+        "mydsl-lsp"
+    );
+    assert_eq!(
+        language_server_uri("tools/mydsl-lsp", Some(&workspace))
+            .unwrap()
+            .to_file_path()
+            .unwrap(),
+        workspace.join("tools").join("mydsl-lsp")
+    );
+    let absolute = std::env::temp_dir().join("mydsl-lsp");
+    assert_eq!(
+        language_server_uri(absolute.to_str().unwrap(), None)
+            .unwrap()
+            .to_file_path()
+            .unwrap(),
+        absolute
+    );
+    assert!(language_server_uri("", Some(&workspace)).is_err());
+}
+
+#[test]
+fn test_language_server_handles() {
+    let config = LanguageServerConfig {
+        command: "mydsl-lsp".to_string(),
+        languages: vec!["mydsl".to_string()],
+        files: vec!["**/*.mdsl".to_string()],
+        ..Default::default()
+    };
+    assert!(language_server_handles(
+        &config,
+        "mydsl",
+        Path::new("/workspace/main.txt")
+    ));
+    assert!(language_server_handles(
+        &config,
+        "plaintext",
+        Path::new("/workspace/src/main.mdsl")
+    ));
+    assert!(!language_server_handles(
+        &config,
+        "rust",
+        Path::new("/workspace/src/main.rs")
+    ));
+    assert!(!language_server_handles(
+        &LanguageServerConfig::default(),
+        "mydsl",
+        Path::new("/workspace/main.mdsl")
+    ));
+}
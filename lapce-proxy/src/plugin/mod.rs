@@ -1,31 +1,42 @@
 pub mod catalog;
 pub mod dap;
 pub mod lsp;
+pub mod output;
 pub mod psp;
+pub mod registry;
+pub mod scaffold;
 pub mod wasi;
 
+#[cfg(test)]
+mod tests;
+
 use std::{
     borrow::Cow,
     collections::HashMap,
     fs,
     path::{Path, PathBuf},
     sync::{
-        Arc,
+        Arc, OnceLock, Weak,
         atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
     },
-    time::Duration,
+    thread,
+    time::{Duration, Instant},
 };
 
 use anyhow::{Result, anyhow};
-use crossbeam_channel::{Receiver, Sender};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
 use dyn_clone::DynClone;
-use flate2::read::GzDecoder;
 use lapce_core::directory::Directory;
 use lapce_rpc::{
     RequestId, RpcError,
     core::CoreRpcHandler,
-    dap_types::{self, DapId, RunDebugConfig, SourceBreakpoint, ThreadId},
-    plugin::{PluginId, VoltInfo, VoltMetadata},
+    dap_types::{
+        self, DapId, RunDebugConfig, SourceBreakpoint, SteppingGranularity, ThreadId,
+    },
+    plugin::{
+        LanguageServerConfig, LanguageServerPolicy, PluginId, PluginOutputChannel,
+        VoltID, VoltInfo, VoltMetadata, VoltPermissions,
+    },
     proxy::ProxyRpcHandler,
     style::LineStyle,
     terminal::TermId,
@@ -41,11 +52,12 @@
     CompletionItem, CompletionItemCapability,
     CompletionItemCapabilityResolveSupport, CompletionParams, CompletionResponse,
     Diagnostic, DocumentFormattingParams, DocumentSymbolClientCapabilities,
-    DocumentSymbolParams, DocumentSymbolResponse, FoldingRange,
-    FoldingRangeClientCapabilities, FoldingRangeParams, FormattingOptions,
-    GotoCapability, GotoDefinitionParams, GotoDefinitionResponse, Hover,
-    HoverClientCapabilities, HoverParams, InlayHint, InlayHintClientCapabilities,
-    InlayHintParams, InlineCompletionClientCapabilities, InlineCompletionParams,
+    DocumentSymbolParams, DocumentSymbolResponse, ExecuteCommandParams,
+    FoldingRange, FoldingRangeClientCapabilities, FoldingRangeParams,
+    FormattingOptions, GotoCapability, GotoDefinitionParams, GotoDefinitionResponse,
+    Hover, HoverClientCapabilities, HoverParams, InlayHint,
+    InlayHintClientCapabilities, InlayHintParams,
+    InlineCompletionClientCapabilities, InlineCompletionParams,
     InlineCompletionResponse, InlineCompletionTriggerKind, Location, MarkupKind,
     MessageActionItemCapabilities, ParameterInformationSettings,
     PartialResultParams, Position, PrepareRenameResponse,
@@ -63,30 +75,35 @@
     request::{
         CallHierarchyIncomingCalls, CallHierarchyPrepare, CodeActionRequest,
         CodeActionResolveRequest, CodeLensRequest, CodeLensResolve, Completion,
-        DocumentSymbolRequest, FoldingRangeRequest, Formatting, GotoDefinition,
-        GotoImplementation, GotoImplementationResponse, GotoTypeDefinition,
-        GotoTypeDefinitionParams, GotoTypeDefinitionResponse, HoverRequest,
-        InlayHintRequest, InlineCompletionRequest, PrepareRenameRequest, References,
-        Rename, Request, ResolveCompletionItem, SelectionRangeRequest,
-        SemanticTokensFullRequest, SignatureHelpRequest, WorkspaceSymbolRequest,
+        DocumentSymbolRequest, ExecuteCommand, FoldingRangeRequest, Formatting,
+        GotoDefinition, GotoImplementation, GotoImplementationResponse,
+        GotoTypeDefinition, GotoTypeDefinitionParams, GotoTypeDefinitionResponse,
+        HoverRequest, InlayHintRequest, InlineCompletionRequest,
+        PrepareRenameRequest, References, Rename, Request, ResolveCompletionItem,
+        SelectionRangeRequest, SemanticTokensFullRequest, SignatureHelpRequest,
+        WorkspaceSymbolRequest,
     },
 };
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::{Map, Value};
-use tar::Archive;
 use tracing::error;
 
 use self::{
     catalog::PluginCatalog,
     dap::DapRpcHandler,
     psp::{ClonableCallback, PluginServerRpcHandler, RpcCallback},
-    wasi::{load_volt, start_volt},
+    registry::{Registry, replace_installed_volt, rollback_volt_dir},
+    wasi::{load_volt, start_volt, volt_permitted},
 };
 use crate::buffer::language_id_from_path;
 
 pub type PluginName = String;
 
+/// How long the plugins have to answer a request whose results are merged,
+/// before the results that came in are used without the others
+const MERGED_REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
 #[allow(clippy::large_enum_variant)]
 pub enum PluginCatalogRpc {
     ServerRequest {
@@ -128,6 +145,41 @@ pub enum PluginCatalogRpc {
                 >,
         >,
     },
+    DapSetVariable {
+        dap_id: DapId,
+        reference: usize,
+        name: String,
+        value: String,
+        f: Box<dyn RpcCallback<dap_types::SetVariableResponse, RpcError>>,
+    },
+    DapSetExpression {
+        dap_id: DapId,
+        frame_id: Option<usize>,
+        expression: String,
+        value: String,
+        f: Box<dyn RpcCallback<dap_types::SetExpressionResponse, RpcError>>,
+    },
+    DapEvaluate {
+        dap_id: DapId,
+        frame_id: Option<usize>,
+        expression: String,
+        context: Option<String>,
+        f: Box<dyn RpcCallback<dap_types::EvaluateResponse, RpcError>>,
+    },
+    DapDisassemble {
+        dap_id: DapId,
+        memory_reference: String,
+        instruction_offset: i64,
+        instruction_count: usize,
+        f: Box<dyn RpcCallback<dap_types::DisassembleResponse, RpcError>>,
+    },
+    DapReadMemory {
+        dap_id: DapId,
+        memory_reference: String,
+        offset: i64,
+        count: usize,
+        f: Box<dyn RpcCallback<dap_types::ReadMemoryResponse, RpcError>>,
+    },
     DidOpenTextDocument {
         document: TextDocumentItem,
     },
@@ -155,12 +207,20 @@ pub enum PluginCatalogRpc {
 #[allow(clippy::large_enum_variant)]
 pub enum PluginCatalogNotification {
     UpdatePluginConfigs(HashMap<String, HashMap<String, serde_json::Value>>),
+    UpdateLanguageServers(HashMap<String, LanguageServerConfig>),
+    UpdateLanguageServerPolicies(HashMap<String, LanguageServerPolicy>),
     UnactivatedVolts(Vec<VoltMetadata>),
     PluginServerLoaded(PluginServerRpcHandler),
     InstallVolt(VoltInfo),
     StopVolt(VoltInfo),
     EnableVolt(VoltInfo),
     ReloadVolt(VoltMetadata),
+    RollbackVolt(VoltMetadata),
+    VoltCrashed(PluginId, VoltMetadata, String),
+    RestartVolt(VoltMetadata),
+    GrantVoltPermissions(VoltID, VoltPermissions),
+    RevokeVoltPermissions(VoltInfo),
+    SetPluginTrace(PluginOutputChannel, bool),
     DapLoaded(DapRpcHandler),
     DapDisconnected(DapId),
     DapStart {
@@ -175,18 +235,25 @@ pub enum PluginCatalogNotification {
     DapContinue {
         dap_id: DapId,
         thread_id: ThreadId,
+        single_thread: bool,
     },
     DapStepOver {
         dap_id: DapId,
         thread_id: ThreadId,
+        single_thread: bool,
+        granularity: Option<SteppingGranularity>,
     },
     DapStepInto {
         dap_id: DapId,
         thread_id: ThreadId,
+        single_thread: bool,
+        granularity: Option<SteppingGranularity>,
     },
     DapStepOut {
         dap_id: DapId,
         thread_id: ThreadId,
+        single_thread: bool,
+        granularity: Option<SteppingGranularity>,
     },
     DapPause {
         dap_id: DapId,
@@ -347,6 +414,64 @@ pub fn mainloop(&self, plugin: &mut PluginCatalog) {
                 } => {
                     plugin.dap_get_scopes(dap_id, frame_id, f);
                 }
+                PluginCatalogRpc::DapSetVariable {
+                    dap_id,
+                    reference,
+                    name,
+                    value,
+                    f,
+                } => {
+                    plugin.dap_set_variable(dap_id, reference, name, value, f);
+                }
+                PluginCatalogRpc::DapSetExpression {
+                    dap_id,
+                    frame_id,
+                    expression,
+                    value,
+                    f,
+                } => {
+                    plugin
+                        .dap_set_expression(dap_id, frame_id, expression, value, f);
+                }
+                PluginCatalogRpc::DapEvaluate {
+                    dap_id,
+                    frame_id,
+                    expression,
+                    context,
+                    f,
+                } => {
+                    plugin.dap_evaluate(dap_id, frame_id, expression, context, f);
+                }
+                PluginCatalogRpc::DapDisassemble {
+                    dap_id,
+                    memory_reference,
+                    instruction_offset,
+                    instruction_count,
+                    f,
+                } => {
+                    plugin.dap_disassemble(
+                        dap_id,
+                        memory_reference,
+                        instruction_offset,
+                        instruction_count,
+                        f,
+                    );
+                }
+                PluginCatalogRpc::DapReadMemory {
+                    dap_id,
+                    memory_reference,
+                    offset,
+                    count,
+                    f,
+                } => {
+                    plugin.dap_read_memory(
+                        dap_id,
+                        memory_reference,
+                        offset,
+                        count,
+                        f,
+                    );
+                }
                 PluginCatalogRpc::Shutdown => {
                     return;
                 }
@@ -430,6 +555,33 @@ fn send_request_to_all_plugins<P, Resp>(
         );
     }
 
+    /// Send the request to all the plugins and call `cb` once with the
+    /// results of every plugin that answered it successfully, so the results
+    /// of several language servers for the same document can be combined
+    fn send_request_to_all_plugins_merged<P, Resp>(
+        &self,
+        method: &'static str,
+        params: P,
+        language_id: Option<String>,
+        path: Option<PathBuf>,
+        cb: impl FnOnce(Result<Vec<(PluginId, Resp)>, RpcError>) + Send + 'static,
+    ) where
+        P: Serialize,
+        Resp: DeserializeOwned + Send + 'static,
+    {
+        let request_sent = Arc::new(AtomicUsize::new(0));
+        self.send_request(
+            None,
+            Some(request_sent.clone()),
+            method,
+            params,
+            language_id,
+            path,
+            true,
+            merge_results(request_sent, MERGED_REQUEST_TIMEOUT, cb),
+        );
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn send_request<P: Serialize>(
         &self,
@@ -764,7 +916,7 @@ pub fn get_code_actions(
         path: &Path,
         position: Position,
         diagnostics: Vec<Diagnostic>,
-        cb: impl FnOnce(PluginId, Result<CodeActionResponse, RpcError>)
+        cb: impl FnOnce(Result<Vec<(PluginId, CodeActionResponse)>, RpcError>)
         + Clone
         + Send
         + 'static,
@@ -787,7 +939,7 @@ pub fn get_code_actions(
         };
         let language_id =
             Some(language_id_from_path(path).unwrap_or("").to_string());
-        self.send_request_to_all_plugins(
+        self.send_request_to_all_plugins_merged(
             method,
             params,
             language_id,
@@ -1138,11 +1290,16 @@ pub fn completion(
         let language_id =
             Some(language_id_from_path(path).unwrap_or("").to_string());
 
-        self.send_request_to_all_plugins(
+        // Every language server of the document gets the request, and the
+        // completion items of each are merged in the editor as they come in
+        self.send_request(
+            None,
+            None,
             method,
             params,
             language_id,
             Some(path.to_path_buf()),
+            true,
             move |plugin_id, result| match result {
                 Ok(value) => {
                     if let Ok(resp) =
@@ -1153,7 +1310,7 @@ pub fn completion(
                     }
                 }
                 Err(err) => {
-                    tracing::error!("{:?}", err);
+                    tracing::debug!("{:?}", err);
                 }
             },
         );
@@ -1276,6 +1433,29 @@ pub fn action_resolve(
         );
     }
 
+    /// Run a command that the plugin registered
+    pub fn execute_command(
+        &self,
+        plugin_id: PluginId,
+        command: String,
+        cb: impl FnOnce(PluginId, Result<Value, RpcError>) + Clone + Send + 'static,
+    ) {
+        self.send_request(
+            Some(plugin_id),
+            None,
+            ExecuteCommand::METHOD,
+            ExecuteCommandParams {
+                command,
+                arguments: Vec::new(),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+            },
+            None,
+            None,
+            false,
+            cb,
+        );
+    }
+
     pub fn did_open_document(
         &self,
         path: &Path,
@@ -1308,6 +1488,21 @@ pub fn unactivated_volts(&self, volts: Vec<VoltMetadata>) -> Result<()> {
         self.catalog_notification(PluginCatalogNotification::UnactivatedVolts(volts))
     }
 
+    pub fn volt_crashed(
+        &self,
+        plugin_id: PluginId,
+        volt: VoltMetadata,
+        error: String,
+    ) -> Result<()> {
+        self.catalog_notification(PluginCatalogNotification::VoltCrashed(
+            plugin_id, volt, error,
+        ))
+    }
+
+    pub fn restart_volt(&self, volt: VoltMetadata) -> Result<()> {
+        self.catalog_notification(PluginCatalogNotification::RestartVolt(volt))
+    }
+
     pub fn plugin_server_loaded(
         &self,
         plugin: PluginServerRpcHandler,
@@ -1326,6 +1521,24 @@ pub fn update_plugin_configs(
         ))
     }
 
+    pub fn update_language_servers(
+        &self,
+        servers: HashMap<String, LanguageServerConfig>,
+    ) -> Result<()> {
+        self.catalog_notification(PluginCatalogNotification::UpdateLanguageServers(
+            servers,
+        ))
+    }
+
+    pub fn update_language_server_policies(
+        &self,
+        policies: HashMap<String, LanguageServerPolicy>,
+    ) -> Result<()> {
+        self.catalog_notification(
+            PluginCatalogNotification::UpdateLanguageServerPolicies(policies),
+        )
+    }
+
     pub fn install_volt(&self, volt: VoltInfo) -> Result<()> {
         self.catalog_notification(PluginCatalogNotification::InstallVolt(volt))
     }
@@ -1368,10 +1581,41 @@ pub fn reload_volt(&self, volt: VoltMetadata) -> Result<()> {
         self.catalog_notification(PluginCatalogNotification::ReloadVolt(volt))
     }
 
+    pub fn rollback_volt(&self, volt: VoltMetadata) -> Result<()> {
+        self.catalog_notification(PluginCatalogNotification::RollbackVolt(volt))
+    }
+
     pub fn enable_volt(&self, volt: VoltInfo) -> Result<()> {
         self.catalog_notification(PluginCatalogNotification::EnableVolt(volt))
     }
 
+    pub fn grant_volt_permissions(
+        &self,
+        volt: VoltID,
+        permissions: VoltPermissions,
+    ) -> Result<()> {
+        self.catalog_notification(PluginCatalogNotification::GrantVoltPermissions(
+            volt,
+            permissions,
+        ))
+    }
+
+    pub fn revoke_volt_permissions(&self, volt: VoltInfo) -> Result<()> {
+        self.catalog_notification(PluginCatalogNotification::RevokeVoltPermissions(
+            volt,
+        ))
+    }
+
+    pub fn set_plugin_trace(
+        &self,
+        channel: PluginOutputChannel,
+        enabled: bool,
+    ) -> Result<()> {
+        self.catalog_notification(PluginCatalogNotification::SetPluginTrace(
+            channel, enabled,
+        ))
+    }
+
     pub fn dap_disconnected(&self, dap_id: DapId) -> Result<()> {
         self.catalog_notification(PluginCatalogNotification::DapDisconnected(dap_id))
     }
@@ -1404,10 +1648,16 @@ pub fn dap_process_id(
         })
     }
 
-    pub fn dap_continue(&self, dap_id: DapId, thread_id: ThreadId) -> Result<()> {
+    pub fn dap_continue(
+        &self,
+        dap_id: DapId,
+        thread_id: ThreadId,
+        single_thread: bool,
+    ) -> Result<()> {
         self.catalog_notification(PluginCatalogNotification::DapContinue {
             dap_id,
             thread_id,
+            single_thread,
         })
     }
 
@@ -1418,24 +1668,48 @@ pub fn dap_pause(&self, dap_id: DapId, thread_id: ThreadId) -> Result<()> {
         })
     }
 
-    pub fn dap_step_over(&self, dap_id: DapId, thread_id: ThreadId) -> Result<()> {
+    pub fn dap_step_over(
+        &self,
+        dap_id: DapId,
+        thread_id: ThreadId,
+        single_thread: bool,
+        granularity: Option<SteppingGranularity>,
+    ) -> Result<()> {
         self.catalog_notification(PluginCatalogNotification::DapStepOver {
             dap_id,
             thread_id,
+            single_thread,
+            granularity,
         })
     }
 
-    pub fn dap_step_into(&self, dap_id: DapId, thread_id: ThreadId) -> Result<()> {
+    pub fn dap_step_into(
+        &self,
+        dap_id: DapId,
+        thread_id: ThreadId,
+        single_thread: bool,
+        granularity: Option<SteppingGranularity>,
+    ) -> Result<()> {
         self.catalog_notification(PluginCatalogNotification::DapStepInto {
             dap_id,
             thread_id,
+            single_thread,
+            granularity,
         })
     }
 
-    pub fn dap_step_out(&self, dap_id: DapId, thread_id: ThreadId) -> Result<()> {
+    pub fn dap_step_out(
+        &self,
+        dap_id: DapId,
+        thread_id: ThreadId,
+        single_thread: bool,
+        granularity: Option<SteppingGranularity>,
+    ) -> Result<()> {
         self.catalog_notification(PluginCatalogNotification::DapStepOut {
             dap_id,
             thread_id,
+            single_thread,
+            granularity,
         })
     }
 
@@ -1506,6 +1780,103 @@ pub fn dap_get_scopes(
         }
     }
 
+    pub fn dap_set_variable(
+        &self,
+        dap_id: DapId,
+        reference: usize,
+        name: String,
+        value: String,
+        f: impl FnOnce(Result<dap_types::SetVariableResponse, RpcError>) + Send + 'static,
+    ) {
+        if let Err(err) = self.plugin_tx.send(PluginCatalogRpc::DapSetVariable {
+            dap_id,
+            reference,
+            name,
+            value,
+            f: Box::new(f),
+        }) {
+            tracing::error!("{:?}", err);
+        }
+    }
+
+    pub fn dap_set_expression(
+        &self,
+        dap_id: DapId,
+        frame_id: Option<usize>,
+        expression: String,
+        value: String,
+        f: impl FnOnce(Result<dap_types::SetExpressionResponse, RpcError>)
+        + Send
+        + 'static,
+    ) {
+        if let Err(err) = self.plugin_tx.send(PluginCatalogRpc::DapSetExpression {
+            dap_id,
+            frame_id,
+            expression,
+            value,
+            f: Box::new(f),
+        }) {
+            tracing::error!("{:?}", err);
+        }
+    }
+
+    pub fn dap_evaluate(
+        &self,
+        dap_id: DapId,
+        frame_id: Option<usize>,
+        expression: String,
+        context: Option<String>,
+        f: impl FnOnce(Result<dap_types::EvaluateResponse, RpcError>) + Send + 'static,
+    ) {
+        if let Err(err) = self.plugin_tx.send(PluginCatalogRpc::DapEvaluate {
+            dap_id,
+            frame_id,
+            expression,
+            context,
+            f: Box::new(f),
+        }) {
+            tracing::error!("{:?}", err);
+        }
+    }
+
+    pub fn dap_disassemble(
+        &self,
+        dap_id: DapId,
+        memory_reference: String,
+        instruction_offset: i64,
+        instruction_count: usize,
+        f: impl FnOnce(Result<dap_types::DisassembleResponse, RpcError>) + Send + 'static,
+    ) {
+        if let Err(err) = self.plugin_tx.send(PluginCatalogRpc::DapDisassemble {
+            dap_id,
+            memory_reference,
+            instruction_offset,
+            instruction_count,
+            f: Box::new(f),
+        }) {
+            tracing::error!("{:?}", err);
+        }
+    }
+
+    pub fn dap_read_memory(
+        &self,
+        dap_id: DapId,
+        memory_reference: String,
+        offset: i64,
+        count: usize,
+        f: impl FnOnce(Result<dap_types::ReadMemoryResponse, RpcError>) + Send + 'static,
+    ) {
+        if let Err(err) = self.plugin_tx.send(PluginCatalogRpc::DapReadMemory {
+            dap_id,
+            memory_reference,
+            offset,
+            count,
+            f: Box::new(f),
+        }) {
+            tracing::error!("{:?}", err);
+        }
+    }
+
     pub fn register_debugger_type(
         &self,
         debugger_type: String,
@@ -1546,56 +1917,159 @@ pub enum PluginNotification {
     },
 }
 
-pub fn volt_icon(volt: &VoltMetadata) -> Option<Vec<u8>> {
-    let dir = volt.dir.as_ref()?;
-    let icon = dir.join(volt.icon.as_ref()?);
-    std::fs::read(icon).ok()
+/// The results of a request sent to all the plugins, collected until all of
+/// them answered
+struct MergedResults<Resp, F> {
+    received: usize,
+    results: Vec<(PluginId, Resp)>,
+    last_err: Option<RpcError>,
+    /// Taken when the results are passed on, which is only done once
+    cb: Option<F>,
 }
 
-pub fn download_volt(volt: &VoltInfo) -> Result<VoltMetadata> {
-    let url = format!(
-        "https://plugins.lapce.dev/api/v1/plugins/{}/{}/{}/download",
-        volt.author, volt.name, volt.version
-    );
-
-    let resp = crate::get_url(url, None)?;
-    if !resp.status().is_success() {
-        return Err(anyhow!("can't download plugin"));
+impl<Resp, F> MergedResults<Resp, F>
+where
+    F: FnOnce(Result<Vec<(PluginId, Resp)>, RpcError>),
+{
+    fn finish(merged: &Mutex<Self>) {
+        let (cb, results, last_err) = {
+            let mut merged = merged.lock();
+            let Some(cb) = merged.cb.take() else {
+                return;
+            };
+            (
+                cb,
+                std::mem::take(&mut merged.results),
+                merged.last_err.take(),
+            )
+        };
+        match last_err {
+            Some(err) if results.is_empty() => cb(Err(err)),
+            _ => cb(Ok(results)),
+        }
     }
+}
 
-    // this is the s3 url
-    let url = resp.text()?;
+/// A deadline, and what's done once it has passed
+type MergeTimeout = (Instant, Box<dyn FnOnce() + Send>);
+
+/// The timeouts of the requests whose results are merged, which all run on the
+/// same thread rather than one for each request
+fn merge_timeouts() -> &'static Sender<MergeTimeout> {
+    static TIMEOUTS: OnceLock<Sender<MergeTimeout>> = OnceLock::new();
+    TIMEOUTS.get_or_init(|| {
+        let (tx, rx) = crossbeam_channel::unbounded::<MergeTimeout>();
+        thread::spawn(move || {
+            let mut pending: Vec<MergeTimeout> = Vec::new();
+            loop {
+                let received = match pending.iter().map(|(at, _)| *at).min() {
+                    Some(deadline) => rx.recv_deadline(deadline),
+                    None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                };
+                match received {
+                    Ok(timeout) => pending.push(timeout),
+                    Err(RecvTimeoutError::Timeout) => {
+                        let now = Instant::now();
+                        let (due, rest): (Vec<_>, Vec<_>) =
+                            pending.into_iter().partition(|(at, _)| *at <= now);
+                        pending = rest;
+                        for (_, f) in due {
+                            f();
+                        }
+                    }
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+        });
+        tx
+    })
+}
 
-    let mut resp = crate::get_url(url, None)?;
-    if !resp.status().is_success() {
-        return Err(anyhow!("can't download plugin"));
+/// The callback of a request sent to all the plugins, which calls `cb` with
+/// the results of the plugins that answered successfully once all of them
+/// answered, or once `timeout` has passed, so that a slow language server
+/// doesn't hold up the results of the others
+fn merge_results<Resp, F>(
+    request_sent: Arc<AtomicUsize>,
+    timeout: Duration,
+    cb: F,
+) -> impl FnOnce(PluginId, Result<Value, RpcError>) + Clone + Send + 'static
+where
+    Resp: DeserializeOwned + Send + 'static,
+    F: FnOnce(Result<Vec<(PluginId, Resp)>, RpcError>) + Send + 'static,
+{
+    let merged = Arc::new(Mutex::new(MergedResults {
+        received: 0,
+        results: Vec::new(),
+        last_err: None,
+        cb: Some(cb),
+    }));
+    {
+        // the results are gone once all the plugins answered
+        let merged = Arc::downgrade(&merged);
+        let finish: Box<dyn FnOnce() + Send> = Box::new(move || {
+            if let Some(merged) = Weak::upgrade(&merged) {
+                MergedResults::finish(&merged);
+            }
+        });
+        if let Err(err) = merge_timeouts().send((Instant::now() + timeout, finish)) {
+            tracing::error!("{:?}", err);
+        }
     }
+    move |plugin_id, result| {
+        let result = result.and_then(|value| {
+            serde_json::from_value::<Resp>(value).map_err(|_| RpcError {
+                code: 0,
+                message: "deserialize error".to_string(),
+            })
+        });
+        let all_received = {
+            let mut merged = merged.lock();
+            match result {
+                Ok(resp) => merged.results.push((plugin_id, resp)),
+                Err(err) => merged.last_err = Some(err),
+            }
+            merged.received += 1;
+            request_sent.load(Ordering::Acquire) == merged.received
+        };
+        if all_received {
+            MergedResults::finish(&merged);
+        }
+    }
+}
 
-    let is_zstd = resp
-        .headers()
-        .get("content-type")
-        .and_then(|v| v.to_str().ok())
-        == Some("application/zstd");
+pub fn volt_icon(volt: &VoltMetadata) -> Option<Vec<u8>> {
+    let dir = volt.dir.as_ref()?;
+    let icon = dir.join(volt.icon.as_ref()?);
+    std::fs::read(icon).ok()
+}
 
+pub fn download_volt(volt: &VoltInfo) -> Result<VoltMetadata> {
     let id = volt.id();
-    let plugin_dir = Directory::plugins_directory()
-        .ok_or_else(|| anyhow!("can't get plugin directory"))?
-        .join(id.to_string());
-    if let Err(err) = fs::remove_dir_all(&plugin_dir) {
-        tracing::error!("{:?}", err);
+    let plugins_dir = Directory::plugins_directory()
+        .ok_or_else(|| anyhow!("can't get plugin directory"))?;
+
+    // The volt is downloaded into a hidden folder first, so that a failed
+    // download leaves the installed version in place
+    let staging = plugins_dir.join(".downloading").join(id.to_string());
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
     }
-    fs::create_dir_all(&plugin_dir)?;
+    fs::create_dir_all(&staging)?;
+
+    Registry::of(volt).download(volt, &staging)?;
 
-    if is_zstd {
-        let tar = zstd::Decoder::new(&mut resp).unwrap();
-        let mut archive = Archive::new(tar);
-        archive.unpack(&plugin_dir)?;
-    } else {
-        let tar = GzDecoder::new(&mut resp);
-        let mut archive = Archive::new(tar);
-        archive.unpack(&plugin_dir)?;
+    let downloaded = load_volt(&staging)?;
+    if downloaded.version != volt.version {
+        return Err(anyhow!(
+            "the registry has version {} of {id} instead of {}",
+            downloaded.version,
+            volt.version
+        ));
     }
 
+    let plugin_dir = plugins_dir.join(id.to_string());
+    replace_installed_volt(&staging, &plugin_dir)?;
     let meta = load_volt(&plugin_dir)?;
     Ok(meta)
 }
@@ -1604,6 +2078,7 @@ pub fn install_volt(
     catalog_rpc: PluginCatalogRpcHandler,
     workspace: Option<PathBuf>,
     configurations: Option<HashMap<String, serde_json::Value>>,
+    granted: Option<VoltPermissions>,
     volt: VoltInfo,
 ) -> Result<()> {
     let download_volt_result = download_volt(&volt);
@@ -1616,9 +2091,46 @@ pub fn install_volt(
     let local_catalog_rpc = catalog_rpc.clone();
     let local_meta = meta.clone();
 
-    if let Err(err) =
-        start_volt(workspace, configurations, local_catalog_rpc, local_meta)
-    {
+    if !volt_permitted(&meta, granted.as_ref()) {
+        catalog_rpc.core_rpc.volt_permissions_required(meta.clone());
+    } else if let Err(err) = start_volt(
+        workspace,
+        configurations,
+        local_catalog_rpc,
+        local_meta,
+        granted.unwrap_or_default(),
+    ) {
+        tracing::error!("{:?}", err);
+    }
+    let icon = volt_icon(&meta);
+    catalog_rpc.core_rpc.volt_installed(meta, icon);
+    Ok(())
+}
+
+/// Go back to the version of the volt that was installed before the current
+/// one, and start it again
+pub fn rollback_volt(
+    catalog_rpc: PluginCatalogRpcHandler,
+    workspace: Option<PathBuf>,
+    configurations: Option<HashMap<String, serde_json::Value>>,
+    granted: Option<VoltPermissions>,
+    volt: VoltMetadata,
+) -> Result<()> {
+    let dir = volt
+        .dir
+        .as_ref()
+        .ok_or_else(|| anyhow!("plugin meta doesn't have dir"))?;
+    let meta = rollback_volt_dir(dir)?;
+
+    if !volt_permitted(&meta, granted.as_ref()) {
+        catalog_rpc.core_rpc.volt_permissions_required(meta.clone());
+    } else if let Err(err) = start_volt(
+        workspace,
+        configurations,
+        catalog_rpc.clone(),
+        meta.clone(),
+        granted.unwrap_or_default(),
+    ) {
         tracing::error!("{:?}", err);
     }
     let icon = volt_icon(&meta);
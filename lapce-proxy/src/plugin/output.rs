@@ -0,0 +1,175 @@
+//! The output of volts and language servers for the Output panel: what they
+//! write to stderr, their `window/logMessage`s and, when it's turned on, a
+//! trace of the JSON-RPC messages with how long each request took.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Instant,
+};
+
+use lapce_rpc::{
+    core::CoreRpcHandler,
+    plugin::{PluginOutputChannel, PluginOutputKind, PluginOutputLine, VoltID},
+};
+use lsp_types::MessageType;
+use parking_lot::Mutex;
+use serde_json::Value;
+
+/// How much of the params or result of a message is shown in the trace, a
+/// `didOpen` has the whole file in it
+const MAX_TRACE_BODY_LEN: usize = 2000;
+
+#[derive(Clone)]
+pub struct PluginOutput {
+    core_rpc: CoreRpcHandler,
+    channel: PluginOutputChannel,
+    trace: Arc<AtomicBool>,
+    /// The method of each request that hasn't been answered yet and when it
+    /// was sent or received, by whether it was sent and its id
+    pending: Arc<Mutex<HashMap<(bool, String), (String, Instant)>>>,
+}
+
+impl PluginOutput {
+    pub fn new(core_rpc: CoreRpcHandler, volt: VoltID, name: String) -> Self {
+        Self {
+            core_rpc,
+            channel: PluginOutputChannel { volt, name },
+            trace: Arc::new(AtomicBool::new(false)),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn channel(&self) -> &PluginOutputChannel {
+        &self.channel
+    }
+
+    pub fn stderr(&self, text: &str) {
+        self.send(PluginOutputKind::Stderr, text.trim_end().to_string(), None);
+    }
+
+    pub fn log(&self, typ: MessageType, text: &str) {
+        self.send(
+            PluginOutputKind::Log(typ),
+            text.trim_end().to_string(),
+            None,
+        );
+    }
+
+    pub fn is_tracing(&self) -> bool {
+        self.trace.load(Ordering::Relaxed)
+    }
+
+    pub fn set_trace(&self, enabled: bool) {
+        self.trace.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            self.pending.lock().clear();
+        }
+    }
+
+    /// Trace a message that's about to be written to the plugin
+    pub fn trace_sent(&self, message: &str) {
+        self.trace(true, message);
+    }
+
+    /// Trace a message that was read from the plugin
+    pub fn trace_received(&self, message: &str) {
+        self.trace(false, message);
+    }
+
+    fn trace(&self, sent: bool, message: &str) {
+        if !self.is_tracing() {
+            return;
+        }
+        let Ok(value) = serde_json::from_str::<Value>(message) else {
+            return;
+        };
+        let Some((text, duration)) = self.trace_text(sent, &value) else {
+            return;
+        };
+        let kind = if sent {
+            PluginOutputKind::Sent
+        } else {
+            PluginOutputKind::Received
+        };
+        self.send(kind, text.trim_end().to_string(), duration);
+    }
+
+    /// The text of the trace of a message, with how long the request took if
+    /// it's a response
+    fn trace_text(
+        &self,
+        sent: bool,
+        value: &Value,
+    ) -> Option<(String, Option<u64>)> {
+        let id = value.get("id").map(|id| match id {
+            Value::String(id) => id.clone(),
+            id => id.to_string(),
+        });
+        let method = value.get("method").and_then(|m| m.as_str());
+        match (method, id) {
+            (Some(method), Some(id)) => {
+                self.pending.lock().insert(
+                    (sent, id.clone()),
+                    (method.to_string(), Instant::now()),
+                );
+                let body = trace_body(value.get("params"));
+                Some((format!("request {method} ({id}) {body}"), None))
+            }
+            (Some(method), None) => {
+                let body = trace_body(value.get("params"));
+                Some((format!("notification {method} {body}"), None))
+            }
+            (None, Some(id)) => {
+                // The request was the other way around
+                let request = self.pending.lock().remove(&(!sent, id.clone()));
+                let (method, duration) = match request {
+                    Some((method, time)) => (
+                        format!("{method} "),
+                        Some(time.elapsed().as_millis() as u64),
+                    ),
+                    None => (String::new(), None),
+                };
+                let body = match value.get("error") {
+                    Some(error) => format!("error {}", trace_body(Some(error))),
+                    None => trace_body(value.get("result")),
+                };
+                Some((format!("response {method}({id}) {body}"), duration))
+            }
+            (None, None) => None,
+        }
+    }
+
+    fn send(&self, kind: PluginOutputKind, text: String, duration: Option<u64>) {
+        self.core_rpc.plugin_output(
+            self.channel.clone(),
+            PluginOutputLine {
+                kind,
+                text,
+                duration,
+            },
+        );
+    }
+}
+
+fn trace_body(body: Option<&Value>) -> String {
+    let Some(body) = body else {
+        return String::new();
+    };
+    let mut body = body.to_string();
+    if body.len() > MAX_TRACE_BODY_LEN {
+        let mut end = MAX_TRACE_BODY_LEN;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        body.truncate(end);
+        body.push('…');
+    }
+    body
+}
@@ -0,0 +1,90 @@
+use lapce_rpc::{
+    core::{CoreNotification, CoreRpc, CoreRpcHandler},
+    plugin::{PluginOutputKind, PluginOutputLine, VoltID},
+};
+use lsp_types::MessageType;
+
+use super::PluginOutput;
+
+fn output_lines(core_rpc: &CoreRpcHandler) -> Vec<PluginOutputLine> {
+    core_rpc
+        .rx()
+        .try_iter()
+        .filter_map(|rpc| match rpc {
+            CoreRpc::Notification(notification) => match *notification {
+                CoreNotification::PluginOutput { line, .. } => Some(line),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+fn plugin_output(core_rpc: &CoreRpcHandler) -> PluginOutput {
+    PluginOutput::new(
+        core_rpc.clone(),
+        VoltID {
+            author: "lapce".to_string(),
+            name: "rust".to_string(),
+        },
+        "Rust".to_string(),
+    )
+}
+
+#[test]
+fn test_plugin_output_logs() {
+    let core_rpc = CoreRpcHandler::new();
+    let output = plugin_output(&core_rpc);
+
+    output.stderr("thread 'main' panicked\n");
+    output.log(MessageType::WARNING, "no Cargo.toml found");
+    assert_eq!(
+        output_lines(&core_rpc),
+        vec![
+            PluginOutputLine {
+                kind: PluginOutputKind::Stderr,
+                text: "thread 'main' panicked".to_string(),
+                duration: None,
+            },
+            PluginOutputLine {
+                kind: PluginOutputKind::Log(MessageType::WARNING),
+                text: "no Cargo.toml found".to_string(),
+                duration: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_plugin_output_trace() {
+    let core_rpc = CoreRpcHandler::new();
+    let output = plugin_output(&core_rpc);
+
+    // Nothing is traced until it's turned on
+    output.trace_sent(r#"{"jsonrpc":"2.0","id":1,"method":"shutdown"}"#);
+    assert!(output_lines(&core_rpc).is_empty());
+
+    output.set_trace(true);
+    output.trace_sent(
+        r#"{"jsonrpc":"2.0","id":2,"method":"textDocument/hover","params":{}}"#,
+    );
+    output.trace_received(r#"{"jsonrpc":"2.0","method":"$/progress"}"#);
+    output.trace_received(r#"{"jsonrpc":"2.0","id":2,"result":null}"#);
+    // A response to a request from the plugin doesn't match a sent request
+    // with the same id
+    output.trace_sent(r#"{"jsonrpc":"2.0","id":2,"result":null}"#);
+
+    let lines = output_lines(&core_rpc);
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0].kind, PluginOutputKind::Sent);
+    assert_eq!(lines[0].text, "request textDocument/hover (2) {}");
+    assert_eq!(lines[0].duration, None);
+    assert_eq!(lines[1].kind, PluginOutputKind::Received);
+    assert_eq!(lines[1].text, "notification $/progress");
+    assert_eq!(lines[2].kind, PluginOutputKind::Received);
+    assert_eq!(lines[2].text, "response textDocument/hover (2) null");
+    assert!(lines[2].duration.is_some());
+    assert_eq!(lines[3].kind, PluginOutputKind::Sent);
+    assert_eq!(lines[3].text, "response (2) null");
+    assert_eq!(lines[3].duration, None);
+}
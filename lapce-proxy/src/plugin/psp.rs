@@ -18,7 +18,12 @@
 use lapce_rpc::{
     RpcError,
     core::{CoreRpcHandler, ServerStatusParams},
-    plugin::{PluginId, VoltID},
+    plugin::{
+        PluginCommand, PluginId, PluginStatusItem, RegisterPluginCommand,
+        RemovePluginStatusItem, RemovePluginStatusItemParams, SetPluginStatusItem,
+        UnregisterPluginCommand, UnregisterPluginCommandParams, VoltID,
+        VoltPermissions,
+    },
     style::{LineStyle, Style},
 };
 use lapce_xi_rope::{Rope, RopeDelta};
@@ -26,17 +31,17 @@
     CancelParams, CodeActionProviderCapability, DidChangeTextDocumentParams,
     DidSaveTextDocumentParams, DocumentSelector, FoldingRangeProviderCapability,
     HoverProviderCapability, ImplementationProviderCapability, InitializeResult,
-    LogMessageParams, MessageType, OneOf, ProgressParams, PublishDiagnosticsParams,
-    Range, Registration, RegistrationParams, SemanticTokens, SemanticTokensLegend,
-    SemanticTokensServerCapabilities, ServerCapabilities, ShowMessageParams,
-    TextDocumentContentChangeEvent, TextDocumentIdentifier,
-    TextDocumentSaveRegistrationOptions, TextDocumentSyncCapability,
-    TextDocumentSyncKind, TextDocumentSyncSaveOptions,
-    VersionedTextDocumentIdentifier,
+    LogMessageParams, LogTraceParams, MessageType, OneOf, ProgressParams,
+    PublishDiagnosticsParams, Range, Registration, RegistrationParams,
+    SemanticTokens, SemanticTokensLegend, SemanticTokensServerCapabilities,
+    ServerCapabilities, ShowMessageParams, TextDocumentContentChangeEvent,
+    TextDocumentIdentifier, TextDocumentSaveRegistrationOptions,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncSaveOptions,
+    Url, VersionedTextDocumentIdentifier,
     notification::{
         Cancel, DidChangeTextDocument, DidOpenTextDocument, DidSaveTextDocument,
-        Initialized, LogMessage, Notification, Progress, PublishDiagnostics,
-        ShowMessage,
+        Initialized, LogMessage, LogTrace, Notification, Progress,
+        PublishDiagnostics, ShowMessage,
     },
     request::{
         CallHierarchyIncomingCalls, CallHierarchyPrepare, CodeActionRequest,
@@ -63,6 +68,7 @@
 use super::{
     PluginCatalogRpcHandler,
     lsp::{DocumentFilter, LspClient},
+    output::PluginOutput,
 };
 
 pub enum ResponseHandler<Resp, Error> {
@@ -174,6 +180,7 @@ pub struct PluginServerRpcHandler {
     pub spawned_by: Option<PluginId>,
     pub plugin_id: PluginId,
     pub volt_id: VoltID,
+    pub output: PluginOutput,
     rpc_tx: Sender<PluginServerRpc>,
     rpc_rx: Receiver<PluginServerRpc>,
     io_tx: Sender<JsonRpc>,
@@ -274,6 +281,7 @@ pub fn new(
         volt_id: VoltID,
         spawned_by: Option<PluginId>,
         plugin_id: Option<PluginId>,
+        output: PluginOutput,
         io_tx: Sender<JsonRpc>,
     ) -> Self {
         let (rpc_tx, rpc_rx) = crossbeam_channel::unbounded();
@@ -281,6 +289,7 @@ pub fn new(
         let rpc = Self {
             spawned_by,
             volt_id,
+            output,
             plugin_id: plugin_id.unwrap_or_else(PluginId::next),
             rpc_tx,
             rpc_rx,
@@ -310,8 +319,18 @@ fn send_server_request(
             let mut pending = self.server_pending.lock();
             pending.insert(id.clone(), rh);
         }
-        let msg = JsonRpc::request_with_params(id, method, params);
-        self.send_server_rpc(msg);
+        let msg = JsonRpc::request_with_params(id.clone(), method, params);
+        if let Err(err) = self.io_tx.send(msg) {
+            tracing::error!("{:?}", err);
+            // the plugin is gone, so the request would never get a response
+            self.handle_server_response(
+                id,
+                Err(RpcError {
+                    code: 0,
+                    message: "plugin stopped".to_string(),
+                }),
+            );
+        }
     }
 
     fn send_server_notification(&self, method: &str, params: Params) {
@@ -441,6 +460,18 @@ pub fn handle_server_response(&self, id: Id, result: Result<Value, RpcError>) {
         }
     }
 
+    /// Answer the requests the plugin will never respond to because it
+    /// crashed with an error, so that nothing keeps waiting for them
+    pub fn fail_pending_requests(&self, message: &str) {
+        let pending: Vec<_> = self.server_pending.lock().drain().collect();
+        for (_, handler) in pending {
+            handler.invoke(Err(RpcError {
+                code: 0,
+                message: message.to_string(),
+            }));
+        }
+    }
+
     pub fn shutdown(&self) {
         // to kill lsp
         self.handle_rpc(PluginServerRpc::Handler(
@@ -623,6 +654,9 @@ struct ServerRegistrations {
 pub struct PluginHostHandler {
     volt_id: VoltID,
     volt_display_name: String,
+    /// What the plugin has been granted by the user, checked before running
+    /// programs on its behalf
+    permissions: VoltPermissions,
     pwd: Option<PathBuf>,
     pub(crate) workspace: Option<PathBuf>,
     document_selector: Vec<DocumentFilter>,
@@ -644,6 +678,7 @@ pub fn new(
         pwd: Option<PathBuf>,
         volt_id: VoltID,
         volt_display_name: String,
+        permissions: VoltPermissions,
         document_selector: DocumentSelector,
         core_rpc: CoreRpcHandler,
         server_rpc: PluginServerRpcHandler,
@@ -658,6 +693,7 @@ pub fn new(
             workspace,
             volt_id,
             volt_display_name,
+            permissions,
             document_selector,
             core_rpc,
             catalog_rpc,
@@ -939,6 +975,44 @@ fn register_capability(&mut self, registration: Registration) -> Result<()> {
         Ok(())
     }
 
+    /// Whether the program is in the `executables` permission, by name or by
+    /// path, where `${volt}` stands for the volt's directory
+    fn executable_permitted(&self, program: &str) -> bool {
+        self.permissions.executables.iter().any(|executable| {
+            if executable == program {
+                return true;
+            }
+            match (executable.strip_prefix("${volt}"), self.pwd.as_ref()) {
+                (Some(rest), Some(pwd)) => {
+                    let rest = rest.trim_start_matches(['/', '\\']);
+                    !rest.is_empty() && pwd.join(rest) == Path::new(program)
+                }
+                _ => false,
+            }
+        })
+    }
+
+    /// Any language server has to be in the `executables` permission, even one
+    /// in the volt's own directory, which the volt can write to.
+    fn check_lsp_server_permitted(&self, server_uri: &Url) -> Result<()> {
+        let server = match server_uri.scheme() {
+            "file" => server_uri
+                .to_file_path()
+                .map_err(|_| anyhow!("invalid server uri {server_uri}"))?
+                .to_string_lossy()
+                .to_string(),
+            _ => server_uri.path().to_string(),
+        };
+        if self.executable_permitted(&server) {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "{} isn't permitted to start {server}",
+                self.volt_display_name
+            ))
+        }
+    }
+
     pub fn handle_request(
         &mut self,
         _id: Id,
@@ -970,6 +1044,13 @@ pub fn process_request(
             ExecuteProcess::METHOD => {
                 let params: ExecuteProcessParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
+                if !self.executable_permitted(&params.program) {
+                    return Err(anyhow!(
+                        "{} isn't permitted to execute {}",
+                        self.volt_display_name,
+                        params.program
+                    ));
+                }
                 let output = std::process::Command::new(params.program)
                     .args(params.args)
                     .output()?;
@@ -993,6 +1074,7 @@ pub fn process_request(
             StartLspServer::METHOD => {
                 let params: StartLspServerParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
+                self.check_lsp_server_permitted(&params.server_uri)?;
                 let workspace = self.workspace.clone();
                 let pwd = self.pwd.clone();
                 let catalog_rpc = self.catalog_rpc.clone();
@@ -1016,6 +1098,7 @@ pub fn process_request(
                         params.server_uri,
                         params.server_args,
                         params.options,
+                        None,
                     ) {
                         tracing::error!("{:?}", err);
                     }
@@ -1107,6 +1190,7 @@ pub fn handle_notification(
 
                 let params: StartLspServerParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
+                self.check_lsp_server_permitted(&params.server_uri)?;
                 let workspace = self.workspace.clone();
                 let pwd = self.pwd.clone();
                 let catalog_rpc = self.catalog_rpc.clone();
@@ -1125,6 +1209,7 @@ pub fn handle_notification(
                         params.server_uri,
                         params.server_args,
                         params.options,
+                        None,
                     ) {
                         tracing::error!("{:?}", err);
                     }
@@ -1149,6 +1234,7 @@ pub fn handle_notification(
             LogMessage::METHOD => {
                 let message: LogMessageParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
+                self.server_rpc.output.log(message.typ, &message.message);
                 self.catalog_rpc.core_rpc.log_message(
                     message,
                     format!(
@@ -1157,11 +1243,49 @@ pub fn handle_notification(
                     ),
                 );
             }
+            LogTrace::METHOD => {
+                let trace: LogTraceParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                let message = match trace.verbose {
+                    Some(verbose) => format!("{}\n{verbose}", trace.message),
+                    None => trace.message,
+                };
+                self.server_rpc.output.log(MessageType::LOG, &message);
+            }
             Cancel::METHOD => {
                 let params: CancelParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
                 self.catalog_rpc.core_rpc.cancel(params);
             }
+            RegisterPluginCommand::METHOD => {
+                let command: PluginCommand =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.core_rpc.plugin_command_registered(
+                    self.server_rpc.plugin_id,
+                    self.volt_id.clone(),
+                    command,
+                );
+            }
+            UnregisterPluginCommand::METHOD => {
+                let params: UnregisterPluginCommandParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.core_rpc.plugin_command_unregistered(
+                    self.volt_id.clone(),
+                    params.command,
+                );
+            }
+            SetPluginStatusItem::METHOD => {
+                let item: PluginStatusItem =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.core_rpc
+                    .plugin_status_item_set(self.volt_id.clone(), item);
+            }
+            RemovePluginStatusItem::METHOD => {
+                let params: RemovePluginStatusItemParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.core_rpc
+                    .plugin_status_item_removed(self.volt_id.clone(), params.id);
+            }
             "experimental/serverStatus" => {
                 let param: ServerStatusParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
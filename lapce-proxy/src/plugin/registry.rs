@@ -0,0 +1,395 @@
+//! Registries that volts are searched for and installed from. A registry is
+//! either a server with the same api as `plugins.lapce.dev`, such as a company
+//! mirror, or a local directory laid out like the plugins directory, with an
+//! unpacked volt in each folder.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    fs,
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+use flate2::read::GzDecoder;
+use lapce_core::directory::Directory;
+use lapce_rpc::plugin::{VoltInfo, VoltMetadata, VoltPreviousVersion};
+use serde::{Deserialize, Serialize};
+use tar::Archive;
+use url::Url;
+
+use super::{volt_icon, wasi::load_volt};
+
+pub const DEFAULT_REGISTRY: &str = "https://plugins.lapce.dev";
+
+/// How many volts a local registry returns for each query
+const LOCAL_PAGE_SIZE: usize = 50;
+
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// The hidden folder of the plugins directory where the version of each volt
+/// that was installed before the current one is kept
+const PREVIOUS_VOLTS_DIR: &str = ".previous";
+
+const CHANGELOG: &str = "CHANGELOG.md";
+
+#[derive(Deserialize, Serialize)]
+pub struct VoltsInfo {
+    pub plugins: Vec<VoltInfo>,
+    pub total: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Registry {
+    /// A server with the `plugins.lapce.dev` api, by its base url
+    Remote(String),
+    /// A directory with a folder for each volt
+    Local(PathBuf),
+}
+
+impl Registry {
+    /// Parse a registry from the `plugin-registries` setting, `file://` urls
+    /// and absolute paths are local registries.
+    pub fn new(registry: &str) -> Self {
+        if let Some(path) = Url::parse(registry)
+            .ok()
+            .filter(|url| url.scheme() == "file")
+            .and_then(|url| url.to_file_path().ok())
+        {
+            return Registry::Local(path);
+        }
+        let path = Path::new(registry);
+        if path.is_absolute() {
+            Registry::Local(path.to_path_buf())
+        } else {
+            Registry::Remote(registry.trim_end_matches('/').to_string())
+        }
+    }
+
+    /// The registry the volt was found in
+    pub fn of(volt: &VoltInfo) -> Self {
+        Self::new(volt.registry.as_deref().unwrap_or(DEFAULT_REGISTRY))
+    }
+
+    pub fn url(&self) -> String {
+        match self {
+            Registry::Remote(url) => url.clone(),
+            Registry::Local(dir) => Url::from_directory_path(dir)
+                .map(String::from)
+                .unwrap_or_else(|_| format!("file://{}", dir.display())),
+        }
+    }
+
+    pub fn query(&self, query: &str, offset: usize) -> Result<VoltsInfo> {
+        let mut volts = match self {
+            Registry::Remote(url) => {
+                let url = format!("{url}/api/v1/plugins?q={query}&offset={offset}");
+                crate::get_url(url, None)?.json()?
+            }
+            Registry::Local(dir) => {
+                let query = query.to_lowercase();
+                let mut plugins: Vec<VoltInfo> = local_volts(dir)
+                    .iter()
+                    .map(VoltMetadata::info)
+                    .filter(|info| {
+                        query.is_empty()
+                            || [
+                                &info.name,
+                                &info.display_name,
+                                &info.author,
+                                &info.description,
+                            ]
+                            .iter()
+                            .any(|s| s.to_lowercase().contains(&query))
+                    })
+                    .collect();
+                plugins.sort_by(|a, b| a.display_name.cmp(&b.display_name));
+                let total = plugins.len();
+                VoltsInfo {
+                    plugins: plugins
+                        .into_iter()
+                        .skip(offset)
+                        .take(LOCAL_PAGE_SIZE)
+                        .collect(),
+                    total,
+                }
+            }
+        };
+        for volt in volts.plugins.iter_mut() {
+            volt.registry = Some(self.url());
+        }
+        Ok(volts)
+    }
+
+    pub fn latest(&self, author: &str, name: &str) -> Result<VoltInfo> {
+        let mut volt: VoltInfo = match self {
+            Registry::Remote(url) => crate::get_url(
+                format!("{url}/api/v1/plugins/{author}/{name}/latest"),
+                None,
+            )?
+            .json()?,
+            Registry::Local(dir) => {
+                load_volt(&dir.join(format!("{author}.{name}")))?.info()
+            }
+        };
+        volt.registry = Some(self.url());
+        Ok(volt)
+    }
+
+    /// The url the icon of the volt is downloaded from, `None` for local
+    /// registries since there's nothing to download
+    pub fn icon_url(&self, volt: &VoltInfo) -> Option<String> {
+        match self {
+            Registry::Remote(url) => Some(format!(
+                "{url}/api/v1/plugins/{}/{}/{}/icon?id={}",
+                volt.author, volt.name, volt.version, volt.updated_at_ts
+            )),
+            Registry::Local(_) => None,
+        }
+    }
+
+    pub fn icon(&self, volt: &VoltInfo) -> Result<Vec<u8>> {
+        match self {
+            Registry::Remote(_) => {
+                let url = self.icon_url(volt).unwrap_or_default();
+                let resp = crate::get_url(&url, None)?;
+                if !resp.status().is_success() {
+                    return Err(anyhow!("can't download icon"));
+                }
+                Ok(resp.bytes()?.to_vec())
+            }
+            Registry::Local(dir) => {
+                let meta = load_volt(&dir.join(volt.id().to_string()))?;
+                volt_icon(&meta).ok_or_else(|| anyhow!("volt doesn't have an icon"))
+            }
+        }
+    }
+
+    /// The readme of the volt, `None` if it doesn't have one
+    pub fn readme(&self, volt: &VoltInfo) -> Result<Option<String>> {
+        match self {
+            Registry::Remote(url) => {
+                let url = format!(
+                    "{url}/api/v1/plugins/{}/{}/{}/readme",
+                    volt.author, volt.name, volt.version
+                );
+                let resp = crate::get_url(&url, None)?;
+                if resp.status() != 200 {
+                    return Ok(None);
+                }
+                Ok(Some(resp.text()?))
+            }
+            Registry::Local(dir) => Ok(fs::read_to_string(
+                dir.join(volt.id().to_string()).join("README.md"),
+            )
+            .ok()),
+        }
+    }
+
+    /// Download the volt into `dir`, which should be empty
+    pub fn download(&self, volt: &VoltInfo, dir: &Path) -> Result<()> {
+        match self {
+            Registry::Remote(url) => {
+                let url = format!(
+                    "{url}/api/v1/plugins/{}/{}/{}/download",
+                    volt.author, volt.name, volt.version
+                );
+
+                let resp = crate::get_url(url, None)?;
+                if !resp.status().is_success() {
+                    return Err(anyhow!("can't download plugin"));
+                }
+
+                // this is the s3 url
+                let url = resp.text()?;
+
+                let resp = crate::get_url(url, None)?;
+                if !resp.status().is_success() {
+                    return Err(anyhow!("can't download plugin"));
+                }
+                unpack_volt(resp, dir)
+            }
+            Registry::Local(registry) => {
+                copy_volt(&registry.join(volt.id().to_string()), dir)
+            }
+        }
+    }
+}
+
+/// The volts of a local registry, skipping any folder that isn't a volt
+fn local_volts(dir: &Path) -> Vec<VoltMetadata> {
+    dir.read_dir()
+        .ok()
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if !path.is_dir() {
+                return None;
+            }
+            load_volt(&path).ok()
+        })
+        .collect()
+}
+
+/// All the files of an unpacked volt, relative to its folder
+pub fn volt_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in walkdir::WalkDir::new(dir) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            files.push(entry.path().strip_prefix(dir)?.to_path_buf());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn copy_volt(from: &Path, to: &Path) -> Result<()> {
+    for file in volt_files(from)? {
+        let target = to.join(&file);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(from.join(&file), target)?;
+    }
+    Ok(())
+}
+
+/// Unpack a volt archive, compressed with either zstd or gzip, into `dir`
+fn unpack_volt(reader: impl Read, dir: &Path) -> Result<()> {
+    let mut reader = BufReader::new(reader);
+    if reader.fill_buf()?.starts_with(&ZSTD_MAGIC) {
+        Archive::new(zstd::Decoder::with_buffer(reader)?).unpack(dir)?;
+    } else {
+        Archive::new(GzDecoder::new(reader)).unpack(dir)?;
+    }
+    Ok(())
+}
+
+/// Install a packaged volt into the plugins directory, replacing the installed
+/// version of the volt if there's one.
+pub fn install_volt_archive(archive: impl Read) -> Result<VoltMetadata> {
+    let plugins_dir = Directory::plugins_directory()
+        .ok_or_else(|| anyhow!("can't get plugin directory"))?;
+
+    // The volt id is only known once its volt.toml can be read, so it's put
+    // in a hidden folder first, which `find_all_volts` skips.
+    let staging = plugins_dir.join(".installing");
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir_all(&staging)?;
+    unpack_volt(archive, &staging)?;
+
+    let meta = load_volt(&staging)?;
+    let plugin_dir = plugins_dir.join(meta.id().to_string());
+    replace_installed_volt(&staging, &plugin_dir)?;
+    load_volt(&plugin_dir)
+}
+
+/// Move the volt unpacked in `staging` to `plugin_dir`. The version that was
+/// installed there is kept as the previous version of the volt, unless it's
+/// the same version.
+pub fn replace_installed_volt(staging: &Path, plugin_dir: &Path) -> Result<()> {
+    if plugin_dir.exists() {
+        let installed = load_volt(plugin_dir).ok().map(|meta| meta.version);
+        let new = load_volt(staging)?.version;
+        match previous_volt_dir(plugin_dir) {
+            Some(previous) if installed.is_some_and(|v| v != new) => {
+                if previous.exists() {
+                    fs::remove_dir_all(&previous)?;
+                }
+                if let Some(parent) = previous.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::rename(plugin_dir, &previous)?;
+            }
+            _ => fs::remove_dir_all(plugin_dir)?,
+        }
+    }
+    fs::rename(staging, plugin_dir)?;
+    Ok(())
+}
+
+/// Where the previous version of the volt installed in `dir` is kept
+fn previous_volt_dir(dir: &Path) -> Option<PathBuf> {
+    Some(
+        dir.parent()?
+            .join(PREVIOUS_VOLTS_DIR)
+            .join(dir.file_name()?),
+    )
+}
+
+/// The previous version of the volt installed in `dir`, with the changelog
+/// entries that are new since then
+pub fn previous_volt_version(dir: &Path) -> Option<VoltPreviousVersion> {
+    let previous_dir = previous_volt_dir(dir)?;
+    let previous = load_volt(&previous_dir).ok()?;
+    let changes = fs::read_to_string(dir.join(CHANGELOG))
+        .ok()
+        .map(|new| {
+            let old =
+                fs::read_to_string(previous_dir.join(CHANGELOG)).unwrap_or_default();
+            changelog_diff(&old, &new)
+        })
+        .filter(|changes| !changes.is_empty());
+    Some(VoltPreviousVersion {
+        version: previous.version,
+        changes,
+    })
+}
+
+/// Swap the volt installed in `dir` with its previous version, so that the
+/// rollback can itself be undone
+pub fn rollback_volt_dir(dir: &Path) -> Result<VoltMetadata> {
+    let previous = previous_volt_dir(dir)
+        .filter(|previous| previous.exists())
+        .ok_or_else(|| anyhow!("there's no previous version of the plugin"))?;
+    let mut swap = previous.clone().into_os_string();
+    swap.push(".swap");
+    let swap = PathBuf::from(swap);
+    fs::rename(dir, &swap)?;
+    fs::rename(&previous, dir)?;
+    fs::rename(&swap, &previous)?;
+    load_volt(dir)
+}
+
+/// The entries of the changelog `new` that aren't in `old`. Entries start at
+/// `## ` headings, usually one for each version, and a changelog without any
+/// is compared line by line instead.
+pub fn changelog_diff(old: &str, new: &str) -> String {
+    fn is_heading(line: &str) -> bool {
+        line.trim_start().starts_with("## ")
+    }
+
+    if !new.lines().any(is_heading) {
+        let old: Vec<&str> = old.lines().map(str::trim).collect();
+        return new
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !old.contains(&line.trim()))
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+
+    let old_headings: Vec<&str> = old
+        .lines()
+        .filter(|line| is_heading(line))
+        .map(str::trim)
+        .collect();
+    let mut changes = Vec::new();
+    // Whether the lines belong to an entry that's new, the lines before the
+    // first entry are the title of the changelog
+    let mut in_new_entry = false;
+    for line in new.lines() {
+        if is_heading(line) {
+            in_new_entry = !old_headings.contains(&line.trim());
+        }
+        if in_new_entry {
+            changes.push(line);
+        }
+    }
+    changes.join("\n").trim().to_string()
+}
@@ -0,0 +1,154 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    DEFAULT_REGISTRY, Registry, changelog_diff, load_volt, replace_installed_volt,
+    rollback_volt_dir, volt_files,
+};
+
+fn test_plugins_dir() -> PathBuf {
+    std::env::current_dir()
+        .expect("Can't get \"lapce-proxy\" directory")
+        .join("src")
+        .join("plugin")
+        .join("wasi")
+        .join("plugins")
+}
+
+#[test]
+fn test_registry_new() {
+    assert_eq!(
+        Registry::new(DEFAULT_REGISTRY),
+        Registry::Remote("https://plugins.lapce.dev".to_string())
+    );
+    assert_eq!(
+        Registry::new("https://mirror.example.com/volts/"),
+        Registry::Remote("https://mirror.example.com/volts".to_string())
+    );
+    assert_eq!(
+        Registry::new("file:///srv/volts"),
+        Registry::Local(PathBuf::from("/srv/volts"))
+    );
+    assert_eq!(
+        Registry::new("file:///srv/my%20volts"),
+        Registry::Local(PathBuf::from("/srv/my volts"))
+    );
+
+    let dir = test_plugins_dir();
+    assert_eq!(
+        Registry::new(dir.to_str().unwrap()),
+        Registry::Local(dir.clone())
+    );
+    assert_eq!(
+        Registry::new(&Registry::Local(dir.clone()).url()),
+        Registry::Local(dir)
+    );
+}
+
+#[test]
+fn test_local_registry_query() {
+    let registry = Registry::Local(test_plugins_dir());
+
+    let volts = registry.query("", 0).unwrap();
+    assert_eq!(volts.total, 3);
+    assert_eq!(volts.plugins.len(), 3);
+    assert!(
+        volts
+            .plugins
+            .iter()
+            .all(|volt| volt.registry == Some(registry.url()))
+    );
+
+    let volts = registry.query("USEFUL", 0).unwrap();
+    assert_eq!(volts.total, 3);
+    let volts = registry.query("useful", 2).unwrap();
+    assert_eq!(volts.plugins.len(), 1);
+
+    let volts = registry.query("some_author.", 0).unwrap();
+    assert_eq!(volts.total, 1);
+
+    let volts = registry.query("not a plugin", 0).unwrap();
+    assert_eq!(volts.total, 0);
+    assert!(volts.plugins.is_empty());
+}
+
+#[test]
+fn test_volt_files() {
+    let dir = test_plugins_dir().join("some_author.test-plugin-two");
+    assert_eq!(
+        volt_files(&dir).unwrap(),
+        ["Light.svg", "Light.toml", "lapce.wasm", "volt.toml"]
+            .map(PathBuf::from)
+            .to_vec()
+    );
+}
+
+#[test]
+fn test_changelog_diff() {
+    let old = "# Changelog\n\n## 0.2.0\n- Hover\n\n## 0.1.0\n- Completion\n";
+    let new = "# Changelog\n\n## 0.3.0\n- Rename\n\n## 0.2.1\n- Fix hover\n\n\
+               ## 0.2.0\n- Hover\n\n## 0.1.0\n- Completion\n";
+    assert_eq!(
+        changelog_diff(old, new),
+        "## 0.3.0\n- Rename\n\n## 0.2.1\n- Fix hover"
+    );
+    assert_eq!(changelog_diff(new, new), "");
+    assert_eq!(
+        changelog_diff("", "# Changelog\n## 0.1.0\n- Completion"),
+        "## 0.1.0\n- Completion"
+    );
+
+    // Without headings only the new lines are kept
+    assert_eq!(changelog_diff("- a\n- b", "- c\n- a\n- b"), "- c");
+}
+
+#[test]
+fn test_replace_and_rollback_volt() {
+    fn write_volt(dir: &Path, version: &str) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(
+            dir.join("volt.toml"),
+            format!(
+                "name = \"plugin\"\nversion = \"{version}\"\n\
+                 display-name = \"Plugin\"\nauthor = \"author\"\n\
+                 description = \"\""
+            ),
+        )
+        .unwrap();
+        fs::write(
+            dir.join("CHANGELOG.md"),
+            format!("## {version}\n- Release {version}\n"),
+        )
+        .unwrap();
+    }
+
+    let plugins_dir = tempfile::tempdir().unwrap();
+    let plugin_dir = plugins_dir.path().join("author.plugin");
+    let staging = plugins_dir.path().join(".installing");
+
+    write_volt(&plugin_dir, "0.1.0");
+    write_volt(&staging, "0.2.0");
+    replace_installed_volt(&staging, &plugin_dir).unwrap();
+    let meta = load_volt(&plugin_dir).unwrap();
+    assert_eq!(meta.version, "0.2.0");
+    let previous = meta.previous.unwrap();
+    assert_eq!(previous.version, "0.1.0");
+    assert_eq!(
+        previous.changes.as_deref(),
+        Some("## 0.2.0\n- Release 0.2.0")
+    );
+
+    // Installing the same version again keeps the previous one
+    write_volt(&staging, "0.2.0");
+    replace_installed_volt(&staging, &plugin_dir).unwrap();
+    let meta = load_volt(&plugin_dir).unwrap();
+    assert_eq!(meta.previous.unwrap().version, "0.1.0");
+
+    let meta = rollback_volt_dir(&plugin_dir).unwrap();
+    assert_eq!(meta.version, "0.1.0");
+    assert_eq!(meta.previous.unwrap().version, "0.2.0");
+    let meta = rollback_volt_dir(&plugin_dir).unwrap();
+    assert_eq!(meta.version, "0.2.0");
+}
@@ -0,0 +1,115 @@
+//! Creating the files of a new volt, for `lapce-proxy new-volt`. The volt is a
+//! Rust crate built to wasm, which Lapce loads with `--plugin-path` and
+//! reloads each time it's rebuilt.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+
+/// The target the volt is built for, set in its `.cargo/config.toml` so that a
+/// plain `cargo build --release` builds it
+const VOLT_TARGET: &str = "wasm32-wasip1";
+
+/// Create a volt named `name` in a new folder of `parent`, and return the
+/// folder
+pub fn scaffold_volt(parent: &Path, name: &str, author: &str) -> Result<PathBuf> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(anyhow!(
+            "the name of a volt can only have letters, numbers, `-` and `_`"
+        ));
+    }
+    let dir = parent.join(name);
+    if dir.exists() {
+        return Err(anyhow!("{} already exists", dir.display()));
+    }
+
+    fs::create_dir_all(dir.join("src"))?;
+    fs::create_dir_all(dir.join(".cargo"))?;
+    fs::write(dir.join("volt.toml"), volt_toml(name, author))?;
+    fs::write(dir.join("Cargo.toml"), cargo_toml(name))?;
+    fs::write(
+        dir.join(".cargo").join("config.toml"),
+        format!("[build]\ntarget = \"{VOLT_TARGET}\"\n"),
+    )?;
+    fs::write(dir.join("src").join("main.rs"), MAIN_RS)?;
+    fs::write(dir.join(".gitignore"), "/target\n")?;
+    Ok(dir)
+}
+
+fn volt_toml(name: &str, author: &str) -> String {
+    format!(
+        r#"name = "{name}"
+version = "0.1.0"
+author = "{author}"
+display-name = "{name}"
+description = ""
+wasm = "target/{VOLT_TARGET}/release/{name}.wasm"
+
+# The volt is started in any workspace, this can be narrowed down to the
+# languages of the documents it handles
+[activation]
+workspace-contains = ["*"]
+"#
+    )
+}
+
+fn cargo_toml(name: &str) -> String {
+    format!(
+        r#"[package]
+name = "{name}"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+anyhow = "1.0"
+serde = {{ version = "1.0", features = ["derive"] }}
+serde_json = "1.0"
+lapce-plugin = "0.1"
+"#
+    )
+}
+
+const MAIN_RS: &str = r#"use lapce_plugin::{
+    psp_types::{
+        lsp_types::{request::Initialize, InitializeParams},
+        Request,
+    },
+    register_plugin, LapcePlugin,
+};
+use serde_json::Value;
+
+#[derive(Default)]
+struct State {}
+
+register_plugin!(State);
+
+fn initialize(_params: InitializeParams) -> anyhow::Result<()> {
+    Ok(())
+}
+
+impl LapcePlugin for State {
+    fn handle_request(&mut self, _id: u64, method: String, params: Value) {
+        #[allow(clippy::single_match)]
+        match method.as_str() {
+            Initialize::METHOD => {
+                let params: InitializeParams =
+                    serde_json::from_value(params).unwrap();
+                if let Err(e) = initialize(params) {
+                    eprintln!("failed to initialize: {e}");
+                }
+            }
+            _ => {}
+        }
+    }
+}
+"#;
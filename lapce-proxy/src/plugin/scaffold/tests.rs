@@ -0,0 +1,37 @@
+use std::fs;
+
+use super::scaffold_volt;
+use crate::plugin::wasi::{load_volt, volt_watched_files};
+
+#[test]
+fn test_scaffold_volt() {
+    let parent = std::env::temp_dir().join("lapce-test-scaffold-volt");
+    let _ = fs::remove_dir_all(&parent);
+    fs::create_dir_all(&parent).unwrap();
+
+    let dir = scaffold_volt(&parent, "my-volt", "me").unwrap();
+    assert_eq!(dir, parent.join("my-volt"));
+    assert!(dir.join("src").join("main.rs").exists());
+
+    let meta = load_volt(&dir).unwrap();
+    assert_eq!(meta.name, "my-volt");
+    assert_eq!(meta.author, "me");
+    // not built yet
+    assert_eq!(meta.wasm, None);
+
+    let files = volt_watched_files(&dir).unwrap();
+    let dir = dir.canonicalize().unwrap();
+    assert_eq!(
+        files,
+        vec![
+            dir.join("volt.toml"),
+            dir.join("target/wasm32-wasip1/release/my-volt.wasm"),
+        ]
+    );
+
+    assert!(scaffold_volt(&parent, "my-volt", "me").is_err());
+    assert!(scaffold_volt(&parent, "my volt", "me").is_err());
+    assert!(scaffold_volt(&parent, "", "me").is_err());
+
+    let _ = fs::remove_dir_all(&parent);
+}
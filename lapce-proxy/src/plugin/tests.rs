@@ -0,0 +1,97 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use crossbeam_channel::Receiver;
+use lapce_rpc::{RpcError, plugin::PluginId};
+use serde_json::json;
+
+use super::merge_results;
+
+type Merged = Result<Vec<(PluginId, Vec<u64>)>, RpcError>;
+
+fn merged(
+    request_sent: usize,
+    timeout: Duration,
+) -> (
+    impl FnOnce(PluginId, Result<serde_json::Value, RpcError>) + Clone,
+    Receiver<Merged>,
+) {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let request_sent = Arc::new(AtomicUsize::new(request_sent));
+    let f = merge_results(request_sent, timeout, move |result: Merged| {
+        tx.send(result).unwrap();
+    });
+    (f, rx)
+}
+
+fn error() -> Result<serde_json::Value, RpcError> {
+    Err(RpcError {
+        code: 0,
+        message: "not supported".to_string(),
+    })
+}
+
+#[test]
+fn test_merge_results() {
+    let (f, rx) = merged(3, Duration::from_secs(60));
+    f.clone()(PluginId(1), Ok(json!([1, 2])));
+    f.clone()(PluginId(2), error());
+    assert!(rx.try_recv().is_err());
+    f(PluginId(3), Ok(json!([3])));
+
+    let results = rx.recv_timeout(Duration::from_secs(5)).unwrap().unwrap();
+    assert_eq!(
+        results,
+        vec![(PluginId(1), vec![1, 2]), (PluginId(3), vec![3])]
+    );
+}
+
+#[test]
+fn test_merge_results_errors() {
+    let (f, rx) = merged(2, Duration::from_secs(60));
+    f.clone()(PluginId(1), error());
+    f(PluginId(2), Ok(json!("not a list")));
+
+    let result = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(result.unwrap_err().message, "deserialize error");
+}
+
+#[test]
+fn test_merge_results_timeout() {
+    let (f, rx) = merged(2, Duration::from_millis(50));
+    f.clone()(PluginId(1), Ok(json!([1])));
+
+    // the second plugin doesn't answer in time
+    let results = rx.recv_timeout(Duration::from_secs(5)).unwrap().unwrap();
+    assert_eq!(results, vec![(PluginId(1), vec![1])]);
+
+    f(PluginId(2), Ok(json!([2])));
+    assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+}
+
+#[test]
+fn test_merge_results_counts_requests_sent_later() {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let request_sent = Arc::new(AtomicUsize::new(1));
+    let f = merge_results(
+        request_sent.clone(),
+        Duration::from_secs(60),
+        move |result: Merged| {
+            tx.send(result).unwrap();
+        },
+    );
+
+    // the chosen server of a policy had no result, so the others are asked
+    request_sent.fetch_add(1, Ordering::Relaxed);
+    f.clone()(PluginId(1), error());
+    assert!(rx.try_recv().is_err());
+    f(PluginId(2), Ok(json!([2])));
+
+    let results = rx.recv_timeout(Duration::from_secs(5)).unwrap().unwrap();
+    assert_eq!(results, vec![(PluginId(2), vec![2])]);
+}
@@ -9,19 +9,22 @@
     process,
     sync::{Arc, RwLock},
     thread,
+    time::Duration,
 };
 
 use anyhow::{Result, anyhow};
 use jsonrpc_lite::{Id, Params};
-use lapce_core::directory::Directory;
+use lapce_core::{directory::Directory, language::LapceLanguage};
 use lapce_rpc::{
     RpcError,
-    plugin::{PluginId, VoltID, VoltInfo, VoltMetadata},
+    plugin::{
+        PluginId, VoltID, VoltInfo, VoltLanguage, VoltMetadata, VoltPermissions,
+    },
     style::LineStyle,
 };
 use lapce_xi_rope::{Rope, RopeDelta};
 use lsp_types::{
-    DocumentFilter, InitializeParams, InitializedParams,
+    DocumentFilter, InitializeParams, InitializedParams, MessageType,
     TextDocumentContentChangeEvent, TextDocumentIdentifier, Url,
     VersionedTextDocumentIdentifier, WorkDoneProgressParams, WorkspaceFolder,
     notification::Initialized, request::Initialize,
@@ -30,18 +33,43 @@
 use psp_types::{Notification, Request};
 use serde_json::Value;
 use wasi_experimental_http_wasmtime::{HttpCtx, HttpState};
-use wasmtime_wasi::WasiCtxBuilder;
+use wasmtime::{StoreLimits, StoreLimitsBuilder};
+use wasmtime_wasi::{WasiCtx, WasiCtxBuilder};
 
 use super::{
     PluginCatalogRpcHandler, client_capabilities,
+    output::PluginOutput,
     psp::{
         PluginHandlerNotification, PluginHostHandler, PluginServerHandler,
         PluginServerRpc, ResponseSender, RpcCallback, handle_plugin_server_message,
     },
+    registry::previous_volt_version,
     volt_icon,
 };
 use crate::plugin::psp::PluginServerRpcHandler;
 
+/// How long a volt can take to handle one message before it's stopped, so that
+/// a volt stuck in a loop can't keep a core busy. It's generous, as volts
+/// download and unpack language servers while handling a message, and the
+/// time spent in host calls counts as well.
+const VOLT_TIME_PER_MESSAGE: Duration = Duration::from_secs(10 * 60);
+/// How often the epoch of the engine of the volts goes up, which is how
+/// precisely the time a volt takes is measured
+const VOLT_EPOCH_TICK: Duration = Duration::from_millis(100);
+/// How much memory the linear memory of a volt can grow to
+const VOLT_MAX_MEMORY: usize = 1 << 30;
+/// The crashes in a row after which a volt isn't restarted anymore
+const VOLT_MAX_RESTARTS: u32 = 5;
+/// How long a volt is restarted after its first crash in a row, doubled
+/// after each of the next ones
+const VOLT_RESTART_BACKOFF: Duration = Duration::from_secs(1);
+
+/// What the store of a volt keeps
+struct VoltState {
+    wasi: WasiCtx,
+    limits: StoreLimits,
+}
+
 #[derive(Default)]
 pub struct WasiPipe {
     buffer: VecDeque<u8>,
@@ -248,8 +276,19 @@ pub fn load_all_volts(
     plugin_rpc: PluginCatalogRpcHandler,
     extra_plugin_paths: &[PathBuf],
     disabled_volts: Vec<VoltID>,
+    volt_permissions: HashMap<VoltID, VoltPermissions>,
 ) {
     let all_volts = find_all_volts(extra_plugin_paths);
+    // Volts without wasm can still add languages, which language servers
+    // need the language id of
+    for meta in all_volts
+        .iter()
+        .filter(|meta| !disabled_volts.contains(&meta.id()))
+    {
+        for language in volt_languages(meta, volt_permissions.get(&meta.id())) {
+            LapceLanguage::register_volt_language(&language);
+        }
+    }
     let volts = all_volts
         .into_iter()
         .filter_map(|meta| {
@@ -359,9 +398,13 @@ pub fn find_all_volts(extra_plugin_paths: &[PathBuf]) -> Vec<VoltMetadata> {
 ///         wasm: None,
 ///         color_themes: None,
 ///         icon_themes: None,
+///         snippets: None,
+///         languages: None,
 ///         dir: parent_path.canonicalize().ok(),
 ///         activation: None,
-///         config: None
+///         config: None,
+///         permissions: None,
+///         previous: None,
 ///     }
 /// );
 /// let _ = std::fs::remove_file(parent_path.join("volt.toml"));
@@ -397,10 +440,54 @@ pub fn load_volt(path: &Path) -> Result<VoltMetadata> {
             })
             .collect()
     });
+    meta.snippets = meta.snippets.take().map(|snippets| {
+        snippets
+            .into_iter()
+            .filter_map(|mut snippets| {
+                snippets.path = path
+                    .join(&snippets.path)
+                    .canonicalize()
+                    .ok()?
+                    .to_str()?
+                    .to_string();
+                Some(snippets)
+            })
+            .collect()
+    });
+    if let Some(languages) = meta.languages.as_mut() {
+        for language in languages {
+            language.grammar = language.grammar.as_ref().and_then(|grammar| {
+                Some(volt_grammar_path(&path, grammar)?.to_str()?.to_string())
+            });
+            language.queries = language.queries.as_ref().and_then(|queries| {
+                Some(
+                    path.join(queries)
+                        .canonicalize()
+                        .ok()?
+                        .to_str()?
+                        .to_string(),
+                )
+            });
+        }
+    }
+    meta.previous = previous_volt_version(&path);
 
     Ok(meta)
 }
 
+/// The files of a volt in development that it's reloaded on a change of: its
+/// `volt.toml` and its wasm, which doesn't need to have been built yet
+pub fn volt_watched_files(path: &Path) -> Result<Vec<PathBuf>> {
+    let path = path.canonicalize()?;
+    let volt_toml = path.join("volt.toml");
+    let meta: VoltMetadata = toml::from_str(&fs::read_to_string(&volt_toml)?)?;
+    let mut files = vec![volt_toml];
+    if let Some(wasm) = meta.wasm {
+        files.push(path.join(wasm));
+    }
+    Ok(files)
+}
+
 pub fn enable_volt(
     plugin_rpc: PluginCatalogRpcHandler,
     volt: VoltInfo,
@@ -413,23 +500,117 @@ pub fn enable_volt(
     Ok(())
 }
 
+/// Whether everything the volt asks for in its `[permissions]` section has
+/// been granted by the user. Volts that don't ask for anything are always
+/// permitted, since they only get access to their own directory.
+pub fn volt_permitted(
+    meta: &VoltMetadata,
+    granted: Option<&VoltPermissions>,
+) -> bool {
+    let requested = meta.permissions();
+    requested.is_empty() || granted.map(|g| g.covers(&requested)).unwrap_or(false)
+}
+
+/// The languages the volt adds, none if it isn't permitted. A tree-sitter
+/// grammar is native code that's loaded into Lapce, so a language only keeps
+/// its grammar when the grammar is in the `executables` permission, like a
+/// program the volt runs.
+pub fn volt_languages(
+    meta: &VoltMetadata,
+    granted: Option<&VoltPermissions>,
+) -> Vec<VoltLanguage> {
+    if !volt_permitted(meta, granted) {
+        return Vec::new();
+    }
+    let grammar_permitted = |grammar: &str| {
+        let (Some(dir), Some(granted)) = (meta.dir.as_ref(), granted) else {
+            return false;
+        };
+        granted.executables.iter().any(|executable| {
+            if executable == grammar {
+                return true;
+            }
+            executable
+                .strip_prefix("${volt}")
+                .map(|rest| rest.trim_start_matches(['/', '\\']))
+                .filter(|rest| !rest.is_empty())
+                .and_then(|rest| volt_grammar_path(dir, rest))
+                .is_some_and(|path| path == Path::new(grammar))
+        })
+    };
+    meta.languages
+        .iter()
+        .flatten()
+        .cloned()
+        .map(|mut language| {
+            if !language.grammar.as_deref().is_none_or(grammar_permitted) {
+                tracing::error!(
+                    "the grammar of {} isn't in the executables permission of {}",
+                    language.name,
+                    meta.id()
+                );
+                language.grammar = None;
+            }
+            language
+        })
+        .collect()
+}
+
+/// The path of a tree-sitter grammar library in the volt's directory, which
+/// can be written without the extension of the platform's libraries
+fn volt_grammar_path(dir: &Path, grammar: &str) -> Option<PathBuf> {
+    let mut grammar = dir.join(grammar);
+    if grammar.extension().is_none() {
+        grammar.set_extension(std::env::consts::DLL_EXTENSION);
+    }
+    grammar.canonicalize().ok()
+}
+
+/// Resolve an entry of the `filesystem` permission to a directory on disk
+fn volt_filesystem_path(dir: &str, workspace: Option<&Path>) -> Result<PathBuf> {
+    if let Some(rest) = dir.strip_prefix("${workspace}") {
+        let workspace =
+            workspace.ok_or_else(|| anyhow!("volt needs a workspace to access"))?;
+        let rest = rest.trim_start_matches(['/', '\\']);
+        return Ok(if rest.is_empty() {
+            workspace.to_path_buf()
+        } else {
+            workspace.join(rest)
+        });
+    }
+    let path = PathBuf::from(dir);
+    if !path.is_absolute() {
+        return Err(anyhow!(
+            "filesystem permission {dir} isn't an absolute path"
+        ));
+    }
+    Ok(path)
+}
+
 pub fn start_volt(
     workspace: Option<PathBuf>,
     configurations: Option<HashMap<String, serde_json::Value>>,
     plugin_rpc: PluginCatalogRpcHandler,
     meta: VoltMetadata,
+    granted: VoltPermissions,
 ) -> Result<()> {
-    let engine = wasmtime::Engine::default();
+    let engine = volt_engine()?;
     let module = wasmtime::Module::from_file(
         &engine,
         meta.wasm
             .as_ref()
             .ok_or_else(|| anyhow!("no wasm in plugin"))?,
     )?;
+    // The volt has already been checked against the user's grants at this
+    // point, and it only gets what the user granted, whatever its `volt.toml`
+    // says now.
+    let permissions = granted;
+
     let mut linker = wasmtime::Linker::new(&engine);
-    wasmtime_wasi::add_to_linker(&mut linker, |s| s)?;
-    HttpState::new()?.add_to_linker(&mut linker, |_| HttpCtx {
-        allowed_hosts: Some(vec!["insecure:allow-all".to_string()]),
+    wasmtime_wasi::add_to_linker(&mut linker, |s: &mut VoltState| &mut s.wasi)?;
+    let allowed_hosts = permissions.network.clone();
+    HttpState::new()?.add_to_linker(&mut linker, move |_| HttpCtx {
+        allowed_hosts: Some(allowed_hosts.clone()),
         max_concurrent_requests: Some(100),
     })?;
 
@@ -464,8 +645,23 @@ pub fn start_volt(
     let stdin = Arc::new(RwLock::new(WasiPipe::new()));
     let stdout = Arc::new(RwLock::new(WasiPipe::new()));
     let stderr = Arc::new(RwLock::new(WasiPipe::new()));
-    let wasi = WasiCtxBuilder::new()
-        .inherit_env()?
+    let mut wasi = WasiCtxBuilder::new();
+    for var in permissions.env.iter() {
+        if let Ok(value) = std::env::var(var) {
+            wasi.env(var, &value)?;
+        }
+    }
+    for dir in permissions.filesystem.iter() {
+        let dir = volt_filesystem_path(dir, workspace.as_deref())?;
+        wasi.preopened_dir(
+            wasmtime_wasi::Dir::open_ambient_dir(
+                &dir,
+                wasmtime_wasi::ambient_authority(),
+            )?,
+            &dir,
+        )?;
+    }
+    let wasi = wasi
         .env("VOLT_OS", std::env::consts::OS)?
         .env("VOLT_ARCH", std::env::consts::ARCH)?
         .env("VOLT_LIBC", volt_libc)?
@@ -492,20 +688,38 @@ pub fn start_volt(
             "/",
         )?
         .build();
-    let mut store = wasmtime::Store::new(&engine, wasi);
+    let mut store = wasmtime::Store::new(
+        &engine,
+        VoltState {
+            wasi,
+            limits: StoreLimitsBuilder::new()
+                .memory_size(VOLT_MAX_MEMORY)
+                .build(),
+        },
+    );
+    store.limiter(|state| &mut state.limits);
 
     let (io_tx, io_rx) = crossbeam_channel::unbounded();
-    let rpc = PluginServerRpcHandler::new(meta.id(), None, None, io_tx);
+    let output = PluginOutput::new(
+        plugin_rpc.core_rpc.clone(),
+        meta.id(),
+        meta.display_name.clone(),
+    );
+    let rpc =
+        PluginServerRpcHandler::new(meta.id(), None, None, output.clone(), io_tx);
 
     let local_rpc = rpc.clone();
     let local_stdin = stdin.clone();
+    let local_output = output.clone();
     let volt_name = format!("volt {}", meta.name);
     linker.func_wrap("lapce", "host_handle_rpc", move || {
         if let Ok(msg) = wasi_read_string(&stdout) {
+            local_output.trace_received(&msg);
             if let Some(resp) =
                 handle_plugin_server_message(&local_rpc, &msg, &volt_name)
             {
                 if let Ok(msg) = serde_json::to_string(&resp) {
+                    local_output.trace_sent(&msg);
                     if let Err(err) = writeln!(local_stdin.write().unwrap(), "{msg}")
                     {
                         tracing::error!("{:?}", err);
@@ -515,46 +729,15 @@ pub fn start_volt(
         }
     })?;
     let plugin_meta = meta.clone();
+    let local_output = output.clone();
+    let local_stderr = stderr.clone();
     linker.func_wrap("lapce", "host_handle_stderr", move || {
-        if let Ok(msg) = wasi_read_string(&stderr) {
+        if let Ok(msg) = wasi_read_string(&local_stderr) {
+            local_output.stderr(&msg);
             tracing_log::log::log!(target: &format!("lapce_proxy::plugin::wasi::{}::{}", plugin_meta.author, plugin_meta.name), tracing_log::log::Level::Debug, "{msg}");
         }
     })?;
     linker.module(&mut store, "", &module)?;
-    let local_rpc = rpc.clone();
-    thread::spawn(move || {
-        let mut exist_id = None;
-        {
-            let instance = linker.instantiate(&mut store, &module).unwrap();
-            let handle_rpc = instance
-                .get_func(&mut store, "handle_rpc")
-                .ok_or_else(|| anyhow!("can't convet to function"))
-                .unwrap()
-                .typed::<(), ()>(&mut store)
-                .unwrap();
-            for msg in io_rx {
-                if msg
-                    .get_method()
-                    .map(|x| x == lsp_types::request::Shutdown::METHOD)
-                    .unwrap_or_default()
-                {
-                    exist_id = msg.get_id();
-                    break;
-                }
-                if let Ok(msg) = serde_json::to_string(&msg) {
-                    if let Err(err) = writeln!(stdin.write().unwrap(), "{msg}") {
-                        tracing::error!("{:?}", err);
-                    }
-                }
-                if let Err(err) = handle_rpc.call(&mut store, ()) {
-                    tracing::error!("{:?}", err);
-                }
-            }
-        }
-        if let Some(id) = exist_id {
-            local_rpc.handle_server_response(id, Ok(Value::Null));
-        }
-    });
 
     let id = PluginId::next();
     let mut plugin = Plugin {
@@ -564,6 +747,7 @@ pub fn start_volt(
             meta.dir.clone(),
             meta.id(),
             meta.display_name.clone(),
+            permissions,
             meta.activation
                 .iter()
                 .flat_map(|m| m.language.iter().flatten())
@@ -596,12 +780,119 @@ pub fn start_volt(
         local_rpc.mainloop(&mut plugin);
     });
 
+    // The catalog has to know about the volt before it can crash, or the crash
+    // is taken for the volt having been stopped on purpose
     if plugin_rpc.plugin_server_loaded(rpc.clone()).is_err() {
         rpc.shutdown();
+        return Ok(());
     }
+
+    let local_rpc = rpc.clone();
+    let local_plugin_rpc = plugin_rpc.clone();
+    let local_meta = meta.clone();
+    thread::spawn(move || {
+        let mut exist_id = None;
+        let result = (|| -> Result<()> {
+            set_volt_deadline(&mut store, VOLT_TIME_PER_MESSAGE);
+            let instance = linker.instantiate(&mut store, &module)?;
+            let handle_rpc = instance
+                .get_func(&mut store, "handle_rpc")
+                .ok_or_else(|| anyhow!("can't convet to function"))?
+                .typed::<(), ()>(&mut store)?;
+            for msg in io_rx {
+                if msg
+                    .get_method()
+                    .map(|x| x == lsp_types::request::Shutdown::METHOD)
+                    .unwrap_or_default()
+                {
+                    exist_id = msg.get_id();
+                    break;
+                }
+                if let Ok(msg) = serde_json::to_string(&msg) {
+                    output.trace_sent(&msg);
+                    if let Err(err) = writeln!(stdin.write().unwrap(), "{msg}") {
+                        tracing::error!("{:?}", err);
+                    }
+                }
+                set_volt_deadline(&mut store, VOLT_TIME_PER_MESSAGE);
+                // A trap leaves the instance in a state it can't be used
+                // in anymore, so the volt is restarted instead
+                handle_rpc.call(&mut store, ())?;
+            }
+            Ok(())
+        })();
+        if let Some(id) = exist_id {
+            local_rpc.handle_server_response(id, Ok(Value::Null));
+        }
+        if let Err(err) = result {
+            tracing::error!("{:?}", err);
+            // A panic writes its message to stderr right before the trap,
+            // without the volt handing it over
+            let panic = wasi_read_string(&stderr)
+                .ok()
+                .map(|msg| msg.trim().to_string())
+                .filter(|msg| !msg.is_empty());
+            if let Some(panic) = panic.as_ref() {
+                output.stderr(panic);
+            }
+            // The debug format has the wasm backtrace of a trap
+            output.log(MessageType::ERROR, &format!("volt crashed: {err:?}"));
+            local_rpc.fail_pending_requests("volt crashed");
+            if let Err(err) = local_plugin_rpc.volt_crashed(
+                local_rpc.plugin_id,
+                local_meta,
+                panic.unwrap_or_else(|| err.to_string()),
+            ) {
+                tracing::error!("{:?}", err);
+            }
+        }
+    });
+
     Ok(())
 }
 
+/// The engine all the volts run on, whose epoch goes up every
+/// `VOLT_EPOCH_TICK` for the deadlines of the volts
+fn volt_engine() -> Result<wasmtime::Engine> {
+    static ENGINE: Mutex<Option<wasmtime::Engine>> = Mutex::new(None);
+    let mut engine = ENGINE.lock();
+    if let Some(engine) = engine.as_ref() {
+        return Ok(engine.clone());
+    }
+
+    let mut config = wasmtime::Config::new();
+    config.epoch_interruption(true);
+    // file and line numbers in the backtraces of volts built with debug info
+    config.wasm_backtrace_details(wasmtime::WasmBacktraceDetails::Enable);
+    let new_engine = wasmtime::Engine::new(&config)?;
+    {
+        let engine = new_engine.clone();
+        thread::spawn(move || {
+            loop {
+                thread::sleep(VOLT_EPOCH_TICK);
+                engine.increment_epoch();
+            }
+        });
+    }
+    *engine = Some(new_engine.clone());
+    Ok(new_engine)
+}
+
+/// Give the volt `time` to handle the next message, after which it traps
+fn set_volt_deadline<T>(store: &mut wasmtime::Store<T>, time: Duration) {
+    let ticks = time.as_millis() / VOLT_EPOCH_TICK.as_millis();
+    store.set_epoch_deadline(ticks.max(1) as u64);
+}
+
+/// How long to wait before restarting a volt after it crashed `crashes` times
+/// in a row, or `None` if it crashed too many times to be restarted
+pub fn volt_restart_delay(crashes: u32) -> Option<Duration> {
+    if crashes == 0 || crashes > VOLT_MAX_RESTARTS {
+        return None;
+    }
+    Some(VOLT_RESTART_BACKOFF * 2u32.pow(crashes - 1))
+}
+
 fn wasi_read_string(stdout: &Arc<RwLock<WasiPipe>>) -> Result<String> {
     let mut buf = String::new();
     stdout.write().unwrap().read_to_string(&mut buf)?;
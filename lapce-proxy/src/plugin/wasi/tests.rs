@@ -1,9 +1,12 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
-use lapce_rpc::plugin::VoltMetadata;
+use lapce_rpc::plugin::{VoltMetadata, VoltPermissions};
 use serde_json::{Value, json};
 
-use super::{load_volt, unflatten_map};
+use super::{
+    VOLT_TIME_PER_MESSAGE, load_volt, set_volt_deadline, unflatten_map, volt_engine,
+    volt_filesystem_path, volt_languages, volt_permitted, volt_restart_delay,
+};
 
 #[test]
 fn test_unflatten_map() {
@@ -143,9 +146,13 @@ fn test_load_volt() {
             wasm: wasm_path,
             color_themes: Some(color_themes_pathes),
             icon_themes: Some(icon_themes_pathes),
+            snippets: None,
+            languages: None,
             dir: parent_path.canonicalize().ok(),
             activation: None,
-            config: None
+            config: None,
+            permissions: None,
+            previous: None,
         }
     );
 
@@ -203,9 +210,13 @@ fn test_load_volt() {
             wasm: wasm_path,
             color_themes: Some(color_themes_pathes),
             icon_themes: Some(icon_themes_pathes),
+            snippets: None,
+            languages: None,
             dir: parent_path.canonicalize().ok(),
             activation: None,
-            config: None
+            config: None,
+            permissions: None,
+            previous: None,
         }
     );
 
@@ -229,9 +240,134 @@ fn test_load_volt() {
             wasm: None,
             color_themes: Some(Vec::new()),
             icon_themes: Some(Vec::new()),
+            snippets: None,
+            languages: None,
             dir: parent_path.canonicalize().ok(),
             activation: None,
-            config: None
+            config: None,
+            permissions: Some(VoltPermissions {
+                network: vec!["https://api.github.com".to_string()],
+                executables: vec!["cargo".to_string()],
+                ..Default::default()
+            }),
+            previous: None,
         }
     );
 }
+
+#[test]
+fn test_volt_permitted() {
+    let mut meta = load_volt(
+        &std::env::current_dir()
+            .unwrap()
+            .join("src/plugin/wasi/plugins/some_author.test-plugin-three"),
+    )
+    .unwrap();
+    assert!(!volt_permitted(&meta, None));
+    assert!(!volt_permitted(&meta, Some(&VoltPermissions::default())));
+    assert!(volt_permitted(&meta, Some(&meta.permissions())));
+
+    meta.permissions = None;
+    assert!(volt_permitted(&meta, None));
+}
+
+#[test]
+fn test_volt_filesystem_path() {
+    let workspace = std::env::current_dir().unwrap();
+    assert_eq!(
+        volt_filesystem_path("${workspace}", Some(&workspace)).unwrap(),
+        workspace
+    );
+    assert_eq!(
+        volt_filesystem_path("${workspace}/target", Some(&workspace)).unwrap(),
+        workspace.join("target")
+    );
+    assert!(volt_filesystem_path("${workspace}", None).is_err());
+    assert!(volt_filesystem_path("relative/path", Some(&workspace)).is_err());
+}
+
+#[test]
+fn test_volt_restart_delay() {
+    assert_eq!(volt_restart_delay(0), None);
+    assert_eq!(volt_restart_delay(1), Some(Duration::from_secs(1)));
+    assert_eq!(volt_restart_delay(2), Some(Duration::from_secs(2)));
+    assert_eq!(volt_restart_delay(5), Some(Duration::from_secs(16)));
+    assert_eq!(volt_restart_delay(6), None);
+}
+
+#[test]
+fn test_volt_languages() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("volt.toml"),
+        "name = \"mydsl\"\nversion = \"0.1.0\"\n\
+         display-name = \"MyDSL\"\nauthor = \"author\"\n\
+         description = \"\"\n\n\
+         [[languages]]\nname = \"MyDSL\"\nextensions = [\"mydsl\"]\n\
+         grammar = \"grammar/mydsl\"\n\n\
+         [permissions]\nexecutables = [\"${volt}/grammar/mydsl\"]\n",
+    )
+    .unwrap();
+    std::fs::create_dir(dir.path().join("grammar")).unwrap();
+    std::fs::write(
+        dir.path()
+            .join("grammar")
+            .join("mydsl")
+            .with_extension(std::env::consts::DLL_EXTENSION),
+        "",
+    )
+    .unwrap();
+    let mut meta = load_volt(dir.path()).unwrap();
+    let granted = meta.permissions();
+
+    // Not permitted
+    assert!(volt_languages(&meta, None).is_empty());
+
+    let languages = volt_languages(&meta, Some(&granted));
+    assert_eq!(languages.len(), 1);
+    assert!(languages[0].grammar.is_some());
+
+    // The grammar isn't in the executables permission
+    meta.permissions = None;
+    let languages = volt_languages(&meta, None);
+    assert_eq!(languages.len(), 1);
+    assert_eq!(languages[0].name, "MyDSL");
+    assert!(languages[0].grammar.is_none());
+    let languages = volt_languages(
+        &meta,
+        Some(&VoltPermissions {
+            executables: vec!["${volt}/other".to_string()],
+            ..Default::default()
+        }),
+    );
+    assert!(languages[0].grammar.is_none());
+}
+
+#[test]
+fn test_volt_deadline() {
+    // counts down from the argument, which can be negative to loop forever
+    let wat = r#"
+        (module
+          (func (export "run") (param $n i64)
+            (loop $count
+              (local.set $n (i64.sub (local.get $n) (i64.const 1)))
+              (br_if $count (i64.ne (local.get $n) (i64.const 0))))))
+    "#;
+    let engine = volt_engine().unwrap();
+    let module = wasmtime::Module::new(&engine, wat).unwrap();
+    let mut store = wasmtime::Store::new(&engine, ());
+    set_volt_deadline(&mut store, VOLT_TIME_PER_MESSAGE);
+    let instance = wasmtime::Instance::new(&mut store, &module, &[]).unwrap();
+    let run = instance
+        .get_typed_func::<i64, ()>(&mut store, "run")
+        .unwrap();
+
+    // a long handler, like unpacking a language server, gets to finish. It's
+    // several billion instructions, more than the fuel volts used to get.
+    set_volt_deadline(&mut store, VOLT_TIME_PER_MESSAGE);
+    run.call(&mut store, 1_000_000_000).unwrap();
+
+    // one stuck in a loop is stopped
+    set_volt_deadline(&mut store, Duration::from_millis(200));
+    assert!(run.call(&mut store, -1).is_err());
+}
@@ -0,0 +1,345 @@
+use std::path::Path;
+
+use lapce_rpc::task::{DetectedTask, TaskKind, TaskSource};
+
+const MAKEFILES: [&str; 3] = ["GNUmakefile", "makefile", "Makefile"];
+const JUSTFILES: [&str; 3] = ["justfile", "Justfile", ".justfile"];
+
+/// Find the tasks in the build files at the root of the workspace.
+pub fn detect_tasks(workspace: &Path) -> Vec<DetectedTask> {
+    let mut tasks = Vec::new();
+
+    if let Ok(content) = std::fs::read_to_string(workspace.join("Cargo.toml")) {
+        tasks.extend(cargo_tasks(workspace, &content));
+    }
+
+    if let Ok(content) = std::fs::read_to_string(workspace.join("package.json")) {
+        tasks.extend(npm_tasks(workspace, &content));
+    }
+
+    if let Some(content) = MAKEFILES
+        .iter()
+        .find_map(|name| std::fs::read_to_string(workspace.join(name)).ok())
+    {
+        tasks.extend(make_targets(&content).into_iter().map(|target| {
+            task(
+                TaskSource::Make,
+                &target,
+                "make",
+                vec![target.clone()],
+                workspace,
+            )
+        }));
+    }
+
+    if let Some(content) = JUSTFILES
+        .iter()
+        .find_map(|name| std::fs::read_to_string(workspace.join(name)).ok())
+    {
+        tasks.extend(just_recipes(&content).into_iter().map(|recipe| {
+            task(
+                TaskSource::Just,
+                &recipe,
+                "just",
+                vec![recipe.clone()],
+                workspace,
+            )
+        }));
+    }
+
+    tasks
+}
+
+fn task(
+    source: TaskSource,
+    name: &str,
+    program: &str,
+    args: Vec<String>,
+    cwd: &Path,
+) -> DetectedTask {
+    DetectedTask {
+        source,
+        kind: task_kind(name),
+        name: name.to_string(),
+        program: program.to_string(),
+        args,
+        cwd: cwd.to_path_buf(),
+    }
+}
+
+/// Guess what a task is for from its name
+fn task_kind(name: &str) -> TaskKind {
+    let command = name.split_whitespace().next().unwrap_or(name);
+    match command {
+        "build" | "all" | "compile" | "check" => TaskKind::Build,
+        "test" | "tests" => TaskKind::Test,
+        "run" | "start" | "dev" | "serve" => TaskKind::Run,
+        _ => TaskKind::Other,
+    }
+}
+
+/// The commands for the package or workspace, and a run for each of its
+/// binaries and examples and a test for each of its integration tests.
+fn cargo_tasks(dir: &Path, content: &str) -> Vec<DetectedTask> {
+    let Ok(manifest) = toml::from_str::<toml::Value>(content) else {
+        return Vec::new();
+    };
+    let cargo = |name: &str| {
+        let args = name.split_whitespace().map(String::from).collect();
+        task(TaskSource::Cargo, name, "cargo", args, dir)
+    };
+
+    let mut tasks = vec![cargo("build"), cargo("check"), cargo("test")];
+
+    let targets = |table: &str| {
+        manifest
+            .get(table)
+            .and_then(|targets| targets.as_array())
+            .map(|targets| {
+                targets
+                    .iter()
+                    .filter_map(|target| target.get("name")?.as_str())
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    };
+    // the targets cargo finds by itself, as well as the declared ones
+    let discovered = |folder: &str| {
+        let mut names = std::fs::read_dir(dir.join(folder))
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .filter_map(|entry| {
+                        let path = entry.path();
+                        if path.extension()? == "rs" {
+                            Some(path.file_stem()?.to_str()?.to_string())
+                        } else if path.join("main.rs").exists() {
+                            Some(entry.file_name().to_str()?.to_string())
+                        } else {
+                            None
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names
+    };
+
+    if let Some(package) = manifest.get("package") {
+        let mut bins = targets("bin");
+        if bins.is_empty() && dir.join("src").join("main.rs").exists() {
+            if let Some(name) = package.get("name").and_then(|n| n.as_str()) {
+                bins.push(name.to_string());
+            }
+        }
+        bins.extend(discovered("src/bin"));
+        bins.sort();
+        bins.dedup();
+        if bins.len() == 1 {
+            tasks.push(cargo("run"));
+        } else {
+            tasks.extend(bins.iter().map(|bin| cargo(&format!("run --bin {bin}"))));
+        }
+
+        let mut examples = targets("example");
+        examples.extend(discovered("examples"));
+        examples.sort();
+        examples.dedup();
+        tasks.extend(
+            examples
+                .iter()
+                .map(|example| cargo(&format!("run --example {example}"))),
+        );
+
+        let mut tests = targets("test");
+        tests.extend(discovered("tests"));
+        tests.sort();
+        tests.dedup();
+        tasks.extend(
+            tests
+                .iter()
+                .map(|test| cargo(&format!("test --test {test}"))),
+        );
+    }
+
+    tasks
+}
+
+/// The scripts of the package, run with the package manager its lock file
+/// belongs to.
+fn npm_tasks(dir: &Path, content: &str) -> Vec<DetectedTask> {
+    let Ok(package) = serde_json::from_str::<serde_json::Value>(content) else {
+        return Vec::new();
+    };
+    let Some(scripts) = package.get("scripts").and_then(|s| s.as_object()) else {
+        return Vec::new();
+    };
+    let program = if dir.join("pnpm-lock.yaml").exists() {
+        "pnpm"
+    } else if dir.join("yarn.lock").exists() {
+        "yarn"
+    } else if dir.join("bun.lockb").exists() || dir.join("bun.lock").exists() {
+        "bun"
+    } else {
+        "npm"
+    };
+    scripts
+        .keys()
+        .map(|script| {
+            task(
+                TaskSource::Npm,
+                script,
+                program,
+                vec!["run".to_string(), script.clone()],
+                dir,
+            )
+        })
+        .collect()
+}
+
+/// The explicit targets of a makefile, leaving out the special and pattern
+/// ones.
+fn make_targets(content: &str) -> Vec<String> {
+    let mut targets = Vec::new();
+    for line in content.lines() {
+        if line.starts_with(['\t', ' ', '#', '.']) {
+            continue;
+        }
+        let Some((names, rest)) = line.split_once(':') else {
+            continue;
+        };
+        // variable assignments with := or ::=
+        if rest.starts_with('=') || rest.starts_with(":=") {
+            continue;
+        }
+        if names.contains(['=', '%', '$', '(']) {
+            continue;
+        }
+        for name in names.split_whitespace() {
+            if !targets.iter().any(|t| t == name) {
+                targets.push(name.to_string());
+            }
+        }
+    }
+    targets
+}
+
+/// The public recipes of a justfile, which are the ones not starting with an
+/// underscore.
+fn just_recipes(content: &str) -> Vec<String> {
+    const KEYWORDS: [&str; 6] = ["set", "alias", "export", "import", "mod", "if"];
+    let mut recipes = Vec::new();
+    let mut private = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed == "[private]" {
+            private = true;
+            continue;
+        }
+        if line.starts_with([' ', '\t', '#', '[']) || trimmed.is_empty() {
+            continue;
+        }
+        let Some((header, rest)) = line.split_once(':') else {
+            continue;
+        };
+        if rest.starts_with('=') || header.contains(":=") {
+            continue;
+        }
+        let name = header
+            .trim_start_matches('@')
+            .split_whitespace()
+            .next()
+            .unwrap_or_default();
+        if name.is_empty()
+            || name.starts_with('_')
+            || KEYWORDS.contains(&name)
+            || !name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        {
+            private = false;
+            continue;
+        }
+        if !private {
+            recipes.push(name.to_string());
+        }
+        private = false;
+    }
+    recipes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use lapce_rpc::task::{TaskKind, TaskSource};
+
+    use super::{cargo_tasks, just_recipes, make_targets, npm_tasks};
+
+    #[test]
+    fn test_cargo_tasks() {
+        let tasks = cargo_tasks(
+            Path::new("/nonexistent"),
+            r#"
+[package]
+name = "app"
+
+[[bin]]
+name = "server"
+
+[[bin]]
+name = "client"
+
+[[example]]
+name = "demo"
+"#,
+        );
+        let names = tasks.iter().map(|t| t.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "build",
+                "check",
+                "test",
+                "run --bin client",
+                "run --bin server",
+                "run --example demo"
+            ]
+        );
+        assert_eq!(tasks[0].kind, TaskKind::Build);
+        assert_eq!(tasks[2].kind, TaskKind::Test);
+        assert_eq!(tasks[3].args, vec!["run", "--bin", "client"]);
+        assert_eq!(tasks[3].label(), "cargo: run --bin client");
+    }
+
+    #[test]
+    fn test_npm_tasks() {
+        let tasks = npm_tasks(
+            Path::new("/nonexistent"),
+            r#"{ "scripts": { "build": "tsc", "test": "jest" } }"#,
+        );
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].source, TaskSource::Npm);
+        assert_eq!(tasks[0].program, "npm");
+        assert_eq!(tasks[0].args, vec!["run", "build"]);
+        assert_eq!(tasks[1].kind, TaskKind::Test);
+    }
+
+    #[test]
+    fn test_make_targets() {
+        let targets = make_targets(
+            "CC := gcc\n.PHONY: all test\nall: main.o\n\t$(CC) -o app main.o\n%.o: %.c\n\t$(CC) -c $<\ntest install: all\n",
+        );
+        assert_eq!(targets, vec!["all", "test", "install"]);
+    }
+
+    #[test]
+    fn test_just_recipes() {
+        let recipes = just_recipes(
+            "set shell := [\"bash\", \"-c\"]\nversion := \"1\"\n\n# build it\nbuild:\n    cargo build\n\n@test filter='': build\n    cargo test {{filter}}\n\n_helper:\n    echo\n\n[private]\nhidden:\n    echo\n",
+        );
+        assert_eq!(recipes, vec!["build", "test"]);
+    }
+}
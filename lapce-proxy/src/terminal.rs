@@ -1,9 +1,9 @@
 use std::{
     borrow::Cow,
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
     io::{self, ErrorKind, Read, Write},
     num::NonZeroUsize,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::Arc,
     time::Duration,
 };
@@ -16,6 +16,7 @@
 use anyhow::Result;
 use crossbeam_channel::{Receiver, Sender};
 use directories::BaseDirs;
+use lapce_core::directory::Directory;
 use lapce_rpc::{
     core::CoreRpcHandler,
     terminal::{TermId, TerminalProfile},
@@ -24,6 +25,15 @@
 
 const READ_BUFFER_SIZE: usize = 0x10_0000;
 
+const SHELL_INTEGRATION_BASH: &str =
+    include_str!("../../extra/shell-integration/lapce.bash");
+const SHELL_INTEGRATION_ZSH: &str =
+    include_str!("../../extra/shell-integration/lapce.zsh");
+const SHELL_INTEGRATION_ZSHENV: &str =
+    include_str!("../../extra/shell-integration/lapce.zshenv");
+const SHELL_INTEGRATION_FISH: &str =
+    include_str!("../../extra/shell-integration/lapce.fish");
+
 #[cfg(any(target_os = "linux", target_os = "macos"))]
 const PTY_READ_WRITE_TOKEN: usize = 0;
 #[cfg(any(target_os = "linux", target_os = "macos"))]
@@ -71,11 +81,18 @@ pub fn new(
     ) -> Result<Terminal> {
         let poll = polling::Poller::new()?.into();
 
+        let mut env = profile.environment.clone().unwrap_or_default();
+        let shell = if profile.shell_integration {
+            Terminal::shell_integration(&profile, &mut env)
+        } else {
+            None
+        };
+
         let options = Options {
-            shell: Terminal::program(&profile),
+            shell: shell.or_else(|| Terminal::program(&profile)),
             working_directory: Terminal::workdir(&profile),
             hold: false,
-            env: profile.environment.unwrap_or_default(),
+            env,
         };
 
         setup_env();
@@ -306,6 +323,86 @@ fn program(profile: &TerminalProfile) -> Option<Shell> {
             None
         }
     }
+
+    /// The shell started with the scripts marking its prompts and commands,
+    /// if it's bash, zsh or fish and the profile doesn't pass it arguments
+    /// of its own.
+    fn shell_integration(
+        profile: &TerminalProfile,
+        env: &mut HashMap<String, String>,
+    ) -> Option<Shell> {
+        if cfg!(target_os = "windows")
+            || profile
+                .arguments
+                .as_ref()
+                .is_some_and(|args| !args.is_empty())
+        {
+            return None;
+        }
+
+        let program = profile
+            .command
+            .clone()
+            .or_else(|| std::env::var("SHELL").ok())?;
+        let name = Path::new(&program).file_name()?.to_str()?.to_string();
+        let dir = Directory::shell_integration_directory()?;
+
+        match name.as_str() {
+            "bash" => {
+                let script =
+                    write_script(&dir, "lapce.bash", SHELL_INTEGRATION_BASH)?;
+                Some(Shell::new(
+                    program,
+                    vec!["--init-file".to_string(), script.to_string_lossy().into()],
+                ))
+            }
+            "zsh" => {
+                // zsh has no flag for an extra startup file, so it's pointed to
+                // a ZDOTDIR of ours that loads the user's files in turn
+                let zdotdir = dir.join("zsh");
+                if !zdotdir.exists() {
+                    if let Err(err) = std::fs::create_dir(&zdotdir) {
+                        tracing::error!("{:?}", err);
+                        return None;
+                    }
+                }
+                write_script(&zdotdir, ".zshenv", SHELL_INTEGRATION_ZSHENV)?;
+                write_script(&zdotdir, ".zshrc", SHELL_INTEGRATION_ZSH)?;
+                let user_zdotdir = env
+                    .get("ZDOTDIR")
+                    .cloned()
+                    .or_else(|| std::env::var("ZDOTDIR").ok());
+                if let Some(user_zdotdir) = user_zdotdir {
+                    env.insert("LAPCE_ZDOTDIR".to_string(), user_zdotdir);
+                }
+                env.insert("ZDOTDIR".to_string(), zdotdir.to_string_lossy().into());
+                Some(Shell::new(program, Vec::new()))
+            }
+            "fish" => {
+                let script =
+                    write_script(&dir, "lapce.fish", SHELL_INTEGRATION_FISH)?;
+                Some(Shell::new(
+                    program,
+                    vec![
+                        "--init-command".to_string(),
+                        format!("source '{}'", script.display()),
+                    ],
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Write out a shell integration script, so that it matches this version of
+/// the proxy
+fn write_script(dir: &Path, name: &str, content: &str) -> Option<PathBuf> {
+    let path = dir.join(name);
+    if let Err(err) = std::fs::write(&path, content) {
+        tracing::error!("{:?}", err);
+        return None;
+    }
+    Some(path)
 }
 
 struct Writing {
@@ -0,0 +1,849 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+    sync::LazyLock,
+};
+
+use anyhow::{Result, anyhow};
+use lapce_rpc::testing::{TestItem, TestResult, TestRunner, TestState};
+use regex::Regex;
+use serde::Deserialize;
+
+static RUST_FN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:async\s+)?(?:unsafe\s+)?fn\s+(\w+)")
+        .unwrap()
+});
+static PYTHON_TEST: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(?:async\s+)?def\s+(test\w*)\s*\(").unwrap());
+static GO_TEST: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^func\s+((?:Test|Benchmark|Example|Fuzz)\w*)\s*\(").unwrap()
+});
+
+/// The functions of the source files with the extension, by name, with the
+/// files and zero based lines they're in.
+type Locations = HashMap<String, Vec<(PathBuf, u32)>>;
+
+/// List the tests of the workspace, with each of the test runners it's set
+/// up for.
+pub fn discover_tests(workspace: &Path) -> Vec<TestItem> {
+    let mut tests = Vec::new();
+    for runner in runners(workspace) {
+        let result = match runner {
+            TestRunner::Cargo => cargo_tests(workspace),
+            TestRunner::Pytest => pytest_tests(workspace),
+            TestRunner::Go => go_tests(workspace),
+        };
+        match result {
+            Ok(found) => tests.extend(found),
+            Err(err) => {
+                tracing::error!("{runner} test discovery failed: {err:?}");
+            }
+        }
+    }
+    tests
+}
+
+pub fn run_tests(
+    workspace: &Path,
+    runner: TestRunner,
+    ids: &[String],
+) -> Result<Vec<TestResult>> {
+    match runner {
+        TestRunner::Cargo => run_cargo_tests(workspace, ids),
+        TestRunner::Pytest => run_pytest_tests(workspace, ids),
+        TestRunner::Go => run_go_tests(workspace, ids),
+    }
+}
+
+fn runners(workspace: &Path) -> Vec<TestRunner> {
+    let mut runners = Vec::new();
+    if workspace.join("Cargo.toml").exists() {
+        runners.push(TestRunner::Cargo);
+    }
+    let mentions_pytest = |name: &str| {
+        std::fs::read_to_string(workspace.join(name))
+            .map(|content| content.contains("pytest"))
+            .unwrap_or(false)
+    };
+    if workspace.join("pytest.ini").exists()
+        || workspace.join("conftest.py").exists()
+        || ["pyproject.toml", "setup.cfg", "tox.ini"]
+            .iter()
+            .any(|name| mentions_pytest(name))
+    {
+        runners.push(TestRunner::Pytest);
+    }
+    if workspace.join("go.mod").exists() {
+        runners.push(TestRunner::Go);
+    }
+    runners
+}
+
+fn command(program: &str, workspace: &Path) -> Command {
+    let mut command = Command::new(program);
+    command.current_dir(workspace);
+    #[cfg(target_os = "windows")]
+    std::os::windows::process::CommandExt::creation_flags(&mut command, 0x08000000);
+    command
+}
+
+/// The error of a runner that didn't report any results
+fn run_error(program: &str, output: &Output) -> anyhow::Error {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let lines = stderr.lines().collect::<Vec<_>>();
+    // the end of the output has the compile errors or the reason it failed
+    let tail = lines[lines.len().saturating_sub(20)..].join("\n");
+    anyhow!("{program} exited with {}\n{tail}", output.status)
+}
+
+#[derive(Deserialize)]
+struct CargoMetadata {
+    packages: Vec<CargoPackage>,
+}
+
+#[derive(Deserialize)]
+struct CargoPackage {
+    name: String,
+}
+
+/// The packages of the workspace, whose tests are listed and run a package at
+/// a time, since the output of libtest doesn't tell them apart.
+fn cargo_packages(workspace: &Path) -> Result<Vec<String>> {
+    let output = command("cargo", workspace)
+        .args(["metadata", "--no-deps", "--format-version", "1"])
+        .output()?;
+    if !output.status.success() {
+        return Err(run_error("cargo", &output));
+    }
+    let metadata: CargoMetadata = serde_json::from_slice(&output.stdout)?;
+    Ok(metadata
+        .packages
+        .into_iter()
+        .map(|package| package.name)
+        .collect())
+}
+
+fn cargo_tests(workspace: &Path) -> Result<Vec<TestItem>> {
+    let locations = rust_test_locations(workspace);
+    let mut tests = Vec::new();
+    for package in cargo_packages(workspace)? {
+        let output = command("cargo", workspace)
+            .args(["test", "--package", &package])
+            .args(["--", "--list", "--format", "terse"])
+            .output()?;
+        if !output.status.success() {
+            return Err(run_error("cargo", &output));
+        }
+        for path in parse_cargo_list(&String::from_utf8_lossy(&output.stdout)) {
+            let name = path.rsplit("::").next().unwrap_or(&path).to_string();
+            let segments = path
+                .split("::")
+                .chain(std::iter::once(package.as_str()))
+                .collect::<Vec<_>>();
+            let location = locations
+                .get(&name)
+                .and_then(|candidates| best_location(candidates, &segments));
+            tests.push(TestItem {
+                id: format!("{package}/{path}"),
+                runner: TestRunner::Cargo,
+                name,
+                package: Some(package.clone()),
+                path: location.map(|(path, _)| path.clone()),
+                line: location.map(|(_, line)| *line),
+            });
+        }
+    }
+    Ok(tests)
+}
+
+/// The tests of `cargo test -- --list --format terse`, without the doc tests,
+/// which can't be run on their own by name.
+fn parse_cargo_list(output: &str) -> Vec<String> {
+    let mut ids: Vec<String> = Vec::new();
+    for line in output.lines() {
+        let Some(id) = line.strip_suffix(": test") else {
+            continue;
+        };
+        if id.contains(" - ") || ids.iter().any(|i| i == id) {
+            continue;
+        }
+        ids.push(id.to_string());
+    }
+    ids
+}
+
+/// Run the tests, whose ids are the package and the path of the test, a
+/// package at a time.
+fn run_cargo_tests(workspace: &Path, ids: &[String]) -> Result<Vec<TestResult>> {
+    let mut packages: Vec<(String, Vec<&str>)> = Vec::new();
+    if ids.is_empty() {
+        packages = cargo_packages(workspace)?
+            .into_iter()
+            .map(|package| (package, Vec::new()))
+            .collect();
+    }
+    for id in ids {
+        let Some((package, path)) = id.split_once('/') else {
+            continue;
+        };
+        match packages.iter_mut().find(|(p, _)| p == package) {
+            Some((_, paths)) => paths.push(path),
+            None => packages.push((package.to_string(), vec![path])),
+        }
+    }
+
+    // the json output of libtest is unstable, so the stable toolchains get
+    // the plain one
+    let json = is_nightly(workspace);
+    let mut results = Vec::new();
+    for (package, paths) in packages {
+        let mut command = command("cargo", workspace);
+        command.args(["test", "--package", &package, "--"]);
+        if json {
+            command.args([
+                "-Z",
+                "unstable-options",
+                "--format",
+                "json",
+                "--report-time",
+            ]);
+        }
+        if !paths.is_empty() {
+            command.arg("--exact").args(&paths);
+        }
+        let output = command.output()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let found = if json {
+            parse_libtest_json(&stdout)
+        } else {
+            parse_libtest(&stdout)
+        };
+        if found.is_empty() && !output.status.success() {
+            return Err(run_error("cargo", &output));
+        }
+        results.extend(found.into_iter().map(|result| TestResult {
+            id: format!("{package}/{}", result.id),
+            ..result
+        }));
+    }
+    Ok(results)
+}
+
+/// Whether the toolchain of the workspace is a nightly one, whose libtest can
+/// report the results as json.
+fn is_nightly(workspace: &Path) -> bool {
+    command("rustc", workspace)
+        .arg("--version")
+        .output()
+        .map(|output| {
+            let version = String::from_utf8_lossy(&output.stdout);
+            version.contains("-nightly") || version.contains("-dev")
+        })
+        .unwrap_or(false)
+}
+
+/// The results of the plain output of libtest, which has the output of the
+/// failed tests after them but not the durations.
+fn parse_libtest(output: &str) -> Vec<TestResult> {
+    let mut results = Vec::new();
+    let mut messages: HashMap<String, String> = HashMap::new();
+    // the failed test whose output is being read, with the output so far
+    let mut failure: Option<(String, String)> = None;
+    for line in output.lines() {
+        if let Some((name, outcome)) = line
+            .strip_prefix("test ")
+            .and_then(|rest| rest.rsplit_once(" ... "))
+        {
+            let state = match outcome {
+                "ok" => TestState::Passed,
+                "FAILED" => TestState::Failed,
+                outcome if outcome.starts_with("ignored") => TestState::Skipped,
+                _ => continue,
+            };
+            results.push(TestResult {
+                id: name.to_string(),
+                runner: TestRunner::Cargo,
+                state,
+                duration: None,
+                message: None,
+            });
+        } else if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|rest| rest.strip_suffix(" stdout ----"))
+        {
+            messages.extend(failure.take());
+            failure = Some((name.to_string(), String::new()));
+        } else if line == "failures:" || line.starts_with("test result: ") {
+            messages.extend(failure.take());
+        } else if let Some((_, message)) = failure.as_mut() {
+            message.push_str(line);
+            message.push('\n');
+        }
+    }
+    messages.extend(failure.take());
+    for result in &mut results {
+        if result.state == TestState::Failed {
+            result.message = messages
+                .remove(&result.id)
+                .map(|message| message.trim_end().to_string());
+        }
+    }
+    results
+}
+
+#[derive(Deserialize)]
+struct LibtestEvent {
+    #[serde(rename = "type")]
+    ty: String,
+    event: String,
+    name: Option<String>,
+    exec_time: Option<f64>,
+    stdout: Option<String>,
+    message: Option<String>,
+}
+
+fn parse_libtest_json(output: &str) -> Vec<TestResult> {
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<LibtestEvent>(line).ok())
+        .filter(|event| event.ty == "test")
+        .filter_map(|event| {
+            let state = match event.event.as_str() {
+                "ok" => TestState::Passed,
+                "failed" | "timeout" => TestState::Failed,
+                "ignored" => TestState::Skipped,
+                _ => return None,
+            };
+            let message = match (event.stdout, event.message) {
+                (Some(stdout), _) if state == TestState::Failed => Some(stdout),
+                (_, message) => message,
+            };
+            Some(TestResult {
+                id: event.name?,
+                runner: TestRunner::Cargo,
+                state,
+                duration: event.exec_time,
+                message,
+            })
+        })
+        .collect()
+}
+
+/// Where the functions marked as tests are, with any of the test attributes,
+/// e.g. `#[test]` and `#[tokio::test]`.
+fn rust_test_locations(workspace: &Path) -> Locations {
+    locations(workspace, "rs", |lines| {
+        let mut found = Vec::new();
+        let mut is_test = false;
+        for (index, line) in lines.enumerate() {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("#[") {
+                is_test |= trimmed.contains("test");
+                continue;
+            }
+            if is_test {
+                if let Some(captures) = RUST_FN.captures(line) {
+                    found.push((captures[1].to_string(), index as u32));
+                }
+            }
+            if !trimmed.is_empty() && !trimmed.starts_with("//") {
+                is_test = false;
+            }
+        }
+        found
+    })
+}
+
+fn locations(
+    workspace: &Path,
+    extension: &str,
+    find: impl Fn(&mut dyn Iterator<Item = &str>) -> Vec<(String, u32)>,
+) -> Locations {
+    let mut locations = Locations::new();
+    for entry in ignore::WalkBuilder::new(workspace).build().flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(extension) {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(path) else {
+            continue;
+        };
+        for (name, line) in find(&mut content.lines()) {
+            locations
+                .entry(name)
+                .or_default()
+                .push((path.to_path_buf(), line));
+        }
+    }
+    locations
+}
+
+/// The location whose path has the most of the segments of the test's id or
+/// package in it, since the same name is often used in several modules.
+fn best_location<'a>(
+    candidates: &'a [(PathBuf, u32)],
+    segments: &[&str],
+) -> Option<&'a (PathBuf, u32)> {
+    candidates.iter().rev().max_by_key(|(path, _)| {
+        path.iter()
+            .filter_map(|component| {
+                let component = Path::new(component).file_stem()?.to_str()?;
+                Some(component)
+            })
+            .filter(|component| segments.contains(component))
+            .count()
+    })
+}
+
+fn pytest_tests(workspace: &Path) -> Result<Vec<TestItem>> {
+    let output = command("pytest", workspace)
+        .args(["--collect-only", "-q"])
+        .output()?;
+    if !output.status.success() {
+        return Err(run_error("pytest", &output));
+    }
+    let mut files: HashMap<PathBuf, Vec<(String, u32)>> = HashMap::new();
+    Ok(
+        parse_pytest_collect(&String::from_utf8_lossy(&output.stdout))
+            .into_iter()
+            .map(|id| {
+                let file = id.split("::").next().unwrap_or_default();
+                let path = workspace.join(file);
+                let name = id
+                    .rsplit("::")
+                    .next()
+                    .unwrap_or_default()
+                    .split('[')
+                    .next()
+                    .unwrap_or_default()
+                    .to_string();
+                let functions = files.entry(path.clone()).or_insert_with(|| {
+                    std::fs::read_to_string(&path)
+                        .map(|content| {
+                            content
+                                .lines()
+                                .enumerate()
+                                .filter_map(|(index, line)| {
+                                    let captures = PYTHON_TEST.captures(line)?;
+                                    Some((captures[1].to_string(), index as u32))
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default()
+                });
+                let line = functions
+                    .iter()
+                    .find(|(function, _)| function == &name)
+                    .map(|(_, line)| *line);
+                TestItem {
+                    runner: TestRunner::Pytest,
+                    name,
+                    package: None,
+                    path: Some(path),
+                    line,
+                    id,
+                }
+            })
+            .collect(),
+    )
+}
+
+/// The node ids of `pytest --collect-only -q`, which come before the summary.
+fn parse_pytest_collect(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .take_while(|line| !line.trim().is_empty())
+        .filter(|line| line.contains("::"))
+        .map(|line| line.trim().to_string())
+        .collect()
+}
+
+fn run_pytest_tests(workspace: &Path, ids: &[String]) -> Result<Vec<TestResult>> {
+    // removed when it's dropped
+    let report = tempfile::Builder::new()
+        .prefix("lapce-pytest-")
+        .suffix(".xml")
+        .tempfile()?;
+    let output = command("pytest", workspace)
+        .arg("-q")
+        .arg(format!("--junitxml={}", report.path().to_string_lossy()))
+        .args(ids)
+        .output()?;
+    let content = std::fs::read_to_string(report.path()).unwrap_or_default();
+    if content.is_empty() {
+        return Err(run_error("pytest", &output));
+    }
+    Ok(parse_junit(&content)
+        .into_iter()
+        .map(|case| TestResult {
+            id: pytest_id(workspace, &case.classname, &case.name),
+            runner: TestRunner::Pytest,
+            state: case.state,
+            duration: case.time,
+            message: case.message,
+        })
+        .collect())
+}
+
+/// The node id of a test case of the junit report, whose class name is the
+/// module path followed by the class of the test, if any.
+fn pytest_id(workspace: &Path, classname: &str, name: &str) -> String {
+    let parts = classname.split('.').collect::<Vec<_>>();
+    for i in (1..=parts.len()).rev() {
+        let file = format!("{}.py", parts[..i].join("/"));
+        if workspace.join(&file).exists() {
+            return std::iter::once(file.as_str())
+                .chain(parts[i..].iter().copied())
+                .chain(std::iter::once(name))
+                .collect::<Vec<_>>()
+                .join("::");
+        }
+    }
+    format!("{classname}::{name}")
+}
+
+#[derive(Debug, PartialEq)]
+struct JunitCase {
+    classname: String,
+    name: String,
+    state: TestState,
+    time: Option<f64>,
+    message: Option<String>,
+}
+
+static JUNIT_CASE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<testcase\b([^>]*?)(?:/>|>(.*?)</testcase>)").unwrap()
+});
+static JUNIT_OUTCOME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<(failure|error|skipped)\b([^>]*?)(?:/>|>(.*?)</(?:failure|error|skipped)>)")
+        .unwrap()
+});
+static XML_ATTR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"([\w:-]+)="([^"]*)""#).unwrap());
+
+/// The test cases of a junit xml report, which is simple enough to not need
+/// a full xml parser.
+fn parse_junit(content: &str) -> Vec<JunitCase> {
+    let attrs = |attrs: &str| {
+        XML_ATTR
+            .captures_iter(attrs)
+            .map(|c| (c[1].to_string(), unescape_xml(&c[2])))
+            .collect::<HashMap<_, _>>()
+    };
+    JUNIT_CASE
+        .captures_iter(content)
+        .map(|case| {
+            let case_attrs = attrs(&case[1]);
+            let outcome = case.get(2).and_then(|body| {
+                let outcome = JUNIT_OUTCOME.captures(body.as_str())?;
+                let kind = outcome[1].to_string();
+                let message = attrs(&outcome[2]).remove("message");
+                let text = outcome.get(3).map(|t| unescape_xml(t.as_str().trim()));
+                let message = match (message, text) {
+                    (Some(message), Some(text)) if !text.is_empty() => {
+                        format!("{message}\n{text}")
+                    }
+                    (Some(message), _) => message,
+                    (None, text) => text.unwrap_or_default(),
+                };
+                Some((kind, message))
+            });
+            let (state, message) = match outcome {
+                Some((kind, message)) if kind == "skipped" => {
+                    (TestState::Skipped, Some(message))
+                }
+                Some((_, message)) => (TestState::Failed, Some(message)),
+                None => (TestState::Passed, None),
+            };
+            JunitCase {
+                classname: case_attrs.get("classname").cloned().unwrap_or_default(),
+                name: case_attrs.get("name").cloned().unwrap_or_default(),
+                state,
+                time: case_attrs.get("time").and_then(|t| t.parse().ok()),
+                message: message.filter(|m| !m.is_empty()),
+            }
+        })
+        .collect()
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#10;", "\n")
+        .replace("&amp;", "&")
+}
+
+fn go_tests(workspace: &Path) -> Result<Vec<TestItem>> {
+    let output = command("go", workspace)
+        .args(["test", "-list", ".", "./..."])
+        .output()?;
+    if !output.status.success() {
+        return Err(run_error("go", &output));
+    }
+    let locations = locations(workspace, "go", |lines| {
+        lines
+            .enumerate()
+            .filter_map(|(index, line)| {
+                let captures = GO_TEST.captures(line)?;
+                Some((captures[1].to_string(), index as u32))
+            })
+            .collect()
+    });
+    Ok(parse_go_list(&String::from_utf8_lossy(&output.stdout))
+        .into_iter()
+        .map(|(package, name)| {
+            let segments = package.split('/').collect::<Vec<_>>();
+            let location = locations
+                .get(&name)
+                .and_then(|candidates| best_location(candidates, &segments));
+            TestItem {
+                id: format!("{package}/{name}"),
+                runner: TestRunner::Go,
+                path: location.map(|(path, _)| path.clone()),
+                line: location.map(|(_, line)| *line),
+                name,
+                package: Some(package),
+            }
+        })
+        .collect())
+}
+
+/// The packages and names of the tests of `go test -list`, which prints the
+/// names of each package's tests and then the package.
+fn parse_go_list(output: &str) -> Vec<(String, String)> {
+    let mut tests = Vec::new();
+    let mut names = Vec::new();
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix("ok") {
+            if let Some(package) = rest.split_whitespace().next() {
+                tests.extend(
+                    names
+                        .drain(..)
+                        .map(|name: String| (package.to_string(), name)),
+                );
+            }
+        } else if line.chars().all(|c| c.is_alphanumeric() || c == '_')
+            && !line.is_empty()
+        {
+            names.push(line.to_string());
+        } else {
+            names.clear();
+        }
+    }
+    tests
+}
+
+fn run_go_tests(workspace: &Path, ids: &[String]) -> Result<Vec<TestResult>> {
+    let mut command = command("go", workspace);
+    command.args(["test", "-json"]);
+    if ids.is_empty() {
+        command.arg("./...");
+    } else {
+        let mut packages = Vec::new();
+        let mut names = Vec::new();
+        for id in ids {
+            let Some((package, name)) = id.rsplit_once('/') else {
+                continue;
+            };
+            if !packages.contains(&package) {
+                packages.push(package);
+            }
+            names.push(regex::escape(name));
+        }
+        command
+            .arg("-run")
+            .arg(format!("^({})$", names.join("|")))
+            .args(packages);
+    }
+    let output = command.output()?;
+    let results = parse_go_json(&String::from_utf8_lossy(&output.stdout));
+    if results.is_empty() && !output.status.success() {
+        return Err(run_error("go", &output));
+    }
+    Ok(results)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct GoEvent {
+    action: String,
+    package: Option<String>,
+    test: Option<String>,
+    elapsed: Option<f64>,
+    output: Option<String>,
+}
+
+fn parse_go_json(output: &str) -> Vec<TestResult> {
+    let mut outputs: HashMap<String, String> = HashMap::new();
+    let mut results = Vec::new();
+    for event in output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<GoEvent>(line).ok())
+    {
+        let (Some(package), Some(test)) = (event.package, event.test) else {
+            continue;
+        };
+        // subtests are reported as part of their test
+        let (test, is_subtest) = match test.split_once('/') {
+            Some((test, _)) => (test.to_string(), true),
+            None => (test, false),
+        };
+        let id = format!("{package}/{test}");
+        let state = match event.action.as_str() {
+            "output" => {
+                if let Some(output) = event.output {
+                    outputs.entry(id).or_default().push_str(&output);
+                }
+                continue;
+            }
+            _ if is_subtest => continue,
+            "pass" => TestState::Passed,
+            "fail" => TestState::Failed,
+            "skip" => TestState::Skipped,
+            _ => continue,
+        };
+        let output = outputs.remove(&id);
+        results.push(TestResult {
+            id,
+            runner: TestRunner::Go,
+            state,
+            duration: event.elapsed,
+            message: output.filter(|_| state == TestState::Failed),
+        });
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use lapce_rpc::testing::TestState;
+
+    use super::{
+        parse_cargo_list, parse_go_json, parse_go_list, parse_junit, parse_libtest,
+        parse_libtest_json, parse_pytest_collect,
+    };
+
+    #[test]
+    fn test_parse_cargo_list() {
+        let ids = parse_cargo_list(
+            "tests::a: test\ntests::b: benchmark\nsrc/lib.rs - f (line 3): test\ntests::a: test\nit::c: test\n",
+        );
+        assert_eq!(ids, vec!["tests::a", "it::c"]);
+    }
+
+    #[test]
+    fn test_parse_libtest_json() {
+        let results = parse_libtest_json(concat!(
+            r#"{ "type": "suite", "event": "started", "test_count": 2 }"#,
+            "\n",
+            r#"{ "type": "test", "event": "started", "name": "a" }"#,
+            "\n",
+            r#"{ "type": "test", "name": "a", "event": "ok", "exec_time": 0.5 }"#,
+            "\n",
+            r#"{ "type": "test", "name": "b", "event": "failed", "stdout": "panicked" }"#,
+            "\n",
+        ));
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].id, "a");
+        assert_eq!(results[0].state, TestState::Passed);
+        assert_eq!(results[0].duration, Some(0.5));
+        assert_eq!(results[1].state, TestState::Failed);
+        assert_eq!(results[1].message.as_deref(), Some("panicked"));
+    }
+
+    #[test]
+    fn test_parse_libtest() {
+        let results = parse_libtest(concat!(
+            "\nrunning 3 tests\n",
+            "test tests::a ... ok\n",
+            "test tests::b ... FAILED\n",
+            "test tests::c ... ignored, not now\n",
+            "\nfailures:\n\n",
+            "---- tests::b stdout ----\n",
+            "thread 'tests::b' panicked at src/lib.rs:3:5:\n",
+            "bad\n\n",
+            "\nfailures:\n    tests::b\n\n",
+            "test result: FAILED. 1 passed; 1 failed; 1 ignored\n",
+        ));
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].id, "tests::a");
+        assert_eq!(results[0].state, TestState::Passed);
+        assert_eq!(results[0].duration, None);
+        assert_eq!(results[1].state, TestState::Failed);
+        assert_eq!(
+            results[1].message.as_deref(),
+            Some("thread 'tests::b' panicked at src/lib.rs:3:5:\nbad")
+        );
+        assert_eq!(results[2].state, TestState::Skipped);
+    }
+
+    #[test]
+    fn test_parse_pytest_collect() {
+        let ids = parse_pytest_collect(
+            "tests/test_a.py::test_b\ntests/test_a.py::TestC::test_d[1]\n\n2 tests collected in 0.01s\n",
+        );
+        assert_eq!(
+            ids,
+            vec![
+                "tests/test_a.py::test_b",
+                "tests/test_a.py::TestC::test_d[1]"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_junit() {
+        let cases = parse_junit(
+            r#"<?xml version="1.0" encoding="utf-8"?><testsuites><testsuite name="pytest">
+<testcase classname="tests.test_a" name="test_b" time="0.010" />
+<testcase classname="tests.test_a.TestC" name="test_d" time="0.002"><failure message="assert 1 == 2">def test_d():
+&gt;       assert 1 == 2</failure></testcase>
+<testcase classname="tests.test_a" name="test_e" time="0.000"><skipped type="pytest.skip" message="not now" /></testcase>
+</testsuite></testsuites>"#,
+        );
+        assert_eq!(cases.len(), 3);
+        assert_eq!(cases[0].state, TestState::Passed);
+        assert_eq!(cases[0].time, Some(0.01));
+        assert_eq!(cases[1].classname, "tests.test_a.TestC");
+        assert_eq!(cases[1].state, TestState::Failed);
+        assert_eq!(
+            cases[1].message.as_deref(),
+            Some("assert 1 == 2\ndef test_d():\n>       assert 1 == 2")
+        );
+        assert_eq!(cases[2].state, TestState::Skipped);
+    }
+
+    #[test]
+    fn test_parse_go() {
+        let tests = parse_go_list(
+            "TestA\nExampleB\nok  \texample.com/m/pkg\t0.002s\n?   \texample.com/m/cmd\t[no test files]\n",
+        );
+        assert_eq!(
+            tests,
+            vec![
+                ("example.com/m/pkg".to_string(), "TestA".to_string()),
+                ("example.com/m/pkg".to_string(), "ExampleB".to_string()),
+            ]
+        );
+
+        let results = parse_go_json(concat!(
+            r#"{"Action":"run","Package":"m/pkg","Test":"TestA"}"#,
+            "\n",
+            r#"{"Action":"output","Package":"m/pkg","Test":"TestA/sub","Output":"a_test.go:5: bad\n"}"#,
+            "\n",
+            r#"{"Action":"fail","Package":"m/pkg","Test":"TestA/sub","Elapsed":0}"#,
+            "\n",
+            r#"{"Action":"fail","Package":"m/pkg","Test":"TestA","Elapsed":0.01}"#,
+            "\n",
+            r#"{"Action":"fail","Package":"m/pkg","Elapsed":0.02}"#,
+            "\n",
+        ));
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "m/pkg/TestA");
+        assert_eq!(results[0].state, TestState::Failed);
+        assert_eq!(results[0].message.as_deref(), Some("a_test.go:5: bad\n"));
+    }
+}
@@ -18,10 +18,14 @@
 use crate::{
     RequestId, RpcError, RpcMessage,
     dap_types::{
-        self, DapId, RunDebugConfig, Scope, StackFrame, Stopped, ThreadId, Variable,
+        self, DapId, DebuggerCapabilities, RunDebugConfig, Scope, StackFrame,
+        Stopped, Thread, ThreadId, Variable,
     },
     file::PathObject,
-    plugin::{PluginId, VoltInfo, VoltMetadata},
+    plugin::{
+        PluginCommand, PluginId, PluginOutputChannel, PluginOutputLine,
+        PluginStatusItem, VoltID, VoltInfo, VoltMetadata,
+    },
     proxy::ProxyStatus,
     source_control::DiffInfo,
     terminal::TermId,
@@ -105,6 +109,41 @@ pub enum CoreNotification {
         volt: VoltInfo,
         only_installing: bool,
     },
+    /// The volt asks for permissions that the user hasn't granted yet, so it
+    /// wasn't started
+    VoltPermissionsRequired {
+        volt: VoltMetadata,
+    },
+    /// The volt trapped, ran out of time or memory, and was stopped. It's
+    /// restarted after `restart_in` seconds, unless it crashed too many times.
+    VoltCrashed {
+        volt: VoltID,
+        name: String,
+        error: String,
+        restart_in: Option<u64>,
+    },
+    PluginCommandRegistered {
+        plugin_id: PluginId,
+        volt: VoltID,
+        command: PluginCommand,
+    },
+    PluginCommandUnregistered {
+        volt: VoltID,
+        command: String,
+    },
+    PluginStatusItemSet {
+        volt: VoltID,
+        item: PluginStatusItem,
+    },
+    PluginStatusItemRemoved {
+        volt: VoltID,
+        id: String,
+    },
+    /// Output of a volt or language server for the Output panel
+    PluginOutput {
+        channel: PluginOutputChannel,
+        line: PluginOutputLine,
+    },
     DiffInfo {
         diff: DiffInfo,
     },
@@ -135,11 +174,22 @@ pub enum CoreNotification {
     DapStopped {
         dap_id: DapId,
         stopped: Stopped,
+        threads: Vec<Thread>,
         stack_frames: HashMap<ThreadId, Vec<StackFrame>>,
         variables: Vec<(Scope, Vec<Variable>)>,
     },
     DapContinued {
         dap_id: DapId,
+        thread_id: Option<ThreadId>,
+        all_threads_continued: bool,
+    },
+    DapThreads {
+        dap_id: DapId,
+        threads: Vec<Thread>,
+    },
+    DapCapabilities {
+        dap_id: DapId,
+        capabilities: DebuggerCapabilities,
     },
     DapBreakpointsResp {
         dap_id: DapId,
@@ -309,6 +359,61 @@ pub fn volt_removed(&self, volt: VoltInfo, only_installing: bool) {
         });
     }
 
+    pub fn volt_permissions_required(&self, volt: VoltMetadata) {
+        self.notification(CoreNotification::VoltPermissionsRequired { volt });
+    }
+
+    pub fn volt_crashed(
+        &self,
+        volt: VoltID,
+        name: String,
+        error: String,
+        restart_in: Option<u64>,
+    ) {
+        self.notification(CoreNotification::VoltCrashed {
+            volt,
+            name,
+            error,
+            restart_in,
+        });
+    }
+
+    pub fn plugin_command_registered(
+        &self,
+        plugin_id: PluginId,
+        volt: VoltID,
+        command: PluginCommand,
+    ) {
+        self.notification(CoreNotification::PluginCommandRegistered {
+            plugin_id,
+            volt,
+            command,
+        });
+    }
+
+    pub fn plugin_command_unregistered(&self, volt: VoltID, command: String) {
+        self.notification(CoreNotification::PluginCommandUnregistered {
+            volt,
+            command,
+        });
+    }
+
+    pub fn plugin_status_item_set(&self, volt: VoltID, item: PluginStatusItem) {
+        self.notification(CoreNotification::PluginStatusItemSet { volt, item });
+    }
+
+    pub fn plugin_status_item_removed(&self, volt: VoltID, id: String) {
+        self.notification(CoreNotification::PluginStatusItemRemoved { volt, id });
+    }
+
+    pub fn plugin_output(
+        &self,
+        channel: PluginOutputChannel,
+        line: PluginOutputLine,
+    ) {
+        self.notification(CoreNotification::PluginOutput { channel, line });
+    }
+
     pub fn run_in_terminal(&self, config: RunDebugConfig) {
         self.notification(CoreNotification::RunInTerminal { config });
     }
@@ -371,19 +476,45 @@ pub fn dap_stopped(
         &self,
         dap_id: DapId,
         stopped: Stopped,
+        threads: Vec<Thread>,
         stack_frames: HashMap<ThreadId, Vec<StackFrame>>,
         variables: Vec<(Scope, Vec<Variable>)>,
     ) {
         self.notification(CoreNotification::DapStopped {
             dap_id,
             stopped,
+            threads,
             stack_frames,
             variables,
         });
     }
 
-    pub fn dap_continued(&self, dap_id: DapId) {
-        self.notification(CoreNotification::DapContinued { dap_id });
+    pub fn dap_continued(
+        &self,
+        dap_id: DapId,
+        thread_id: Option<ThreadId>,
+        all_threads_continued: bool,
+    ) {
+        self.notification(CoreNotification::DapContinued {
+            dap_id,
+            thread_id,
+            all_threads_continued,
+        });
+    }
+
+    pub fn dap_threads(&self, dap_id: DapId, threads: Vec<Thread>) {
+        self.notification(CoreNotification::DapThreads { dap_id, threads });
+    }
+
+    pub fn dap_capabilities(
+        &self,
+        dap_id: DapId,
+        capabilities: DebuggerCapabilities,
+    ) {
+        self.notification(CoreNotification::DapCapabilities {
+            dap_id,
+            capabilities,
+        });
     }
 
     pub fn dap_breakpoints_resp(
@@ -53,6 +53,16 @@ pub struct RunDebugConfig {
     pub cwd: Option<String>,
     pub env: Option<HashMap<String, String>>,
     pub prelaunch: Option<RunDebugProgram>,
+    /// Run after the debug session has ended
+    pub post_debug: Option<RunDebugProgram>,
+    /// The names of the configs that have to be ready before this one starts
+    pub depends_on: Option<Vec<String>>,
+    /// Matched against the terminal output to tell when the configs that
+    /// depend on this one can start. Without it they wait for it to exit
+    /// successfully.
+    pub ready_pattern: Option<String>,
+    /// Turn the output into diagnostics in the Problems panel
+    pub problem_matchers: Option<Vec<ProblemMatcher>>,
     #[serde(skip)]
     pub debug_command: Option<Vec<String>>,
     #[serde(skip)]
@@ -63,12 +73,33 @@ pub struct RunDebugConfig {
     pub config_source: ConfigSource,
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum ProblemMatcher {
+    /// One of the built-in matchers: `cargo`, `tsc`, `gcc` or `go`
+    Preset(String),
+    Pattern(ProblemPattern),
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct ProblemPattern {
+    /// Matched against each line of the output, with the named groups `file`,
+    /// `line` and `message`, and optionally `column`, `end_line`,
+    /// `end_column` and `severity`. It spans several lines if it matches `\n`.
+    pub regexp: String,
+    /// The severity of the problems when the regexp doesn't capture one
+    pub severity: Option<String>,
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
 pub enum ConfigSource {
     #[default]
     Palette,
     RunInTerminal,
     CodeLens,
+    /// A task detected from the build files of the workspace
+    Task,
 }
 impl ConfigSource {
     pub fn from_palette(&self) -> bool {
@@ -280,6 +311,8 @@ pub struct DebuggerCapabilities {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub supports_exception_filter_options: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub supports_single_thread_execution_requests: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub exception_breakpoint_filters: Option<Vec<ExceptionBreakpointsFilter>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub completion_trigger_characters: Option<Vec<String>>,
@@ -573,6 +606,8 @@ impl Request for ConfigurationDone {
 #[serde(rename_all = "camelCase")]
 pub struct ContinueArguments {
     pub thread_id: ThreadId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub single_thread: Option<bool>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
@@ -822,12 +857,22 @@ impl Request for Variables {
     const COMMAND: &'static str = "variables";
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SteppingGranularity {
+    Statement,
+    Line,
+    Instruction,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct NextArguments {
     pub thread_id: ThreadId,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub granularity: Option<String>,
+    pub single_thread: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub granularity: Option<SteppingGranularity>,
 }
 
 #[derive(Debug)]
@@ -844,9 +889,11 @@ impl Request for Next {
 pub struct StepInArguments {
     pub thread_id: ThreadId,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub single_thread: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub target_id: Option<usize>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub granularity: Option<String>,
+    pub granularity: Option<SteppingGranularity>,
 }
 
 #[derive(Debug)]
@@ -863,7 +910,9 @@ impl Request for StepIn {
 pub struct StepOutArguments {
     pub thread_id: ThreadId,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub granularity: Option<String>,
+    pub single_thread: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub granularity: Option<SteppingGranularity>,
 }
 
 #[derive(Debug)]
@@ -874,3 +923,190 @@ impl Request for StepOut {
     type Result = ();
     const COMMAND: &'static str = "stepOut";
 }
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetVariableArguments {
+    pub variables_reference: usize,
+    pub name: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<ValueFormat>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetVariableResponse {
+    pub value: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub ty: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variables_reference: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub named_variables: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indexed_variables: Option<usize>,
+}
+
+#[derive(Debug)]
+pub enum SetVariable {}
+
+impl Request for SetVariable {
+    type Arguments = SetVariableArguments;
+    type Result = SetVariableResponse;
+    const COMMAND: &'static str = "setVariable";
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetExpressionArguments {
+    pub expression: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_id: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<ValueFormat>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetExpressionResponse {
+    pub value: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub ty: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presentation_hint: Option<VariablePresentationHint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variables_reference: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub named_variables: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indexed_variables: Option<usize>,
+}
+
+#[derive(Debug)]
+pub enum SetExpression {}
+
+impl Request for SetExpression {
+    type Arguments = SetExpressionArguments;
+    type Result = SetExpressionResponse;
+    const COMMAND: &'static str = "setExpression";
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluateArguments {
+    pub expression: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_id: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<ValueFormat>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluateResponse {
+    pub result: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub ty: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presentation_hint: Option<VariablePresentationHint>,
+    pub variables_reference: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub named_variables: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indexed_variables: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_reference: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum Evaluate {}
+
+impl Request for Evaluate {
+    type Arguments = EvaluateArguments;
+    type Result = EvaluateResponse;
+    const COMMAND: &'static str = "evaluate";
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisassembleArguments {
+    pub memory_reference: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instruction_offset: Option<i64>,
+    pub instruction_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolve_symbols: Option<bool>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisassembledInstruction {
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instruction_bytes: Option<String>,
+    pub instruction: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<Source>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_column: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presentation_hint: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisassembleResponse {
+    pub instructions: Vec<DisassembledInstruction>,
+}
+
+#[derive(Debug)]
+pub enum Disassemble {}
+
+impl Request for Disassemble {
+    type Arguments = DisassembleArguments;
+    type Result = DisassembleResponse;
+    const COMMAND: &'static str = "disassemble";
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadMemoryArguments {
+    pub memory_reference: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<i64>,
+    pub count: usize,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadMemoryResponse {
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unreadable_bytes: Option<usize>,
+    /// The bytes read, encoded as base64
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum ReadMemory {}
+
+impl Request for ReadMemory {
+    type Arguments = ReadMemoryArguments;
+    type Result = ReadMemoryResponse;
+    const COMMAND: &'static str = "readMemory";
+}
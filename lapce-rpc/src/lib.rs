@@ -12,7 +12,9 @@
 pub mod source_control;
 pub mod stdio;
 pub mod style;
+pub mod task;
 pub mod terminal;
+pub mod testing;
 
 pub use parse::{Call, RequestId, RpcObject};
 use serde::{Deserialize, Serialize};
@@ -1,6 +1,14 @@
 use core::fmt;
 use std::{collections::HashMap, path::PathBuf};
 
+use lsp_types::{
+    MessageType,
+    notification::Notification,
+    request::{
+        Formatting, GotoDefinition, GotoImplementation, GotoTypeDefinition,
+        HoverRequest, RangeFormatting, Request,
+    },
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -34,6 +42,9 @@ pub struct VoltInfo {
     pub repository: Option<String>,
     pub wasm: bool,
     pub updated_at_ts: i64,
+    /// The registry the volt was found in, the default registry if `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry: Option<String>,
 }
 
 impl VoltInfo {
@@ -55,6 +66,223 @@ pub struct VoltConfig {
     pub description: String,
 }
 
+/// Capabilities a volt asks for in the `[permissions]` section of its
+/// `volt.toml`. Anything that isn't listed is denied to the volt.
+#[derive(Deserialize, Clone, Debug, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct VoltPermissions {
+    /// Hosts the volt can make http requests to, e.g. `https://api.github.com`
+    pub network: Vec<String>,
+    /// Programs the volt can run, or start as a language server, by name or
+    /// path, `${volt}` is replaced with the volt's directory
+    pub executables: Vec<String>,
+    /// Environment variables that are passed through to the volt
+    pub env: Vec<String>,
+    /// Directories the volt can access besides its own, `${workspace}` is
+    /// replaced with the workspace root
+    pub filesystem: Vec<String>,
+}
+
+impl VoltPermissions {
+    pub fn is_empty(&self) -> bool {
+        self.network.is_empty()
+            && self.executables.is_empty()
+            && self.env.is_empty()
+            && self.filesystem.is_empty()
+    }
+
+    /// Whether everything in `requested` is part of these permissions
+    pub fn covers(&self, requested: &VoltPermissions) -> bool {
+        self.missing(requested).is_empty()
+    }
+
+    /// The part of `requested` that isn't part of these permissions
+    pub fn missing(&self, requested: &VoltPermissions) -> VoltPermissions {
+        fn missing(granted: &[String], requested: &[String]) -> Vec<String> {
+            requested
+                .iter()
+                .filter(|r| !granted.contains(r))
+                .cloned()
+                .collect()
+        }
+
+        VoltPermissions {
+            network: missing(&self.network, &requested.network),
+            executables: missing(&self.executables, &requested.executables),
+            env: missing(&self.env, &requested.env),
+            filesystem: missing(&self.filesystem, &requested.filesystem),
+        }
+    }
+}
+
+/// A language server declared in a `[lsp.<name>]` section of the settings,
+/// which is started directly instead of through a volt.
+#[derive(Deserialize, Clone, Debug, Default, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct LanguageServerConfig {
+    /// The program to run, by name or path
+    pub command: String,
+    pub args: Vec<String>,
+    pub env: HashMap<String, String>,
+    /// Language ids of the documents the server handles
+    pub languages: Vec<String>,
+    /// Globs of the files the server handles, e.g. `**/*.mydsl`
+    pub files: Vec<String>,
+    /// Sent as the `initializationOptions` of the `initialize` request
+    pub initialization_options: Option<Value>,
+    /// Workspace settings, returned for `workspace/configuration` requests
+    pub settings: Option<Value>,
+}
+
+impl LanguageServerConfig {
+    /// The part of the workspace settings under the dotted `section`, or all of
+    /// them if there's no section
+    pub fn settings_section(&self, section: Option<&str>) -> Value {
+        let Some(settings) = self.settings.as_ref() else {
+            return Value::Null;
+        };
+        let Some(section) = section.filter(|s| !s.is_empty()) else {
+            return settings.clone();
+        };
+        section
+            .split('.')
+            .try_fold(settings, |value, key| value.get(key))
+            .cloned()
+            .unwrap_or(Value::Null)
+    }
+}
+
+/// How the results of the language servers of a language are combined when
+/// more than one handles a document, from a `[lsp-policy.<language>]` section
+/// of the settings. Completions and code actions are always merged from all of
+/// them. A server is named by its `[lsp.<name>]` section or by its volt, e.g.
+/// `lapce-rust` or `lapce.lapce-rust`.
+#[derive(Deserialize, Clone, Debug, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct LanguageServerPolicy {
+    /// The server that formats the documents
+    pub formatter: Option<String>,
+    /// The server whose hover, definition, type definition and implementation
+    /// are used. The others are only asked when it has nothing.
+    pub primary: Option<String>,
+}
+
+impl LanguageServerPolicy {
+    /// The server that answers requests of `method` on its own, and whether the
+    /// others are asked when its result is empty rather than only when it fails
+    pub fn server_for(&self, method: &str) -> Option<(&str, bool)> {
+        match method {
+            Formatting::METHOD | RangeFormatting::METHOD => {
+                self.formatter.as_deref().map(|server| (server, false))
+            }
+            HoverRequest::METHOD
+            | GotoDefinition::METHOD
+            | GotoTypeDefinition::METHOD
+            | GotoImplementation::METHOD => {
+                self.primary.as_deref().map(|server| (server, true))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A command a volt registers with `lapce/registerCommand`. It's listed in the
+/// command palette and can be bound in the keymaps by its id, and when it's
+/// run the volt gets a `workspace/executeCommand` request for it.
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginCommand {
+    /// The id of the command, which starts with the name of the volt, e.g.
+    /// `mydsl.restartServer`
+    pub command: String,
+    pub title: String,
+    /// The default keybinding, in the format of the keymaps, e.g. `ctrl+k r`
+    pub key: Option<String>,
+    /// When the default keybinding applies, in the format of the keymaps
+    pub when: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UnregisterPluginCommandParams {
+    pub command: String,
+}
+
+/// An item a volt shows in the status bar with `lapce/setStatusItem`, which
+/// replaces the volt's item with the same id
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginStatusItem {
+    pub id: String,
+    pub text: String,
+    pub tooltip: Option<String>,
+    /// The command that is run when the item is clicked, either a command of
+    /// Lapce or one registered by a volt
+    pub command: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RemovePluginStatusItemParams {
+    pub id: String,
+}
+
+pub enum RegisterPluginCommand {}
+
+impl Notification for RegisterPluginCommand {
+    type Params = PluginCommand;
+    const METHOD: &'static str = "lapce/registerCommand";
+}
+
+pub enum UnregisterPluginCommand {}
+
+impl Notification for UnregisterPluginCommand {
+    type Params = UnregisterPluginCommandParams;
+    const METHOD: &'static str = "lapce/unregisterCommand";
+}
+
+pub enum SetPluginStatusItem {}
+
+impl Notification for SetPluginStatusItem {
+    type Params = PluginStatusItem;
+    const METHOD: &'static str = "lapce/setStatusItem";
+}
+
+pub enum RemovePluginStatusItem {}
+
+impl Notification for RemovePluginStatusItem {
+    type Params = RemovePluginStatusItemParams;
+    const METHOD: &'static str = "lapce/removeStatusItem";
+}
+
+/// A channel of the Output panel, there's one for each volt and each language
+/// server
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq, Hash)]
+pub struct PluginOutputChannel {
+    pub volt: VoltID,
+    pub name: String,
+}
+
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
+pub enum PluginOutputKind {
+    /// What the plugin wrote to stderr
+    Stderr,
+    /// A `window/logMessage` or `$/logTrace` from the plugin
+    Log(MessageType),
+    /// The trace of a JSON-RPC message sent to the plugin
+    Sent,
+    /// The trace of a JSON-RPC message received from the plugin
+    Received,
+}
+
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct PluginOutputLine {
+    pub kind: PluginOutputKind,
+    pub text: String,
+    /// How long the request took in milliseconds, for the trace of a response
+    pub duration: Option<u64>,
+}
+
 #[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub struct VoltMetadata {
@@ -68,9 +296,60 @@ pub struct VoltMetadata {
     pub wasm: Option<String>,
     pub color_themes: Option<Vec<String>>,
     pub icon_themes: Option<Vec<String>>,
+    pub snippets: Option<Vec<VoltSnippets>>,
+    pub languages: Option<Vec<VoltLanguage>>,
     pub dir: Option<PathBuf>,
     pub activation: Option<VoltActivation>,
     pub config: Option<HashMap<String, VoltConfig>>,
+    pub permissions: Option<VoltPermissions>,
+    /// The version that was installed before this one, which is filled in
+    /// when the volt is loaded from the plugins directory
+    pub previous: Option<VoltPreviousVersion>,
+}
+
+/// An earlier version of an installed volt, which is kept on disk after an
+/// update so that the update can be rolled back
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct VoltPreviousVersion {
+    pub version: String,
+    /// The entries of the changelog that are new since this version
+    pub changes: Option<String>,
+}
+
+/// A file of snippets for a language, in the JSON format of VS Code's snippet
+/// files
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct VoltSnippets {
+    /// The language, by its name in Lapce like `rust`, or the name of a language
+    /// added by a volt
+    pub language: String,
+    pub path: String,
+}
+
+/// A language a volt adds to the ones Lapce knows about
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct VoltLanguage {
+    /// The name shown in the language picker, which is also the language id
+    /// sent to language servers in lowercase
+    pub name: String,
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// File names that are of the language, like `Makefile`
+    #[serde(default)]
+    pub files: Vec<String>,
+    /// The token of a single line comment
+    pub comment: Option<String>,
+    pub indent: Option<String>,
+    /// The tree-sitter grammar library, the extension can be left out to use
+    /// the one of the platform's libraries. It's native code, so it's only
+    /// loaded when it's in the `executables` permission, as `${volt}/<grammar>`.
+    pub grammar: Option<String>,
+    /// The name of the grammar in its `tree_sitter_<name>` function, the
+    /// lowercase name of the language by default
+    pub grammar_fn: Option<String>,
+    /// The folder with the `highlights.scm` and `injections.scm` queries
+    pub queries: Option<String>,
 }
 
 impl VoltMetadata {
@@ -78,6 +357,12 @@ pub fn id(&self) -> VoltID {
         VoltID::from(self)
     }
 
+    /// The permissions requested by the volt, empty if it doesn't have a
+    /// `[permissions]` section
+    pub fn permissions(&self) -> VoltPermissions {
+        self.permissions.clone().unwrap_or_default()
+    }
+
     pub fn info(&self) -> VoltInfo {
         VoltInfo {
             name: self.name.clone(),
@@ -88,6 +373,7 @@ pub fn info(&self) -> VoltInfo {
             repository: self.repository.clone(),
             wasm: self.wasm.is_some(),
             updated_at_ts: 0,
+            registry: None,
         }
     }
 }
@@ -98,6 +384,14 @@ pub struct VoltID {
     pub name: String,
 }
 
+impl VoltID {
+    /// Whether a server named in the settings is this volt's, by its name with
+    /// or without the author
+    pub fn is_named(&self, name: &str) -> bool {
+        self.name == name || self.to_string() == name
+    }
+}
+
 impl fmt::Display for VoltID {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}.{}", self.author, self.name)
@@ -142,7 +436,12 @@ fn from(volt: &VoltInfo) -> Self {
 
 #[cfg(test)]
 mod tests {
-    use super::{VoltID, VoltInfo, VoltMetadata};
+    use serde_json::json;
+
+    use super::{
+        LanguageServerConfig, LanguageServerPolicy, VoltID, VoltInfo, VoltMetadata,
+        VoltPermissions,
+    };
 
     #[test]
     fn test_volt_metadata_id() {
@@ -157,9 +456,13 @@ fn test_volt_metadata_id() {
             wasm: None,
             color_themes: None,
             icon_themes: None,
+            snippets: None,
+            languages: None,
             dir: std::env::current_dir().unwrap().canonicalize().ok(),
             activation: None,
             config: None,
+            permissions: None,
+            previous: None,
         };
         let volt_id = VoltID {
             author: "Author".to_string(),
@@ -195,9 +498,13 @@ fn test_volt_metadata_info() {
             wasm: None,
             color_themes: None,
             icon_themes: None,
+            snippets: None,
+            languages: None,
             dir: std::env::current_dir().unwrap().canonicalize().ok(),
             activation: None,
             config: None,
+            permissions: None,
+            previous: None,
         };
         let volt_info = VoltInfo {
             name: "plugin".to_string(),
@@ -208,6 +515,7 @@ fn test_volt_metadata_info() {
             repository: None,
             wasm: false,
             updated_at_ts: 0,
+            registry: None,
         };
         assert_eq!(volt_metadata.info(), volt_info);
     }
@@ -223,6 +531,7 @@ fn test_volt_info_id() {
             repository: None,
             wasm: false,
             updated_at_ts: 0,
+            registry: None,
         };
         let volt_id = VoltID {
             author: "Author".to_string(),
@@ -234,4 +543,81 @@ fn test_volt_info_id() {
         assert_eq!(<&VoltInfo as Into<VoltID>>::into(&volt_info), volt_id);
         assert_eq!(<VoltInfo as Into<VoltID>>::into(volt_info), volt_id);
     }
+
+    #[test]
+    fn test_volt_permissions_covers() {
+        let requested = VoltPermissions {
+            network: vec!["https://api.github.com".to_string()],
+            executables: vec!["cargo".to_string()],
+            env: vec!["PATH".to_string()],
+            filesystem: Vec::new(),
+        };
+        assert!(!VoltPermissions::default().covers(&requested));
+        assert!(requested.covers(&requested));
+        assert!(requested.covers(&VoltPermissions::default()));
+
+        let granted = VoltPermissions {
+            network: vec!["https://api.github.com".to_string()],
+            env: vec!["PATH".to_string(), "HOME".to_string()],
+            ..Default::default()
+        };
+        assert!(!granted.covers(&requested));
+        assert_eq!(
+            granted.missing(&requested),
+            VoltPermissions {
+                executables: vec!["cargo".to_string()],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_language_server_settings_section() {
+        let config = LanguageServerConfig {
+            settings: Some(json!({ "mydsl": { "lint": { "enable": true } } })),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.settings_section(None),
+            json!({ "mydsl": { "lint": { "enable": true } } })
+        );
+        assert_eq!(
+            config.settings_section(Some("mydsl.lint")),
+            json!({ "enable": true })
+        );
+        assert_eq!(config.settings_section(Some("mydsl.format")), json!(null));
+        assert_eq!(
+            LanguageServerConfig::default().settings_section(Some("mydsl")),
+            json!(null)
+        );
+    }
+
+    #[test]
+    fn test_language_server_policy() {
+        let policy = LanguageServerPolicy {
+            formatter: Some("ruff".to_string()),
+            primary: Some("pyright".to_string()),
+        };
+        assert_eq!(
+            policy.server_for("textDocument/formatting"),
+            Some(("ruff", false))
+        );
+        assert_eq!(
+            policy.server_for("textDocument/hover"),
+            Some(("pyright", true))
+        );
+        assert_eq!(policy.server_for("textDocument/completion"), None);
+        assert_eq!(
+            LanguageServerPolicy::default().server_for("textDocument/definition"),
+            None
+        );
+
+        let volt_id = VoltID {
+            author: "lapce".to_string(),
+            name: "lapce-python".to_string(),
+        };
+        assert!(volt_id.is_named("lapce-python"));
+        assert!(volt_id.is_named("lapce.lapce-python"));
+        assert!(!volt_id.is_named("pyright"));
+    }
 }
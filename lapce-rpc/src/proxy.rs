@@ -11,7 +11,7 @@
 use indexmap::IndexMap;
 use lapce_xi_rope::RopeDelta;
 use lsp_types::{
-    CallHierarchyIncomingCall, CallHierarchyItem, CodeAction, CodeActionResponse,
+    CallHierarchyIncomingCall, CallHierarchyItem, CodeAction, CodeActionOrCommand,
     CodeLens, CompletionItem, Diagnostic, DocumentSymbolResponse, FoldingRange,
     GotoDefinitionResponse, Hover, InlayHint, InlineCompletionResponse,
     InlineCompletionTriggerKind, Location, Position, PrepareRenameResponse,
@@ -25,13 +25,20 @@
 use crate::{
     RequestId, RpcError, RpcMessage,
     buffer::BufferId,
-    dap_types::{self, DapId, RunDebugConfig, SourceBreakpoint, ThreadId},
+    dap_types::{
+        self, DapId, RunDebugConfig, SourceBreakpoint, SteppingGranularity, ThreadId,
+    },
     file::{FileNodeItem, PathObject},
     file_line::FileLine,
-    plugin::{PluginId, VoltInfo, VoltMetadata},
+    plugin::{
+        LanguageServerConfig, LanguageServerPolicy, PluginId, PluginOutputChannel,
+        VoltInfo, VoltMetadata, VoltPermissions,
+    },
     source_control::FileDiff,
     style::SemanticStyles,
+    task::DetectedTask,
     terminal::{TermId, TerminalProfile},
+    testing::{TestItem, TestResult, TestRunner},
 };
 
 #[allow(clippy::large_enum_variant)]
@@ -172,9 +179,28 @@ pub enum ProxyRequest {
     GetFiles {
         path: String,
     },
+    /// Find the tasks in the build files at the root of the workspace
+    DetectTasks {},
+    /// List the tests of the workspace with the test runners it uses
+    DiscoverTests {},
+    /// Run the tests with the ids, or all the tests of the runner if there are
+    /// none
+    RunTests {
+        runner: TestRunner,
+        ids: Vec<String>,
+    },
     ReadDir {
         path: PathBuf,
     },
+    /// Install a packaged volt, a tar archive compressed with gzip or zstd,
+    /// into the plugins directory of the proxy
+    InstallVoltArchive {
+        archive: Vec<u8>,
+    },
+    /// The first of the paths that exists on the proxy's side
+    FindExistingPath {
+        paths: Vec<PathBuf>,
+    },
     Save {
         rev: u64,
         path: PathBuf,
@@ -217,6 +243,36 @@ pub enum ProxyRequest {
         dap_id: DapId,
         frame_id: usize,
     },
+    DapSetVariable {
+        dap_id: DapId,
+        reference: usize,
+        name: String,
+        value: String,
+    },
+    DapSetExpression {
+        dap_id: DapId,
+        frame_id: Option<usize>,
+        expression: String,
+        value: String,
+    },
+    DapEvaluate {
+        dap_id: DapId,
+        frame_id: Option<usize>,
+        expression: String,
+        context: Option<String>,
+    },
+    DapDisassemble {
+        dap_id: DapId,
+        memory_reference: String,
+        instruction_offset: i64,
+        instruction_count: usize,
+    },
+    DapReadMemory {
+        dap_id: DapId,
+        memory_reference: String,
+        offset: i64,
+        count: usize,
+    },
     ReferencesResolve {
         items: Vec<Location>,
     },
@@ -229,9 +285,15 @@ pub enum ProxyNotification {
     Initialize {
         workspace: Option<PathBuf>,
         disabled_volts: Vec<VoltID>,
+        /// Permissions the user has granted to volts
+        volt_permissions: Vec<(VoltID, VoltPermissions)>,
         /// Paths to extra plugins that should be loaded
         extra_plugin_paths: Vec<PathBuf>,
         plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
+        /// Language servers declared in the settings
+        language_servers: HashMap<String, LanguageServerConfig>,
+        /// How the results of the language servers are combined, by language
+        language_server_policies: HashMap<String, LanguageServerPolicy>,
         window_id: usize,
         tab_id: usize,
     },
@@ -261,6 +323,12 @@ pub enum ProxyNotification {
     UpdatePluginConfigs {
         configs: HashMap<String, HashMap<String, serde_json::Value>>,
     },
+    UpdateLanguageServers {
+        servers: HashMap<String, LanguageServerConfig>,
+    },
+    UpdateLanguageServerPolicies {
+        policies: HashMap<String, LanguageServerPolicy>,
+    },
     NewTerminal {
         term_id: TermId,
         profile: TerminalProfile,
@@ -274,12 +342,34 @@ pub enum ProxyNotification {
     ReloadVolt {
         volt: VoltMetadata,
     },
+    /// Go back to the version of the volt that was installed before
+    RollbackVolt {
+        volt: VoltMetadata,
+    },
     DisableVolt {
         volt: VoltInfo,
     },
     EnableVolt {
         volt: VoltInfo,
     },
+    GrantVoltPermissions {
+        volt: VoltID,
+        permissions: VoltPermissions,
+    },
+    RevokeVoltPermissions {
+        volt: VoltInfo,
+    },
+    /// Turn the JSON-RPC trace of the plugins of an Output panel channel on
+    /// or off
+    SetPluginTrace {
+        channel: PluginOutputChannel,
+        enabled: bool,
+    },
+    /// Run a command that a volt registered
+    ExecutePluginCommand {
+        plugin_id: PluginId,
+        command: String,
+    },
     GitCommit {
         message: String,
         diffs: Vec<FileDiff>,
@@ -319,18 +409,29 @@ pub enum ProxyNotification {
     DapContinue {
         dap_id: DapId,
         thread_id: ThreadId,
+        /// Whether only the thread is resumed, when the adapter supports it
+        single_thread: bool,
     },
     DapStepOver {
         dap_id: DapId,
         thread_id: ThreadId,
+        /// Whether the other threads are kept paused, when the adapter supports it
+        single_thread: bool,
+        granularity: Option<SteppingGranularity>,
     },
     DapStepInto {
         dap_id: DapId,
         thread_id: ThreadId,
+        /// Whether the other threads are kept paused, when the adapter supports it
+        single_thread: bool,
+        granularity: Option<SteppingGranularity>,
     },
     DapStepOut {
         dap_id: DapId,
         thread_id: ThreadId,
+        /// Whether the other threads are kept paused, when the adapter supports it
+        single_thread: bool,
+        granularity: Option<SteppingGranularity>,
     },
     DapPause {
         dap_id: DapId,
@@ -371,6 +472,13 @@ pub enum ProxyResponse {
     ReadDirResponse {
         items: Vec<FileNodeItem>,
     },
+    FindExistingPathResponse {
+        path: Option<PathBuf>,
+    },
+    InstallVoltArchiveResponse {
+        volt: VoltMetadata,
+        icon: Option<Vec<u8>>,
+    },
     CompletionResolveResponse {
         item: Box<CompletionItem>,
     },
@@ -398,9 +506,10 @@ pub enum ProxyResponse {
     GetReferencesResponse {
         references: Vec<Location>,
     },
+    /// The code actions of all the language servers, each with the plugin
+    /// that resolves and runs it
     GetCodeActionsResponse {
-        plugin_id: PluginId,
-        resp: CodeActionResponse,
+        actions: Vec<(PluginId, CodeActionOrCommand)>,
     },
     LspFoldingRangeResponse {
         plugin_id: PluginId,
@@ -421,6 +530,15 @@ pub enum ProxyResponse {
     GetFilesResponse {
         items: Vec<PathBuf>,
     },
+    DetectTasksResponse {
+        tasks: Vec<DetectedTask>,
+    },
+    DiscoverTestsResponse {
+        tests: Vec<TestItem>,
+    },
+    RunTestsResponse {
+        results: Vec<TestResult>,
+    },
     GetDocumentFormatting {
         edits: Vec<TextEdit>,
     },
@@ -460,6 +578,21 @@ pub enum ProxyResponse {
     DapGetScopesResponse {
         scopes: Vec<(dap_types::Scope, Vec<dap_types::Variable>)>,
     },
+    DapSetVariableResponse {
+        resp: dap_types::SetVariableResponse,
+    },
+    DapSetExpressionResponse {
+        resp: dap_types::SetExpressionResponse,
+    },
+    DapEvaluateResponse {
+        resp: dap_types::EvaluateResponse,
+    },
+    DapDisassembleResponse {
+        resp: dap_types::DisassembleResponse,
+    },
+    DapReadMemoryResponse {
+        resp: dap_types::ReadMemoryResponse,
+    },
     CreatePathResponse {
         path: PathBuf,
     },
@@ -617,6 +750,10 @@ pub fn reload_volt(&self, volt: VoltMetadata) {
         self.notification(ProxyNotification::ReloadVolt { volt });
     }
 
+    pub fn rollback_volt(&self, volt: VoltMetadata) {
+        self.notification(ProxyNotification::RollbackVolt { volt });
+    }
+
     pub fn remove_volt(&self, volt: VoltMetadata) {
         self.notification(ProxyNotification::RemoveVolt { volt });
     }
@@ -629,6 +766,32 @@ pub fn enable_volt(&self, volt: VoltInfo) {
         self.notification(ProxyNotification::EnableVolt { volt });
     }
 
+    pub fn grant_volt_permissions(
+        &self,
+        volt: VoltID,
+        permissions: VoltPermissions,
+    ) {
+        self.notification(ProxyNotification::GrantVoltPermissions {
+            volt,
+            permissions,
+        });
+    }
+
+    pub fn revoke_volt_permissions(&self, volt: VoltInfo) {
+        self.notification(ProxyNotification::RevokeVoltPermissions { volt });
+    }
+
+    pub fn set_plugin_trace(&self, channel: PluginOutputChannel, enabled: bool) {
+        self.notification(ProxyNotification::SetPluginTrace { channel, enabled });
+    }
+
+    pub fn execute_plugin_command(&self, plugin_id: PluginId, command: String) {
+        self.notification(ProxyNotification::ExecutePluginCommand {
+            plugin_id,
+            command,
+        });
+    }
+
     pub fn shutdown(&self) {
         self.notification(ProxyNotification::Shutdown {});
         if let Err(err) = self.tx.send(ProxyRpc::Shutdown) {
@@ -636,20 +799,27 @@ pub fn shutdown(&self) {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize(
         &self,
         workspace: Option<PathBuf>,
         disabled_volts: Vec<VoltID>,
+        volt_permissions: Vec<(VoltID, VoltPermissions)>,
         extra_plugin_paths: Vec<PathBuf>,
         plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
+        language_servers: HashMap<String, LanguageServerConfig>,
+        language_server_policies: HashMap<String, LanguageServerPolicy>,
         window_id: usize,
         tab_id: usize,
     ) {
         self.notification(ProxyNotification::Initialize {
             workspace,
             disabled_volts,
+            volt_permissions,
             extra_plugin_paths,
             plugin_configurations,
+            language_servers,
+            language_server_policies,
             window_id,
             tab_id,
         });
@@ -830,10 +1000,43 @@ pub fn get_open_files_content(&self) -> Result<ProxyResponse, RpcError> {
         self.request(ProxyRequest::GetOpenFilesContent {})
     }
 
+    pub fn detect_tasks(&self, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::DetectTasks {}, f);
+    }
+
+    pub fn discover_tests(&self, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::DiscoverTests {}, f);
+    }
+
+    pub fn run_tests(
+        &self,
+        runner: TestRunner,
+        ids: Vec<String>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::RunTests { runner, ids }, f);
+    }
+
     pub fn read_dir(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
         self.request_async(ProxyRequest::ReadDir { path }, f);
     }
 
+    pub fn find_existing_path(
+        &self,
+        paths: Vec<PathBuf>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::FindExistingPath { paths }, f);
+    }
+
+    pub fn install_volt_archive(
+        &self,
+        archive: Vec<u8>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::InstallVoltArchive { archive }, f);
+    }
+
     pub fn completion_resolve(
         &self,
         plugin_id: PluginId,
@@ -1101,6 +1304,22 @@ pub fn update_plugin_configs(
         self.notification(ProxyNotification::UpdatePluginConfigs { configs });
     }
 
+    pub fn update_language_servers(
+        &self,
+        servers: HashMap<String, LanguageServerConfig>,
+    ) {
+        self.notification(ProxyNotification::UpdateLanguageServers { servers });
+    }
+
+    pub fn update_language_server_policies(
+        &self,
+        policies: HashMap<String, LanguageServerPolicy>,
+    ) {
+        self.notification(ProxyNotification::UpdateLanguageServerPolicies {
+            policies,
+        });
+    }
+
     pub fn git_discard_files_changes(&self, files: Vec<PathBuf>) {
         self.notification(ProxyNotification::GitDiscardFilesChanges { files });
     }
@@ -1153,20 +1372,62 @@ pub fn dap_restart(
         })
     }
 
-    pub fn dap_continue(&self, dap_id: DapId, thread_id: ThreadId) {
-        self.notification(ProxyNotification::DapContinue { dap_id, thread_id })
+    pub fn dap_continue(
+        &self,
+        dap_id: DapId,
+        thread_id: ThreadId,
+        single_thread: bool,
+    ) {
+        self.notification(ProxyNotification::DapContinue {
+            dap_id,
+            thread_id,
+            single_thread,
+        })
     }
 
-    pub fn dap_step_over(&self, dap_id: DapId, thread_id: ThreadId) {
-        self.notification(ProxyNotification::DapStepOver { dap_id, thread_id })
+    pub fn dap_step_over(
+        &self,
+        dap_id: DapId,
+        thread_id: ThreadId,
+        single_thread: bool,
+        granularity: Option<SteppingGranularity>,
+    ) {
+        self.notification(ProxyNotification::DapStepOver {
+            dap_id,
+            thread_id,
+            single_thread,
+            granularity,
+        })
     }
 
-    pub fn dap_step_into(&self, dap_id: DapId, thread_id: ThreadId) {
-        self.notification(ProxyNotification::DapStepInto { dap_id, thread_id })
+    pub fn dap_step_into(
+        &self,
+        dap_id: DapId,
+        thread_id: ThreadId,
+        single_thread: bool,
+        granularity: Option<SteppingGranularity>,
+    ) {
+        self.notification(ProxyNotification::DapStepInto {
+            dap_id,
+            thread_id,
+            single_thread,
+            granularity,
+        })
     }
 
-    pub fn dap_step_out(&self, dap_id: DapId, thread_id: ThreadId) {
-        self.notification(ProxyNotification::DapStepOut { dap_id, thread_id })
+    pub fn dap_step_out(
+        &self,
+        dap_id: DapId,
+        thread_id: ThreadId,
+        single_thread: bool,
+        granularity: Option<SteppingGranularity>,
+    ) {
+        self.notification(ProxyNotification::DapStepOut {
+            dap_id,
+            thread_id,
+            single_thread,
+            granularity,
+        })
     }
 
     pub fn dap_pause(&self, dap_id: DapId, thread_id: ThreadId) {
@@ -1211,6 +1472,101 @@ pub fn dap_get_scopes(
     ) {
         self.request_async(ProxyRequest::DapGetScopes { dap_id, frame_id }, f);
     }
+
+    pub fn dap_set_variable(
+        &self,
+        dap_id: DapId,
+        reference: usize,
+        name: String,
+        value: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::DapSetVariable {
+                dap_id,
+                reference,
+                name,
+                value,
+            },
+            f,
+        );
+    }
+
+    pub fn dap_set_expression(
+        &self,
+        dap_id: DapId,
+        frame_id: Option<usize>,
+        expression: String,
+        value: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::DapSetExpression {
+                dap_id,
+                frame_id,
+                expression,
+                value,
+            },
+            f,
+        );
+    }
+
+    pub fn dap_evaluate(
+        &self,
+        dap_id: DapId,
+        frame_id: Option<usize>,
+        expression: String,
+        context: Option<String>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::DapEvaluate {
+                dap_id,
+                frame_id,
+                expression,
+                context,
+            },
+            f,
+        );
+    }
+
+    pub fn dap_disassemble(
+        &self,
+        dap_id: DapId,
+        memory_reference: String,
+        instruction_offset: i64,
+        instruction_count: usize,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::DapDisassemble {
+                dap_id,
+                memory_reference,
+                instruction_offset,
+                instruction_count,
+            },
+            f,
+        );
+    }
+
+    pub fn dap_read_memory(
+        &self,
+        dap_id: DapId,
+        memory_reference: String,
+        offset: i64,
+        count: usize,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::DapReadMemory {
+                dap_id,
+                memory_reference,
+                offset,
+                count,
+            },
+            f,
+        );
+    }
 }
 
 impl Default for ProxyRpcHandler {
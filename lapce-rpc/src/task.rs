@@ -0,0 +1,55 @@
+use std::{fmt, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// The build tool a task was detected from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TaskSource {
+    Cargo,
+    Npm,
+    Make,
+    Just,
+}
+
+impl fmt::Display for TaskSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TaskSource::Cargo => "cargo",
+            TaskSource::Npm => "npm",
+            TaskSource::Make => "make",
+            TaskSource::Just => "just",
+        };
+        f.write_str(name)
+    }
+}
+
+/// What a task is for, which decides whether it can be the default build or
+/// test task
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TaskKind {
+    Build,
+    Test,
+    Run,
+    Other,
+}
+
+/// A task found in the build files of the workspace, which can be run
+/// without a run config.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DetectedTask {
+    pub source: TaskSource,
+    pub kind: TaskKind,
+    pub name: String,
+    pub program: String,
+    pub args: Vec<String>,
+    /// The directory of the build file the task was found in
+    pub cwd: PathBuf,
+}
+
+impl DetectedTask {
+    /// The name of the task that's shown and that its terminal is given,
+    /// e.g. `cargo: build`
+    pub fn label(&self) -> String {
+        format!("{}: {}", self.source, self.name)
+    }
+}
@@ -2,7 +2,7 @@
 
 use serde::{Deserialize, Serialize};
 
-use crate::counter::Counter;
+use crate::{counter::Counter, dap_types::ProblemMatcher};
 
 #[derive(Eq, PartialEq, Hash, Copy, Clone, Debug, Serialize, Deserialize)]
 pub struct TermId(pub u64);
@@ -21,6 +21,11 @@ pub struct TerminalProfile {
     pub arguments: Option<Vec<String>>,
     pub workdir: Option<url::Url>,
     pub environment: Option<HashMap<String, String>>,
+    pub problem_matchers: Option<Vec<ProblemMatcher>>,
+    /// Load the scripts reporting the prompts, commands and working directory
+    /// into the shell, if it's one they're written for
+    #[serde(default)]
+    pub shell_integration: bool,
 }
 
 impl TerminalProfile {}
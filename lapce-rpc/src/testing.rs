@@ -0,0 +1,57 @@
+use std::{fmt, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// The test runner a test was discovered with and is run by
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TestRunner {
+    Cargo,
+    Pytest,
+    Go,
+}
+
+impl fmt::Display for TestRunner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TestRunner::Cargo => "cargo",
+            TestRunner::Pytest => "pytest",
+            TestRunner::Go => "go",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestItem {
+    /// What the runner calls the test, e.g. `module::tests::name` for cargo
+    /// and `tests/test_a.py::test_b` for pytest. Cargo and go tests are
+    /// prefixed with their package to tell them apart.
+    pub id: String,
+    pub runner: TestRunner,
+    /// The name of the test function
+    pub name: String,
+    /// The cargo or go package of the test
+    pub package: Option<String>,
+    pub path: Option<PathBuf>,
+    /// Zero based line of the test function
+    pub line: Option<u32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TestState {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TestResult {
+    /// The id of the test, as in [`TestItem::id`]
+    pub id: String,
+    pub runner: TestRunner,
+    pub state: TestState,
+    /// In seconds, when the runner reports it
+    pub duration: Option<f64>,
+    /// The output of a failed test
+    pub message: Option<String>,
+}